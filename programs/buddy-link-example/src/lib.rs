@@ -0,0 +1,689 @@
+//! Reference integrator of the `buddy-link` crate.
+//!
+//! A toy swap paying a referral cut from its vault, plus one instruction per CPI wrapper of `buddy_link::cpi`
+//! and one through its native counterpart in `buddy_link::cpi::native`.
+//! The vault is a PDA: it signs the transfers with its seeds, as the SOL source and as the owner of the token vault.
+//! Only the secure local reward and the validation are signed by the user (the referee).
+//! The payouts from the vault are signed by the admin stored in the config, picked by the upgrade authority.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_spl::token::{self, Token, Transfer};
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::cpi as buddy_link_cpi;
+use buddy_link::instruction::{
+    TierRecipient, TransferUncheckedLocalSharedRewardArgs, UnclaimedTiers,
+};
+
+declare_id!("FaJ5Gtr6PxwqRqPHg1L9DypgdPQmELXQSxVGwgFGvqyW");
+
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const CONFIG_SEED: &[u8] = b"config";
+
+const MAX_BPS: u64 = 10_000;
+
+#[error_code]
+pub enum ExampleError {
+    #[msg("Referral fee above 100%")]
+    InvalidReferralFee,
+    #[msg("Amount overflow")]
+    AmountOverflow,
+    #[msg("Only the upgrade authority of the program can initialize it")]
+    NotUpgradeAuthority,
+}
+
+/// Admin of the payouts, the only one allowed to pay from the vault.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+}
+
+#[program]
+pub mod buddy_link_example {
+    use super::*;
+
+    /// Stores the admin of the payouts, signed by the upgrade authority of the program.
+    pub fn initialize(ctx: Context<Initialize>, admin: Pubkey) -> Result<()> {
+        ctx.accounts.config.admin = admin;
+
+        Ok(())
+    }
+
+    /// Takes the tokens of the user into the vault, and pays a cut of them to the referrer of the user
+    /// (and to its global referrer if given) from the vault.
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, referral_fee_bps: u16) -> Result<()> {
+        require!(
+            u64::from(referral_fee_bps) <= MAX_BPS,
+            ExampleError::InvalidReferralFee
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        let referral_fee = amount_in
+            .checked_mul(u64::from(referral_fee_bps))
+            .ok_or(ExampleError::AmountOverflow)?
+            / MAX_BPS;
+        if referral_fee == 0 {
+            return Ok(());
+        }
+
+        let accounts = &ctx.accounts;
+        buddy_link_cpi::transfer_checked_global_reward(
+            CpiContext::new(
+                accounts.buddy_link_program.to_account_info(),
+                buddy_link_cpi::TransferCheckedGlobalReward {
+                    buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                    authority: accounts.vault.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    from_token_account: accounts.vault_token_account.to_account_info(),
+                    referrer_token_account: accounts.referrer_token_account.to_account_info(),
+                    referrer_member: accounts.referrer_member.clone(),
+                    referrer_treasury: accounts.referrer_treasury.to_account_info(),
+                    referrer_treasury_for_reward: accounts
+                        .referrer_treasury_for_reward
+                        .to_account_info(),
+                    referee_member: accounts.referee_member.to_account_info(),
+                    buddy_global_referrer_treasury: accounts.global_referrer_treasury.clone(),
+                    buddy_global_referrer_token_account: accounts
+                        .global_referrer_token_account
+                        .clone(),
+                },
+            ),
+            referral_fee,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Splits SOL of the vault between the referrers in the remaining accounts.
+    pub fn pay_shared_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayFromVaultSol<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+    ) -> Result<()> {
+        buddy_link_cpi::transfer_unchecked_local_shared_reward_sol(
+            ctx.accounts.cpi_context(ctx.remaining_accounts),
+            total_amount,
+            shares_in_bps,
+            members_included,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Same as [`pay_shared_sol`], through the native wrapper taking the account infos as they are.
+    pub fn pay_shared_sol_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayFromVaultSol<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        buddy_link_cpi::native::transfer_unchecked_local_shared_reward_sol(
+            &accounts.buddy_link_program,
+            accounts.vault.as_ref(),
+            accounts.system_program.as_ref(),
+            ctx.remaining_accounts,
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount,
+                shares_in_bps,
+                members_included,
+            },
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Splits tokens of the vault between the referrers in the remaining accounts.
+    pub fn pay_shared_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayFromVaultSpl<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+    ) -> Result<()> {
+        buddy_link_cpi::transfer_unchecked_local_shared_reward_spl(
+            ctx.accounts.cpi_context(ctx.remaining_accounts),
+            total_amount,
+            shares_in_bps,
+            members_included,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Splits SOL of the vault between levels of referrers, the unclaimed tiers going to the fallback if given.
+    pub fn pay_multi_tier_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayFromVaultSol<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        members_included: bool,
+        fallback: Option<Pubkey>,
+        fallback_member: Option<Pubkey>,
+    ) -> Result<()> {
+        buddy_link_cpi::transfer_multi_tier_reward_sol(
+            ctx.accounts.cpi_context(ctx.remaining_accounts),
+            total_amount,
+            schedule,
+            unclaimed_tiers(fallback, fallback_member),
+            members_included,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Splits tokens of the vault between levels of referrers, the unclaimed tiers going to the fallback if given.
+    pub fn pay_multi_tier_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayFromVaultSpl<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        members_included: bool,
+        fallback: Option<Pubkey>,
+        fallback_member: Option<Pubkey>,
+    ) -> Result<()> {
+        buddy_link_cpi::transfer_multi_tier_reward_spl(
+            ctx.accounts.cpi_context(ctx.remaining_accounts),
+            total_amount,
+            schedule,
+            unclaimed_tiers(fallback, fallback_member),
+            members_included,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Pays the global referrer of the user in SOL from the vault.
+    pub fn pay_global_only_sol(ctx: Context<PayGlobalOnlySol>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        buddy_link_cpi::transfer_checked_global_only_reward_sol(
+            CpiContext::new(
+                accounts.buddy_link_program.to_account_info(),
+                buddy_link_cpi::TransferCheckedGlobalOnlyRewardSol {
+                    buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                    authority: accounts.vault.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    global_referrer_treasury: accounts.global_referrer_treasury.to_account_info(),
+                    global_referrer_treasury_for_reward: accounts
+                        .global_referrer_treasury_for_reward
+                        .to_account_info(),
+                    referee_buddy_profile: accounts.referee_buddy_profile.to_account_info(),
+                    referee_buddy: accounts.referee_buddy.to_account_info(),
+                },
+            ),
+            amount,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// Pays the global referrer of the user in tokens from the vault.
+    pub fn pay_global_only_spl(ctx: Context<PayGlobalOnlySpl>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        buddy_link_cpi::transfer_checked_global_only_reward_spl(
+            CpiContext::new(
+                accounts.buddy_link_program.to_account_info(),
+                buddy_link_cpi::TransferCheckedGlobalOnlyRewardSpl {
+                    buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                    authority: accounts.vault.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    from_token_account: accounts.vault_token_account.to_account_info(),
+                    referrer_token_account: accounts.referrer_token_account.to_account_info(),
+                    global_referrer_treasury: accounts.global_referrer_treasury.to_account_info(),
+                    global_referrer_treasury_for_reward: accounts
+                        .global_referrer_treasury_for_reward
+                        .to_account_info(),
+                    referee_buddy_profile: accounts.referee_buddy_profile.to_account_info(),
+                    referee_buddy: accounts.referee_buddy.to_account_info(),
+                },
+            ),
+            amount,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+
+        Ok(())
+    }
+
+    /// The user pays its referrer in the organization, with the referee fully checked.
+    pub fn pay_secure_local(ctx: Context<PaySecureLocal>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        buddy_link_cpi::transfer_secure_local_reward(
+            CpiContext::new(
+                accounts.buddy_link_program.to_account_info(),
+                buddy_link_cpi::TransferSecureLocalReward {
+                    buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                    authority: accounts.user.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    from_token_account: accounts.user_token_account.to_account_info(),
+                    referrer_token_account: accounts.referrer_token_account.to_account_info(),
+                    referrer_member: accounts.referrer_member.to_account_info(),
+                    referrer_treasury: accounts.referrer_treasury.to_account_info(),
+                    referrer_treasury_for_reward: accounts
+                        .referrer_treasury_for_reward
+                        .to_account_info(),
+                    referee_buddy_profile: accounts.referee_buddy_profile.to_account_info(),
+                    referee_buddy: accounts.referee_buddy.to_account_info(),
+                    referee_treasury: accounts.referee_treasury.to_account_info(),
+                    referee_member: accounts.referee_member.to_account_info(),
+                },
+            ),
+            amount,
+            &[],
+        )?;
+
+        Ok(())
+    }
+
+    /// Checks the referrer of the user before letting it in.
+    pub fn validate_referral(ctx: Context<ValidateReferral>) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        buddy_link_cpi::validate_referrer(CpiContext::new(
+            accounts.buddy_link_program.to_account_info(),
+            buddy_link_cpi::ValidateReferrer {
+                buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                payer: accounts.user.to_account_info(),
+                authority: accounts.user.to_account_info(),
+                referee_buddy_profile: accounts.referee_buddy_profile.to_account_info(),
+                referee_buddy: accounts.referee_buddy.to_account_info(),
+                referee_treasury: accounts.referee_treasury.to_account_info(),
+                referee_member: accounts.referee_member.to_account_info(),
+                referrer_member: accounts.referrer_member.clone(),
+                referrer_treasury: accounts.referrer_treasury.clone(),
+                referrer_treasury_for_reward: accounts.referrer_treasury_for_reward.clone(),
+                referrer_token_account: accounts.referrer_token_account.clone(),
+                mint: accounts.mint.clone(),
+            },
+        ))?;
+
+        Ok(())
+    }
+
+    /// Pays the referrer of the user from the vault, letting BuddyLink pick the transfer variant.
+    pub fn pay_referral(ctx: Context<PayReferral>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+
+        let (variant, reason) = buddy_link_cpi::pay_referral_reward(
+            CpiContext::new(
+                accounts.buddy_link_program.to_account_info(),
+                buddy_link_cpi::PayReferralReward {
+                    buddy_link_program: accounts.buddy_link_program.to_account_info(),
+                    authority: accounts.vault.to_account_info(),
+                    system_program: accounts.system_program.clone(),
+                    mint: accounts.mint.clone(),
+                    token_program: accounts.token_program.clone(),
+                    from_token_account: accounts.vault_token_account.clone(),
+                    referee_buddy_profile: accounts.referee_buddy_profile.to_account_info(),
+                    referee_buddy: accounts.referee_buddy.to_account_info(),
+                    referee_treasury: accounts.referee_treasury.clone(),
+                    referee_member: accounts.referee_member.clone(),
+                    referrer_member: accounts.referrer_member.clone(),
+                    referrer_treasury: accounts.referrer_treasury.clone(),
+                    referrer_treasury_for_reward: accounts.referrer_treasury_for_reward.clone(),
+                    referrer_token_account: accounts.referrer_token_account.clone(),
+                    global_referrer_treasury: accounts.global_referrer_treasury.clone(),
+                    global_referrer_treasury_for_reward: accounts
+                        .global_referrer_treasury_for_reward
+                        .clone(),
+                    global_referrer_token_account: accounts.global_referrer_token_account.clone(),
+                },
+            ),
+            amount,
+            &[&[VAULT_SEED, &[ctx.bumps.vault]]],
+        )?;
+        msg!("Paid with {:?}: {}", variant, reason);
+
+        Ok(())
+    }
+}
+
+fn unclaimed_tiers(fallback: Option<Pubkey>, fallback_member: Option<Pubkey>) -> UnclaimedTiers {
+    match fallback {
+        Some(recipient) => UnclaimedTiers::Fallback(TierRecipient {
+            recipient,
+            member: fallback_member,
+        }),
+        None => UnclaimedTiers::Redistribute,
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub upgrade_authority: Signer<'info>,
+    #[account(
+        init,
+        payer = upgrade_authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key())
+            @ ExampleError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Token account of the user, checked by the token program.
+    #[account(mut)]
+    pub user_token_account: AccountInfo<'info>,
+
+    /// CHECK: PDA owning the token vault.
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: AccountInfo<'info>,
+    /// CHECK: Token account of the vault, checked by the token program.
+    #[account(mut)]
+    pub vault_token_account: AccountInfo<'info>,
+    /// CHECK: Mint of the swapped tokens.
+    pub mint: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_token_account: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_member: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury_for_reward: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_member: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_token_account: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct PayFromVaultSol<'info> {
+    #[account(seeds = [CONFIG_SEED], bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PayFromVaultSol<'info> {
+    fn cpi_context(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> CpiContext<'_, '_, '_, 'info, buddy_link_cpi::TransferRewardUncheckedMultipleSol<'info>>
+    {
+        CpiContext::new(
+            self.buddy_link_program.to_account_info(),
+            buddy_link_cpi::TransferRewardUncheckedMultipleSol {
+                buddy_link_program: self.buddy_link_program.to_account_info(),
+                authority: self.vault.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PayFromVaultSpl<'info> {
+    #[account(seeds = [CONFIG_SEED], bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    /// CHECK: PDA owning the token vault.
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: AccountInfo<'info>,
+    /// CHECK: Token account of the vault, checked by the token program.
+    #[account(mut)]
+    pub vault_token_account: AccountInfo<'info>,
+    /// CHECK: Mint of the reward.
+    pub mint: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> PayFromVaultSpl<'info> {
+    fn cpi_context(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> CpiContext<'_, '_, '_, 'info, buddy_link_cpi::TransferRewardUncheckedMultipleSpl<'info>>
+    {
+        CpiContext::new(
+            self.buddy_link_program.to_account_info(),
+            buddy_link_cpi::TransferRewardUncheckedMultipleSpl {
+                buddy_link_program: self.buddy_link_program.to_account_info(),
+                authority: self.vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                from_token_account: self.vault_token_account.to_account_info(),
+            },
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PayGlobalOnlySol<'info> {
+    #[account(seeds = [CONFIG_SEED], bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury_for_reward: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PayGlobalOnlySpl<'info> {
+    #[account(seeds = [CONFIG_SEED], bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    /// CHECK: PDA owning the token vault.
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: AccountInfo<'info>,
+    /// CHECK: Token account of the vault, checked by the token program.
+    #[account(mut)]
+    pub vault_token_account: AccountInfo<'info>,
+    /// CHECK: Mint of the reward.
+    pub mint: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_token_account: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury_for_reward: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PaySecureLocal<'info> {
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Token account of the user, checked by the token program.
+    #[account(mut)]
+    pub user_token_account: AccountInfo<'info>,
+    /// CHECK: Mint of the reward.
+    pub mint: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_token_account: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_member: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury_for_reward: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_buddy: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_member: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateReferral<'info> {
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_treasury: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_member: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referrer_member: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    pub referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    pub referrer_treasury_for_reward: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    pub referrer_token_account: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    pub mint: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct PayReferral<'info> {
+    #[account(seeds = [CONFIG_SEED], bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+    /// CHECK: PDA sending the SOL and owning the token vault.
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub vault: AccountInfo<'info>,
+    /// CHECK: System program for SOL rewards.
+    pub system_program: Option<AccountInfo<'info>>,
+    /// CHECK: Mint of SPL rewards.
+    pub mint: Option<AccountInfo<'info>>,
+    /// CHECK: Token program of SPL rewards.
+    pub token_program: Option<AccountInfo<'info>>,
+    /// CHECK: Token account of the vault for SPL rewards.
+    #[account(mut)]
+    pub vault_token_account: Option<AccountInfo<'info>>,
+
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    pub referee_buddy: AccountInfo<'info>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referee_member: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_member: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_treasury_for_reward: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub referrer_token_account: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_treasury_for_reward: Option<AccountInfo<'info>>,
+    /// CHECK: Checked by BuddyLink.
+    #[account(mut)]
+    pub global_referrer_token_account: Option<AccountInfo<'info>>,
+}
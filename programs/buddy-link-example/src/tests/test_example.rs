@@ -0,0 +1,922 @@
+use anchor_lang::error::{ErrorCode, ERROR_CODE_OFFSET};
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use buddy_link::client::AccountSnapshot;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::state::Buddy;
+use buddy_link_example::{accounts, instruction, Config, ExampleError, CONFIG_SEED, VAULT_SEED};
+use solana_program::account_info::AccountInfo;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+//Same fixtures as the amman validator (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+const VAULT_LAMPORTS: u64 = 1_000_000_000;
+const VAULT_TOKENS: u64 = 1_000;
+
+/// Anchor ties the accounts to the lifetime of their infos, program-test only lends the slice for the call.
+/// The infos are rebuilt on borrows of the lent ones (a duplicated account shares its info), and a data resized by the
+/// program is resized on the lent info too, program-test reading the data back from it.
+fn example_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let mut unique: Vec<&AccountInfo> = Vec::new();
+    for account in accounts {
+        if !unique.iter().any(|x| x.key == account.key) {
+            unique.push(account);
+        }
+    }
+
+    let data_lens: Vec<usize> = {
+        let mut lamports: Vec<_> = unique.iter().map(|x| x.lamports.borrow_mut()).collect();
+        let mut account_data: Vec<_> = unique.iter().map(|x| x.data.borrow_mut()).collect();
+        let rebuilt: Vec<AccountInfo> = unique
+            .iter()
+            .zip(&mut lamports)
+            .zip(&mut account_data)
+            .map(|((account, lamports), account_data)| {
+                AccountInfo::new(
+                    account.key,
+                    account.is_signer,
+                    account.is_writable,
+                    lamports,
+                    account_data,
+                    account.owner,
+                    account.executable,
+                    account.rent_epoch,
+                )
+            })
+            .collect();
+        let accounts: Vec<AccountInfo> = accounts
+            .iter()
+            .map(|account| {
+                let index = unique.iter().position(|x| x.key == account.key).unwrap();
+                rebuilt[index].clone()
+            })
+            .collect();
+
+        buddy_link_example::entry(program_id, &accounts, data)?;
+        rebuilt.iter().map(AccountInfo::data_len).collect()
+    };
+
+    for (account, data_len) in unique.into_iter().zip(data_lens) {
+        if account.data_len() != data_len {
+            account.realloc(data_len, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Accounts of the amman fixtures, at the root of the workspace.
+fn snapshot() -> AccountSnapshot {
+    AccountSnapshot::load_dir(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../.amman/accounts"
+    ))
+    .unwrap()
+}
+
+fn vault() -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_SEED], &buddy_link_example::ID).0
+}
+
+fn config() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], &buddy_link_example::ID).0
+}
+
+struct Setup {
+    program_test: ProgramTest,
+    vault_token_account: Pubkey,
+    admin: Keypair,
+}
+
+/// The example program against the deployed BuddyLink program and its fixtures, with a funded vault and an admin.
+fn setup() -> Setup {
+    setup_with(false)
+}
+
+/// Same as [`setup`], with the mock instead of the deployed program.
+fn setup_with_mock() -> Setup {
+    setup_with(true)
+}
+
+fn setup_with(mock: bool) -> Setup {
+    let mut program_test = ProgramTest::default();
+    // The example is native
+    program_test.prefer_bpf(false);
+    program_test.add_program(
+        "buddy_link_example",
+        buddy_link_example::ID,
+        processor!(example_processor),
+    );
+    if mock {
+        program_test.add_program(
+            "buddy_link_mock",
+            BL_PROGRAM_ID,
+            processor!(buddy_link_mock::process_instruction),
+        );
+    }
+    for (pubkey, account) in snapshot().program_test_accounts() {
+        if !(mock && pubkey == BL_PROGRAM_ID) {
+            program_test.add_account(pubkey, account);
+        }
+    }
+
+    program_test.add_account(
+        vault(),
+        Account {
+            lamports: VAULT_LAMPORTS,
+            owner: system_program::ID,
+            ..Account::default()
+        },
+    );
+    let vault_token_account = add_token_account(&mut program_test, &vault(), VAULT_TOKENS);
+
+    let admin = Keypair::new();
+    let mut data = Vec::new();
+    Config {
+        admin: admin.pubkey(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    program_test.add_account(
+        config(),
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: buddy_link_example::ID,
+            ..Account::default()
+        },
+    );
+
+    Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    }
+}
+
+fn add_user(program_test: &mut ProgramTest) -> Keypair {
+    let user = Keypair::new();
+    program_test.add_account(
+        user.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            owner: system_program::ID,
+            ..Account::default()
+        },
+    );
+
+    user
+}
+
+/// The referee buddy of the fixtures, owned by the user.
+fn add_referee_buddy(program_test: &mut ProgramTest, authority: &Pubkey) {
+    let mut buddy = snapshot().accounts()[&REFEREE_GLOBAL_BUDDY].clone();
+    let offset = Buddy::<&[u8]>::AUTHORITY_OFFSET;
+    buddy.data[offset..offset + 32].copy_from_slice(authority.as_ref());
+
+    program_test.add_account(REFEREE_GLOBAL_BUDDY, buddy);
+}
+
+fn add_token_account(program_test: &mut ProgramTest, owner: &Pubkey, amount: u64) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: MINT,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    address
+}
+
+fn example_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: buddy_link_example::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn with_remaining_accounts(mut instruction: Instruction, remaining: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(
+        remaining
+            .iter()
+            .map(|address| AccountMeta::new(*address, false)),
+    );
+
+    instruction
+}
+
+async fn execute(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn error_code(result: Result<(), BanksClientError>) -> u32 {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        error => panic!("unexpected error {error:?}"),
+    }
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+fn pay_from_vault_sol(admin: &Keypair) -> accounts::PayFromVaultSol {
+    accounts::PayFromVaultSol {
+        config: config(),
+        admin: admin.pubkey(),
+        buddy_link_program: BL_PROGRAM_ID,
+        vault: vault(),
+        system_program: system_program::ID,
+    }
+}
+
+fn pay_from_vault_spl(admin: &Keypair, vault_token_account: Pubkey) -> accounts::PayFromVaultSpl {
+    accounts::PayFromVaultSpl {
+        config: config(),
+        admin: admin.pubkey(),
+        buddy_link_program: BL_PROGRAM_ID,
+        vault: vault(),
+        vault_token_account,
+        mint: MINT,
+        token_program: spl_token::ID,
+    }
+}
+
+#[tokio::test]
+async fn test_initialize() {
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(false);
+    program_test.add_program(
+        "buddy_link_example",
+        buddy_link_example::ID,
+        processor!(example_processor),
+    );
+    let upgrade_authority = add_user(&mut program_test);
+    let stranger = add_user(&mut program_test);
+    let program_data = Pubkey::find_program_address(
+        &[buddy_link_example::ID.as_ref()],
+        &bpf_loader_upgradeable::ID,
+    )
+    .0;
+    program_test.add_account(
+        program_data,
+        Account::new_data(
+            1_000_000_000,
+            &UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority.pubkey()),
+            },
+            &bpf_loader_upgradeable::ID,
+        )
+        .unwrap(),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let admin = Pubkey::new_unique();
+    let initialize = |upgrade_authority: Pubkey| {
+        example_instruction(
+            accounts::Initialize {
+                upgrade_authority,
+                config: config(),
+                program_data,
+                system_program: system_program::ID,
+            },
+            instruction::Initialize { admin },
+        )
+    };
+
+    //Only the upgrade authority picks the admin
+    let result = execute(&mut context, initialize(stranger.pubkey()), &[&stranger]).await;
+    assert_eq!(
+        error_code(result),
+        ERROR_CODE_OFFSET + ExampleError::NotUpgradeAuthority as u32
+    );
+
+    let result = execute(
+        &mut context,
+        initialize(upgrade_authority.pubkey()),
+        &[&upgrade_authority],
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let account = context
+        .banks_client
+        .get_account(config())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut data: &[u8] = &account.data;
+    assert_eq!(Config::try_deserialize(&mut data).unwrap().admin, admin);
+}
+
+#[tokio::test]
+async fn test_payout_requires_admin() {
+    let Setup {
+        mut program_test,
+        vault_token_account,
+        ..
+    } = setup();
+    let stranger = add_user(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let sol = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_sol(&stranger),
+            instruction::PaySharedSol {
+                total_amount: 10_000,
+                shares_in_bps: vec![10_000],
+                members_included: false,
+            },
+        ),
+        &[REFERRER_TREASURY],
+    );
+    let spl = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_spl(&stranger, vault_token_account),
+            instruction::PaySharedSpl {
+                total_amount: 100,
+                shares_in_bps: vec![10_000],
+                members_included: true,
+            },
+        ),
+        &[REFERRER_ATA, REFERRER_MEMBER],
+    );
+
+    //Signed, but not by the admin of the config
+    for instruction in [sol, spl] {
+        let result = execute(&mut context, instruction, &[&stranger]).await;
+        assert_eq!(error_code(result), ErrorCode::ConstraintHasOne as u32);
+    }
+    assert_eq!(lamports(&mut context, vault()).await, VAULT_LAMPORTS);
+    assert_eq!(
+        token_amount(&mut context, vault_token_account).await,
+        VAULT_TOKENS
+    );
+}
+
+#[tokio::test]
+async fn test_swap() {
+    let Setup {
+        mut program_test,
+        vault_token_account,
+        ..
+    } = setup();
+    let user = add_user(&mut program_test);
+    let user_token_account = add_token_account(&mut program_test, &user.pubkey(), 1_000);
+    let mut context = program_test.start_with_context().await;
+
+    let swap = |referral_fee_bps: u16| {
+        example_instruction(
+            accounts::Swap {
+                user: user.pubkey(),
+                user_token_account,
+                vault: vault(),
+                vault_token_account,
+                mint: MINT,
+                token_program: spl_token::ID,
+                buddy_link_program: BL_PROGRAM_ID,
+                referrer_token_account: REFERRER_ATA,
+                referrer_member: Some(REFERRER_MEMBER),
+                referrer_treasury: REFERRER_TREASURY,
+                referrer_treasury_for_reward: REFERRER_TREASURY,
+                referee_member: REFEREE_MEMBER,
+                global_referrer_treasury: Some(REFERRER_TREASURY),
+                global_referrer_token_account: Some(REFERRER_ATA),
+            },
+            instruction::Swap {
+                amount_in: 400,
+                referral_fee_bps,
+            },
+        )
+    };
+
+    let before = token_amount(&mut context, REFERRER_ATA).await;
+    let result = execute(&mut context, swap(250), &[&user]).await;
+    assert!(result.is_ok());
+
+    // 2.5% of the swapped amount, paid by the vault
+    assert_eq!(token_amount(&mut context, user_token_account).await, 600);
+    assert_eq!(
+        token_amount(&mut context, vault_token_account).await,
+        VAULT_TOKENS + 400 - 10
+    );
+    assert_eq!(token_amount(&mut context, REFERRER_ATA).await, before + 10);
+
+    let result = execute(&mut context, swap(10_001), &[&user]).await;
+    assert_eq!(
+        error_code(result),
+        ERROR_CODE_OFFSET + ExampleError::InvalidReferralFee as u32
+    );
+}
+
+#[tokio::test]
+async fn test_pay_shared() {
+    let Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    } = setup();
+    let mut context = program_test.start_with_context().await;
+
+    let before = [
+        lamports(&mut context, REFERRER_TREASURY).await,
+        lamports(&mut context, REFEREE_TREASURY).await,
+        token_amount(&mut context, REFERRER_ATA).await,
+    ];
+
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_sol(&admin),
+            instruction::PaySharedSol {
+                total_amount: 10_000,
+                shares_in_bps: vec![7_500, 2_500],
+                members_included: false,
+            },
+        ),
+        &[REFERRER_TREASURY, REFEREE_TREASURY],
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 7_500
+    );
+    assert_eq!(
+        lamports(&mut context, REFEREE_TREASURY).await,
+        before[1] + 2_500
+    );
+    assert_eq!(
+        lamports(&mut context, vault()).await,
+        VAULT_LAMPORTS - 10_000
+    );
+
+    // Same through the native wrapper
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_sol(&admin),
+            instruction::PaySharedSolNative {
+                total_amount: 10_000,
+                shares_in_bps: vec![7_500, 2_500],
+                members_included: false,
+            },
+        ),
+        &[REFERRER_TREASURY, REFEREE_TREASURY],
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 15_000
+    );
+    assert_eq!(
+        lamports(&mut context, REFEREE_TREASURY).await,
+        before[1] + 5_000
+    );
+    assert_eq!(
+        lamports(&mut context, vault()).await,
+        VAULT_LAMPORTS - 20_000
+    );
+
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_spl(&admin, vault_token_account),
+            instruction::PaySharedSpl {
+                total_amount: 100,
+                shares_in_bps: vec![10_000],
+                members_included: true,
+            },
+        ),
+        &[REFERRER_ATA, REFERRER_MEMBER],
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+
+    assert_eq!(
+        token_amount(&mut context, REFERRER_ATA).await,
+        before[2] + 100
+    );
+    assert_eq!(
+        token_amount(&mut context, vault_token_account).await,
+        VAULT_TOKENS - 100
+    );
+}
+
+#[tokio::test]
+async fn test_pay_multi_tier() {
+    let Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    } = setup();
+    let mut context = program_test.start_with_context().await;
+
+    let before = [
+        lamports(&mut context, REFERRER_TREASURY).await,
+        lamports(&mut context, REFEREE_TREASURY).await,
+        token_amount(&mut context, REFERRER_ATA).await,
+    ];
+
+    // One referrer, the second and third tiers go to the fallback (last remaining account)
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_sol(&admin),
+            instruction::PayMultiTierSol {
+                total_amount: 10_000,
+                schedule: vec![5_000, 3_000, 2_000],
+                members_included: false,
+                fallback: Some(REFEREE_TREASURY),
+                fallback_member: None,
+            },
+        ),
+        &[REFERRER_TREASURY, REFEREE_TREASURY],
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 5_000
+    );
+    assert_eq!(
+        lamports(&mut context, REFEREE_TREASURY).await,
+        before[1] + 5_000
+    );
+
+    // Redistributed to the only referrer
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_spl(&admin, vault_token_account),
+            instruction::PayMultiTierSpl {
+                total_amount: 100,
+                schedule: vec![5_000, 3_000, 2_000],
+                members_included: false,
+                fallback: None,
+                fallback_member: None,
+            },
+        ),
+        &[REFERRER_ATA],
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+
+    assert_eq!(
+        token_amount(&mut context, REFERRER_ATA).await,
+        before[2] + 100
+    );
+
+    // The fallback isn't the last remaining account
+    let instruction = with_remaining_accounts(
+        example_instruction(
+            pay_from_vault_sol(&admin),
+            instruction::PayMultiTierSol {
+                total_amount: 10_000,
+                schedule: vec![5_000, 5_000],
+                members_included: false,
+                fallback: Some(REFEREE_TREASURY),
+                fallback_member: None,
+            },
+        ),
+        &[REFEREE_TREASURY, REFERRER_TREASURY],
+    );
+    let result = execute(&mut context, instruction, &[&admin]).await;
+    assert!(matches!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+    ));
+}
+
+#[tokio::test]
+async fn test_pay_global_only() {
+    let Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    } = setup();
+    let mut context = program_test.start_with_context().await;
+
+    // Only the account checks are run: the deployed program doesn't complete the global only transfers of the
+    // fixtures (it panics on its SOL branch and rejects the token accounts on the SPL one), the transfers are run
+    // against the mock in test_pay_global_only_with_mock
+    let sol = example_instruction(
+        accounts::PayGlobalOnlySol {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            system_program: system_program::ID,
+            global_referrer_treasury: REFEREE_MEMBER,
+            global_referrer_treasury_for_reward: REFERRER_TREASURY,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+        },
+        instruction::PayGlobalOnlySol { amount: 1_000 },
+    );
+    let spl = example_instruction(
+        accounts::PayGlobalOnlySpl {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            vault_token_account,
+            mint: MINT,
+            token_program: spl_token::ID,
+            referrer_token_account: REFERRER_ATA,
+            global_referrer_treasury: REFEREE_MEMBER,
+            global_referrer_treasury_for_reward: REFERRER_TREASURY,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+        },
+        instruction::PayGlobalOnlySpl { amount: 10 },
+    );
+
+    //Not a treasury
+    for instruction in [sol, spl] {
+        let result = execute(&mut context, instruction, &[&admin]).await;
+        assert_eq!(
+            error_code(result),
+            ErrorCode::AccountDiscriminatorMismatch as u32
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_pay_global_only_with_mock() {
+    let Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    } = setup_with_mock();
+    let mut context = program_test.start_with_context().await;
+
+    let before = [
+        lamports(&mut context, REFERRER_TREASURY).await,
+        token_amount(&mut context, REFERRER_ATA).await,
+    ];
+
+    // The global referrer of the referee buddy
+    let instruction = example_instruction(
+        accounts::PayGlobalOnlySol {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            system_program: system_program::ID,
+            global_referrer_treasury: REFERRER_TREASURY,
+            global_referrer_treasury_for_reward: REFERRER_TREASURY,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+        },
+        instruction::PayGlobalOnlySol { amount: 1_000 },
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 1_000
+    );
+    assert_eq!(
+        lamports(&mut context, vault()).await,
+        VAULT_LAMPORTS - 1_000
+    );
+
+    let instruction = example_instruction(
+        accounts::PayGlobalOnlySpl {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            vault_token_account,
+            mint: MINT,
+            token_program: spl_token::ID,
+            referrer_token_account: REFERRER_ATA,
+            global_referrer_treasury: REFERRER_TREASURY,
+            global_referrer_treasury_for_reward: REFERRER_TREASURY,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+        },
+        instruction::PayGlobalOnlySpl { amount: 10 },
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+    assert_eq!(
+        token_amount(&mut context, REFERRER_ATA).await,
+        before[1] + 10
+    );
+    assert_eq!(
+        token_amount(&mut context, vault_token_account).await,
+        VAULT_TOKENS - 10
+    );
+}
+
+#[tokio::test]
+async fn test_pay_secure_local_and_validate_referral() {
+    let Setup {
+        mut program_test, ..
+    } = setup();
+    let user = add_user(&mut program_test);
+    add_referee_buddy(&mut program_test, &user.pubkey());
+    let user_token_account = add_token_account(&mut program_test, &user.pubkey(), 100);
+    let stranger = add_user(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let validate_referral = |user: Pubkey| {
+        example_instruction(
+            accounts::ValidateReferral {
+                buddy_link_program: BL_PROGRAM_ID,
+                user,
+                referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+                referee_buddy: REFEREE_GLOBAL_BUDDY,
+                referee_treasury: REFEREE_TREASURY,
+                referee_member: REFEREE_MEMBER,
+                referrer_member: Some(REFERRER_MEMBER),
+                referrer_treasury: Some(REFERRER_TREASURY),
+                referrer_treasury_for_reward: Some(REFERRER_TREASURY),
+                referrer_token_account: Some(REFERRER_ATA),
+                mint: Some(MINT),
+            },
+            instruction::ValidateReferral {},
+        )
+    };
+    assert!(
+        execute(&mut context, validate_referral(user.pubkey()), &[&user])
+            .await
+            .is_ok()
+    );
+
+    //The stranger doesn't own the buddy profile
+    let result = execute(
+        &mut context,
+        validate_referral(stranger.pubkey()),
+        &[&stranger],
+    )
+    .await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::CantCreateMemberWithReferrer.code()
+    );
+
+    let before = token_amount(&mut context, REFERRER_ATA).await;
+    let instruction = example_instruction(
+        accounts::PaySecureLocal {
+            buddy_link_program: BL_PROGRAM_ID,
+            user: user.pubkey(),
+            user_token_account,
+            mint: MINT,
+            token_program: spl_token::ID,
+            referrer_token_account: REFERRER_ATA,
+            referrer_member: REFERRER_MEMBER,
+            referrer_treasury: REFERRER_TREASURY,
+            referrer_treasury_for_reward: REFERRER_TREASURY,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+            referee_treasury: REFEREE_TREASURY,
+            referee_member: REFEREE_MEMBER,
+        },
+        instruction::PaySecureLocal { amount: 10 },
+    );
+    assert!(execute(&mut context, instruction, &[&user]).await.is_ok());
+    assert_eq!(token_amount(&mut context, REFERRER_ATA).await, before + 10);
+    assert_eq!(token_amount(&mut context, user_token_account).await, 90);
+}
+
+#[tokio::test]
+async fn test_pay_referral() {
+    let Setup {
+        program_test,
+        vault_token_account,
+        admin,
+    } = setup();
+    let mut context = program_test.start_with_context().await;
+
+    let before = [
+        lamports(&mut context, REFERRER_TREASURY).await,
+        token_amount(&mut context, REFERRER_ATA).await,
+    ];
+
+    // The referrer within the organization, in SOL (the global only transfer isn't completed by the deployed
+    // program with the fixtures, see test_pay_global_only)
+    let instruction = example_instruction(
+        accounts::PayReferral {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            system_program: Some(system_program::ID),
+            mint: None,
+            token_program: None,
+            vault_token_account: None,
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+            referee_treasury: Some(REFEREE_TREASURY),
+            referee_member: Some(REFEREE_MEMBER),
+            referrer_member: Some(REFERRER_MEMBER),
+            referrer_treasury: Some(REFERRER_TREASURY),
+            referrer_treasury_for_reward: Some(REFERRER_TREASURY),
+            referrer_token_account: None,
+            global_referrer_treasury: Some(REFERRER_TREASURY),
+            global_referrer_treasury_for_reward: Some(REFERRER_TREASURY),
+            global_referrer_token_account: None,
+        },
+        instruction::PayReferral { amount: 1_000 },
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 1_000
+    );
+
+    // The referrer within the organization, in tokens
+    let instruction = example_instruction(
+        accounts::PayReferral {
+            config: config(),
+            admin: admin.pubkey(),
+            buddy_link_program: BL_PROGRAM_ID,
+            vault: vault(),
+            system_program: None,
+            mint: Some(MINT),
+            token_program: Some(spl_token::ID),
+            vault_token_account: Some(vault_token_account),
+            referee_buddy_profile: REFEREE_GLOBAL_BUDDY,
+            referee_buddy: REFEREE_GLOBAL_BUDDY,
+            referee_treasury: Some(REFEREE_TREASURY),
+            referee_member: Some(REFEREE_MEMBER),
+            referrer_member: Some(REFERRER_MEMBER),
+            referrer_treasury: Some(REFERRER_TREASURY),
+            referrer_treasury_for_reward: Some(REFERRER_TREASURY),
+            referrer_token_account: Some(REFERRER_ATA),
+            global_referrer_treasury: Some(REFERRER_TREASURY),
+            global_referrer_treasury_for_reward: Some(REFERRER_TREASURY),
+            global_referrer_token_account: Some(REFERRER_ATA),
+        },
+        instruction::PayReferral { amount: 10 },
+    );
+    assert!(execute(&mut context, instruction, &[&admin]).await.is_ok());
+    assert_eq!(
+        token_amount(&mut context, REFERRER_ATA).await,
+        before[1] + 10
+    );
+    assert_eq!(
+        token_amount(&mut context, vault_token_account).await,
+        VAULT_TOKENS - 10
+    );
+}
@@ -0,0 +1,114 @@
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::state::{Buddy, Member, Treasury};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+
+/// Optional accounts are replaced by the BuddyLink program.
+pub fn optional<'a, 'info>(account: &'a AccountInfo<'info>) -> Option<&'a AccountInfo<'info>> {
+    if account.key == &BL_PROGRAM_ID {
+        return None;
+    }
+
+    Some(account)
+}
+
+/// The authority owns the referee profile, the paid buddy belongs to the same authority,
+/// the referee treasury is owned by the paid buddy and the referee member by the referee treasury.
+pub fn check_referee(
+    authority: &AccountInfo,
+    referee_buddy_profile: &AccountInfo,
+    referee_buddy: &AccountInfo,
+    referee_treasury: &AccountInfo,
+    referee_member: &AccountInfo,
+) -> ProgramResult {
+    let profile = Buddy::from_account_info(referee_buddy_profile)?;
+    if &profile.authority() != authority.key {
+        return Err(BuddyLinkError::CantCreateMemberWithReferrer.into());
+    }
+
+    let buddy = Buddy::from_account_info(referee_buddy)?;
+    if buddy.authority() != profile.authority() {
+        return Err(BuddyLinkError::InvalidAuthorityProvided.into());
+    }
+
+    if !Treasury::from_account_info(referee_treasury)?.is_owned_by(referee_buddy.key) {
+        return Err(BuddyLinkError::TreasuryNotOwnedByBuddy.into());
+    }
+
+    if &Member::from_account_info(referee_member)?.owner_treasury() != referee_treasury.key {
+        return Err(BuddyLinkError::OwnersMismatched.into());
+    }
+
+    Ok(())
+}
+
+/// The referee member was referred by the referrer treasury, which owns the referrer member (if given),
+/// and the treasury for reward has the same owners and holds the mint (if given).
+pub fn check_referrer(
+    referee_member: &AccountInfo,
+    referrer_member: Option<&AccountInfo>,
+    referrer_treasury: &AccountInfo,
+    referrer_treasury_for_reward: &AccountInfo,
+    mint: Option<&Pubkey>,
+) -> ProgramResult {
+    if Member::from_account_info(referee_member)?.referrer_treasury()
+        != Some(*referrer_treasury.key)
+    {
+        return Err(BuddyLinkError::InvalidReferrerTreasury.into());
+    }
+
+    if let Some(referrer_member) = referrer_member {
+        if &Member::from_account_info(referrer_member)?.owner_treasury() != referrer_treasury.key {
+            return Err(BuddyLinkError::InvalidMemberForReferrer.into());
+        }
+    }
+
+    check_treasury_for_reward(referrer_treasury, referrer_treasury_for_reward, mint)
+}
+
+/// The treasury for reward has the same owners as the treasury and holds the mint (if given).
+pub fn check_treasury_for_reward(
+    treasury: &AccountInfo,
+    treasury_for_reward: &AccountInfo,
+    mint: Option<&Pubkey>,
+) -> ProgramResult {
+    let reward_treasury = Treasury::from_account_info(treasury_for_reward)?;
+
+    if treasury.key != treasury_for_reward.key {
+        let treasury = Treasury::from_account_info(treasury)?;
+        if !treasury.owners().eq(reward_treasury.owners()) {
+            return Err(BuddyLinkError::OwnersMismatched.into());
+        }
+    }
+
+    if let Some(mint) = mint {
+        if &reward_treasury.mint() != mint {
+            return Err(BuddyLinkError::InvalidMint.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// The token account is owned by the treasury and holds the mint.
+pub fn check_token_account(
+    token_account: &AccountInfo,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> ProgramResult {
+    let account = spl_token::state::Account::unpack(&token_account.try_borrow_data()?)
+        .map_err(|_| BuddyLinkError::MissingTokenAccountForMint)?;
+
+    if &account.owner != owner {
+        return Err(BuddyLinkError::InvalidTokenAccountOwner.into());
+    }
+
+    if &account.mint != mint {
+        return Err(BuddyLinkError::InvalidMint.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! Open mock of the BuddyLink program, for tests only.
+//!
+//! Implements the instructions targeted by the `buddy-link` crate with the same discriminators,
+//! account order and error codes, so integrators can run them in program-test or a local validator,
+//! step through the referral checks and inject faults.
+//!
+//! Only the checks documented by the crate are done, the analytics of the real program aren't kept.
+
+mod checks;
+mod processor;
+
+pub use processor::{process_instruction, process_instruction_with_faults, Faults};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
@@ -0,0 +1,367 @@
+use crate::checks::{
+    check_referee, check_referrer, check_token_account, check_treasury_for_reward, optional,
+};
+use anchor_lang::error::ErrorCode;
+use borsh::BorshDeserialize;
+use buddy_link::constants::{
+    BL_PROGRAM_ID, TRANSFER_REWARD_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SPL_DISCRIMINATOR,
+    TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR, VALIDATE_REFERRER_DISCRIMINATOR,
+};
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    bps_amount, global_referrer_share_bps, shared_reward_amounts, GeneralTransferRewardArgs,
+    TransferUncheckedLocalSharedRewardArgs,
+};
+use buddy_link::state::{Buddy, Member};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::invoke;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_instruction, system_program};
+
+/// Faults injected in the mock.
+///
+/// Pass them through a wrapper of [`process_instruction_with_faults`] given to program-test, for example:
+/// `fn processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult`
+/// calling it with the faults of the test.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Faults {
+    /// Error returned by every instruction, before anything is checked.
+    pub error: Option<BuddyLinkError>,
+    /// Accepts any referee, referrer and treasury (token accounts are still checked).
+    pub skip_referral_checks: bool,
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    process_instruction_with_faults(program_id, accounts, instruction_data, &Faults::default())
+}
+
+pub fn process_instruction_with_faults(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+    faults: &Faults,
+) -> ProgramResult {
+    if instruction_data.len() < 8 {
+        return Err(anchor_error(ErrorCode::InstructionMissing));
+    }
+
+    if let Some(error) = faults.error {
+        return Err(error.into());
+    }
+
+    let (discriminator, args) = instruction_data.split_at(8);
+
+    match <[u8; 8]>::try_from(discriminator).unwrap() {
+        VALIDATE_REFERRER_DISCRIMINATOR => validate_referrer(accounts, faults),
+        TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR => {
+            transfer_reward_unchecked_multiple(accounts, &deserialize(args)?)
+        }
+        TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR => {
+            transfer_reward_secure_no_global(accounts, &deserialize(args)?, faults)
+        }
+        TRANSFER_REWARD_SPL_DISCRIMINATOR => {
+            transfer_reward_spl(accounts, &deserialize(args)?, faults)
+        }
+        TRANSFER_REWARD_GLOBAL_DISCRIMINATOR => {
+            transfer_reward_global(accounts, &deserialize(args)?, faults)
+        }
+        _ => Err(anchor_error(ErrorCode::InstructionFallbackNotFound)),
+    }
+}
+
+fn anchor_error(error: ErrorCode) -> ProgramError {
+    ProgramError::Custom(error as u32)
+}
+
+fn deserialize<T: BorshDeserialize>(args: &[u8]) -> Result<T, ProgramError> {
+    T::try_from_slice(args).map_err(|_| anchor_error(ErrorCode::InstructionDidNotDeserialize))
+}
+
+fn accounts_array<'a, 'info, const N: usize>(
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<&'a [AccountInfo<'info>; N], ProgramError> {
+    accounts
+        .get(..N)
+        .and_then(|x| x.try_into().ok())
+        .ok_or(anchor_error(ErrorCode::AccountNotEnoughKeys))
+}
+
+fn check_signer(authority: &AccountInfo) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(anchor_error(ErrorCode::AccountNotSigner));
+    }
+
+    Ok(())
+}
+
+fn transfer_sol<'info>(
+    authority: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &system_instruction::transfer(authority.key, to.key, amount),
+        &[authority.clone(), to.clone(), system_program.clone()],
+    )
+}
+
+fn transfer_spl<'info>(
+    authority: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            from.key,
+            to.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[
+            from.clone(),
+            to.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+    )
+}
+
+fn validate_referrer(accounts: &[AccountInfo], faults: &Faults) -> ProgramResult {
+    let [payer, authority, referee_buddy_profile, referee_buddy, referee_treasury, referee_member, referrer_member, referrer_treasury, referrer_treasury_for_reward, mint, referrer_token_account] =
+        accounts_array(accounts)?;
+
+    check_signer(payer)?;
+
+    if faults.skip_referral_checks {
+        return Ok(());
+    }
+
+    check_referee(
+        authority,
+        referee_buddy_profile,
+        referee_buddy,
+        referee_treasury,
+        referee_member,
+    )?;
+
+    if let (Some(referrer_treasury), Some(referrer_treasury_for_reward)) = (
+        optional(referrer_treasury),
+        optional(referrer_treasury_for_reward),
+    ) {
+        let mint = optional(mint).map(|x| x.key);
+
+        check_referrer(
+            referee_member,
+            optional(referrer_member),
+            referrer_treasury,
+            referrer_treasury_for_reward,
+            mint,
+        )?;
+
+        if let (Some(mint), Some(referrer_token_account)) = (mint, optional(referrer_token_account))
+        {
+            check_token_account(
+                referrer_token_account,
+                referrer_treasury_for_reward.key,
+                mint,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn transfer_reward_unchecked_multiple(
+    accounts: &[AccountInfo],
+    args: &TransferUncheckedLocalSharedRewardArgs,
+) -> ProgramResult {
+    let [authority, system_program, mint, token_program, from] = accounts_array(accounts)?;
+    let remaining_accounts = &accounts[5..];
+
+    check_signer(authority)?;
+
+    // Recipients are followed by their member when members are included
+    let recipients: Vec<&AccountInfo> = if args.members_included {
+        remaining_accounts.iter().step_by(2).collect()
+    } else {
+        remaining_accounts.iter().collect()
+    };
+
+    if args.shares_in_bps.len() != recipients.len() {
+        return Err(BuddyLinkError::InvalidNumberOfSharesSpecified.into());
+    }
+
+    let amounts = shared_reward_amounts(args.total_amount, &args.shares_in_bps)?;
+
+    for (recipient, amount) in recipients.into_iter().zip(amounts) {
+        if system_program.key == &system_program::ID {
+            transfer_sol(authority, recipient, system_program, amount)?;
+        } else if mint.key != &BL_PROGRAM_ID {
+            transfer_spl(authority, from, recipient, token_program, amount)?;
+        } else {
+            return Err(BuddyLinkError::InvalidTokenSpecified.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn transfer_reward_secure_no_global(
+    accounts: &[AccountInfo],
+    args: &GeneralTransferRewardArgs,
+    faults: &Faults,
+) -> ProgramResult {
+    let [authority, mint, token_program, from, referrer_member, referrer_treasury, referrer_treasury_for_reward, referee_buddy_profile, referee_buddy, referee_treasury, referee_member, referrer_token_account] =
+        accounts_array(accounts)?;
+
+    check_signer(authority)?;
+
+    if !faults.skip_referral_checks {
+        check_referee(
+            authority,
+            referee_buddy_profile,
+            referee_buddy,
+            referee_treasury,
+            referee_member,
+        )?;
+        check_referrer(
+            referee_member,
+            Some(referrer_member),
+            referrer_treasury,
+            referrer_treasury_for_reward,
+            Some(mint.key),
+        )?;
+    }
+
+    check_token_account(
+        referrer_token_account,
+        referrer_treasury_for_reward.key,
+        mint.key,
+    )?;
+
+    transfer_spl(
+        authority,
+        from,
+        referrer_token_account,
+        token_program,
+        args.amount,
+    )
+}
+
+fn transfer_reward_spl(
+    accounts: &[AccountInfo],
+    args: &GeneralTransferRewardArgs,
+    faults: &Faults,
+) -> ProgramResult {
+    let [authority, global_referrer_treasury, global_referrer_token_account, referrer_member, referrer_treasury, referrer_treasury_for_reward, referee_member, mint, token_program, from, referrer_token_account] =
+        accounts_array(accounts)?;
+
+    check_signer(authority)?;
+
+    if !faults.skip_referral_checks {
+        check_referrer(
+            referee_member,
+            optional(referrer_member),
+            referrer_treasury,
+            referrer_treasury_for_reward,
+            Some(mint.key),
+        )?;
+    }
+
+    check_token_account(
+        referrer_token_account,
+        referrer_treasury_for_reward.key,
+        mint.key,
+    )?;
+
+    let mut local_amount = args.amount;
+
+    // The global referrer is paid its share first, the referrer gets the rest
+    match (
+        optional(global_referrer_treasury),
+        optional(global_referrer_token_account),
+    ) {
+        (Some(global_referrer_treasury), Some(global_referrer_token_account)) => {
+            check_token_account(
+                global_referrer_token_account,
+                global_referrer_treasury.key,
+                mint.key,
+            )?;
+
+            let share_in_bps =
+                global_referrer_share_bps(&Member::from_account_info(referee_member)?)?;
+            let global_amount = bps_amount(args.amount, share_in_bps)?;
+            transfer_spl(
+                authority,
+                from,
+                global_referrer_token_account,
+                token_program,
+                global_amount,
+            )?;
+            local_amount -= global_amount;
+        }
+        (None, None) => {}
+        _ => return Err(BuddyLinkError::MissingGlobalReferrerAccount.into()),
+    }
+
+    transfer_spl(
+        authority,
+        from,
+        referrer_token_account,
+        token_program,
+        local_amount,
+    )
+}
+
+fn transfer_reward_global(
+    accounts: &[AccountInfo],
+    args: &GeneralTransferRewardArgs,
+    faults: &Faults,
+) -> ProgramResult {
+    let [authority, global_referrer_treasury, global_referrer_treasury_for_reward, _referee_buddy_profile, referee_buddy, system_program, mint, token_program, to, from] =
+        accounts_array(accounts)?;
+
+    check_signer(authority)?;
+
+    let mint = optional(mint).map(|x| x.key);
+
+    if !faults.skip_referral_checks {
+        if Buddy::from_account_info(referee_buddy)?.referrer_treasury()
+            != Some(*global_referrer_treasury.key)
+        {
+            return Err(BuddyLinkError::InvalidReferrerProvidedForBuddy.into());
+        }
+
+        check_treasury_for_reward(
+            global_referrer_treasury,
+            global_referrer_treasury_for_reward,
+            mint,
+        )?;
+    }
+
+    if system_program.key == &system_program::ID {
+        return transfer_sol(
+            authority,
+            global_referrer_treasury_for_reward,
+            system_program,
+            args.amount,
+        );
+    }
+
+    let mint = mint.ok_or(BuddyLinkError::InvalidTokenSpecified)?;
+    check_token_account(to, global_referrer_treasury_for_reward.key, mint)?;
+
+    transfer_spl(authority, from, to, token_program, args.amount)
+}
@@ -0,0 +1,489 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, transfer_checked_global_reward,
+    transfer_multi_tier_reward, transfer_secure_local_reward,
+    transfer_unchecked_local_shared_reward, validate_referrer, GeneralTransferRewardArgs,
+    MultiTierRewardArgs, RewardAsset, SharedRewardAsset, TierRecipient,
+    TransferUncheckedLocalSharedRewardArgs, UnclaimedTiers,
+};
+use buddy_link::state::Buddy;
+use buddy_link_mock::{process_instruction, process_instruction_with_faults, Faults};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::fs;
+use std::str::FromStr;
+
+//Same fixtures as the amman validator (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_AUTHORITY: Pubkey = pubkey!("HFnGHHTEKdggiHVFYEs1VAKKmjPvoD31HQsApkZqHqEx");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+const FIXTURES: [Pubkey; 8] = [
+    MINT,
+    REFERRER_TREASURY,
+    REFERRER_ATA,
+    REFERRER_MEMBER,
+    REFEREE_GLOBAL_BUDDY,
+    REFEREE_TREASURY,
+    REFEREE_MEMBER,
+    pubkey!("C2LZp5DNf6JsjEWoiQiS1jXQPopi3y4K2H7zN6App7mH"),
+];
+
+fn fixture(address: &Pubkey) -> Account {
+    let path = format!(
+        "{}/../../.amman/accounts/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        address
+    );
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let account = &json["account"];
+
+    Account {
+        lamports: account["lamports"].as_u64().unwrap(),
+        data: STANDARD
+            .decode(account["data"][0].as_str().unwrap())
+            .unwrap(),
+        owner: Pubkey::from_str(account["owner"].as_str().unwrap()).unwrap(),
+        executable: account["executable"].as_bool().unwrap(),
+        rent_epoch: 0,
+    }
+}
+
+fn fault_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    process_instruction_with_faults(
+        program_id,
+        accounts,
+        data,
+        &Faults {
+            error: Some(BuddyLinkError::InvalidClaimAmount),
+            skip_referral_checks: false,
+        },
+    )
+}
+
+fn program_test(faults: bool) -> ProgramTest {
+    let mut program_test = if faults {
+        ProgramTest::new(
+            "buddy_link_mock",
+            BL_PROGRAM_ID,
+            processor!(fault_processor),
+        )
+    } else {
+        ProgramTest::new(
+            "buddy_link_mock",
+            BL_PROGRAM_ID,
+            processor!(process_instruction),
+        )
+    };
+    program_test.prefer_bpf(false);
+
+    for address in FIXTURES {
+        program_test.add_account(address, fixture(&address));
+    }
+
+    program_test
+}
+
+/// The referee buddy of the fixtures, owned by a keypair of the test.
+fn add_referee_buddy(program_test: &mut ProgramTest, authority: &Pubkey) {
+    let mut buddy = fixture(&REFEREE_GLOBAL_BUDDY);
+    let offset = Buddy::<&[u8]>::AUTHORITY_OFFSET;
+    buddy.data[offset..offset + 32].copy_from_slice(authority.as_ref());
+
+    program_test.add_account(REFEREE_GLOBAL_BUDDY, buddy);
+}
+
+fn add_token_account(program_test: &mut ProgramTest, owner: &Pubkey, amount: u64) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: MINT,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    address
+}
+
+async fn execute(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn error_code(result: Result<(), BanksClientError>) -> u32 {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        error => panic!("unexpected error {error:?}"),
+    }
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+fn validate_referrer_instruction(payer: Pubkey, authority: Pubkey) -> Instruction {
+    validate_referrer(
+        payer,
+        authority,
+        Some(MINT),
+        Some(REFERRER_ATA),
+        Some(REFERRER_MEMBER),
+        Some(REFERRER_TREASURY),
+        Some(REFERRER_TREASURY),
+        REFEREE_GLOBAL_BUDDY,
+        REFEREE_GLOBAL_BUDDY,
+        REFEREE_TREASURY,
+        REFEREE_MEMBER,
+    )
+}
+
+#[tokio::test]
+async fn test_validate_referrer() {
+    let mut context = program_test(false).start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let result = execute(
+        &mut context,
+        validate_referrer_instruction(payer, REFEREE_AUTHORITY),
+        &[],
+    )
+    .await;
+    assert!(result.is_ok());
+
+    let result = execute(
+        &mut context,
+        validate_referrer_instruction(payer, payer),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::CantCreateMemberWithReferrer.code()
+    );
+}
+
+fn shared_reward_instruction(
+    authority: Pubkey,
+    recipients: &[Pubkey],
+    shares_in_bps: Vec<u16>,
+) -> Instruction {
+    transfer_unchecked_local_shared_reward(
+        authority,
+        &SharedRewardAsset::Sol,
+        recipients,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount: 10_000,
+            shares_in_bps,
+            members_included: false,
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_transfer_unchecked_local_shared_reward() {
+    let mut context = program_test(false).start_with_context().await;
+    let payer = context.payer.pubkey();
+    let recipients = [REFERRER_TREASURY, REFEREE_TREASURY];
+
+    let before = [
+        lamports(&mut context, recipients[0]).await,
+        lamports(&mut context, recipients[1]).await,
+    ];
+
+    let result = execute(
+        &mut context,
+        shared_reward_instruction(payer, &recipients, vec![7_500, 2_500]),
+        &[],
+    )
+    .await;
+    assert!(result.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, recipients[0]).await,
+        before[0] + 7_500
+    );
+    assert_eq!(
+        lamports(&mut context, recipients[1]).await,
+        before[1] + 2_500
+    );
+
+    let result = execute(
+        &mut context,
+        shared_reward_instruction(payer, &recipients, vec![7_500, 2_000]),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::InvalidBPSProvided.code()
+    );
+
+    let result = execute(
+        &mut context,
+        shared_reward_instruction(payer, &recipients, vec![10_000]),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::InvalidNumberOfSharesSpecified.code()
+    );
+}
+
+#[tokio::test]
+async fn test_transfer_multi_tier_reward() {
+    let mut context = program_test(false).start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let referrer = TierRecipient {
+        recipient: REFERRER_TREASURY,
+        member: Some(REFERRER_MEMBER),
+    };
+    let fallback = TierRecipient {
+        recipient: REFEREE_TREASURY,
+        member: Some(REFEREE_MEMBER),
+    };
+
+    let before = [
+        lamports(&mut context, REFERRER_TREASURY).await,
+        lamports(&mut context, REFEREE_TREASURY).await,
+    ];
+
+    // Second and third tiers unclaimed, paid to the fallback
+    let instruction = transfer_multi_tier_reward(
+        payer,
+        &SharedRewardAsset::Sol,
+        &[Some(referrer), None],
+        &MultiTierRewardArgs {
+            total_amount: 10_000,
+            schedule: vec![5_000, 3_000, 2_000],
+            unclaimed: UnclaimedTiers::Fallback(fallback),
+        },
+    )
+    .unwrap();
+    assert!(execute(&mut context, instruction, &[]).await.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 5_000
+    );
+    assert_eq!(
+        lamports(&mut context, REFEREE_TREASURY).await,
+        before[1] + 5_000
+    );
+
+    // Redistributed to the only referrer
+    let instruction = transfer_multi_tier_reward(
+        payer,
+        &SharedRewardAsset::Sol,
+        &[Some(referrer)],
+        &MultiTierRewardArgs {
+            total_amount: 10_000,
+            schedule: vec![5_000, 3_000, 2_000],
+            unclaimed: UnclaimedTiers::Redistribute,
+        },
+    )
+    .unwrap();
+    assert!(execute(&mut context, instruction, &[]).await.is_ok());
+
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before[0] + 15_000
+    );
+}
+
+#[tokio::test]
+async fn test_transfer_secure_local_reward() {
+    let authority = Keypair::new();
+    let mut program_test = program_test(false);
+    add_referee_buddy(&mut program_test, &authority.pubkey());
+    let from = add_token_account(&mut program_test, &authority.pubkey(), 100);
+
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let instruction = |authority: Pubkey| {
+        transfer_secure_local_reward(
+            authority,
+            MINT,
+            spl_token::ID,
+            from,
+            REFERRER_ATA,
+            REFERRER_MEMBER,
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_TREASURY,
+            REFEREE_MEMBER,
+            &GeneralTransferRewardArgs { amount: 10 },
+        )
+    };
+
+    let result = execute(&mut context, instruction(authority.pubkey()), &[&authority]).await;
+    assert!(result.is_ok());
+    assert_eq!(token_amount(&mut context, REFERRER_ATA).await, 10);
+    assert_eq!(token_amount(&mut context, from).await, 90);
+
+    //The payer doesn't own the buddy profile
+    let result = execute(&mut context, instruction(payer), &[]).await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::CantCreateMemberWithReferrer.code()
+    );
+}
+
+#[tokio::test]
+async fn test_transfer_checked_global_reward() {
+    let authority = Keypair::new();
+    let global_referrer_treasury = Pubkey::new_unique();
+    let mut program_test = program_test(false);
+    let from = add_token_account(&mut program_test, &authority.pubkey(), 2_000);
+    let global_referrer_ata = add_token_account(&mut program_test, &global_referrer_treasury, 0);
+
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = |global_referrer: Option<(Pubkey, Pubkey)>| {
+        transfer_checked_global_reward(
+            authority.pubkey(),
+            MINT,
+            spl_token::ID,
+            from,
+            REFERRER_ATA,
+            Some(REFERRER_MEMBER),
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_MEMBER,
+            global_referrer.map(|x| x.0),
+            global_referrer.map(|x| x.1),
+            &GeneralTransferRewardArgs { amount: 999 },
+        )
+    };
+
+    // The referee member was referred, the global referrer gets 2.5%
+    let result = execute(
+        &mut context,
+        instruction(Some((global_referrer_treasury, global_referrer_ata))),
+        &[&authority],
+    )
+    .await;
+    assert!(result.is_ok());
+    assert_eq!(token_amount(&mut context, global_referrer_ata).await, 24);
+    assert_eq!(token_amount(&mut context, REFERRER_ATA).await, 975);
+
+    // Without a global referrer, the referrer gets everything
+    let result = execute(&mut context, instruction(None), &[&authority]).await;
+    assert!(result.is_ok());
+    assert_eq!(token_amount(&mut context, REFERRER_ATA).await, 975 + 999);
+    assert_eq!(token_amount(&mut context, from).await, 2);
+}
+
+#[tokio::test]
+async fn test_transfer_checked_global_only_reward() {
+    let mut context = program_test(false).start_with_context().await;
+    let payer = context.payer.pubkey();
+    let before = lamports(&mut context, REFERRER_TREASURY).await;
+
+    let instruction = |treasury: Pubkey| {
+        transfer_checked_global_only_reward(
+            payer,
+            &RewardAsset::Sol,
+            treasury,
+            treasury,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        )
+    };
+
+    let result = execute(&mut context, instruction(REFERRER_TREASURY), &[]).await;
+    assert!(result.is_ok());
+    assert_eq!(
+        lamports(&mut context, REFERRER_TREASURY).await,
+        before + 1_000
+    );
+
+    //Not the global referrer of the referee
+    let result = execute(&mut context, instruction(REFEREE_TREASURY), &[]).await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::InvalidReferrerProvidedForBuddy.code()
+    );
+}
+
+#[tokio::test]
+async fn test_fault_injection() {
+    let mut context = program_test(true).start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let result = execute(
+        &mut context,
+        validate_referrer_instruction(payer, REFEREE_AUTHORITY),
+        &[],
+    )
+    .await;
+    assert_eq!(
+        error_code(result),
+        BuddyLinkError::InvalidClaimAmount.code()
+    );
+}
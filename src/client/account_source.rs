@@ -0,0 +1,363 @@
+use crate::client::error::{Error, Result};
+use async_trait::async_trait;
+use solana_account_decoder::{UiAccount, UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::{nonblocking, rpc_client::RpcClient};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::{Account, AccountSharedData};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Max number of accounts of a `getMultipleAccounts` request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+///# Account Source
+///
+/// Where the client helpers read the accounts from: an RPC node, an in-memory [`AccountSnapshot`],
+/// or anything else (a bank, an SVM, a replay) implementing it.
+pub trait AccountSource {
+    /// None if the account doesn't exist.
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>>;
+
+    /// One entry per pubkey, in the same order, None for the accounts that don't exist.
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>>;
+
+    /// Accounts owned by the program and matching all the filters, with only the slice of their data if given.
+    ///
+    /// Used by the queries of [`crate::client::discovery`] and by the local referral chains,
+    /// sources without an index of the program accounts return [`Error::Unsupported`].
+    fn get_program_accounts(
+        &self,
+        _program_id: &Pubkey,
+        _filters: &[RpcFilterType],
+        _data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Err(Error::Unsupported("getProgramAccounts"))
+    }
+}
+
+/// Async counterpart of [`AccountSource`].
+#[async_trait]
+pub trait AsyncAccountSource: Send + Sync {
+    /// None if the account doesn't exist.
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>>;
+
+    /// One entry per pubkey, in the same order, None for the accounts that don't exist.
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>>;
+
+    /// See [`AccountSource::get_program_accounts`].
+    async fn get_program_accounts(
+        &self,
+        _program_id: &Pubkey,
+        _filters: &[RpcFilterType],
+        _data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Err(Error::Unsupported("getProgramAccounts"))
+    }
+}
+
+fn program_accounts_config(
+    filters: &[RpcFilterType],
+    data_slice: Option<UiDataSliceConfig>,
+) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(filters.to_vec()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice,
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// Reads with the commitment of the client, `getMultipleAccounts` are split in requests of [`MAX_MULTIPLE_ACCOUNTS`].
+impl AccountSource for RpcClient {
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .get_account_with_commitment(pubkey, self.commitment())?
+            .value)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(RpcClient::get_multiple_accounts(self, chunk)?);
+        }
+
+        Ok(accounts)
+    }
+
+    fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.get_program_accounts_with_config(
+            program_id,
+            program_accounts_config(filters, data_slice),
+        )?)
+    }
+}
+
+/// Same as the blocking [`RpcClient`], the requests of 100 accounts are sent concurrently.
+#[async_trait]
+impl AsyncAccountSource for nonblocking::rpc_client::RpcClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .get_account_with_commitment(pubkey, self.commitment())
+            .await?
+            .value)
+    }
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let chunks = pubkeys
+            .chunks(MAX_MULTIPLE_ACCOUNTS)
+            .map(|chunk| nonblocking::rpc_client::RpcClient::get_multiple_accounts(self, chunk));
+
+        Ok(futures::future::try_join_all(chunks).await?.concat())
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self
+            .get_program_accounts_with_config(
+                program_id,
+                program_accounts_config(filters, data_slice),
+            )
+            .await?)
+    }
+}
+
+/// Accounts are read one by one (program-test keeps them in memory), `getProgramAccounts` isn't supported.
+#[cfg(feature = "banks-client")]
+#[async_trait]
+impl AsyncAccountSource for solana_banks_client::BanksClient {
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        let mut client = self.clone();
+        Ok(solana_banks_client::BanksClient::get_account(&mut client, *pubkey).await?)
+    }
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let mut client = self.clone();
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+
+        for pubkey in pubkeys {
+            accounts
+                .push(solana_banks_client::BanksClient::get_account(&mut client, *pubkey).await?);
+        }
+
+        Ok(accounts)
+    }
+}
+
+///# Account Snapshot
+///
+/// Accounts kept in memory, to resolve offline (replays, unit tests) with the same code as against a node.
+///
+/// [`AccountSnapshot::load_dir`] loads the JSON files written by `solana account --output json`
+/// (like the `.amman/accounts` fixtures).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl AccountSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.json` file of the directory.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir)
+            .map_err(|error| Error::Snapshot(format!("{}: {}", dir.display(), error)))?;
+
+        let mut snapshot = Self::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|error| Error::Snapshot(format!("{}: {}", dir.display(), error)))?
+                .path();
+
+            if path.extension().is_some_and(|x| x == "json") {
+                snapshot.load_file(&path)?;
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Loads a `{"pubkey": ..., "account": {...}}` JSON file, returns the pubkey of the account.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<Pubkey> {
+        let path = path.as_ref();
+        let invalid = |reason: &dyn std::fmt::Display| {
+            Error::Snapshot(format!("{}: {}", path.display(), reason))
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).map_err(|x| invalid(&x))?)
+                .map_err(|x| invalid(&x))?;
+
+        let pubkey: Pubkey = json["pubkey"]
+            .as_str()
+            .ok_or_else(|| invalid(&"missing pubkey"))?
+            .parse()
+            .map_err(|x| invalid(&x))?;
+        let account: Account = serde_json::from_value::<UiAccount>(json["account"].clone())
+            .map_err(|x| invalid(&x))?
+            .decode()
+            .ok_or_else(|| invalid(&"undecodable account"))?;
+
+        self.accounts.insert(pubkey, account);
+
+        Ok(pubkey)
+    }
+
+    pub fn insert(&mut self, pubkey: Pubkey, account: Account) -> Option<Account> {
+        self.accounts.insert(pubkey, account)
+    }
+
+    pub fn remove(&mut self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.remove(pubkey)
+    }
+
+    pub fn accounts(&self) -> &HashMap<Pubkey, Account> {
+        &self.accounts
+    }
+
+    /// Accounts to add to a `ProgramTest` (requires the `testing` feature), to run the programs of the snapshot as
+    /// deployed.
+    ///
+    /// A program of the upgradeable loader is added the way `ProgramTest::add_program` adds the programs it finds in
+    /// `BPF_OUT_DIR` (an executable account of the BPF loader holding the ELF of its program data), without a file nor
+    /// the environment variable so that the tests can run in parallel. The program data accounts are left out.
+    #[cfg(feature = "testing")]
+    pub fn program_test_accounts(&self) -> Vec<(Pubkey, Account)> {
+        use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+        use solana_program::{bpf_loader, rent::Rent};
+
+        let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+
+        self.accounts
+            .iter()
+            .filter_map(|(pubkey, account)| {
+                if account.owner != bpf_loader_upgradeable::ID {
+                    return Some((*pubkey, account.clone()));
+                }
+
+                let Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) = account.deserialize_data()
+                else {
+                    return None;
+                };
+                let elf = self.accounts.get(&programdata_address)?.data[metadata_len..].to_vec();
+
+                Some((
+                    *pubkey,
+                    Account {
+                        lamports: Rent::default().minimum_balance(elf.len()).max(1),
+                        data: elf,
+                        owner: bpf_loader::ID,
+                        executable: true,
+                        rent_epoch: 0,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Vec<(Pubkey, Account)> {
+        let mut accounts: Vec<(Pubkey, Account)> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| &account.owner == program_id)
+            .filter(|(_, account)| {
+                let account = AccountSharedData::from((*account).clone());
+                filters.iter().all(|filter| filter.allows(&account))
+            })
+            .map(|(pubkey, account)| {
+                let mut account = account.clone();
+
+                if let Some(data_slice) = data_slice {
+                    let start = data_slice.offset.min(account.data.len());
+                    let end = account.data.len().min(start + data_slice.length);
+                    account.data = account.data[start..end].to_vec();
+                }
+
+                (*pubkey, account)
+            })
+            .collect();
+        accounts.sort_unstable_by_key(|(pubkey, _)| *pubkey);
+
+        accounts
+    }
+}
+
+impl From<HashMap<Pubkey, Account>> for AccountSnapshot {
+    fn from(accounts: HashMap<Pubkey, Account>) -> Self {
+        Self { accounts }
+    }
+}
+
+impl FromIterator<(Pubkey, Account)> for AccountSnapshot {
+    fn from_iter<I: IntoIterator<Item = (Pubkey, Account)>>(iter: I) -> Self {
+        Self {
+            accounts: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl AccountSource for AccountSnapshot {
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self.accounts.get(pubkey).cloned())
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        Ok(pubkeys
+            .iter()
+            .map(|pubkey| self.accounts.get(pubkey).cloned())
+            .collect())
+    }
+
+    fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.program_accounts(program_id, filters, data_slice))
+    }
+}
+
+#[async_trait]
+impl AsyncAccountSource for AccountSnapshot {
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        AccountSource::get_account(self, pubkey)
+    }
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        AccountSource::get_multiple_accounts(self, pubkeys)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.program_accounts(program_id, filters, data_slice))
+    }
+}
@@ -0,0 +1,573 @@
+use crate::client::error::{Error, Result};
+use crate::instruction::{
+    transfer_unchecked_local_shared_reward, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs,
+};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// Sum of the shares of a shared reward.
+pub const MAX_BPS: u16 = 10_000;
+
+/// Accounts a transaction can lock on the clusters.
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+const BPS: u64 = MAX_BPS as u64;
+
+/// Largest amount paid per share unit, so `total_amount * share_in_bps` can't overflow on-chain.
+const MAX_AMOUNT_PER_BPS: u64 = u64::MAX / BPS / BPS;
+
+/// Amount a recipient receives from a shared reward, as computed by the program.
+/// None if the multiplication overflows (the instruction would fail).
+pub fn share_amount(total_amount: u64, share_in_bps: u16) -> Option<u64> {
+    total_amount
+        .checked_mul(u64::from(share_in_bps))
+        .map(|x| x / BPS)
+}
+
+/// One shared reward instruction of a split, `shares` are the indexes of the recipients with their share in bps.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SharedRewardSplit {
+    pub total_amount: u64,
+    pub shares: Vec<(usize, u16)>,
+}
+
+///# Exact Shared Reward Splits
+///
+/// Splits amounts into shared rewards paying exactly these amounts (no rounding loss).
+/// A recipient can be paid by multiple splits, the amounts it receives add up to its amount.
+/// Recipients with a zero amount aren't paid.
+///
+/// The program rounds each share down and leaves the rest with the authority, so a split can have a `total_amount`
+/// a bit above the amounts it pays (less than one unit per recipient). Whether a single split pays all the amounts is
+/// decided exactly, so a result of one or two splits is always the fewest possible:
+/// 1. A single split paying every amount
+/// 2. One recipient alone and a split paying the others
+///
+/// Otherwise the one with the fewest splits of two heuristics is returned, which isn't proven to be the fewest:
+/// 1. Group the amounts that share exactly (every recipient is in a single split)
+/// 2. Pay the rest of the division of the sum by 10_000 to one recipient, then multiples of 10_000 units
+pub fn exact_shared_reward_splits(amounts: &[u64]) -> Vec<SharedRewardSplit> {
+    let indexes: Vec<usize> = (0..amounts.len()).filter(|&x| amounts[x] > 0).collect();
+    if indexes.is_empty() {
+        return Vec::new();
+    }
+    if let Some(split) = single_split(amounts, &indexes) {
+        return vec![split];
+    }
+    if let Some(splits) = split_one_out(amounts, &indexes) {
+        return splits;
+    }
+
+    [split_by_groups(amounts), split_by_remainders(amounts)]
+        .into_iter()
+        .flatten()
+        .min_by_key(|splits| splits.len())
+        .unwrap_or_default()
+}
+
+/// Single shared reward paying exactly the amounts of the indexes, if any.
+///
+/// The rounding loses less than one unit per recipient, so the total is between the sum and the sum plus the count.
+/// For each total, the shares paying an amount are a range: the amounts are paid if the ranges can add up to 10_000.
+fn single_split(amounts: &[u64], indexes: &[usize]) -> Option<SharedRewardSplit> {
+    let bps = u128::from(BPS);
+    let sum: u128 = indexes
+        .iter()
+        .map(|&index| u128::from(amounts[index]))
+        .sum();
+    // Any share of the total can be multiplied on-chain
+    let max_total_amount = u128::from(u64::MAX / BPS);
+
+    (sum..sum + indexes.len() as u128)
+        .take_while(|&total_amount| total_amount <= max_total_amount)
+        .find_map(|total_amount| {
+            let mut ranges = Vec::with_capacity(indexes.len());
+            for &index in indexes {
+                let amount = u128::from(amounts[index]);
+                let low = (amount * bps).div_ceil(total_amount);
+                let high = ((amount + 1) * bps).div_ceil(total_amount) - 1;
+                if low > high.min(bps) {
+                    return None;
+                }
+                ranges.push((index, low, high.min(bps)));
+            }
+
+            let lows: u128 = ranges.iter().map(|x| x.1).sum();
+            let highs: u128 = ranges.iter().map(|x| x.2).sum();
+            if lows > bps || highs < bps {
+                return None;
+            }
+
+            let mut extra = bps - lows;
+            let shares = ranges
+                .into_iter()
+                .map(|(index, low, high)| {
+                    let added = extra.min(high - low);
+                    extra -= added;
+                    (index, (low + added) as u16)
+                })
+                .collect();
+
+            Some(SharedRewardSplit {
+                total_amount: total_amount as u64,
+                shares,
+            })
+        })
+}
+
+/// First recipient that can be paid alone while a single split pays the others.
+fn split_one_out(amounts: &[u64], indexes: &[usize]) -> Option<Vec<SharedRewardSplit>> {
+    (0..indexes.len()).find_map(|position| {
+        let mut others = indexes.to_vec();
+        let index = others.remove(position);
+
+        Some(vec![
+            single_split(amounts, &others)?,
+            single_split(amounts, &[index])?,
+        ])
+    })
+}
+
+/// First fit of the amounts in groups that share exactly, None if an amount is too large to be paid alone.
+fn split_by_groups(amounts: &[u64]) -> Option<Vec<SharedRewardSplit>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for (index, _) in amounts.iter().enumerate().filter(|(_, x)| **x > 0) {
+        let fitting_group = groups.iter_mut().find(|group| {
+            let mut candidate = group.to_vec();
+            candidate.push(index);
+            single_split(amounts, &candidate).is_some()
+        });
+
+        match fitting_group {
+            Some(group) => group.push(index),
+            None => {
+                single_split(amounts, &[index])?;
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups
+        .iter()
+        .map(|group| single_split(amounts, group))
+        .collect()
+}
+
+/// Pays the rest of the division of the sum by 10_000 to a single recipient, then multiples of 10_000
+/// until the remainders share exactly. None if no recipient can receive that rest.
+fn split_by_remainders(amounts: &[u64]) -> Option<Vec<SharedRewardSplit>> {
+    let mut remainders = amounts.to_vec();
+    let mut splits = Vec::new();
+
+    let rest = (amounts.iter().map(|&x| u128::from(x)).sum::<u128>() % u128::from(BPS)) as u64;
+    if rest > 0 {
+        let index = (0..remainders.len()).max_by_key(|&x| remainders[x])?;
+        remainders[index] = remainders[index].checked_sub(rest)?;
+
+        splits.push(SharedRewardSplit {
+            total_amount: rest,
+            shares: vec![(index, MAX_BPS)],
+        });
+    }
+
+    loop {
+        let indexes: Vec<usize> = (0..remainders.len())
+            .filter(|&index| remainders[index] > 0)
+            .collect();
+
+        if indexes.is_empty() {
+            return Some(splits);
+        }
+
+        if let Some(split) = single_split(&remainders, &indexes) {
+            splits.push(split);
+            return Some(splits);
+        }
+
+        // The sum is a multiple of 10_000 and at least 20_000, 10_000 would share exactly
+        let multiple = indexes
+            .iter()
+            .map(|&x| u128::from(remainders[x]))
+            .sum::<u128>()
+            / u128::from(BPS);
+
+        let units = |per_bps: u64| -> u128 {
+            indexes
+                .iter()
+                .map(|&x| u128::from(remainders[x] / per_bps))
+                .sum()
+        };
+
+        // Leaving exactly 10_000 lets the next split pay every remainder, otherwise pay as much as possible
+        let mut per_bps = MAX_AMOUNT_PER_BPS.min((multiple - 1) as u64);
+        if units(per_bps) < u128::from(BPS) {
+            let (mut low, mut high) = (1, per_bps);
+            while low < high {
+                let middle = low + (high - low).div_ceil(2);
+                if units(middle) >= u128::from(BPS) {
+                    low = middle;
+                } else {
+                    high = middle - 1;
+                }
+            }
+            per_bps = low;
+        }
+
+        let mut shares: Vec<(usize, u64)> = indexes
+            .iter()
+            .map(|&x| (x, remainders[x] / per_bps))
+            .collect();
+
+        // Remove the extra bps from the largest shares
+        shares.sort_by_key(|x| std::cmp::Reverse(x.1));
+        let mut excess = shares.iter().map(|x| u128::from(x.1)).sum::<u128>() - u128::from(BPS);
+        for share in shares.iter_mut() {
+            let removed = excess.min(u128::from(share.1)) as u64;
+            share.1 -= removed;
+            excess -= u128::from(removed);
+        }
+        shares.retain(|x| x.1 > 0);
+        shares.sort_by_key(|x| x.0);
+
+        for &(index, share) in &shares {
+            remainders[index] -= share * per_bps;
+        }
+
+        splits.push(SharedRewardSplit {
+            total_amount: per_bps * BPS,
+            shares: shares.into_iter().map(|(x, y)| (x, y as u16)).collect(),
+        });
+    }
+}
+
+/// Limits a transaction has to respect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TransactionLimits {
+    /// Size of the serialized transaction, signatures included.
+    pub max_size: usize,
+    /// Number of accounts used by the transaction, including the ones loaded from lookup tables.
+    pub max_accounts: usize,
+}
+
+impl Default for TransactionLimits {
+    fn default() -> Self {
+        Self {
+            max_size: PACKET_DATA_SIZE,
+            max_accounts: MAX_TRANSACTION_ACCOUNTS,
+        }
+    }
+}
+
+///# Shared Reward Batcher
+///
+/// Plans the transactions paying a list of (recipient, amount) with shared rewards.
+/// Recipients are the treasuries (SOL) or the token accounts (SPL) receiving the rewards.
+///
+/// Each planned transaction leaves room for the compute budget instructions (unless disabled),
+/// the transactions are compiled as v0 messages when lookup tables are given, legacy ones otherwise.
+pub struct SharedRewardBatcher<'a> {
+    authority: Pubkey,
+    fee_payer: Pubkey,
+    asset: SharedRewardAsset,
+    lookup_tables: &'a [AddressLookupTableAccount],
+    limits: TransactionLimits,
+    compute_budget: bool,
+}
+
+impl<'a> SharedRewardBatcher<'a> {
+    pub fn new(authority: Pubkey, asset: SharedRewardAsset) -> Self {
+        Self {
+            authority,
+            fee_payer: authority,
+            asset,
+            lookup_tables: &[],
+            limits: TransactionLimits::default(),
+            compute_budget: true,
+        }
+    }
+
+    pub fn fee_payer(mut self, fee_payer: Pubkey) -> Self {
+        self.fee_payer = fee_payer;
+        self
+    }
+
+    pub fn lookup_tables(mut self, lookup_tables: &'a [AddressLookupTableAccount]) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+
+    pub fn limits(mut self, limits: TransactionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Whether room is left for the SetComputeUnitLimit and SetComputeUnitPrice instructions.
+    pub fn compute_budget(mut self, compute_budget: bool) -> Self {
+        self.compute_budget = compute_budget;
+        self
+    }
+
+    /// Instructions of each transaction paying the rewards.
+    /// Recipients present multiple times are paid the sum of their amounts.
+    pub fn plan(&self, payouts: &[(Pubkey, u64)]) -> Result<Vec<Vec<Instruction>>> {
+        let mut recipients: Vec<Pubkey> = Vec::new();
+        let mut amounts: Vec<u64> = Vec::new();
+        let mut indexes: HashMap<Pubkey, usize> = HashMap::new();
+
+        for (recipient, amount) in payouts.iter().filter(|x| x.1 > 0) {
+            match indexes.entry(*recipient) {
+                Entry::Occupied(entry) => {
+                    let index = *entry.get();
+                    amounts[index] = amounts[index]
+                        .checked_add(*amount)
+                        .ok_or(Error::InvalidPayout(*recipient))?
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(recipients.len());
+                    recipients.push(*recipient);
+                    amounts.push(*amount);
+                }
+            }
+        }
+
+        // The first table holding an address is the one it's loaded from
+        let mut table_indexes: HashMap<Pubkey, usize> = HashMap::new();
+        for (table_index, lookup_table) in self.lookup_tables.iter().enumerate() {
+            for address in &lookup_table.addresses {
+                table_indexes.entry(*address).or_insert(table_index);
+            }
+        }
+        let empty_transaction = self.empty_transaction(&table_indexes);
+
+        let mut instructions = Vec::new();
+        let mut start = 0;
+
+        while start < recipients.len() {
+            // Largest batch whose instruction paying every recipient fits in a transaction
+            let mut transaction = empty_transaction.clone();
+            transaction.add_instruction(&self.instruction(&[], &SharedRewardSplit::default()));
+            let mut end = start;
+            while end < recipients.len() {
+                transaction.add_recipient(recipients[end]);
+                if !transaction.fits(&self.limits) {
+                    break;
+                }
+                end += 1;
+            }
+
+            if end == start {
+                return Err(Error::TransactionTooLarge);
+            }
+
+            let batch = &recipients[start..end];
+            for split in exact_shared_reward_splits(&amounts[start..end]) {
+                instructions.push(self.instruction(batch, &split));
+            }
+
+            start = end;
+        }
+
+        let mut transactions: Vec<Vec<Instruction>> = Vec::new();
+        let mut current: Vec<Instruction> = Vec::new();
+        let mut transaction = empty_transaction.clone();
+
+        for instruction in instructions {
+            let mut candidate = transaction.clone();
+            candidate.add_instruction(&instruction);
+
+            if candidate.fits(&self.limits) {
+                transaction = candidate;
+            } else {
+                transactions.push(std::mem::take(&mut current));
+                transaction = empty_transaction.clone();
+                transaction.add_instruction(&instruction);
+            }
+            current.push(instruction);
+        }
+
+        if !current.is_empty() {
+            transactions.push(current);
+        }
+
+        Ok(transactions)
+    }
+
+    fn instruction(&self, recipients: &[Pubkey], split: &SharedRewardSplit) -> Instruction {
+        let remaining_accounts: Vec<Pubkey> =
+            split.shares.iter().map(|x| recipients[x.0]).collect();
+
+        transfer_unchecked_local_shared_reward(
+            self.authority,
+            &self.asset,
+            &remaining_accounts,
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount: split.total_amount,
+                shares_in_bps: split.shares.iter().map(|x| x.1).collect(),
+                members_included: false,
+            },
+        )
+    }
+
+    /// Transaction with only the compute budget instructions (if enabled).
+    fn empty_transaction<'b>(
+        &self,
+        table_indexes: &'b HashMap<Pubkey, usize>,
+    ) -> TransactionSize<'b> {
+        let mut transaction = TransactionSize {
+            table_indexes,
+            versioned: !self.lookup_tables.is_empty(),
+            signers: HashSet::from([self.fee_payer]),
+            static_keys: HashSet::from([self.fee_payer]),
+            loaded_keys: HashMap::new(),
+            loaded_counts: vec![(0, 0); self.lookup_tables.len()],
+            instructions: Vec::new(),
+        };
+
+        if self.compute_budget {
+            transaction.add_instruction(&ComputeBudgetInstruction::set_compute_unit_limit(0));
+            transaction.add_instruction(&ComputeBudgetInstruction::set_compute_unit_price(0));
+        }
+
+        transaction
+    }
+}
+
+/// Size and accounts of a transaction as compiled by `legacy::Message::new` or `v0::Message::try_compile`,
+/// updated as instructions are added instead of compiling and serializing the message every time.
+#[derive(Clone)]
+struct TransactionSize<'a> {
+    table_indexes: &'a HashMap<Pubkey, usize>,
+    versioned: bool,
+    signers: HashSet<Pubkey>,
+    static_keys: HashSet<Pubkey>,
+    /// Addresses loaded from the lookup tables, with their table and whether they're writable.
+    loaded_keys: HashMap<Pubkey, (usize, bool)>,
+    /// Writable and readonly addresses loaded from each table.
+    loaded_counts: Vec<(usize, usize)>,
+    /// Accounts and data length of each instruction.
+    instructions: Vec<(usize, usize)>,
+}
+
+impl TransactionSize<'_> {
+    fn add_instruction(&mut self, instruction: &Instruction) {
+        // Invoked programs can't be loaded from a lookup table
+        self.unload(&instruction.program_id);
+        self.static_keys.insert(instruction.program_id);
+
+        for meta in &instruction.accounts {
+            self.add_key(meta);
+        }
+        self.instructions
+            .push((instruction.accounts.len(), instruction.data.len()));
+    }
+
+    /// Adds a recipient to the last instruction, a shared reward one.
+    fn add_recipient(&mut self, recipient: Pubkey) {
+        self.add_key(&AccountMeta::new(recipient, false));
+        if let Some((accounts, data_len)) = self.instructions.last_mut() {
+            *accounts += 1;
+            *data_len += 2;
+        }
+    }
+
+    fn add_key(&mut self, meta: &AccountMeta) {
+        if meta.is_signer {
+            self.unload(&meta.pubkey);
+            self.signers.insert(meta.pubkey);
+            self.static_keys.insert(meta.pubkey);
+            return;
+        }
+        if self.static_keys.contains(&meta.pubkey) {
+            return;
+        }
+
+        match self.loaded_keys.get_mut(&meta.pubkey) {
+            Some((table_index, is_writable)) => {
+                if meta.is_writable && !*is_writable {
+                    *is_writable = true;
+                    self.loaded_counts[*table_index].0 += 1;
+                    self.loaded_counts[*table_index].1 -= 1;
+                }
+            }
+            None => match self.table_indexes.get(&meta.pubkey) {
+                Some(&table_index) => {
+                    self.loaded_keys
+                        .insert(meta.pubkey, (table_index, meta.is_writable));
+                    if meta.is_writable {
+                        self.loaded_counts[table_index].0 += 1;
+                    } else {
+                        self.loaded_counts[table_index].1 += 1;
+                    }
+                }
+                None => {
+                    self.static_keys.insert(meta.pubkey);
+                }
+            },
+        }
+    }
+
+    fn unload(&mut self, key: &Pubkey) {
+        if let Some((table_index, is_writable)) = self.loaded_keys.remove(key) {
+            if is_writable {
+                self.loaded_counts[table_index].0 -= 1;
+            } else {
+                self.loaded_counts[table_index].1 -= 1;
+            }
+        }
+    }
+
+    fn accounts(&self) -> usize {
+        self.static_keys.len() + self.loaded_keys.len()
+    }
+
+    /// Serialized size, signatures included.
+    fn size(&self) -> usize {
+        let signatures = compact_len(self.signers.len()) + 64 * self.signers.len();
+        // Header, account keys and recent blockhash
+        let mut message =
+            3 + compact_len(self.static_keys.len()) + 32 * self.static_keys.len() + 32;
+
+        message += compact_len(self.instructions.len());
+        for &(accounts, data_len) in &self.instructions {
+            message += 1 + compact_len(accounts) + accounts + compact_len(data_len) + data_len;
+        }
+
+        if self.versioned {
+            let lookups: Vec<&(usize, usize)> = self
+                .loaded_counts
+                .iter()
+                .filter(|(writable, readonly)| writable + readonly > 0)
+                .collect();
+
+            // Version prefix
+            message += 1 + compact_len(lookups.len());
+            for &&(writable, readonly) in &lookups {
+                message += 32 + compact_len(writable) + writable + compact_len(readonly) + readonly;
+            }
+        }
+
+        signatures + message
+    }
+
+    fn fits(&self, limits: &TransactionLimits) -> bool {
+        // Account indexes are a single byte
+        self.size() <= limits.max_size
+            && self.accounts() <= limits.max_accounts.min(usize::from(u8::MAX) + 1)
+    }
+}
+
+/// Length of a short vec prefix (compact-u16).
+fn compact_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
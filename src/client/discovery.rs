@@ -0,0 +1,303 @@
+use crate::client::account_source::{AccountSource, MAX_MULTIPLE_ACCOUNTS};
+use crate::client::error::Result;
+use crate::constants::BL_PROGRAM_ID;
+use crate::state::{Buddy, Member, Treasury};
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Max number of accounts of a `getMultipleAccounts` request.
+pub const MAX_PAGE_SIZE: usize = MAX_MULTIPLE_ACCOUNTS;
+
+/// Max number of owners a treasury account can hold.
+pub const MAX_TREASURY_OWNERS: usize =
+    (Treasury::<&[u8]>::LEN - Treasury::<&[u8]>::OWNERS_OFFSET - 4) / Treasury::<&[u8]>::OWNER_LEN;
+
+///# Account Pages
+///
+/// Accounts found by a [`Discovery`] query, fetched lazily page by page with `getMultipleAccounts`
+/// and decoded with the views of the state module.
+///
+/// Accounts closed (or not decoding anymore) since the query are skipped, pages can be shorter than the page size.
+pub struct AccountPages<'a, T> {
+    client: &'a dyn AccountSource,
+    keys: Vec<Pubkey>,
+    page_size: usize,
+    position: usize,
+    decode: Box<dyn Fn(Vec<u8>) -> Option<T> + 'a>,
+}
+
+impl<'a, T> AccountPages<'a, T> {
+    /// Keys of all the accounts found by the query.
+    pub fn keys(&self) -> &[Pubkey] {
+        &self.keys
+    }
+
+    /// Fetches the remaining pages.
+    pub fn all(self) -> Result<Vec<(Pubkey, T)>> {
+        let mut accounts = Vec::with_capacity(self.keys.len());
+
+        for page in self {
+            accounts.extend(page?);
+        }
+
+        Ok(accounts)
+    }
+}
+
+impl<'a, T> Iterator for AccountPages<'a, T> {
+    type Item = Result<Vec<(Pubkey, T)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.keys.len() {
+            return None;
+        }
+
+        let end = self.keys.len().min(self.position + self.page_size);
+        let keys = &self.keys[self.position..end];
+        self.position = end;
+
+        let accounts = match self.client.get_multiple_accounts(keys) {
+            Ok(accounts) => accounts,
+            Err(error) => return Some(Err(error)),
+        };
+
+        Some(Ok(decode_page(keys, accounts, &self.decode)))
+    }
+}
+
+/// Accounts of a page still owned by the program and decoding.
+pub(crate) fn decode_page<T>(
+    keys: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+    decode: impl Fn(Vec<u8>) -> Option<T>,
+) -> Vec<(Pubkey, T)> {
+    keys.iter()
+        .zip(accounts)
+        .filter_map(|(pubkey, account)| {
+            let account = account.filter(|x| x.owner == BL_PROGRAM_ID)?;
+            Some((*pubkey, decode(account.data)?))
+        })
+        .collect()
+}
+
+///# Discovery
+///
+/// Finds BuddyLink accounts with `getProgramAccounts`, using the discriminator and `memcmp` filters
+/// on the layouts of the state module.
+///
+/// Queries only return the keys of the accounts (empty data slice), the accounts are then fetched by [`AccountPages`].
+pub struct Discovery<'a> {
+    client: &'a dyn AccountSource,
+    page_size: usize,
+    max_treasury_owners: usize,
+}
+
+impl<'a> Discovery<'a> {
+    pub fn new(client: &'a dyn AccountSource) -> Self {
+        Self {
+            client,
+            page_size: MAX_PAGE_SIZE,
+            max_treasury_owners: MAX_TREASURY_OWNERS,
+        }
+    }
+
+    /// Number of accounts fetched per page (at most [`MAX_PAGE_SIZE`]).
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Owner positions of the treasuries looked at when searching the treasuries of a buddy,
+    /// one `getProgramAccounts` per position. Lower it if the treasuries have a known max number of owners.
+    pub fn max_treasury_owners(mut self, max_treasury_owners: usize) -> Self {
+        self.max_treasury_owners = max_treasury_owners.clamp(1, MAX_TREASURY_OWNERS);
+        self
+    }
+
+    /// Members of an organization.
+    ///
+    /// The organization name isn't at a fixed offset, so every member of the program is listed
+    /// and the members are filtered while fetching the pages.
+    pub fn organization_members(
+        &self,
+        organization_name: &str,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let organization_name = organization_name.to_string();
+        let keys = self.find_keys(&Member::<&[u8]>::DISCRIMINATOR, &[])?;
+
+        Ok(self.pages(keys, move |data| {
+            Member::new(data)
+                .ok()
+                .filter(|x| x.organization_name() == Ok(organization_name.as_str()))
+        }))
+    }
+
+    /// Members referred by a treasury (direct referees in the organizations).
+    pub fn referees(
+        &self,
+        referrer_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let keys = self.find_keys(
+            &Member::<&[u8]>::DISCRIMINATOR,
+            &[(Member::<&[u8]>::REFERRER_TREASURY_OFFSET, referrer_treasury)],
+        )?;
+
+        Ok(self.pages(keys, |data| Member::new(data).ok()))
+    }
+
+    /// Members owned by a treasury (one per organization joined).
+    pub fn treasury_members(
+        &self,
+        owner_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let keys = self.find_keys(
+            &Member::<&[u8]>::DISCRIMINATOR,
+            &[(Member::<&[u8]>::OWNER_TREASURY_OFFSET, owner_treasury)],
+        )?;
+
+        Ok(self.pages(keys, |data| Member::new(data).ok()))
+    }
+
+    /// Buddies referred by a treasury (direct referees in the global referral tree).
+    pub fn global_referees(
+        &self,
+        referrer_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Buddy<Vec<u8>>>> {
+        let keys = self.find_keys(
+            &Buddy::<&[u8]>::DISCRIMINATOR,
+            &[(Buddy::<&[u8]>::REFERRER_TREASURY_OFFSET, referrer_treasury)],
+        )?;
+
+        Ok(self.pages(keys, |data| Buddy::new(data).ok()))
+    }
+
+    /// Buddies of a wallet (profile and paid buddies).
+    pub fn buddies(&self, wallet: &Pubkey) -> Result<AccountPages<'a, Buddy<Vec<u8>>>> {
+        let keys = self.find_keys(
+            &Buddy::<&[u8]>::DISCRIMINATOR,
+            &[(Buddy::<&[u8]>::AUTHORITY_OFFSET, wallet)],
+        )?;
+
+        Ok(self.pages(keys, |data| Buddy::new(data).ok()))
+    }
+
+    /// Treasuries (partially) owned by a buddy.
+    pub fn buddy_treasuries(&self, buddy: &Pubkey) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let keys = self.find_buddy_treasury_keys(buddy)?;
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    /// Treasuries (partially) owned by any buddy of a wallet.
+    pub fn wallet_treasuries(
+        &self,
+        wallet: &Pubkey,
+    ) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let buddies = self.find_keys(
+            &Buddy::<&[u8]>::DISCRIMINATOR,
+            &[(Buddy::<&[u8]>::AUTHORITY_OFFSET, wallet)],
+        )?;
+
+        let mut keys = Vec::new();
+        for buddy in &buddies {
+            keys.extend(self.find_buddy_treasury_keys(buddy)?);
+        }
+        dedup(&mut keys);
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    /// Treasuries holding the rewards of a mint.
+    pub fn mint_treasuries(&self, mint: &Pubkey) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let keys = self.find_keys(
+            &Treasury::<&[u8]>::DISCRIMINATOR,
+            &[(Treasury::<&[u8]>::MINT_OFFSET, mint)],
+        )?;
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    fn find_buddy_treasury_keys(&self, buddy: &Pubkey) -> Result<Vec<Pubkey>> {
+        let mut keys = Vec::new();
+
+        for index in 0..self.max_treasury_owners {
+            keys.extend(self.find_keys(
+                &Treasury::<&[u8]>::DISCRIMINATOR,
+                &[(treasury_owner_offset(index), buddy)],
+            )?);
+        }
+        dedup(&mut keys);
+
+        Ok(keys)
+    }
+
+    /// Keys of the program accounts with the discriminator and the pubkeys at the given offsets.
+    fn find_keys(
+        &self,
+        discriminator: &[u8; 8],
+        pubkeys: &[(usize, &Pubkey)],
+    ) -> Result<Vec<Pubkey>> {
+        let accounts = self.client.get_program_accounts(
+            &BL_PROGRAM_ID,
+            &key_filters(discriminator, pubkeys),
+            Some(KEYS_ONLY),
+        )?;
+
+        Ok(sorted_keys(accounts))
+    }
+
+    fn pages<T>(
+        &self,
+        keys: Vec<Pubkey>,
+        decode: impl Fn(Vec<u8>) -> Option<T> + 'a,
+    ) -> AccountPages<'a, T> {
+        AccountPages {
+            client: self.client,
+            keys,
+            page_size: self.page_size,
+            position: 0,
+            decode: Box::new(decode),
+        }
+    }
+}
+
+/// Data slice of the queries, only the keys are returned.
+pub(crate) const KEYS_ONLY: UiDataSliceConfig = UiDataSliceConfig {
+    offset: 0,
+    length: 0,
+};
+
+/// Filters on the discriminator and the pubkeys at the given offsets.
+pub(crate) fn key_filters(
+    discriminator: &[u8; 8],
+    pubkeys: &[(usize, &Pubkey)],
+) -> Vec<RpcFilterType> {
+    std::iter::once(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        discriminator,
+    )))
+    .chain(pubkeys.iter().map(|(offset, pubkey)| {
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(*offset, pubkey.as_ref()))
+    }))
+    .collect()
+}
+
+/// Offset of the buddy of an owner of a treasury.
+pub(crate) fn treasury_owner_offset(index: usize) -> usize {
+    Treasury::<&[u8]>::OWNERS_OFFSET + 4 + index * Treasury::<&[u8]>::OWNER_LEN
+}
+
+pub(crate) fn sorted_keys(accounts: Vec<(Pubkey, Account)>) -> Vec<Pubkey> {
+    let mut keys: Vec<Pubkey> = accounts.into_iter().map(|(pubkey, _)| pubkey).collect();
+    // Stable pages across queries
+    keys.sort_unstable();
+
+    keys
+}
+
+pub(crate) fn dedup(keys: &mut Vec<Pubkey>) {
+    keys.sort_unstable();
+    keys.dedup();
+}
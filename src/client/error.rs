@@ -0,0 +1,117 @@
+use solana_client::client_error::ClientError;
+use solana_program::address_lookup_table::state::LOOKUP_TABLE_MAX_ADDRESSES;
+use solana_program::message::CompileError;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::TransactionError;
+use std::fmt;
+
+/// Errors of the client side helpers.
+#[derive(Debug)]
+pub enum Error {
+    /// The RPC request failed.
+    Rpc(Box<ClientError>),
+    /// The instructions couldn't be compiled into a message.
+    Compile(CompileError),
+    /// The transaction couldn't be signed.
+    Signer(SignerError),
+    /// The simulation of the transaction failed.
+    Simulation {
+        error: TransactionError,
+        logs: Vec<String>,
+    },
+    /// The amounts paid to this recipient overflow.
+    InvalidPayout(Pubkey),
+    /// A single instruction doesn't fit in a transaction.
+    TransactionTooLarge,
+    /// The account doesn't exist.
+    AccountNotFound(Pubkey),
+    /// The account exists but couldn't be decoded.
+    InvalidAccountData(Pubkey),
+    /// No member of the organization is owned by this referrer treasury.
+    ReferrerMemberNotFound(Pubkey),
+    /// No referrer of this referee can receive the reward.
+    NoReferrer(Pubkey),
+    /// The arguments were rejected by the instruction builder.
+    Program(ProgramError),
+    /// The account source (or the payout journal) doesn't support this request.
+    Unsupported(&'static str),
+    /// An account snapshot file couldn't be loaded.
+    Snapshot(String),
+    /// The payout journal couldn't be read or written.
+    Journal(String),
+    /// An exported transaction couldn't be decoded.
+    InvalidTransaction(String),
+    /// This number of addresses doesn't fit in a lookup table.
+    LookupTableFull(usize),
+    /// The banks client request failed.
+    #[cfg(feature = "banks-client")]
+    Banks(Box<solana_banks_client::BanksClientError>),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Rpc(error) => write!(f, "RPC error: {}", error),
+            Error::Compile(error) => write!(f, "Compile error: {}", error),
+            Error::Signer(error) => write!(f, "Signer error: {}", error),
+            Error::Simulation { error, .. } => write!(f, "Simulation error: {}", error),
+            Error::InvalidPayout(pubkey) => write!(f, "Invalid payout for {}", pubkey),
+            Error::TransactionTooLarge => write!(f, "Instruction too large for a transaction"),
+            Error::AccountNotFound(pubkey) => write!(f, "Account {} not found", pubkey),
+            Error::InvalidAccountData(pubkey) => write!(f, "Invalid data for account {}", pubkey),
+            Error::ReferrerMemberNotFound(pubkey) => {
+                write!(f, "No member owned by the referrer treasury {}", pubkey)
+            }
+            Error::NoReferrer(pubkey) => write!(f, "No referrer can be paid for {}", pubkey),
+            Error::Program(error) => write!(f, "Program error: {}", error),
+            Error::Unsupported(request) => write!(f, "Unsupported request: {}", request),
+            Error::Snapshot(reason) => write!(f, "Invalid snapshot: {}", reason),
+            Error::Journal(reason) => write!(f, "Payout journal error: {}", reason),
+            Error::InvalidTransaction(reason) => write!(f, "Invalid transaction: {}", reason),
+            Error::LookupTableFull(addresses) => write!(
+                f,
+                "{} addresses don't fit in a lookup table of {}",
+                addresses, LOOKUP_TABLE_MAX_ADDRESSES
+            ),
+            #[cfg(feature = "banks-client")]
+            Error::Banks(error) => write!(f, "Banks client error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        Error::Rpc(Box::new(error))
+    }
+}
+
+impl From<CompileError> for Error {
+    fn from(error: CompileError) -> Self {
+        Error::Compile(error)
+    }
+}
+
+impl From<SignerError> for Error {
+    fn from(error: SignerError) -> Self {
+        Error::Signer(error)
+    }
+}
+
+impl From<ProgramError> for Error {
+    fn from(error: ProgramError) -> Self {
+        Error::Program(error)
+    }
+}
+
+#[cfg(feature = "banks-client")]
+impl From<solana_banks_client::BanksClientError> for Error {
+    fn from(error: solana_banks_client::BanksClientError) -> Self {
+        Error::Banks(Box::new(error))
+    }
+}
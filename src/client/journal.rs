@@ -0,0 +1,473 @@
+//! Journal of the payouts, so a reward is paid exactly once even if the worker crashes.
+//!
+//! Each transfer is recorded, signed, before being sent. On restart, [`PayoutJournal::recover`] looks up the
+//! signatures of the pending payouts: the ones that landed are marked as such, the others are sent again
+//! (same signature, they can't be paid twice) and only re-signed once their blockhash has expired.
+
+use crate::client::error::{Error, Result};
+use crate::client::transaction::TransactionBuilder;
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_program::hash::{hashv, Hash};
+use solana_program::instruction::Instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Identifier of a payout, derived from what it pays (a trade, an epoch of a referee...) so that retrying the
+/// same payout gives the same job. Each part is prefixed by its length, so regrouping the same bytes into other
+/// parts gives another job.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct JobId(pub Hash);
+
+impl JobId {
+    pub fn new(parts: &[&[u8]]) -> Self {
+        let lengths: Vec<[u8; 8]> = parts
+            .iter()
+            .map(|part| (part.len() as u64).to_le_bytes())
+            .collect();
+        let prefixed: Vec<&[u8]> = parts
+            .iter()
+            .zip(&lengths)
+            .flat_map(|(part, length)| [length.as_slice(), part])
+            .collect();
+
+        Self(hashv(&prefixed))
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// State of a payout, as last recorded.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PayoutState {
+    /// Signed and (maybe) sent, not known to have landed.
+    Pending,
+    /// Landed successfully, at the commitment of the client.
+    Paid { slot: u64 },
+    /// Landed with an error, nothing was transferred.
+    Failed { error: TransactionError },
+}
+
+/// A payout of the journal.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PayoutEntry {
+    pub job: JobId,
+    /// Instructions given to the transaction builder (without the compute budget).
+    pub instructions: Vec<Instruction>,
+    /// Last signed transaction, its blockhash is the one of the last attempt.
+    pub transaction: VersionedTransaction,
+    /// Last block height at which the transaction can land.
+    pub last_valid_block_height: u64,
+    /// Number of times the payout was signed.
+    pub attempts: u32,
+    pub state: PayoutState,
+}
+
+impl PayoutEntry {
+    /// Signature of the last attempt.
+    pub fn signature(&self) -> Signature {
+        self.transaction.signatures[0]
+    }
+
+    fn to_json(&self) -> Result<Value> {
+        let state = match &self.state {
+            PayoutState::Pending => json!("pending"),
+            PayoutState::Paid { slot } => json!({ "paid": { "slot": slot } }),
+            PayoutState::Failed { error } => {
+                json!({ "failed": serde_json::to_value(error).map_err(json_error)? })
+            }
+        };
+
+        Ok(json!({
+            "job": self.job.to_string(),
+            "instructions": serde_json::to_value(&self.instructions).map_err(json_error)?,
+            "transaction": base64::engine::general_purpose::STANDARD.encode(
+                bincode::serialize(&self.transaction)
+                    .map_err(|error| Error::Journal(error.to_string()))?
+            ),
+            "last_valid_block_height": self.last_valid_block_height,
+            "attempts": self.attempts,
+            "state": state,
+        }))
+    }
+
+    fn from_json(value: Value) -> Result<Self> {
+        let state = match &value["state"] {
+            Value::String(state) if state == "pending" => PayoutState::Pending,
+            state if state["paid"].is_object() => PayoutState::Paid {
+                slot: u64_field(&state["paid"]["slot"])?,
+            },
+            state if !state["failed"].is_null() => PayoutState::Failed {
+                error: serde_json::from_value(state["failed"].clone()).map_err(json_error)?,
+            },
+            state => return Err(Error::Journal(format!("invalid state {}", state))),
+        };
+
+        let job = value["job"]
+            .as_str()
+            .and_then(|x| x.parse().ok())
+            .map(JobId)
+            .ok_or_else(|| Error::Journal(format!("invalid job {}", value["job"])))?;
+
+        Ok(Self {
+            job,
+            instructions: serde_json::from_value(value["instructions"].clone())
+                .map_err(json_error)?,
+            transaction: value["transaction"]
+                .as_str()
+                .and_then(|x| base64::engine::general_purpose::STANDARD.decode(x).ok())
+                .and_then(|x| bincode::deserialize(&x).ok())
+                .ok_or_else(|| Error::Journal(format!("invalid transaction of job {}", job)))?,
+            last_valid_block_height: u64_field(&value["last_valid_block_height"])?,
+            attempts: u64_field(&value["attempts"])? as u32,
+            state,
+        })
+    }
+}
+
+fn json_error(error: serde_json::Error) -> Error {
+    Error::Journal(error.to_string())
+}
+
+fn u64_field(value: &Value) -> Result<u64> {
+    value
+        .as_u64()
+        .ok_or_else(|| Error::Journal(format!("invalid number {}", value)))
+}
+
+/// Storage of the journal, an entry must be durable once [`JournalStore::save`] returns.
+pub trait JournalStore {
+    /// Last saved version of each entry.
+    fn load(&mut self) -> Result<Vec<PayoutEntry>>;
+
+    /// Saves an entry, replacing the previous version of its job.
+    fn save(&mut self, entry: &PayoutEntry) -> Result<()>;
+}
+
+/// Journal kept in memory, for tests or when the process doesn't need to survive a crash.
+#[derive(Clone, Default, Debug)]
+pub struct MemoryJournalStore {
+    entries: Vec<PayoutEntry>,
+}
+
+impl JournalStore for MemoryJournalStore {
+    fn load(&mut self) -> Result<Vec<PayoutEntry>> {
+        Ok(self.entries.clone())
+    }
+
+    fn save(&mut self, entry: &PayoutEntry) -> Result<()> {
+        match self.entries.iter_mut().find(|x| x.job == entry.job) {
+            Some(saved) => *saved = entry.clone(),
+            None => self.entries.push(entry.clone()),
+        }
+
+        Ok(())
+    }
+}
+
+///# File Journal Store
+///
+/// Append-only file with one JSON entry per line, synced to the disk after each save.
+/// The last version of an entry wins.
+/// [`FileJournalStore::compact`] rewrites the file with only the last versions.
+#[derive(Debug)]
+pub struct FileJournalStore {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileJournalStore {
+    /// Opens the journal, creating the file if it doesn't exist.
+    /// A last line torn by a crash while saving is truncated (that version of the entry wasn't sent).
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let io_error =
+            |error: std::io::Error| Error::Journal(format!("{}: {}", path.display(), error));
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(&path)
+            .map_err(io_error)?;
+
+        let content = std::fs::read(&path).map_err(io_error)?;
+        if content.last().is_some_and(|x| *x != b'\n') {
+            let len = content
+                .iter()
+                .rposition(|x| *x == b'\n')
+                .map_or(0, |x| x + 1);
+            file.set_len(len as u64).map_err(io_error)?;
+            file.sync_all().map_err(io_error)?;
+        }
+
+        Ok(Self { path, file })
+    }
+
+    /// Rewrites the file with the last version of each entry (atomically, through a renamed copy).
+    pub fn compact(&mut self) -> Result<()> {
+        let entries = self.load()?;
+        let path = self.path.with_extension("compact");
+        let io_error =
+            |error: std::io::Error| Error::Journal(format!("{}: {}", path.display(), error));
+
+        let mut file = File::create(&path).map_err(io_error)?;
+        for entry in &entries {
+            writeln!(file, "{}", entry.to_json()?).map_err(io_error)?;
+        }
+        file.sync_all().map_err(io_error)?;
+        std::fs::rename(&path, &self.path).map_err(io_error)?;
+
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
+
+impl JournalStore for FileJournalStore {
+    fn load(&mut self) -> Result<Vec<PayoutEntry>> {
+        let io_error =
+            |error: std::io::Error| Error::Journal(format!("{}: {}", self.path.display(), error));
+        let lines: Vec<String> = BufReader::new(File::open(&self.path).map_err(io_error)?)
+            .lines()
+            .collect::<std::io::Result<_>>()
+            .map_err(io_error)?;
+
+        let mut entries: Vec<PayoutEntry> = Vec::new();
+        for line in lines {
+            let entry = PayoutEntry::from_json(serde_json::from_str(&line).map_err(json_error)?)?;
+
+            match entries.iter_mut().find(|x| x.job == entry.job) {
+                Some(saved) => *saved = entry,
+                None => entries.push(entry),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn save(&mut self, entry: &PayoutEntry) -> Result<()> {
+        let io_error =
+            |error: std::io::Error| Error::Journal(format!("{}: {}", self.path.display(), error));
+
+        writeln!(self.file, "{}", entry.to_json()?).map_err(io_error)?;
+        self.file.sync_data().map_err(io_error)
+    }
+}
+
+///# Payout Journal
+///
+/// Sends BuddyLink transfers at most once per [`JobId`]:
+/// 1. The signed transaction is saved before being sent, with the last block height of its blockhash.
+/// 2. [`PayoutJournal::reconcile`] looks up its signature: landed, it's paid (or failed, nothing was transferred).
+/// 3. Not landed, it's sent again as is while the blockhash is valid, and re-signed on a new blockhash once the
+///    finalized block height is past the last valid one (the first transaction can't land anymore).
+///
+/// A failed payout isn't sent again by [`PayoutJournal::pay`], [`PayoutJournal::retry`] re-signs it.
+/// Builders with a durable nonce are rejected: their transaction doesn't expire with the block height, and
+/// re-signing it on a recent blockhash would drop the nonce advance.
+pub struct PayoutJournal<'a, S: JournalStore> {
+    client: &'a RpcClient,
+    store: S,
+    entries: HashMap<JobId, PayoutEntry>,
+}
+
+impl<'a, S: JournalStore> PayoutJournal<'a, S> {
+    /// Loads the entries of the store, call [`PayoutJournal::recover`] to settle the pending ones.
+    pub fn open(client: &'a RpcClient, mut store: S) -> Result<Self> {
+        let entries = store
+            .load()?
+            .into_iter()
+            .map(|entry| (entry.job, entry))
+            .collect();
+
+        Ok(Self {
+            client,
+            store,
+            entries,
+        })
+    }
+
+    pub fn entry(&self, job: &JobId) -> Option<&PayoutEntry> {
+        self.entries.get(job)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &PayoutEntry> {
+        self.entries.values()
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Pays a job once: a new job is signed, saved and sent, a known one is reconciled.
+    /// Fails with [`Error::Unsupported`] if the builder uses a durable nonce.
+    pub fn pay<T: Signers + ?Sized>(
+        &mut self,
+        job: JobId,
+        builder: &TransactionBuilder,
+        signers: &T,
+    ) -> Result<PayoutState> {
+        if builder.uses_durable_nonce() {
+            return Err(Error::Unsupported(
+                "durable nonce in the payout journal, its expiry is tracked by block height",
+            ));
+        }
+
+        if self.entries.contains_key(&job) {
+            return self.reconcile(&job, signers);
+        }
+
+        let (blockhash, last_valid_block_height) = self
+            .client
+            .get_latest_blockhash_with_commitment(self.client.commitment())?;
+        let entry = PayoutEntry {
+            job,
+            instructions: builder.added_instructions().to_vec(),
+            transaction: builder.build_on(self.client, blockhash, signers)?,
+            last_valid_block_height,
+            attempts: 1,
+            state: PayoutState::Pending,
+        };
+
+        self.save(entry)?;
+        self.send(&job)?;
+        Ok(PayoutState::Pending)
+    }
+
+    /// Settles a job from the status of its signature, sending it again (or re-signing it) if it didn't land.
+    pub fn reconcile<T: Signers + ?Sized>(
+        &mut self,
+        job: &JobId,
+        signers: &T,
+    ) -> Result<PayoutState> {
+        let entry = self.entry_or_error(job)?;
+        if entry.state != PayoutState::Pending {
+            return Ok(entry.state.clone());
+        }
+        let signature = entry.signature();
+        let last_valid_block_height = entry.last_valid_block_height;
+
+        // The height first: a signature not found after it is past won't land anymore
+        let block_height = self
+            .client
+            .get_block_height_with_commitment(CommitmentConfig::finalized())?;
+        let status = self
+            .client
+            .get_signature_statuses_with_history(&[signature])?
+            .value
+            .remove(0);
+
+        match status {
+            Some(status) => {
+                if !status.satisfies_commitment(self.client.commitment()) {
+                    return Ok(PayoutState::Pending);
+                }
+
+                let mut entry = entry.clone();
+                entry.state = match status.err {
+                    None => PayoutState::Paid { slot: status.slot },
+                    Some(error) => PayoutState::Failed { error },
+                };
+                let state = entry.state.clone();
+                self.save(entry)?;
+                Ok(state)
+            }
+            None if block_height > last_valid_block_height => self.resign(job, signers),
+            None => {
+                self.send(job)?;
+                Ok(PayoutState::Pending)
+            }
+        }
+    }
+
+    /// Reconciles all the pending jobs, after a restart.
+    pub fn recover<T: Signers + ?Sized>(
+        &mut self,
+        signers: &T,
+    ) -> Result<Vec<(JobId, PayoutState)>> {
+        let pending: Vec<JobId> = self
+            .entries
+            .values()
+            .filter(|x| x.state == PayoutState::Pending)
+            .map(|x| x.job)
+            .collect();
+
+        pending
+            .into_iter()
+            .map(|job| Ok((job, self.reconcile(&job, signers)?)))
+            .collect()
+    }
+
+    /// Re-signs and sends a failed job (the failed transaction transferred nothing).
+    pub fn retry<T: Signers + ?Sized>(&mut self, job: &JobId, signers: &T) -> Result<PayoutState> {
+        match self.entry_or_error(job)?.state {
+            PayoutState::Failed { .. } => self.resign(job, signers),
+            ref state => Ok(state.clone()),
+        }
+    }
+
+    fn resign<T: Signers + ?Sized>(&mut self, job: &JobId, signers: &T) -> Result<PayoutState> {
+        let (blockhash, last_valid_block_height) = self
+            .client
+            .get_latest_blockhash_with_commitment(self.client.commitment())?;
+
+        let mut entry = self.entry_or_error(job)?.clone();
+        // Same blockhash, same signature: the cluster would see it as already processed
+        if *entry.transaction.message.recent_blockhash() == blockhash {
+            return Err(Error::Journal(format!(
+                "blockhash of job {} didn't change, retry later",
+                job
+            )));
+        }
+        let mut message = entry.transaction.message.clone();
+        message.set_recent_blockhash(blockhash);
+        entry.transaction = VersionedTransaction::try_new(message, signers)?;
+        entry.last_valid_block_height = last_valid_block_height;
+        entry.attempts += 1;
+        entry.state = PayoutState::Pending;
+
+        self.save(entry)?;
+        self.send(job)?;
+        Ok(PayoutState::Pending)
+    }
+
+    /// Sends without preflight: the transaction may already have been processed, and a failing one is
+    /// better recorded as failed than retried.
+    fn send(&self, job: &JobId) -> Result<()> {
+        self.client.send_transaction_with_config(
+            &self.entry_or_error(job)?.transaction,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn save(&mut self, entry: PayoutEntry) -> Result<()> {
+        self.store.save(&entry)?;
+        self.entries.insert(entry.job, entry);
+
+        Ok(())
+    }
+
+    fn entry_or_error(&self, job: &JobId) -> Result<&PayoutEntry> {
+        self.entries
+            .get(job)
+            .ok_or_else(|| Error::Journal(format!("unknown job {}", job)))
+    }
+}
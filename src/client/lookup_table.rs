@@ -0,0 +1,184 @@
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::constants::BL_PROGRAM_ID;
+use solana_program::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_program::address_lookup_table::state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::{v0, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Number of addresses added by a single extend instruction, keeps the transaction under the packet size.
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// Static accounts of an organization, used by most of its reward payouts.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct OrganizationLookupTableAccounts {
+    /// Treasuries of the organization.
+    pub treasuries: Vec<Pubkey>,
+    /// Mints the rewards are paid in.
+    pub mints: Vec<Pubkey>,
+    /// Token programs of the mints.
+    pub token_programs: Vec<Pubkey>,
+    /// Treasuries (or token accounts) of the referrers that are paid often.
+    pub referrer_treasuries: Vec<Pubkey>,
+}
+
+impl OrganizationLookupTableAccounts {
+    /// All the addresses to store in the table, without duplicates.
+    /// The BuddyLink program and the system program always come first.
+    ///
+    /// Errors with [`Error::LookupTableFull`] if there are more than `LOOKUP_TABLE_MAX_ADDRESSES`, split the accounts
+    /// over several tables instead.
+    pub fn addresses(&self) -> Result<Vec<Pubkey>> {
+        let mut addresses = vec![BL_PROGRAM_ID, solana_program::system_program::ID];
+
+        for address in self
+            .treasuries
+            .iter()
+            .chain(&self.mints)
+            .chain(&self.token_programs)
+            .chain(&self.referrer_treasuries)
+        {
+            if !addresses.contains(address) {
+                addresses.push(*address);
+            }
+        }
+
+        if addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+            return Err(Error::LookupTableFull(addresses.len()));
+        }
+
+        Ok(addresses)
+    }
+}
+
+///# Create Organization Lookup Table
+///
+/// Instructions creating a lookup table owned by `authority` and filling it with the static accounts of the organization.
+/// The first two instructions can be sent in the same transaction, the others should be sent one per transaction.
+/// Returns the address of the table with the instructions.
+///
+/// `recent_slot` has to be a recent finalized slot (see `RpcClient::get_slot`).
+/// Errors with [`Error::LookupTableFull`] if the accounts don't fit in a table.
+pub fn create_organization_lookup_table(
+    authority: Pubkey,
+    payer: Pubkey,
+    recent_slot: u64,
+    accounts: &OrganizationLookupTableAccounts,
+) -> Result<(Pubkey, Vec<Instruction>)> {
+    let (create_instruction, lookup_table) = create_lookup_table(authority, payer, recent_slot);
+
+    let mut instructions = vec![create_instruction];
+    instructions.extend(extend_instructions(
+        lookup_table,
+        authority,
+        payer,
+        &accounts.addresses()?,
+    ));
+
+    Ok((lookup_table, instructions))
+}
+
+///# Extend Organization Lookup Table
+///
+/// Instructions adding the static accounts of the organization that are not in the table yet.
+/// Addresses added to a table can only be used by transactions from the next slot.
+///
+/// Errors with [`Error::LookupTableFull`] if the missing accounts don't fit in the table.
+pub fn extend_organization_lookup_table(
+    lookup_table: &AddressLookupTableAccount,
+    authority: Pubkey,
+    payer: Pubkey,
+    accounts: &OrganizationLookupTableAccounts,
+) -> Result<Vec<Instruction>> {
+    let missing_addresses: Vec<Pubkey> = accounts
+        .addresses()?
+        .into_iter()
+        .filter(|address| !lookup_table.addresses.contains(address))
+        .collect();
+
+    let addresses = lookup_table.addresses.len() + missing_addresses.len();
+    if addresses > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(Error::LookupTableFull(addresses));
+    }
+
+    Ok(extend_instructions(
+        lookup_table.key,
+        authority,
+        payer,
+        &missing_addresses,
+    ))
+}
+
+fn extend_instructions(
+    lookup_table: Pubkey,
+    authority: Pubkey,
+    payer: Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(MAX_ADDRESSES_PER_EXTEND)
+        .map(|chunk| extend_lookup_table(lookup_table, authority, Some(payer), chunk.to_vec()))
+        .collect()
+}
+
+/// Fetches and decodes a lookup table.
+pub fn fetch_lookup_table(
+    client: &dyn AccountSource,
+    lookup_table: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = client
+        .get_account(lookup_table)?
+        .ok_or(Error::AccountNotFound(*lookup_table))?;
+
+    decode_lookup_table(lookup_table, &account.data)
+}
+
+/// Decodes the data of a lookup table account.
+pub fn decode_lookup_table(
+    lookup_table: &Pubkey,
+    data: &[u8],
+) -> Result<AddressLookupTableAccount> {
+    let table = AddressLookupTable::deserialize(data)
+        .map_err(|_| Error::InvalidAccountData(*lookup_table))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *lookup_table,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Compiles BuddyLink instructions into a v0 message, using the lookup tables for every account that isn't a signer.
+pub fn compile_v0_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage> {
+    Ok(VersionedMessage::V0(v0::Message::try_compile(
+        payer,
+        instructions,
+        lookup_tables,
+        recent_blockhash,
+    )?))
+}
+
+/// Compiles BuddyLink instructions into a signed v0 transaction, the first signer pays the fees.
+pub fn build_v0_transaction<T: Signers + ?Sized>(
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+    signers: &T,
+) -> Result<VersionedTransaction> {
+    let payer = signers.pubkeys().first().copied().ok_or(Error::Signer(
+        solana_sdk::signer::SignerError::NotEnoughSigners,
+    ))?;
+
+    let message = compile_v0_message(&payer, instructions, lookup_tables, recent_blockhash)?;
+
+    Ok(VersionedTransaction::try_new(message, signers)?)
+}
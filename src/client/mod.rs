@@ -0,0 +1,19 @@
+//! Client side helpers (requires the `client` feature).
+
+pub mod account_source;
+pub mod batching;
+pub mod discovery;
+pub mod error;
+pub mod journal;
+pub mod lookup_table;
+pub mod nonblocking;
+pub mod offline;
+pub mod referral_chain;
+pub mod referral_reward;
+pub mod sender;
+pub mod simulation;
+pub mod token_accounts;
+pub mod transaction;
+
+pub use account_source::{AccountSnapshot, AccountSource, AsyncAccountSource};
+pub use error::{Error, Result};
@@ -0,0 +1,279 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::discovery::{
+    decode_page, dedup, key_filters, sorted_keys, treasury_owner_offset, KEYS_ONLY, MAX_PAGE_SIZE,
+    MAX_TREASURY_OWNERS,
+};
+use crate::client::error::Result;
+use crate::client::nonblocking::DEFAULT_CONCURRENCY;
+use crate::constants::BL_PROGRAM_ID;
+use crate::state::{Buddy, Member, Treasury};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use solana_program::pubkey::Pubkey;
+
+type Decode<'a, T> = Box<dyn Fn(Vec<u8>) -> Option<T> + Send + Sync + 'a>;
+
+///# Account Pages
+///
+/// Async [`crate::client::discovery::AccountPages`]: pages are fetched one by one with [`AccountPages::next_page`],
+/// or concurrently with [`AccountPages::all`].
+pub struct AccountPages<'a, T> {
+    client: &'a dyn AsyncAccountSource,
+    keys: Vec<Pubkey>,
+    page_size: usize,
+    position: usize,
+    concurrency: usize,
+    decode: Decode<'a, T>,
+}
+
+impl<'a, T> AccountPages<'a, T> {
+    /// Keys of all the accounts found by the query.
+    pub fn keys(&self) -> &[Pubkey] {
+        &self.keys
+    }
+
+    /// Fetches the next page, None once all the pages are fetched.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<(Pubkey, T)>>> {
+        if self.position >= self.keys.len() {
+            return None;
+        }
+
+        let end = self.keys.len().min(self.position + self.page_size);
+        let keys = &self.keys[self.position..end];
+        self.position = end;
+
+        Some(
+            self.client
+                .get_multiple_accounts(keys)
+                .await
+                .map(|accounts| decode_page(keys, accounts, &self.decode)),
+        )
+    }
+
+    /// Fetches the remaining pages concurrently, in order.
+    pub async fn all(self) -> Result<Vec<(Pubkey, T)>> {
+        let client = self.client;
+        let decode = &self.decode;
+
+        let pages: Vec<Vec<(Pubkey, T)>> =
+            stream::iter(self.keys[self.position..].chunks(self.page_size))
+                .map(|keys| async move {
+                    client
+                        .get_multiple_accounts(keys)
+                        .await
+                        .map(|accounts| decode_page(keys, accounts, decode))
+                })
+                .buffered(self.concurrency)
+                .try_collect()
+                .await?;
+
+        Ok(pages.into_iter().flatten().collect())
+    }
+}
+
+///# Discovery
+///
+/// Async [`crate::client::discovery::Discovery`], with the same queries.
+/// The `getProgramAccounts` of a query on several offsets (treasuries of a buddy or a wallet) are sent concurrently.
+pub struct Discovery<'a> {
+    client: &'a dyn AsyncAccountSource,
+    page_size: usize,
+    max_treasury_owners: usize,
+    concurrency: usize,
+}
+
+impl<'a> Discovery<'a> {
+    pub fn new(client: &'a dyn AsyncAccountSource) -> Self {
+        Self {
+            client,
+            page_size: MAX_PAGE_SIZE,
+            max_treasury_owners: MAX_TREASURY_OWNERS,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Number of accounts fetched per page (at most [`MAX_PAGE_SIZE`]).
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Owner positions of the treasuries looked at when searching the treasuries of a buddy,
+    /// one `getProgramAccounts` per position. Lower it if the treasuries have a known max number of owners.
+    pub fn max_treasury_owners(mut self, max_treasury_owners: usize) -> Self {
+        self.max_treasury_owners = max_treasury_owners.clamp(1, MAX_TREASURY_OWNERS);
+        self
+    }
+
+    /// Requests in flight for a query or when fetching all the pages.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Members of an organization, filtered while fetching the pages.
+    pub async fn organization_members(
+        &self,
+        organization_name: &str,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let organization_name = organization_name.to_string();
+        let keys = self.find_keys(&Member::<&[u8]>::DISCRIMINATOR, &[]).await?;
+
+        Ok(self.pages(keys, move |data| {
+            Member::new(data)
+                .ok()
+                .filter(|x| x.organization_name() == Ok(organization_name.as_str()))
+        }))
+    }
+
+    /// Members referred by a treasury (direct referees in the organizations).
+    pub async fn referees(
+        &self,
+        referrer_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let keys = self
+            .find_keys(
+                &Member::<&[u8]>::DISCRIMINATOR,
+                &[(Member::<&[u8]>::REFERRER_TREASURY_OFFSET, referrer_treasury)],
+            )
+            .await?;
+
+        Ok(self.pages(keys, |data| Member::new(data).ok()))
+    }
+
+    /// Members owned by a treasury (one per organization joined).
+    pub async fn treasury_members(
+        &self,
+        owner_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Member<Vec<u8>>>> {
+        let keys = self
+            .find_keys(
+                &Member::<&[u8]>::DISCRIMINATOR,
+                &[(Member::<&[u8]>::OWNER_TREASURY_OFFSET, owner_treasury)],
+            )
+            .await?;
+
+        Ok(self.pages(keys, |data| Member::new(data).ok()))
+    }
+
+    /// Buddies referred by a treasury (direct referees in the global referral tree).
+    pub async fn global_referees(
+        &self,
+        referrer_treasury: &Pubkey,
+    ) -> Result<AccountPages<'a, Buddy<Vec<u8>>>> {
+        let keys = self
+            .find_keys(
+                &Buddy::<&[u8]>::DISCRIMINATOR,
+                &[(Buddy::<&[u8]>::REFERRER_TREASURY_OFFSET, referrer_treasury)],
+            )
+            .await?;
+
+        Ok(self.pages(keys, |data| Buddy::new(data).ok()))
+    }
+
+    /// Buddies of a wallet (profile and paid buddies).
+    pub async fn buddies(&self, wallet: &Pubkey) -> Result<AccountPages<'a, Buddy<Vec<u8>>>> {
+        let keys = self
+            .find_keys(
+                &Buddy::<&[u8]>::DISCRIMINATOR,
+                &[(Buddy::<&[u8]>::AUTHORITY_OFFSET, wallet)],
+            )
+            .await?;
+
+        Ok(self.pages(keys, |data| Buddy::new(data).ok()))
+    }
+
+    /// Treasuries (partially) owned by a buddy.
+    pub async fn buddy_treasuries(
+        &self,
+        buddy: &Pubkey,
+    ) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let keys = self.find_buddy_treasury_keys(&[*buddy]).await?;
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    /// Treasuries (partially) owned by any buddy of a wallet.
+    pub async fn wallet_treasuries(
+        &self,
+        wallet: &Pubkey,
+    ) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let buddies = self
+            .find_keys(
+                &Buddy::<&[u8]>::DISCRIMINATOR,
+                &[(Buddy::<&[u8]>::AUTHORITY_OFFSET, wallet)],
+            )
+            .await?;
+        let keys = self.find_buddy_treasury_keys(&buddies).await?;
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    /// Treasuries holding the rewards of a mint.
+    pub async fn mint_treasuries(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<AccountPages<'a, Treasury<Vec<u8>>>> {
+        let keys = self
+            .find_keys(
+                &Treasury::<&[u8]>::DISCRIMINATOR,
+                &[(Treasury::<&[u8]>::MINT_OFFSET, mint)],
+            )
+            .await?;
+
+        Ok(self.pages(keys, |data| Treasury::new(data).ok()))
+    }
+
+    /// One `getProgramAccounts` per buddy and owner position, sent concurrently.
+    async fn find_buddy_treasury_keys(&self, buddies: &[Pubkey]) -> Result<Vec<Pubkey>> {
+        let queries = buddies.iter().flat_map(|buddy| {
+            (0..self.max_treasury_owners).map(move |index| (treasury_owner_offset(index), buddy))
+        });
+
+        let keys: Vec<Vec<Pubkey>> = stream::iter(queries)
+            .map(|(offset, buddy)| async move {
+                self.find_keys(&Treasury::<&[u8]>::DISCRIMINATOR, &[(offset, buddy)])
+                    .await
+            })
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let mut keys = keys.concat();
+        dedup(&mut keys);
+
+        Ok(keys)
+    }
+
+    /// Keys of the program accounts with the discriminator and the pubkeys at the given offsets.
+    async fn find_keys(
+        &self,
+        discriminator: &[u8; 8],
+        pubkeys: &[(usize, &Pubkey)],
+    ) -> Result<Vec<Pubkey>> {
+        let accounts = self
+            .client
+            .get_program_accounts(
+                &BL_PROGRAM_ID,
+                &key_filters(discriminator, pubkeys),
+                Some(KEYS_ONLY),
+            )
+            .await?;
+
+        Ok(sorted_keys(accounts))
+    }
+
+    fn pages<T>(
+        &self,
+        keys: Vec<Pubkey>,
+        decode: impl Fn(Vec<u8>) -> Option<T> + Send + Sync + 'a,
+    ) -> AccountPages<'a, T> {
+        AccountPages {
+            client: self.client,
+            keys,
+            page_size: self.page_size,
+            position: 0,
+            concurrency: self.concurrency,
+            decode: Box::new(decode),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::error::{Error, Result};
+use crate::client::lookup_table::decode_lookup_table;
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::pubkey::Pubkey;
+
+/// Fetches and decodes a lookup table.
+pub async fn fetch_lookup_table(
+    client: &dyn AsyncAccountSource,
+    lookup_table: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = client
+        .get_account(lookup_table)
+        .await?
+        .ok_or(Error::AccountNotFound(*lookup_table))?;
+
+    decode_lookup_table(lookup_table, &account.data)
+}
+
+/// Fetches and decodes lookup tables with a single `getMultipleAccounts`.
+pub async fn fetch_lookup_tables(
+    client: &dyn AsyncAccountSource,
+    lookup_tables: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>> {
+    client
+        .get_multiple_accounts(lookup_tables)
+        .await?
+        .into_iter()
+        .zip(lookup_tables)
+        .map(|(account, lookup_table)| {
+            let account = account.ok_or(Error::AccountNotFound(*lookup_table))?;
+            decode_lookup_table(lookup_table, &account.data)
+        })
+        .collect()
+}
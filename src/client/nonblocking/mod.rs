@@ -0,0 +1,16 @@
+//! Async versions of the client helpers, built on `solana_client::nonblocking` (for tokio services).
+//!
+//! The accounts are read through [`AsyncAccountSource`](crate::client::AsyncAccountSource).
+//! Independent requests are sent concurrently, and the `*_many` functions resolve many referees at once
+//! with a bounded number of them in flight.
+
+pub mod discovery;
+pub mod lookup_table;
+pub mod referral_chain;
+pub mod referral_reward;
+pub mod simulation;
+pub mod token_accounts;
+pub mod transaction;
+
+/// Referees resolved at the same time by the `*_many` functions, and pages fetched at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 16;
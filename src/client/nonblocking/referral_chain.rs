@@ -0,0 +1,201 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::error::Result;
+use crate::client::nonblocking::DEFAULT_CONCURRENCY;
+use crate::client::referral_chain::{
+    decode_buddy, decode_link, decode_member, link_keys, main_owner, member_filters,
+    organization_name, reward_mint_of, select_member, ChainEnd, ChainWalk, Referee, ReferralChain,
+    RewardMint, DEFAULT_MAX_DEPTH,
+};
+use crate::constants::BL_PROGRAM_ID;
+use crate::instruction::{transfer_multi_tier_reward, MultiTierRewardArgs, SharedRewardAsset};
+use futures::stream::{self, StreamExt};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+///# Referral Chain Fetcher
+///
+/// Async [`crate::client::referral_chain::ReferralChainFetcher`], the requests of a level are sent concurrently
+/// (`getMultipleAccounts` of the link and `getProgramAccounts` of the referrer member for local chains).
+///
+/// [`ReferralChainFetcher::fetch_many`] walks many chains at once, their first accounts fetched together.
+pub struct ReferralChainFetcher<'a> {
+    client: &'a dyn AsyncAccountSource,
+    max_depth: usize,
+    reward_mint: Option<RewardMint>,
+    concurrency: usize,
+}
+
+impl<'a> ReferralChainFetcher<'a> {
+    pub fn new(client: &'a dyn AsyncAccountSource) -> Self {
+        Self {
+            client,
+            max_depth: DEFAULT_MAX_DEPTH,
+            reward_mint: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Max number of referrers in the chain.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Resolves the accounts of the referrers for this mint instead of SOL.
+    pub fn reward_mint(mut self, mint: Pubkey, token_program: Pubkey) -> Self {
+        self.reward_mint = Some(RewardMint {
+            mint,
+            token_program,
+        });
+        self
+    }
+
+    /// Chains walked at the same time by [`Self::fetch_many`].
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Referrers of a member or of a buddy.
+    pub async fn fetch(&self, referee: &Referee) -> Result<ReferralChain> {
+        let key = referee_key(referee);
+        let account = self.fetch_account(&key).await?;
+
+        self.walk(referee, account).await
+    }
+
+    /// Referrers of a member, within its organization.
+    pub async fn fetch_local(&self, referee_member: &Pubkey) -> Result<ReferralChain> {
+        self.fetch(&Referee::Member(*referee_member)).await
+    }
+
+    /// Referrers of a buddy, in the global referral tree.
+    pub async fn fetch_global(&self, referee_buddy: &Pubkey) -> Result<ReferralChain> {
+        self.fetch(&Referee::Buddy(*referee_buddy)).await
+    }
+
+    /// Referrers of many referees, in the same order.
+    ///
+    /// The referees are fetched together (`getMultipleAccounts` of 100 accounts), then the chains are walked
+    /// concurrently. The outer error is the one of the first fetch, a chain failing doesn't stop the others.
+    pub async fn fetch_many(&self, referees: &[Referee]) -> Result<Vec<Result<ReferralChain>>> {
+        let keys: Vec<Pubkey> = referees.iter().map(referee_key).collect();
+        let accounts = self.client.get_multiple_accounts(&keys).await?;
+
+        Ok(stream::iter(referees.iter().zip(accounts))
+            .map(|(referee, account)| self.walk(referee, account))
+            .buffered(self.concurrency)
+            .collect()
+            .await)
+    }
+
+    async fn walk(&self, referee: &Referee, account: Option<Account>) -> Result<ReferralChain> {
+        match referee {
+            Referee::Member(member) => self.walk_local(member, account).await,
+            Referee::Buddy(buddy) => self.walk_global(buddy, account).await,
+        }
+    }
+
+    async fn walk_local(
+        &self,
+        referee_member: &Pubkey,
+        account: Option<Account>,
+    ) -> Result<ReferralChain> {
+        let mut member = decode_member(referee_member, account)?;
+        let organization = organization_name(referee_member, &member)?;
+        let mut walk = ChainWalk::new(self.max_depth, Some(member.owner_treasury()));
+
+        let end = loop {
+            let treasury = match walk.next(member.referrer_treasury()) {
+                Ok(treasury) => treasury,
+                Err(end) => break end,
+            };
+
+            let keys = link_keys(self.reward_mint, treasury, member.referrer_treasury_key());
+            let filters = member_filters(&treasury);
+            let (accounts, members) = futures::try_join!(
+                self.client.get_multiple_accounts(&keys),
+                self.client
+                    .get_program_accounts(&BL_PROGRAM_ID, &filters, None),
+            )?;
+
+            let (mut link, _) = decode_link(self.reward_mint, &keys, accounts)?;
+            let (referrer, referrer_member) = select_member(&treasury, &organization, members)?;
+
+            link.member = Some(referrer);
+            walk.links.push(link);
+            member = referrer_member;
+        };
+
+        Ok(walk.finish(end, self.reward_mint))
+    }
+
+    async fn walk_global(
+        &self,
+        referee_buddy: &Pubkey,
+        account: Option<Account>,
+    ) -> Result<ReferralChain> {
+        let mut buddy = decode_buddy(referee_buddy, account)?;
+        let mut walk = ChainWalk::new(self.max_depth, None);
+
+        let end = loop {
+            let treasury = match walk.next(buddy.referrer_treasury()) {
+                Ok(treasury) => treasury,
+                Err(end) => break end,
+            };
+
+            let keys = link_keys(self.reward_mint, treasury, None);
+            let (mut link, treasury_data) = decode_link(
+                self.reward_mint,
+                &keys,
+                self.client.get_multiple_accounts(&keys).await?,
+            )?;
+
+            let Some(owner) = main_owner(&treasury_data) else {
+                walk.links.push(link);
+                break ChainEnd::Root;
+            };
+
+            link.buddy = Some(owner);
+            walk.links.push(link);
+            buddy = decode_buddy(&owner, self.fetch_account(&owner).await?)?;
+        };
+
+        Ok(walk.finish(end, self.reward_mint))
+    }
+
+    async fn fetch_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .client
+            .get_multiple_accounts(std::slice::from_ref(pubkey))
+            .await?
+            .pop()
+            .flatten())
+    }
+}
+
+fn referee_key(referee: &Referee) -> Pubkey {
+    match referee {
+        Referee::Member(member) => *member,
+        Referee::Buddy(buddy) => *buddy,
+    }
+}
+
+/// Async [`crate::client::referral_chain::multi_tier_reward`].
+pub async fn multi_tier_reward(
+    client: &dyn AsyncAccountSource,
+    authority: Pubkey,
+    referee: &Referee,
+    asset: &SharedRewardAsset,
+    transfer_args: &MultiTierRewardArgs,
+) -> Result<(Instruction, ReferralChain)> {
+    let mut fetcher = ReferralChainFetcher::new(client).max_depth(transfer_args.schedule.len());
+    fetcher.reward_mint = reward_mint_of(asset);
+
+    let chain = fetcher.fetch(referee).await?;
+    let instruction =
+        transfer_multi_tier_reward(authority, asset, &chain.tier_recipients(), transfer_args)?;
+
+    Ok((instruction, chain))
+}
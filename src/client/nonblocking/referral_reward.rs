@@ -0,0 +1,111 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::error::Result;
+use crate::client::nonblocking::discovery::Discovery;
+use crate::client::nonblocking::referral_chain::ReferralChainFetcher;
+use crate::client::referral_reward::{
+    decode_buddies, plan_referral_reward, select_referee_member, RefereeMember, ReferralReward,
+    ReferralRewardPlan, ResolvedReferral,
+};
+use crate::instruction::SharedRewardAsset;
+use futures::stream::{self, StreamExt};
+use solana_program::pubkey::Pubkey;
+
+/// Async [`crate::client::referral_reward::pay_referral_reward`].
+///
+/// The referee buddies, the organization referrer and the global referrer are resolved concurrently.
+pub async fn pay_referral_reward(
+    client: &dyn AsyncAccountSource,
+    authority: Pubkey,
+    reward: &ReferralReward,
+) -> Result<ReferralRewardPlan> {
+    let referee_buddy_profile = reward.referee_buddy_profile.unwrap_or(reward.referee_buddy);
+
+    let mut fetcher = ReferralChainFetcher::new(client).max_depth(1);
+    if let SharedRewardAsset::Spl {
+        mint,
+        token_program,
+        ..
+    } = reward.asset
+    {
+        fetcher = fetcher.reward_mint(mint, token_program);
+    }
+
+    let buddies = async {
+        let accounts = client
+            .get_multiple_accounts(&[reward.referee_buddy, referee_buddy_profile])
+            .await?;
+        decode_buddies(reward, accounts)
+    };
+
+    let local = async {
+        let Some(organization) = &reward.organization else {
+            return Ok((None, None));
+        };
+
+        let referee = find_referee_member(client, &reward.referee_buddy, organization).await?;
+        let local_link = match referee.as_ref().filter(|x| x.referred) {
+            Some(referee) => fetcher.fetch_local(&referee.member).await?.links.pop(),
+            None => None,
+        };
+
+        Ok((Some(referee), local_link))
+    };
+
+    // Empty chain for a buddy without referrer
+    let global = async {
+        Ok(fetcher
+            .fetch_global(&reward.referee_buddy)
+            .await?
+            .links
+            .pop())
+    };
+
+    let ((buddy, profile), (referee_member, local_link), global_link) =
+        futures::try_join!(buddies, local, global)?;
+
+    plan_referral_reward(
+        authority,
+        reward,
+        ResolvedReferral {
+            buddy,
+            profile,
+            referee_member,
+            local_link,
+            global_link,
+        },
+    )
+}
+
+/// Plans of many rewards, in the same order, with at most `concurrency` rewards resolved at the same time
+/// (see [`super::DEFAULT_CONCURRENCY`]). A reward failing doesn't stop the others.
+pub async fn pay_referral_rewards(
+    client: &dyn AsyncAccountSource,
+    authority: Pubkey,
+    rewards: &[ReferralReward],
+    concurrency: usize,
+) -> Vec<Result<ReferralRewardPlan>> {
+    stream::iter(rewards)
+        .map(|reward| pay_referral_reward(client, authority, reward))
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Member of the organization owned by one of the treasuries of the buddy (as first owner).
+async fn find_referee_member(
+    client: &dyn AsyncAccountSource,
+    buddy: &Pubkey,
+    organization: &str,
+) -> Result<Option<RefereeMember>> {
+    let discovery = Discovery::new(client).max_treasury_owners(1);
+
+    for treasury in discovery.buddy_treasuries(buddy).await?.keys() {
+        let members = discovery.treasury_members(treasury).await?.all().await?;
+
+        if let Some(referee) = select_referee_member(treasury, organization, members) {
+            return Ok(Some(referee));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,31 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::error::Result;
+use crate::client::simulation::{
+    party_keys, reward_parties, reward_simulation, reward_simulation_config, RewardSimulation,
+};
+use crate::client::transaction::TransactionBuilder;
+use solana_client::nonblocking::rpc_client::RpcClient;
+
+/// Async [`crate::client::simulation::simulate_reward`].
+pub async fn simulate_reward(
+    client: &RpcClient,
+    builder: &TransactionBuilder<'_>,
+) -> Result<RewardSimulation> {
+    let parties = reward_parties(builder);
+    let keys = party_keys(&parties);
+
+    let (pre_accounts, recent_blockhash) = futures::try_join!(
+        AsyncAccountSource::get_multiple_accounts(client, &keys),
+        async { Ok(client.get_latest_blockhash().await?) },
+    )?;
+    let transaction = builder.unsigned_transaction(false, recent_blockhash)?;
+    let simulation = client
+        .simulate_transaction_with_config(
+            &transaction,
+            reward_simulation_config(client.commitment(), &keys),
+        )
+        .await?
+        .value;
+
+    reward_simulation(&parties, pre_accounts, simulation)
+}
@@ -0,0 +1,37 @@
+use crate::client::account_source::AsyncAccountSource;
+use crate::client::error::Result;
+use crate::client::token_accounts::{all_referrer_token_accounts, select_missing};
+use crate::instruction::{create_referrer_token_account, ReferrerTokenAccount};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+/// Async [`crate::client::token_accounts::missing_referrer_token_accounts`].
+pub async fn missing_referrer_token_accounts(
+    client: &dyn AsyncAccountSource,
+    instructions: &[Instruction],
+) -> Result<Vec<ReferrerTokenAccount>> {
+    let token_accounts = all_referrer_token_accounts(instructions);
+    if token_accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let keys: Vec<_> = token_accounts.iter().map(|x| x.token_account).collect();
+    let accounts = client.get_multiple_accounts(&keys).await?;
+
+    select_missing(token_accounts, accounts)
+}
+
+/// Async [`crate::client::token_accounts::with_referrer_token_accounts`].
+pub async fn with_referrer_token_accounts(
+    client: &dyn AsyncAccountSource,
+    rent_payer: Pubkey,
+    instructions: Vec<Instruction>,
+) -> Result<Vec<Instruction>> {
+    let missing = missing_referrer_token_accounts(client, &instructions).await?;
+
+    Ok(missing
+        .iter()
+        .map(|x| create_referrer_token_account(rent_payer, x))
+        .chain(instructions)
+        .collect())
+}
@@ -0,0 +1,127 @@
+use crate::client::error::Result;
+use crate::client::transaction::{
+    check_simulation, fee_percentile, preflight_config, unsigned_simulation_config,
+    FixedPriorityFee, TransactionBuilder,
+};
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Async [`crate::client::transaction::PriorityFeeEstimator`].
+#[async_trait]
+pub trait AsyncPriorityFeeEstimator: Send + Sync {
+    /// `writable_accounts` are the accounts locked by the transaction, fees are local to them.
+    async fn compute_unit_price(&self, writable_accounts: &[Pubkey]) -> Result<u64>;
+}
+
+#[async_trait]
+impl AsyncPriorityFeeEstimator for FixedPriorityFee {
+    async fn compute_unit_price(&self, _writable_accounts: &[Pubkey]) -> Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Uses a percentile of the prioritization fees paid recently for the writable accounts.
+pub struct RecentPrioritizationFees<'a> {
+    pub client: &'a RpcClient,
+    /// Between 0 and 100, 50 is the median.
+    pub percentile: u8,
+}
+
+#[async_trait]
+impl AsyncPriorityFeeEstimator for RecentPrioritizationFees<'_> {
+    async fn compute_unit_price(&self, writable_accounts: &[Pubkey]) -> Result<u64> {
+        let fees = self
+            .client
+            .get_recent_prioritization_fees(writable_accounts)
+            .await?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+
+        Ok(fee_percentile(fees, self.percentile))
+    }
+}
+
+/// Async [`TransactionBuilder::simulate_compute_unit_limit`].
+///
+/// The priority fee estimator of the builder is blocking, so it isn't called by the async functions:
+/// the price instruction is added if `priority_fee_estimator` is given.
+pub async fn simulate_compute_unit_limit(
+    builder: &TransactionBuilder<'_>,
+    client: &RpcClient,
+    priority_fee_estimator: Option<&dyn AsyncPriorityFeeEstimator>,
+) -> Result<u32> {
+    let transaction = builder.unsigned_transaction(
+        priority_fee_estimator.is_some(),
+        client.get_latest_blockhash().await?,
+    )?;
+
+    let simulation = client
+        .simulate_transaction_with_config(
+            &transaction,
+            unsigned_simulation_config(client.commitment()),
+        )
+        .await?
+        .value;
+
+    builder.compute_unit_limit_from(simulation)
+}
+
+/// Async [`TransactionBuilder::build`], the compute unit price is given by `priority_fee_estimator`.
+pub async fn build<T: Signers + ?Sized>(
+    builder: &TransactionBuilder<'_>,
+    client: &RpcClient,
+    priority_fee_estimator: Option<&dyn AsyncPriorityFeeEstimator>,
+    signers: &T,
+) -> Result<VersionedTransaction> {
+    let compute_unit_limit = async {
+        match builder.given_compute_unit_limit() {
+            Some(compute_unit_limit) => Ok(compute_unit_limit),
+            None => simulate_compute_unit_limit(builder, client, priority_fee_estimator).await,
+        }
+    };
+
+    let writable_accounts = builder.writable_accounts();
+    let compute_unit_price = async {
+        match priority_fee_estimator {
+            Some(estimator) => Ok(Some(
+                estimator.compute_unit_price(&writable_accounts).await?,
+            )),
+            None => Ok(None),
+        }
+    };
+
+    let recent_blockhash = async { Ok(client.get_latest_blockhash().await?) };
+
+    let (compute_unit_limit, compute_unit_price, recent_blockhash) =
+        futures::try_join!(compute_unit_limit, compute_unit_price, recent_blockhash)?;
+
+    builder.build_with(
+        compute_unit_limit,
+        compute_unit_price,
+        recent_blockhash,
+        signers,
+    )
+}
+
+/// Async [`crate::client::transaction::preflight`].
+pub async fn preflight(client: &RpcClient, transaction: &VersionedTransaction) -> Result<u64> {
+    let simulation = client
+        .simulate_transaction_with_config(transaction, preflight_config(client.commitment()))
+        .await?
+        .value;
+
+    Ok(check_simulation(simulation)?.unwrap_or_default())
+}
+
+/// Sends the transaction and waits for its confirmation at the commitment of the client.
+pub async fn send_and_confirm(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<Signature> {
+    Ok(client.send_and_confirm_transaction(transaction).await?)
+}
@@ -0,0 +1,466 @@
+//! Offline signing of BuddyLink transactions, for reward authorities held in an air-gapped custody.
+//!
+//! The transaction is built on a durable nonce ([`TransactionBuilder::durable_nonce`]) so it doesn't expire while
+//! waiting for the signatures, exported with [`export_transaction`], reviewed with [`summarize`], signed by each
+//! party with [`sign_partial`] and imported back with [`import_transaction`] once [`missing_signers`] is empty.
+//!
+//! [`TransactionBuilder::durable_nonce`]: crate::client::transaction::TransactionBuilder::durable_nonce
+
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::client::simulation::{instruction_parties, Party};
+use crate::constants::{
+    BL_PROGRAM_ID, TRANSFER_REWARD_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SPL_DISCRIMINATOR,
+    TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR, VALIDATE_REFERRER_DISCRIMINATOR,
+};
+use crate::instruction::{
+    GeneralTransferRewardArgs, RewardVariant, TransferUncheckedLocalSharedRewardArgs,
+};
+use base64::Engine;
+use borsh::BorshDeserialize;
+use solana_client::nonce_utils::data_from_account;
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::message::VersionedMessage;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::SystemInstruction;
+use solana_program::system_program;
+use solana_sdk::bs58;
+use solana_sdk::compute_budget;
+use solana_sdk::signer::SignerError;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::VersionedTransaction;
+use std::fmt;
+
+/// Nonce account advanced by a durable nonce transaction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    /// Signer of the AdvanceNonceAccount instruction.
+    pub authority: Pubkey,
+}
+
+/// Nonce stored in a nonce account, the blockhash of the transactions advancing it.
+pub fn nonce_blockhash<S: AccountSource + ?Sized>(
+    source: &S,
+    nonce_account: &Pubkey,
+) -> Result<Hash> {
+    let account = source
+        .get_account(nonce_account)?
+        .ok_or(Error::AccountNotFound(*nonce_account))?;
+
+    Ok(data_from_account(&account)
+        .map_err(|_| Error::InvalidAccountData(*nonce_account))?
+        .blockhash())
+}
+
+/// Text encoding of an exported transaction (of its wire format).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    Base64,
+    Base58,
+}
+
+/// Encodes a transaction, signed or not, to be moved to (or back from) the offline signer.
+pub fn export_transaction(
+    transaction: &VersionedTransaction,
+    encoding: Encoding,
+) -> Result<String> {
+    let bytes = bincode::serialize(transaction)
+        .map_err(|error| Error::InvalidTransaction(error.to_string()))?;
+
+    Ok(match encoding {
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+        Encoding::Base58 => bs58::encode(bytes).into_string(),
+    })
+}
+
+/// Decodes an exported transaction, checking it is well-formed (sanitized) with a signature slot per required signer.
+pub fn import_transaction(encoded: &str, encoding: Encoding) -> Result<VersionedTransaction> {
+    let bytes = match encoding {
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|error| Error::InvalidTransaction(error.to_string()))?,
+        Encoding::Base58 => bs58::decode(encoded.trim())
+            .into_vec()
+            .map_err(|error| Error::InvalidTransaction(error.to_string()))?,
+    };
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+        .map_err(|error| Error::InvalidTransaction(error.to_string()))?;
+    transaction
+        .sanitize()
+        .map_err(|error| Error::InvalidTransaction(error.to_string()))?;
+
+    let required = usize::from(transaction.message.header().num_required_signatures);
+    if transaction.signatures.len() != required {
+        return Err(Error::InvalidTransaction(format!(
+            "{} signatures for {} signers",
+            transaction.signatures.len(),
+            required
+        )));
+    }
+
+    Ok(transaction)
+}
+
+/// Adds the signatures of these signers, the other signatures are kept.
+///
+/// Errors with `KeypairPubkeyMismatch` if a signer isn't required by the transaction, and with
+/// [`Error::InvalidTransaction`] if it doesn't have a key and a signature slot per required signer.
+pub fn sign_partial<T: Signers + ?Sized>(
+    transaction: &mut VersionedTransaction,
+    signers: &T,
+) -> Result<()> {
+    let required = usize::from(transaction.message.header().num_required_signatures);
+    let required_keys = transaction
+        .message
+        .static_account_keys()
+        .get(..required)
+        .ok_or_else(|| {
+            Error::InvalidTransaction(format!("fewer account keys than {} signers", required))
+        })?;
+    let positions = signers
+        .try_pubkeys()?
+        .iter()
+        .map(|pubkey| {
+            required_keys
+                .iter()
+                .position(|x| x == pubkey)
+                .ok_or(SignerError::KeypairPubkeyMismatch)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let signatures = signers.try_sign_message(&transaction.message.serialize())?;
+    for (position, signature) in positions.into_iter().zip(signatures) {
+        let slot = transaction.signatures.get_mut(position).ok_or_else(|| {
+            Error::InvalidTransaction(format!("no signature slot for signer {}", position))
+        })?;
+        *slot = signature;
+    }
+
+    Ok(())
+}
+
+/// Required signers of the transaction, with whether their signature is present and valid.
+pub fn signer_status(transaction: &VersionedTransaction) -> Vec<(Pubkey, bool)> {
+    let message = transaction.message.serialize();
+
+    transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .zip(&transaction.signatures)
+        .map(|(pubkey, signature)| (*pubkey, signature.verify(pubkey.as_ref(), &message)))
+        .collect()
+}
+
+/// Required signers whose signature is missing (or invalid).
+pub fn missing_signers(transaction: &VersionedTransaction) -> Vec<Pubkey> {
+    signer_status(transaction)
+        .into_iter()
+        .filter(|(_, signed)| !signed)
+        .map(|(pubkey, _)| pubkey)
+        .collect()
+}
+
+/// Decoded instruction of a transaction to sign.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InstructionSummary {
+    AdvanceNonce {
+        nonce_account: Pubkey,
+        nonce_authority: Pubkey,
+    },
+    ComputeUnitLimit(u32),
+    /// In micro-lamports per compute unit.
+    ComputeUnitPrice(u64),
+    /// BuddyLink transfer of a single reward.
+    Reward {
+        variant: RewardVariant,
+        amount: u64,
+        parties: Vec<(Party, Pubkey)>,
+    },
+    /// BuddyLink transfer of a shared reward.
+    SharedReward {
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        parties: Vec<(Party, Pubkey)>,
+    },
+    ValidateReferrer,
+    /// Instruction not decoded (or invalid BuddyLink instruction).
+    Other {
+        program_id: Pubkey,
+    },
+}
+
+///# Transaction Summary
+///
+/// What a transaction does, for the review before signing it offline. Its `Display` is the human-readable version.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TransactionSummary {
+    pub fee_payer: Pubkey,
+    /// Recent blockhash, or nonce of the durable nonce account.
+    pub recent_blockhash: Hash,
+    /// Set when the first instruction advances a nonce account.
+    pub durable_nonce: Option<DurableNonce>,
+    pub signers: Vec<(Pubkey, bool)>,
+    pub instructions: Vec<InstructionSummary>,
+}
+
+/// Decodes the instructions of a transaction, the lookup tables of a v0 message are needed to resolve its accounts.
+///
+/// Errors with [`Error::InvalidTransaction`] if the message isn't well-formed (sanitized).
+pub fn summarize(
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<TransactionSummary> {
+    let message = &transaction.message;
+    message
+        .sanitize()
+        .map_err(|error| Error::InvalidTransaction(error.to_string()))?;
+    let fee_payer = message
+        .static_account_keys()
+        .first()
+        .copied()
+        .ok_or_else(|| Error::InvalidTransaction("no fee payer".to_string()))?;
+    let account_keys = account_keys(message, lookup_tables)?;
+    let required = usize::from(message.header().num_required_signatures);
+
+    let instructions: Vec<InstructionSummary> = message
+        .instructions()
+        .iter()
+        .map(|compiled| {
+            let key = |index: u8| {
+                account_keys
+                    .get(usize::from(index))
+                    .copied()
+                    .ok_or_else(|| {
+                        Error::InvalidTransaction(format!("account index {} out of range", index))
+                    })
+            };
+
+            Ok(summarize_instruction(&Instruction {
+                program_id: key(compiled.program_id_index)?,
+                accounts: compiled
+                    .accounts
+                    .iter()
+                    .map(|index| {
+                        Ok(AccountMeta {
+                            pubkey: key(*index)?,
+                            is_signer: usize::from(*index) < required,
+                            is_writable: message.is_maybe_writable(usize::from(*index)),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                data: compiled.data.clone(),
+            }))
+        })
+        .collect::<Result<_>>()?;
+
+    let durable_nonce = match instructions.first() {
+        Some(InstructionSummary::AdvanceNonce {
+            nonce_account,
+            nonce_authority,
+        }) => Some(DurableNonce {
+            account: *nonce_account,
+            authority: *nonce_authority,
+        }),
+        _ => None,
+    };
+
+    Ok(TransactionSummary {
+        fee_payer,
+        recent_blockhash: *message.recent_blockhash(),
+        durable_nonce,
+        signers: signer_status(transaction),
+        instructions,
+    })
+}
+
+/// Static keys, then the writable and the readonly addresses loaded from the lookup tables.
+fn account_keys(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Pubkey>> {
+    let mut keys = message.static_account_keys().to_vec();
+    let Some(lookups) = message.address_table_lookups() else {
+        return Ok(keys);
+    };
+
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let table = lookup_tables
+            .iter()
+            .find(|x| x.key == lookup.account_key)
+            .ok_or_else(|| {
+                Error::InvalidTransaction(format!("lookup table {} not given", lookup.account_key))
+            })?;
+        let address = |index: &u8| {
+            table
+                .addresses
+                .get(usize::from(*index))
+                .copied()
+                .ok_or_else(|| {
+                    Error::InvalidTransaction(format!(
+                        "index {} out of lookup table {}",
+                        index, table.key
+                    ))
+                })
+        };
+
+        for index in &lookup.writable_indexes {
+            writable.push(address(index)?);
+        }
+        for index in &lookup.readonly_indexes {
+            readonly.push(address(index)?);
+        }
+    }
+
+    keys.extend(writable);
+    keys.extend(readonly);
+    Ok(keys)
+}
+
+fn summarize_instruction(instruction: &Instruction) -> InstructionSummary {
+    let other = InstructionSummary::Other {
+        program_id: instruction.program_id,
+    };
+
+    if instruction.program_id == system_program::ID {
+        return match bincode::deserialize(&instruction.data) {
+            Ok(SystemInstruction::AdvanceNonceAccount) if instruction.accounts.len() == 3 => {
+                InstructionSummary::AdvanceNonce {
+                    nonce_account: instruction.accounts[0].pubkey,
+                    nonce_authority: instruction.accounts[2].pubkey,
+                }
+            }
+            _ => other,
+        };
+    }
+
+    if instruction.program_id == compute_budget::ID {
+        // Borsh enum: the variant index, then its field
+        return match (instruction.data.first(), instruction.data.get(1..)) {
+            (Some(2), Some(limit)) => match limit.try_into() {
+                Ok(limit) => InstructionSummary::ComputeUnitLimit(u32::from_le_bytes(limit)),
+                Err(_) => other,
+            },
+            (Some(3), Some(price)) => match price.try_into() {
+                Ok(price) => InstructionSummary::ComputeUnitPrice(u64::from_le_bytes(price)),
+                Err(_) => other,
+            },
+            _ => other,
+        };
+    }
+
+    if instruction.program_id != BL_PROGRAM_ID || instruction.data.len() < 8 {
+        return other;
+    }
+
+    let discriminator: [u8; 8] = instruction.data[..8].try_into().unwrap();
+    let variant = match discriminator {
+        TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR => RewardVariant::SecureLocal,
+        TRANSFER_REWARD_SPL_DISCRIMINATOR => RewardVariant::CheckedGlobal,
+        TRANSFER_REWARD_GLOBAL_DISCRIMINATOR => RewardVariant::CheckedGlobalOnly,
+        TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR => {
+            return match TransferUncheckedLocalSharedRewardArgs::try_from_slice(
+                &instruction.data[8..],
+            ) {
+                Ok(args) => InstructionSummary::SharedReward {
+                    total_amount: args.total_amount,
+                    shares_in_bps: args.shares_in_bps,
+                    parties: instruction_parties(instruction),
+                },
+                Err(_) => other,
+            };
+        }
+        VALIDATE_REFERRER_DISCRIMINATOR => return InstructionSummary::ValidateReferrer,
+        _ => return other,
+    };
+
+    match GeneralTransferRewardArgs::try_from_slice(&instruction.data[8..]) {
+        Ok(args) => InstructionSummary::Reward {
+            variant,
+            amount: args.amount,
+            parties: instruction_parties(instruction),
+        },
+        Err(_) => other,
+    }
+}
+
+impl fmt::Display for TransactionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Fee payer: {}", self.fee_payer)?;
+        match self.durable_nonce {
+            Some(durable_nonce) => writeln!(
+                f,
+                "Durable nonce: {} of account {}",
+                self.recent_blockhash, durable_nonce.account
+            )?,
+            None => writeln!(f, "Recent blockhash: {}", self.recent_blockhash)?,
+        }
+
+        writeln!(f, "Signers:")?;
+        for (pubkey, signed) in &self.signers {
+            let status = if *signed { "signed" } else { "missing" };
+            writeln!(f, "  {} ({})", pubkey, status)?;
+        }
+
+        writeln!(f, "Instructions:")?;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            write!(f, "  {}. ", index + 1)?;
+            match instruction {
+                InstructionSummary::AdvanceNonce {
+                    nonce_account,
+                    nonce_authority,
+                } => writeln!(
+                    f,
+                    "Advance nonce account {} (authority {})",
+                    nonce_account, nonce_authority
+                )?,
+                InstructionSummary::ComputeUnitLimit(limit) => {
+                    writeln!(f, "Compute unit limit: {}", limit)?
+                }
+                InstructionSummary::ComputeUnitPrice(price) => {
+                    writeln!(f, "Compute unit price: {} micro-lamports", price)?
+                }
+                InstructionSummary::Reward {
+                    variant,
+                    amount,
+                    parties,
+                } => {
+                    writeln!(f, "BuddyLink {:?} reward of {}", variant, amount)?;
+                    write_parties(f, parties)?;
+                }
+                InstructionSummary::SharedReward {
+                    total_amount,
+                    shares_in_bps,
+                    parties,
+                } => {
+                    writeln!(
+                        f,
+                        "BuddyLink shared reward of {} (shares in bps: {:?})",
+                        total_amount, shares_in_bps
+                    )?;
+                    write_parties(f, parties)?;
+                }
+                InstructionSummary::ValidateReferrer => writeln!(f, "BuddyLink validate referrer")?,
+                InstructionSummary::Other { program_id } => {
+                    writeln!(f, "Instruction of program {}", program_id)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_parties(f: &mut fmt::Formatter<'_>, parties: &[(Party, Pubkey)]) -> fmt::Result {
+    for (party, pubkey) in parties {
+        writeln!(f, "       {:?}: {}", party, pubkey)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,443 @@
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::constants::BL_PROGRAM_ID;
+use crate::instruction::{
+    transfer_multi_tier_reward, MultiTierRewardArgs, SharedRewardAsset, TierRecipient,
+};
+use crate::state::{find_treasury_address, Buddy, Member, Treasury};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use std::collections::HashSet;
+
+/// Number of referrers fetched when no max depth is given.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Mint of the rewards, used to resolve the treasury for reward and the token account of each referrer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RewardMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// One referrer of the chain, with the accounts expected by the transfer builders.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReferrerLink {
+    /// Treasury that referred the previous link (`referrer_treasury` of the builders).
+    pub treasury: Pubkey,
+    /// Member of the referrer in the organization, organization-local chains only (`referrer_member`).
+    pub member: Option<Pubkey>,
+    /// Buddy owning the largest share of the treasury, global chains only.
+    pub buddy: Option<Pubkey>,
+    /// Treasury of the referrer holding the reward mint (`referrer_treasury_for_reward`).
+    /// None if it doesn't exist, or if it can't be derived (global chain with a treasury of another mint).
+    pub treasury_for_reward: Option<Pubkey>,
+    /// Associated token account of the treasury for reward (`referrer_token_account`).
+    /// None for SOL rewards or if it doesn't exist.
+    pub token_account: Option<Pubkey>,
+}
+
+/// Starting point of a chain: a member (organization-local referral tree) or a buddy (global referral tree).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Referee {
+    Member(Pubkey),
+    Buddy(Pubkey),
+}
+
+/// Why the traversal stopped.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChainEnd {
+    /// The last referrer wasn't referred by anyone.
+    Root,
+    /// The max depth was reached, there could be more referrers.
+    MaxDepth,
+    /// This treasury was already part of the chain.
+    Cycle(Pubkey),
+}
+
+///# Referral Chain
+///
+/// Referrers of a referee, from the direct referrer (first link) up to the top of the referral tree.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReferralChain {
+    pub links: Vec<ReferrerLink>,
+    pub end: ChainEnd,
+    /// Mint the links were resolved for, None for SOL.
+    pub reward_mint: Option<RewardMint>,
+}
+
+impl ReferralChain {
+    /// Accounts receiving the rewards of each link: the token account for SPL rewards, the treasury for reward for SOL.
+    /// None if one of them isn't resolved.
+    pub fn recipients(&self) -> Option<Vec<Pubkey>> {
+        self.links
+            .iter()
+            .map(|link| match self.reward_mint {
+                Some(_) => link.token_account,
+                None => link.treasury_for_reward,
+            })
+            .collect()
+    }
+
+    /// Recipient of each link for [`transfer_multi_tier_reward`], None if it isn't resolved (unclaimed tier).
+    pub fn tier_recipients(&self) -> Vec<Option<TierRecipient>> {
+        self.links
+            .iter()
+            .map(|link| {
+                let recipient = match self.reward_mint {
+                    Some(_) => link.token_account,
+                    None => link.treasury_for_reward,
+                };
+
+                Some(TierRecipient {
+                    recipient: recipient?,
+                    member: link.member,
+                })
+            })
+            .collect()
+    }
+
+    /// Remaining accounts of [`transfer_unchecked_local_shared_reward`], paying every link of the chain.
+    /// None if a recipient (or a member when they are included) isn't resolved.
+    ///
+    /// [`transfer_unchecked_local_shared_reward`]: crate::instruction::transfer_unchecked_local_shared_reward
+    pub fn remaining_accounts(&self, members_included: bool) -> Option<Vec<Pubkey>> {
+        let recipients = self.recipients()?;
+
+        if !members_included {
+            return Some(recipients);
+        }
+
+        recipients
+            .into_iter()
+            .zip(&self.links)
+            .map(|(recipient, link)| Some([recipient, link.member?]))
+            .collect::<Option<Vec<_>>>()
+            .map(|pairs| pairs.concat())
+    }
+}
+
+///# Referral Chain Fetcher
+///
+/// Walks the referrer links upward, from a member (organization-local referral tree)
+/// or from a buddy (global referral tree).
+///
+/// Each level is fetched with a single `getMultipleAccounts` (treasury, treasury for reward and token account),
+/// plus a `getProgramAccounts` for local chains to find the member of the referrer in the organization.
+///
+/// Global chains go through the buddy owning the largest share of each treasury.
+pub struct ReferralChainFetcher<'a> {
+    client: &'a dyn AccountSource,
+    max_depth: usize,
+    reward_mint: Option<RewardMint>,
+}
+
+impl<'a> ReferralChainFetcher<'a> {
+    pub fn new(client: &'a dyn AccountSource) -> Self {
+        Self {
+            client,
+            max_depth: DEFAULT_MAX_DEPTH,
+            reward_mint: None,
+        }
+    }
+
+    /// Max number of referrers in the chain.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Resolves the accounts of the referrers for this mint instead of SOL.
+    pub fn reward_mint(mut self, mint: Pubkey, token_program: Pubkey) -> Self {
+        self.reward_mint = Some(RewardMint {
+            mint,
+            token_program,
+        });
+        self
+    }
+
+    /// Referrers of a member or of a buddy.
+    pub fn fetch(&self, referee: &Referee) -> Result<ReferralChain> {
+        match referee {
+            Referee::Member(member) => self.fetch_local(member),
+            Referee::Buddy(buddy) => self.fetch_global(buddy),
+        }
+    }
+
+    /// Referrers of a member, within its organization.
+    pub fn fetch_local(&self, referee_member: &Pubkey) -> Result<ReferralChain> {
+        let mut member = decode_member(referee_member, self.fetch_account(referee_member)?)?;
+        let organization = organization_name(referee_member, &member)?;
+        let mut walk = ChainWalk::new(self.max_depth, Some(member.owner_treasury()));
+
+        let end = loop {
+            let treasury = match walk.next(member.referrer_treasury()) {
+                Ok(treasury) => treasury,
+                Err(end) => break end,
+            };
+
+            let keys = link_keys(self.reward_mint, treasury, member.referrer_treasury_key());
+            let (mut link, _) = decode_link(self.reward_mint, &keys, self.fetch_accounts(&keys)?)?;
+            let (referrer, referrer_member) = select_member(
+                &treasury,
+                &organization,
+                self.client.get_program_accounts(
+                    &BL_PROGRAM_ID,
+                    &member_filters(&treasury),
+                    None,
+                )?,
+            )?;
+
+            link.member = Some(referrer);
+            walk.links.push(link);
+            member = referrer_member;
+        };
+
+        Ok(walk.finish(end, self.reward_mint))
+    }
+
+    /// Referrers of a buddy, in the global referral tree.
+    pub fn fetch_global(&self, referee_buddy: &Pubkey) -> Result<ReferralChain> {
+        let mut buddy = decode_buddy(referee_buddy, self.fetch_account(referee_buddy)?)?;
+        let mut walk = ChainWalk::new(self.max_depth, None);
+
+        let end = loop {
+            let treasury = match walk.next(buddy.referrer_treasury()) {
+                Ok(treasury) => treasury,
+                Err(end) => break end,
+            };
+
+            let keys = link_keys(self.reward_mint, treasury, None);
+            let (mut link, treasury_data) =
+                decode_link(self.reward_mint, &keys, self.fetch_accounts(&keys)?)?;
+
+            let Some(owner) = main_owner(&treasury_data) else {
+                walk.links.push(link);
+                break ChainEnd::Root;
+            };
+
+            link.buddy = Some(owner);
+            walk.links.push(link);
+            buddy = decode_buddy(&owner, self.fetch_account(&owner)?)?;
+        };
+
+        Ok(walk.finish(end, self.reward_mint))
+    }
+
+    fn fetch_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .fetch_accounts(std::slice::from_ref(pubkey))?
+            .pop()
+            .flatten())
+    }
+
+    fn fetch_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        self.client.get_multiple_accounts(pubkeys)
+    }
+}
+
+/// Links of a chain being walked, stops at the root, the max depth or a cycle.
+pub(crate) struct ChainWalk {
+    pub(crate) links: Vec<ReferrerLink>,
+    visited: HashSet<Pubkey>,
+    max_depth: usize,
+}
+
+impl ChainWalk {
+    pub(crate) fn new(max_depth: usize, start: Option<Pubkey>) -> Self {
+        Self {
+            links: Vec::new(),
+            visited: start.into_iter().collect(),
+            max_depth,
+        }
+    }
+
+    /// Treasury of the next link, or why the chain ends.
+    pub(crate) fn next(
+        &mut self,
+        referrer_treasury: Option<Pubkey>,
+    ) -> std::result::Result<Pubkey, ChainEnd> {
+        let Some(treasury) = referrer_treasury else {
+            return Err(ChainEnd::Root);
+        };
+        if self.links.len() == self.max_depth {
+            return Err(ChainEnd::MaxDepth);
+        }
+        if !self.visited.insert(treasury) {
+            return Err(ChainEnd::Cycle(treasury));
+        }
+
+        Ok(treasury)
+    }
+
+    pub(crate) fn finish(self, end: ChainEnd, reward_mint: Option<RewardMint>) -> ReferralChain {
+        ReferralChain {
+            links: self.links,
+            end,
+            reward_mint,
+        }
+    }
+}
+
+/// Keys fetched in the same request for a link: the treasury, its treasury for reward and token account.
+/// The treasury for reward is derived from the key kept by the referee member, or is the treasury itself.
+pub(crate) fn link_keys(
+    reward_mint: Option<RewardMint>,
+    treasury: Pubkey,
+    treasury_key: Option<Pubkey>,
+) -> Vec<Pubkey> {
+    let treasury_for_reward = match (reward_mint, treasury_key) {
+        (Some(reward_mint), Some(key)) => find_treasury_address(&reward_mint.mint, &key).0,
+        _ => treasury,
+    };
+    let token_account = reward_mint.map(|reward_mint| {
+        get_associated_token_address_with_program_id(
+            &treasury_for_reward,
+            &reward_mint.mint,
+            &reward_mint.token_program,
+        )
+    });
+
+    let mut keys = vec![treasury, treasury_for_reward];
+    keys.extend(token_account);
+
+    keys
+}
+
+/// Link from the accounts of [`link_keys`].
+pub(crate) fn decode_link(
+    reward_mint: Option<RewardMint>,
+    keys: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+) -> Result<(ReferrerLink, Treasury<Vec<u8>>)> {
+    let (treasury, treasury_for_reward, token_account) = (keys[0], keys[1], keys.get(2).copied());
+    let mut accounts = accounts.into_iter();
+
+    let treasury_data = accounts
+        .next()
+        .flatten()
+        .ok_or(Error::AccountNotFound(treasury))?;
+    let treasury_data =
+        Treasury::new(treasury_data.data).map_err(|_| Error::InvalidAccountData(treasury))?;
+
+    let treasury_for_reward = accounts
+        .next()
+        .flatten()
+        .and_then(|account| Treasury::new(account.data).ok())
+        .filter(|x| reward_mint.is_none_or(|reward_mint| x.mint() == reward_mint.mint))
+        .map(|_| treasury_for_reward);
+
+    let token_account = token_account
+        .filter(|_| treasury_for_reward.is_some())
+        .filter(|_| accounts.next().flatten().is_some());
+
+    Ok((
+        ReferrerLink {
+            treasury,
+            member: None,
+            buddy: None,
+            treasury_for_reward,
+            token_account,
+        },
+        treasury_data,
+    ))
+}
+
+/// `getProgramAccounts` filters of the members owned by a treasury.
+pub(crate) fn member_filters(treasury: &Pubkey) -> [RpcFilterType; 2] {
+    [
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &Member::<&[u8]>::DISCRIMINATOR,
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            Member::<&[u8]>::OWNER_TREASURY_OFFSET,
+            treasury.as_ref(),
+        )),
+    ]
+}
+
+/// Member of the organization among the members owned by the treasury.
+pub(crate) fn select_member(
+    treasury: &Pubkey,
+    organization: &str,
+    members: Vec<(Pubkey, Account)>,
+) -> Result<(Pubkey, Member<Vec<u8>>)> {
+    members
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, Member::new(account.data).ok()?)))
+        .find(|(_, member)| member.organization_name() == Ok(organization))
+        .ok_or(Error::ReferrerMemberNotFound(*treasury))
+}
+
+/// Buddy owning the largest share of the treasury.
+pub(crate) fn main_owner(treasury: &Treasury<Vec<u8>>) -> Option<Pubkey> {
+    treasury
+        .owners()
+        .reduce(|main, owner| {
+            if owner.share_in_bps > main.share_in_bps {
+                owner
+            } else {
+                main
+            }
+        })
+        .map(|owner| owner.buddy)
+}
+
+pub(crate) fn organization_name(pubkey: &Pubkey, member: &Member<Vec<u8>>) -> Result<String> {
+    Ok(member
+        .organization_name()
+        .map_err(|_| Error::InvalidAccountData(*pubkey))?
+        .to_string())
+}
+
+pub(crate) fn decode_member(pubkey: &Pubkey, account: Option<Account>) -> Result<Member<Vec<u8>>> {
+    Member::new(account.ok_or(Error::AccountNotFound(*pubkey))?.data)
+        .map_err(|_| Error::InvalidAccountData(*pubkey))
+}
+
+pub(crate) fn decode_buddy(pubkey: &Pubkey, account: Option<Account>) -> Result<Buddy<Vec<u8>>> {
+    Buddy::new(account.ok_or(Error::AccountNotFound(*pubkey))?.data)
+        .map_err(|_| Error::InvalidAccountData(*pubkey))
+}
+
+/// Mint the links are resolved for, None for SOL.
+pub(crate) fn reward_mint_of(asset: &SharedRewardAsset) -> Option<RewardMint> {
+    match *asset {
+        SharedRewardAsset::Sol => None,
+        SharedRewardAsset::Spl {
+            mint,
+            token_program,
+            ..
+        } => Some(RewardMint {
+            mint,
+            token_program,
+        }),
+    }
+}
+
+///# Multi Tier Reward
+///
+/// Resolves the referrers of a referee for the asset (as many as tiers in the schedule)
+/// and splits the reward between them with [`transfer_multi_tier_reward`].
+///
+/// Referrers without a treasury for reward or a token account for the mint are unclaimed tiers.
+/// The chain is returned with the instruction to know who is paid.
+pub fn multi_tier_reward(
+    client: &dyn AccountSource,
+    authority: Pubkey,
+    referee: &Referee,
+    asset: &SharedRewardAsset,
+    transfer_args: &MultiTierRewardArgs,
+) -> Result<(Instruction, ReferralChain)> {
+    let mut fetcher = ReferralChainFetcher::new(client).max_depth(transfer_args.schedule.len());
+    fetcher.reward_mint = reward_mint_of(asset);
+
+    let chain = fetcher.fetch(referee)?;
+    let instruction =
+        transfer_multi_tier_reward(authority, asset, &chain.tier_recipients(), transfer_args)?;
+
+    Ok((instruction, chain))
+}
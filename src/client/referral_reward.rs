@@ -0,0 +1,312 @@
+use crate::client::account_source::AccountSource;
+use crate::client::discovery::Discovery;
+use crate::client::error::{Error, Result};
+use crate::client::referral_chain::{decode_buddy, ReferralChainFetcher, ReferrerLink};
+use crate::instruction::{
+    choose_reward_variant, transfer_checked_global_only_reward, transfer_checked_global_reward,
+    transfer_secure_local_reward, transfer_unchecked_local_shared_reward,
+    GeneralTransferRewardArgs, LocalReferrer, RewardAsset, RewardRoute, RewardVariant,
+    RewardVariantReason, SharedRewardAsset, TransferUncheckedLocalSharedRewardArgs,
+};
+use crate::state::{Buddy, Member};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Referral reward to pay, the accounts of the referrers are resolved by [`pay_referral_reward`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReferralReward {
+    /// Buddy of the referee (paid buddy or profile).
+    pub referee_buddy: Pubkey,
+    /// Profile of the referee, None if it's the referee buddy.
+    pub referee_buddy_profile: Option<Pubkey>,
+    /// Organization paying the reward, None to only use the global referral tree.
+    pub organization: Option<String>,
+    /// Asset of the reward, the receiving accounts are resolved.
+    pub asset: SharedRewardAsset,
+    pub amount: u64,
+}
+
+/// Instruction paying a [`ReferralReward`], with the variant chosen and why.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReferralRewardPlan {
+    pub instruction: Instruction,
+    pub variant: RewardVariant,
+    pub reason: RewardVariantReason,
+    /// Referrer paid by the instruction, the global referrer for [`RewardVariant::CheckedGlobalOnly`].
+    pub referrer: ReferrerLink,
+}
+
+/// Referee within the organization.
+pub(crate) struct RefereeMember {
+    pub(crate) member: Pubkey,
+    pub(crate) treasury: Pubkey,
+    /// The member was referred in the organization.
+    pub(crate) referred: bool,
+}
+
+/// Accounts of a [`ReferralReward`] resolved on-chain.
+pub(crate) struct ResolvedReferral {
+    pub(crate) buddy: Buddy<Vec<u8>>,
+    pub(crate) profile: Buddy<Vec<u8>>,
+    /// None without organization, Some(None) if the referee isn't a member of it.
+    pub(crate) referee_member: Option<Option<RefereeMember>>,
+    /// Direct referrer in the organization.
+    pub(crate) local_link: Option<ReferrerLink>,
+    /// Direct referrer in the global referral tree.
+    pub(crate) global_link: Option<ReferrerLink>,
+}
+
+///# Pay Referral Reward
+///
+/// Resolves the referrers of a referee and pays the reward with the variant fitting what exists on-chain
+/// (see [`choose_reward_variant`]).
+///
+/// With an organization, the referee member is looked up among the members owned by the treasuries
+/// the referee buddy is the first owner of.
+pub fn pay_referral_reward(
+    client: &dyn AccountSource,
+    authority: Pubkey,
+    reward: &ReferralReward,
+) -> Result<ReferralRewardPlan> {
+    let referee_buddy_profile = reward.referee_buddy_profile.unwrap_or(reward.referee_buddy);
+    let (buddy, profile) = decode_buddies(
+        reward,
+        client.get_multiple_accounts(&[reward.referee_buddy, referee_buddy_profile])?,
+    )?;
+
+    let mut fetcher = ReferralChainFetcher::new(client).max_depth(1);
+    if let SharedRewardAsset::Spl {
+        mint,
+        token_program,
+        ..
+    } = reward.asset
+    {
+        fetcher = fetcher.reward_mint(mint, token_program);
+    }
+
+    let mut local_link = None;
+    let mut referee_member = None;
+    if let Some(organization) = &reward.organization {
+        let referee = find_referee_member(client, &reward.referee_buddy, organization)?;
+
+        if let Some(referee) = referee.as_ref().filter(|x| x.referred) {
+            local_link = fetcher.fetch_local(&referee.member)?.links.pop();
+        }
+        referee_member = Some(referee);
+    }
+
+    let global_link = match buddy.referrer_treasury() {
+        Some(_) => fetcher.fetch_global(&reward.referee_buddy)?.links.pop(),
+        None => None,
+    };
+
+    plan_referral_reward(
+        authority,
+        reward,
+        ResolvedReferral {
+            buddy,
+            profile,
+            referee_member,
+            local_link,
+            global_link,
+        },
+    )
+}
+
+/// Referee buddy and profile.
+pub(crate) type RefereeBuddies = (Buddy<Vec<u8>>, Buddy<Vec<u8>>);
+
+/// Referee buddy and profile, from the accounts of `[referee_buddy, referee_buddy_profile]`.
+pub(crate) fn decode_buddies(
+    reward: &ReferralReward,
+    accounts: Vec<Option<Account>>,
+) -> Result<RefereeBuddies> {
+    let referee_buddy_profile = reward.referee_buddy_profile.unwrap_or(reward.referee_buddy);
+    let mut accounts = accounts.into_iter();
+
+    Ok((
+        decode_buddy(&reward.referee_buddy, accounts.next().flatten())?,
+        decode_buddy(&referee_buddy_profile, accounts.next().flatten())?,
+    ))
+}
+
+/// Chooses the variant from the resolved accounts and builds its instruction.
+pub(crate) fn plan_referral_reward(
+    authority: Pubkey,
+    reward: &ReferralReward,
+    resolved: ResolvedReferral,
+) -> Result<ReferralRewardPlan> {
+    let ResolvedReferral {
+        buddy,
+        profile,
+        referee_member,
+        local_link,
+        global_link,
+    } = resolved;
+    let referee_buddy_profile = reward.referee_buddy_profile.unwrap_or(reward.referee_buddy);
+
+    let sol = reward.asset == SharedRewardAsset::Sol;
+    let can_receive = |link: &ReferrerLink| {
+        link.treasury_for_reward.is_some() && (sol || link.token_account.is_some())
+    };
+
+    let local_referrer = match &referee_member {
+        None => LocalReferrer::NoOrganization,
+        Some(None) => LocalReferrer::NotAMember,
+        Some(Some(referee)) if !referee.referred => LocalReferrer::NotReferred,
+        Some(Some(_)) => match &local_link {
+            Some(link) if can_receive(link) => LocalReferrer::Resolved,
+            _ => LocalReferrer::CantReceive,
+        },
+    };
+    let referee_member = referee_member.flatten();
+    let global_link = global_link.filter(can_receive);
+
+    let (variant, reason) = choose_reward_variant(&RewardRoute {
+        local_referrer,
+        global_referrer: global_link.is_some(),
+        referee_signed: buddy.authority() == authority && profile.authority() == authority,
+        sol,
+    })
+    .ok_or(Error::NoReferrer(reward.referee_buddy))?;
+
+    let transfer_args = GeneralTransferRewardArgs {
+        amount: reward.amount,
+    };
+
+    // The chosen variant guarantees the links and the referee member it uses are resolved
+    let (instruction, referrer) = match (variant, reward.asset) {
+        (RewardVariant::CheckedGlobalOnly, asset) => {
+            let link = global_link.expect("global referrer");
+            let asset = match asset {
+                SharedRewardAsset::Sol => RewardAsset::Sol,
+                SharedRewardAsset::Spl {
+                    mint,
+                    token_program,
+                    from,
+                } => RewardAsset::Spl {
+                    mint,
+                    token_program,
+                    from,
+                    to: link.token_account.expect("token account"),
+                },
+            };
+
+            let instruction = transfer_checked_global_only_reward(
+                authority,
+                &asset,
+                link.treasury,
+                link.treasury_for_reward.expect("treasury for reward"),
+                referee_buddy_profile,
+                reward.referee_buddy,
+                &transfer_args,
+            );
+            (instruction, link)
+        }
+        (_, SharedRewardAsset::Sol) => {
+            let link = local_link.expect("organization referrer");
+            let instruction = transfer_unchecked_local_shared_reward(
+                authority,
+                &SharedRewardAsset::Sol,
+                &[
+                    link.treasury_for_reward.expect("treasury for reward"),
+                    link.member.expect("referrer member"),
+                ],
+                &TransferUncheckedLocalSharedRewardArgs {
+                    total_amount: reward.amount,
+                    shares_in_bps: vec![10_000],
+                    members_included: true,
+                },
+            );
+            (instruction, link)
+        }
+        (
+            variant,
+            SharedRewardAsset::Spl {
+                mint,
+                token_program,
+                from,
+            },
+        ) => {
+            let link = local_link.expect("organization referrer");
+            let referee = referee_member.expect("referee member");
+
+            let instruction = if variant == RewardVariant::SecureLocal {
+                transfer_secure_local_reward(
+                    authority,
+                    mint,
+                    token_program,
+                    from,
+                    link.token_account.expect("token account"),
+                    link.member.expect("referrer member"),
+                    link.treasury,
+                    link.treasury_for_reward.expect("treasury for reward"),
+                    referee_buddy_profile,
+                    reward.referee_buddy,
+                    referee.treasury,
+                    referee.member,
+                    &transfer_args,
+                )
+            } else {
+                transfer_checked_global_reward(
+                    authority,
+                    mint,
+                    token_program,
+                    from,
+                    link.token_account.expect("token account"),
+                    link.member,
+                    link.treasury,
+                    link.treasury_for_reward.expect("treasury for reward"),
+                    referee.member,
+                    global_link.as_ref().and_then(|x| x.treasury_for_reward),
+                    global_link.as_ref().and_then(|x| x.token_account),
+                    &transfer_args,
+                )
+            };
+            (instruction, link)
+        }
+    };
+
+    Ok(ReferralRewardPlan {
+        instruction,
+        variant,
+        reason,
+        referrer,
+    })
+}
+
+/// Member of the organization owned by one of the treasuries of the buddy (as first owner).
+fn find_referee_member(
+    client: &dyn AccountSource,
+    buddy: &Pubkey,
+    organization: &str,
+) -> Result<Option<RefereeMember>> {
+    let discovery = Discovery::new(client).max_treasury_owners(1);
+
+    for treasury in discovery.buddy_treasuries(buddy)?.keys() {
+        let members = discovery.treasury_members(treasury)?.all()?;
+
+        if let Some(referee) = select_referee_member(treasury, organization, members) {
+            return Ok(Some(referee));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Referee member of the organization among the members owned by a treasury.
+pub(crate) fn select_referee_member(
+    treasury: &Pubkey,
+    organization: &str,
+    members: Vec<(Pubkey, Member<Vec<u8>>)>,
+) -> Option<RefereeMember> {
+    members
+        .into_iter()
+        .find(|(_, member)| member.organization_name() == Ok(organization))
+        .map(|(member, data)| RefereeMember {
+            member,
+            treasury: *treasury,
+            referred: data.referrer_treasury().is_some(),
+        })
+}
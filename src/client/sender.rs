@@ -0,0 +1,252 @@
+//! Send and confirm aware of the expiry of the blockhash, instead of confirming with a spinner and sleeping.
+
+use crate::client::error::Result;
+use crate::client::transaction::TransactionBuilder;
+use crate::constants::BL_PROGRAM_ID;
+use crate::error::BuddyLinkError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_program::instruction::InstructionError;
+use solana_program::message::VersionedMessage;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Interval between two broadcasts of a transaction not landed yet.
+pub const DEFAULT_REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval between two lookups of the signature.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Final state of a sent transaction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SendOutcome {
+    /// Landed successfully, at the commitment of the client.
+    Confirmed { signature: Signature, slot: u64 },
+    /// Landed with an error: the fees were paid, nothing was transferred.
+    Failed {
+        signature: Signature,
+        slot: u64,
+        error: TransactionError,
+        /// The error, when returned by a BuddyLink instruction.
+        buddy_link_error: Option<BuddyLinkError>,
+    },
+    /// The finalized block height is past the last valid one without the transaction landing: it can't land
+    /// anymore, re-signing it on a new blockhash is safe.
+    Expired { signature: Signature },
+    /// Gave up before the blockhash expired: the transaction may still land, don't re-sign it yet.
+    Unknown { signature: Signature },
+}
+
+impl SendOutcome {
+    pub fn signature(&self) -> Signature {
+        match self {
+            SendOutcome::Confirmed { signature, .. }
+            | SendOutcome::Failed { signature, .. }
+            | SendOutcome::Expired { signature }
+            | SendOutcome::Unknown { signature } => *signature,
+        }
+    }
+
+    /// Whether the transaction can be re-signed and sent again without risking to execute it twice.
+    pub fn is_safe_to_retry(&self) -> bool {
+        matches!(
+            self,
+            SendOutcome::Failed { .. } | SendOutcome::Expired { .. }
+        )
+    }
+}
+
+/// Progress of [`ReliableSender::send`], given to the callback.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SendProgress {
+    /// Broadcast of the transaction, 1 for the first one.
+    Sent {
+        signature: Signature,
+        broadcast: u32,
+    },
+    /// The signature was found, below the commitment of the client.
+    Landed { signature: Signature, slot: u64 },
+    /// The signature wasn't found yet.
+    Waiting {
+        signature: Signature,
+        block_height: u64,
+        last_valid_block_height: u64,
+    },
+}
+
+/// Error of the BuddyLink program returned by a failed transaction, None if the failing instruction
+/// isn't a BuddyLink one or the error isn't a custom BuddyLink error.
+pub fn decode_buddy_link_error(
+    message: &VersionedMessage,
+    error: &TransactionError,
+) -> Option<BuddyLinkError> {
+    let TransactionError::InstructionError(index, InstructionError::Custom(code)) = error else {
+        return None;
+    };
+
+    let instruction = message.instructions().get(usize::from(*index))?;
+    if message
+        .static_account_keys()
+        .get(usize::from(instruction.program_id_index))
+        != Some(&BL_PROGRAM_ID)
+    {
+        return None;
+    }
+
+    BuddyLinkError::from_code(*code)
+}
+
+///# Reliable Sender
+///
+/// Sends a transaction and waits for it at the commitment of the client:
+/// - it's broadcast again every `rebroadcast_interval` while not landed (the RPC node doesn't retry it),
+/// - it's [`SendOutcome::Expired`] once the finalized block height is past its `last_valid_block_height`,
+/// - it's [`SendOutcome::Unknown`] if the timeout is reached first.
+///
+/// Preflight is skipped, a failing transaction lands as [`SendOutcome::Failed`] with the decoded error.
+pub struct ReliableSender<'a> {
+    client: &'a RpcClient,
+    rebroadcast_interval: Duration,
+    poll_interval: Duration,
+    timeout: Option<Duration>,
+    on_progress: Option<&'a dyn Fn(&SendProgress)>,
+}
+
+impl<'a> ReliableSender<'a> {
+    pub fn new(client: &'a RpcClient) -> Self {
+        Self {
+            client,
+            rebroadcast_interval: DEFAULT_REBROADCAST_INTERVAL,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: None,
+            on_progress: None,
+        }
+    }
+
+    pub fn rebroadcast_interval(mut self, rebroadcast_interval: Duration) -> Self {
+        self.rebroadcast_interval = rebroadcast_interval;
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Gives up with [`SendOutcome::Unknown`] after this duration, waits for the expiry otherwise.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn on_progress(mut self, on_progress: &'a dyn Fn(&SendProgress)) -> Self {
+        self.on_progress = Some(on_progress);
+        self
+    }
+
+    /// Signs the transaction on the latest blockhash and sends it.
+    pub fn sign_and_send<T: Signers + ?Sized>(
+        &self,
+        builder: &TransactionBuilder,
+        signers: &T,
+    ) -> Result<SendOutcome> {
+        let (blockhash, last_valid_block_height) = self
+            .client
+            .get_latest_blockhash_with_commitment(self.client.commitment())?;
+        let transaction = builder.build_on(self.client, blockhash, signers)?;
+
+        self.send(&transaction, last_valid_block_height)
+    }
+
+    /// Sends a transaction whose blockhash is valid until `last_valid_block_height`.
+    pub fn send(
+        &self,
+        transaction: &VersionedTransaction,
+        last_valid_block_height: u64,
+    ) -> Result<SendOutcome> {
+        let signature = transaction.signatures[0];
+        let start = Instant::now();
+        let mut broadcast = 0;
+        let mut last_broadcast: Option<Instant> = None;
+
+        loop {
+            if last_broadcast.is_none_or(|x| x.elapsed() >= self.rebroadcast_interval) {
+                self.broadcast(transaction)?;
+                broadcast += 1;
+                last_broadcast = Some(Instant::now());
+                self.progress(SendProgress::Sent {
+                    signature,
+                    broadcast,
+                });
+            }
+
+            // The height first: a signature not found after it is past won't land anymore
+            let block_height = self
+                .client
+                .get_block_height_with_commitment(CommitmentConfig::finalized())?;
+            let status = self
+                .client
+                .get_signature_statuses(&[signature])?
+                .value
+                .remove(0);
+
+            match status {
+                Some(status) if status.satisfies_commitment(self.client.commitment()) => {
+                    return Ok(match status.err {
+                        None => SendOutcome::Confirmed {
+                            signature,
+                            slot: status.slot,
+                        },
+                        Some(error) => SendOutcome::Failed {
+                            signature,
+                            slot: status.slot,
+                            buddy_link_error: decode_buddy_link_error(&transaction.message, &error),
+                            error,
+                        },
+                    });
+                }
+                Some(status) => self.progress(SendProgress::Landed {
+                    signature,
+                    slot: status.slot,
+                }),
+                None if block_height > last_valid_block_height => {
+                    return Ok(SendOutcome::Expired { signature });
+                }
+                None => self.progress(SendProgress::Waiting {
+                    signature,
+                    block_height,
+                    last_valid_block_height,
+                }),
+            }
+
+            if self.timeout.is_some_and(|x| start.elapsed() >= x) {
+                return Ok(SendOutcome::Unknown { signature });
+            }
+
+            sleep(self.poll_interval);
+        }
+    }
+
+    fn broadcast(&self, transaction: &VersionedTransaction) -> Result<()> {
+        self.client.send_transaction_with_config(
+            transaction,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                max_retries: Some(0),
+                ..RpcSendTransactionConfig::default()
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn progress(&self, progress: SendProgress) {
+        if let Some(on_progress) = self.on_progress {
+            on_progress(&progress);
+        }
+    }
+}
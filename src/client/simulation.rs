@@ -0,0 +1,458 @@
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::client::transaction::TransactionBuilder;
+use crate::constants::{
+    BL_PROGRAM_ID, TRANSFER_REWARD_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SPL_DISCRIMINATOR,
+    TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR,
+};
+use crate::instruction::TransferUncheckedLocalSharedRewardArgs;
+use anchor_spl::token_2022::spl_token_2022;
+use base64::Engine;
+use borsh::BorshDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_program::instruction::Instruction;
+use solana_program::program_pack::IsInitialized;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use spl_token_2022::extension::StateWithExtensions;
+
+/// Role of an account in the BuddyLink transfer instructions.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Party {
+    FeePayer,
+    /// Authority of the account sending the funds (sends the SOL rewards).
+    Authority,
+    /// Token account sending the funds.
+    Source,
+    ReferrerMember,
+    ReferrerTreasury,
+    /// Referrer treasury linked to the mint of the reward.
+    ReferrerTreasuryForReward,
+    /// Token account receiving the funds of the referrer.
+    ReferrerTokenAccount,
+    RefereeMember,
+    GlobalReferrerTreasury,
+    /// Global referrer treasury linked to the mint of the reward.
+    GlobalReferrerTreasuryForReward,
+    /// Token account receiving the funds of the global referrer.
+    GlobalReferrerTokenAccount,
+    /// Treasury or token account of a shared reward, in the order of the shares.
+    SharedRecipient(usize),
+    /// Member of the recipient of a shared reward.
+    SharedRecipientMember(usize),
+}
+
+/// Balance of an account before and after the simulation (0 if the account doesn't exist).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct BalanceChange {
+    pub pre: u64,
+    pub post: u64,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> i128 {
+        i128::from(self.post) - i128::from(self.pre)
+    }
+}
+
+/// Token balance of a token account (SPL token or token 2022).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TokenBalanceChange {
+    pub mint: Pubkey,
+    /// Owner of the token account.
+    pub owner: Pubkey,
+    pub amount: BalanceChange,
+}
+
+/// Balance changes of an account, with all its roles in the instructions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PartyBalance {
+    pub pubkey: Pubkey,
+    pub parties: Vec<Party>,
+    pub lamports: BalanceChange,
+    /// None if the account isn't a token account before nor after the simulation.
+    pub token: Option<TokenBalanceChange>,
+}
+
+/// Line of the program logs.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ProgramLog {
+    Invoke {
+        program: Pubkey,
+        depth: usize,
+    },
+    /// `msg!` of the program.
+    Log {
+        program: Option<Pubkey>,
+        message: String,
+    },
+    /// Events emitted by the program (`sol_log_data`), decoded from base64.
+    Data {
+        program: Option<Pubkey>,
+        data: Vec<Vec<u8>>,
+    },
+    Return {
+        program: Pubkey,
+        data: Vec<u8>,
+    },
+    Consumed {
+        program: Pubkey,
+        units: u64,
+        limit: u64,
+    },
+    Success {
+        program: Pubkey,
+    },
+    Failed {
+        program: Pubkey,
+        error: String,
+    },
+    /// Line not emitted by a program (log truncated for example).
+    Other(String),
+}
+
+/// Outcome of [`simulate_reward`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RewardSimulation {
+    /// One entry per account, in the order of the instructions.
+    pub balances: Vec<PartyBalance>,
+    pub logs: Vec<ProgramLog>,
+    pub units_consumed: Option<u64>,
+}
+
+impl RewardSimulation {
+    /// First account with this role.
+    pub fn party(&self, party: Party) -> Option<&PartyBalance> {
+        self.balances.iter().find(|x| x.parties.contains(&party))
+    }
+
+    pub fn account(&self, pubkey: &Pubkey) -> Option<&PartyBalance> {
+        self.balances.iter().find(|x| x.pubkey == *pubkey)
+    }
+}
+
+///# Simulate Reward
+///
+/// Simulates the transaction (signatures aren't verified) and reports the lamports and tokens
+/// received or sent by each party of the BuddyLink transfer instructions, with the decoded logs.
+///
+/// The pre balances are fetched right before the simulation, at the commitment of the client.
+/// Transaction fees aren't charged by the simulation so they aren't in the balances of the fee payer.
+///
+/// Errors with [`Error::Simulation`] and the program logs if the transaction would fail.
+pub fn simulate_reward(
+    client: &RpcClient,
+    builder: &TransactionBuilder,
+) -> Result<RewardSimulation> {
+    let parties = reward_parties(builder);
+    let keys = party_keys(&parties);
+
+    let pre_accounts = AccountSource::get_multiple_accounts(client, &keys)?;
+    let transaction = builder.unsigned_transaction(false, client.get_latest_blockhash()?)?;
+    let simulation = client
+        .simulate_transaction_with_config(
+            &transaction,
+            reward_simulation_config(client.commitment(), &keys),
+        )?
+        .value;
+
+    reward_simulation(&parties, pre_accounts, simulation)
+}
+
+/// Roles of the accounts of the fee payer and of the BuddyLink transfer instructions of the builder.
+pub(crate) fn reward_parties(builder: &TransactionBuilder) -> Vec<(Party, Pubkey)> {
+    let mut parties = vec![(Party::FeePayer, builder.fee_payer())];
+    for instruction in builder.added_instructions() {
+        parties.extend(instruction_parties(instruction));
+    }
+
+    parties
+}
+
+///# Instruction Parties
+///
+/// Roles of the accounts of a BuddyLink transfer instruction, empty for any other instruction.
+/// Optional accounts left out of the instruction aren't returned.
+pub fn instruction_parties(instruction: &Instruction) -> Vec<(Party, Pubkey)> {
+    if instruction.program_id != BL_PROGRAM_ID || instruction.data.len() < 8 {
+        return Vec::new();
+    }
+
+    let discriminator: [u8; 8] = instruction.data[..8].try_into().unwrap();
+    let roles: Vec<(Party, usize)> = match discriminator {
+        TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR => {
+            let Ok(args) =
+                TransferUncheckedLocalSharedRewardArgs::deserialize(&mut &instruction.data[8..])
+            else {
+                return Vec::new();
+            };
+            let accounts_per_share = if args.members_included { 2 } else { 1 };
+
+            [(Party::Authority, 0), (Party::Source, 4)]
+                .into_iter()
+                .chain((5..instruction.accounts.len()).map(|index| {
+                    let share = (index - 5) / accounts_per_share;
+                    match (index - 5) % accounts_per_share {
+                        0 => (Party::SharedRecipient(share), index),
+                        _ => (Party::SharedRecipientMember(share), index),
+                    }
+                }))
+                .collect()
+        }
+        TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR => vec![
+            (Party::Authority, 0),
+            (Party::Source, 3),
+            (Party::ReferrerMember, 4),
+            (Party::ReferrerTreasury, 5),
+            (Party::ReferrerTreasuryForReward, 6),
+            (Party::RefereeMember, 10),
+            (Party::ReferrerTokenAccount, 11),
+        ],
+        TRANSFER_REWARD_SPL_DISCRIMINATOR => vec![
+            (Party::Authority, 0),
+            (Party::GlobalReferrerTreasury, 1),
+            (Party::GlobalReferrerTokenAccount, 2),
+            (Party::ReferrerMember, 3),
+            (Party::ReferrerTreasury, 4),
+            (Party::ReferrerTreasuryForReward, 5),
+            (Party::RefereeMember, 6),
+            (Party::Source, 9),
+            (Party::ReferrerTokenAccount, 10),
+        ],
+        TRANSFER_REWARD_GLOBAL_DISCRIMINATOR => vec![
+            (Party::Authority, 0),
+            (Party::GlobalReferrerTreasury, 1),
+            (Party::GlobalReferrerTreasuryForReward, 2),
+            (Party::GlobalReferrerTokenAccount, 8),
+            (Party::Source, 9),
+        ],
+        _ => Vec::new(),
+    };
+
+    // Optional accounts are replaced by the program id
+    roles
+        .into_iter()
+        .filter_map(|(party, index)| Some((party, instruction.accounts.get(index)?.pubkey)))
+        .filter(|(_, pubkey)| *pubkey != BL_PROGRAM_ID)
+        .collect()
+}
+
+/// Accounts of the parties, without duplicates.
+pub(crate) fn party_keys(parties: &[(Party, Pubkey)]) -> Vec<Pubkey> {
+    let mut keys: Vec<Pubkey> = Vec::with_capacity(parties.len());
+    for (_, pubkey) in parties {
+        if !keys.contains(pubkey) {
+            keys.push(*pubkey);
+        }
+    }
+
+    keys
+}
+
+/// Simulation of a transaction with empty signatures, returning the accounts after it.
+pub(crate) fn reward_simulation_config(
+    commitment: CommitmentConfig,
+    keys: &[Pubkey],
+) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(commitment),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: keys.iter().map(Pubkey::to_string).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+/// Balance changes from the accounts of [`party_keys`] before the simulation and the accounts returned by it.
+pub(crate) fn reward_simulation(
+    parties: &[(Party, Pubkey)],
+    pre_accounts: Vec<Option<Account>>,
+    simulation: RpcSimulateTransactionResult,
+) -> Result<RewardSimulation> {
+    let logs = simulation.logs.unwrap_or_default();
+    if let Some(error) = simulation.err {
+        return Err(Error::Simulation { error, logs });
+    }
+
+    let keys = party_keys(parties);
+    let post_accounts = simulation.accounts.unwrap_or_default();
+
+    let balances = keys
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| {
+            let pre = pre_accounts.get(index).cloned().flatten();
+            let post = match post_accounts.get(index).cloned().flatten() {
+                Some(account) => Some(
+                    account
+                        .decode::<Account>()
+                        .ok_or(Error::InvalidAccountData(*pubkey))?,
+                ),
+                None => None,
+            };
+
+            Ok(PartyBalance {
+                pubkey: *pubkey,
+                parties: parties
+                    .iter()
+                    .filter(|(_, key)| key == pubkey)
+                    .map(|(party, _)| *party)
+                    .collect(),
+                lamports: BalanceChange {
+                    pre: pre.as_ref().map_or(0, |x| x.lamports),
+                    post: post.as_ref().map_or(0, |x| x.lamports),
+                },
+                token: token_balance_change(pre.as_ref(), post.as_ref()),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(RewardSimulation {
+        balances,
+        logs: decode_logs(&logs),
+        units_consumed: simulation.units_consumed,
+    })
+}
+
+fn token_balance_change(
+    pre: Option<&Account>,
+    post: Option<&Account>,
+) -> Option<TokenBalanceChange> {
+    let pre = pre.and_then(token_account);
+    let post = post.and_then(token_account);
+    let (mint, owner) = post.or(pre).map(|x| (x.mint, x.owner))?;
+
+    Some(TokenBalanceChange {
+        mint,
+        owner,
+        amount: BalanceChange {
+            pre: pre.map_or(0, |x| x.amount),
+            post: post.map_or(0, |x| x.amount),
+        },
+    })
+}
+
+fn token_account(account: &Account) -> Option<spl_token_2022::state::Account> {
+    if account.owner != anchor_spl::token::ID && account.owner != spl_token_2022::ID {
+        return None;
+    }
+
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .ok()
+        .map(|x| x.base)
+        .filter(IsInitialized::is_initialized)
+}
+
+///# Decode Logs
+///
+/// Parses the logs of a transaction, the `msg!` and the data are attributed to the program running.
+pub fn decode_logs(logs: &[String]) -> Vec<ProgramLog> {
+    let mut stack: Vec<Pubkey> = Vec::new();
+
+    logs.iter()
+        .map(|line| {
+            let log = decode_log(line, stack.last().copied());
+            match &log {
+                ProgramLog::Invoke { program, .. } => stack.push(*program),
+                ProgramLog::Success { .. } | ProgramLog::Failed { .. } => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            log
+        })
+        .collect()
+}
+
+fn decode_log(line: &str, current: Option<Pubkey>) -> ProgramLog {
+    let other = || ProgramLog::Other(line.to_string());
+
+    let Some(rest) = line.strip_prefix("Program ") else {
+        return other();
+    };
+
+    if let Some(message) = rest.strip_prefix("log: ") {
+        return ProgramLog::Log {
+            program: current,
+            message: message.to_string(),
+        };
+    }
+
+    if let Some(data) = rest.strip_prefix("data: ") {
+        return match data
+            .split_whitespace()
+            .map(|x| base64::engine::general_purpose::STANDARD.decode(x))
+            .collect()
+        {
+            Ok(data) => ProgramLog::Data {
+                program: current,
+                data,
+            },
+            Err(_) => other(),
+        };
+    }
+
+    if let Some(data) = rest.strip_prefix("return: ") {
+        return match data.split_once(' ').and_then(|(program, data)| {
+            Some(ProgramLog::Return {
+                program: program.parse().ok()?,
+                data: base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .ok()?,
+            })
+        }) {
+            Some(log) => log,
+            None => other(),
+        };
+    }
+
+    let Some((program, event)) = rest.split_once(' ') else {
+        return other();
+    };
+    let Ok(program) = program.parse::<Pubkey>() else {
+        return other();
+    };
+
+    if event == "success" {
+        return ProgramLog::Success { program };
+    }
+
+    if let Some(error) = event.strip_prefix("failed: ") {
+        return ProgramLog::Failed {
+            program,
+            error: error.to_string(),
+        };
+    }
+
+    if let Some(depth) = event
+        .strip_prefix("invoke [")
+        .and_then(|x| x.strip_suffix(']'))
+        .and_then(|x| x.parse().ok())
+    {
+        return ProgramLog::Invoke { program, depth };
+    }
+
+    // "consumed {units} of {limit} compute units"
+    let words: Vec<&str> = event.split(' ').collect();
+    if let ["consumed", units, "of", limit, "compute", "units"] = words[..] {
+        if let (Ok(units), Ok(limit)) = (units.parse(), limit.parse()) {
+            return ProgramLog::Consumed {
+                program,
+                units,
+                limit,
+            };
+        }
+    }
+
+    other()
+}
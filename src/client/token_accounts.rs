@@ -0,0 +1,81 @@
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::instruction::{
+    create_referrer_token_account, referrer_token_accounts, ReferrerTokenAccount,
+};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Referrer token accounts of the instructions, without duplicates.
+pub(crate) fn all_referrer_token_accounts(
+    instructions: &[Instruction],
+) -> Vec<ReferrerTokenAccount> {
+    let mut token_accounts: Vec<ReferrerTokenAccount> = Vec::new();
+    for token_account in instructions.iter().flat_map(referrer_token_accounts) {
+        if !token_accounts
+            .iter()
+            .any(|x| x.token_account == token_account.token_account)
+        {
+            token_accounts.push(token_account);
+        }
+    }
+
+    token_accounts
+}
+
+/// Token accounts without account, errors if one of them can't be created.
+pub(crate) fn select_missing(
+    token_accounts: Vec<ReferrerTokenAccount>,
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<ReferrerTokenAccount>> {
+    token_accounts
+        .into_iter()
+        .zip(accounts)
+        .filter(|(_, account)| account.is_none())
+        .map(|(token_account, _)| match token_account.is_associated() {
+            true => Ok(token_account),
+            false => Err(Error::AccountNotFound(token_account.token_account)),
+        })
+        .collect()
+}
+
+///# Missing Referrer Token Accounts
+///
+/// Token accounts receiving the rewards of the instructions (see [`referrer_token_accounts`]) that don't exist yet,
+/// checked with a single `getMultipleAccounts`.
+///
+/// Errors with [`Error::AccountNotFound`] if a missing one isn't the associated token account of its treasury
+/// (it can't be created for it).
+pub fn missing_referrer_token_accounts(
+    client: &dyn AccountSource,
+    instructions: &[Instruction],
+) -> Result<Vec<ReferrerTokenAccount>> {
+    let token_accounts = all_referrer_token_accounts(instructions);
+    if token_accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let keys: Vec<_> = token_accounts.iter().map(|x| x.token_account).collect();
+    let accounts = client.get_multiple_accounts(&keys)?;
+
+    select_missing(token_accounts, accounts)
+}
+
+///# With Referrer Token Accounts
+///
+/// Prepends to the instructions the idempotent creation of the referrer token accounts that don't exist yet,
+/// owned by the BuddyLink treasuries for reward, the rent being paid by `rent_payer`.
+pub fn with_referrer_token_accounts(
+    client: &dyn AccountSource,
+    rent_payer: Pubkey,
+    instructions: Vec<Instruction>,
+) -> Result<Vec<Instruction>> {
+    let missing = missing_referrer_token_accounts(client, &instructions)?;
+
+    Ok(missing
+        .iter()
+        .map(|x| create_referrer_token_account(rent_payer, x))
+        .chain(instructions)
+        .collect())
+}
@@ -0,0 +1,439 @@
+use crate::client::account_source::AccountSource;
+use crate::client::error::{Error, Result};
+use crate::client::offline::{nonce_blockhash, DurableNonce};
+use crate::client::token_accounts::with_referrer_token_accounts;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::{legacy, v0, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::signature::Signature;
+use solana_sdk::signers::Signers;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Maximum compute units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Margin added on top of the simulated compute units, in bps.
+pub const DEFAULT_COMPUTE_UNIT_MARGIN_BPS: u16 = 1_000;
+
+/// Gives the compute unit price (in micro-lamports) to pay for a transaction.
+pub trait PriorityFeeEstimator {
+    /// `writable_accounts` are the accounts locked by the transaction, fees are local to them.
+    fn compute_unit_price(&self, writable_accounts: &[Pubkey]) -> Result<u64>;
+}
+
+/// Always pays the same compute unit price.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedPriorityFee(pub u64);
+
+impl PriorityFeeEstimator for FixedPriorityFee {
+    fn compute_unit_price(&self, _writable_accounts: &[Pubkey]) -> Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Uses a percentile of the prioritization fees paid recently for the writable accounts.
+pub struct RecentPrioritizationFees<'a> {
+    pub client: &'a RpcClient,
+    /// Between 0 and 100, 50 is the median.
+    pub percentile: u8,
+}
+
+impl PriorityFeeEstimator for RecentPrioritizationFees<'_> {
+    fn compute_unit_price(&self, writable_accounts: &[Pubkey]) -> Result<u64> {
+        let fees = self
+            .client
+            .get_recent_prioritization_fees(writable_accounts)?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+
+        Ok(fee_percentile(fees, self.percentile))
+    }
+}
+
+/// Percentile of the fees, 0 without fees.
+pub(crate) fn fee_percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * usize::from(percentile.min(100)) / 100;
+
+    fees[index]
+}
+
+///# Transaction Builder
+///
+/// Wraps BuddyLink instructions with the compute budget instructions:
+/// 1. SetComputeUnitLimit, sized by simulating the transaction (unless a limit is given)
+/// 2. SetComputeUnitPrice, given by the priority fee estimator (if any)
+///
+/// The fee payer can be different from the reward authority, both have to be in the signers.
+/// A v0 message is compiled when lookup tables are given, a legacy one otherwise.
+/// With a durable nonce, the AdvanceNonceAccount instruction comes before the compute budget ones.
+pub struct TransactionBuilder<'a> {
+    fee_payer: Pubkey,
+    instructions: Vec<Instruction>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_margin_bps: u16,
+    priority_fee_estimator: Option<&'a dyn PriorityFeeEstimator>,
+    durable_nonce: Option<DurableNonce>,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    pub fn new(fee_payer: Pubkey) -> Self {
+        Self {
+            fee_payer,
+            instructions: Vec::new(),
+            lookup_tables: Vec::new(),
+            compute_unit_limit: None,
+            compute_unit_margin_bps: DEFAULT_COMPUTE_UNIT_MARGIN_BPS,
+            priority_fee_estimator: None,
+            durable_nonce: None,
+        }
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    pub fn lookup_tables(
+        mut self,
+        lookup_tables: impl IntoIterator<Item = AddressLookupTableAccount>,
+    ) -> Self {
+        self.lookup_tables.extend(lookup_tables);
+        self
+    }
+
+    /// Uses this limit instead of simulating the transaction.
+    pub fn compute_unit_limit(mut self, compute_unit_limit: u32) -> Self {
+        self.compute_unit_limit = Some(compute_unit_limit);
+        self
+    }
+
+    /// Margin added on top of the simulated compute units.
+    pub fn compute_unit_margin_bps(mut self, compute_unit_margin_bps: u16) -> Self {
+        self.compute_unit_margin_bps = compute_unit_margin_bps;
+        self
+    }
+
+    pub fn priority_fee_estimator(mut self, estimator: &'a dyn PriorityFeeEstimator) -> Self {
+        self.priority_fee_estimator = Some(estimator);
+        self
+    }
+
+    /// Advances this nonce account first, the transaction is built on its nonce instead of a recent blockhash
+    /// and doesn't expire until the nonce is advanced.
+    pub fn durable_nonce(mut self, nonce_account: Pubkey, nonce_authority: Pubkey) -> Self {
+        self.durable_nonce = Some(DurableNonce {
+            account: nonce_account,
+            authority: nonce_authority,
+        });
+        self
+    }
+
+    /// Creates first the referrer token accounts of the instructions added so far that don't exist yet, the rent being
+    /// paid by `rent_payer` (see [`with_referrer_token_accounts`]).
+    pub fn create_referrer_token_accounts(
+        mut self,
+        client: &dyn AccountSource,
+        rent_payer: Pubkey,
+    ) -> Result<Self> {
+        self.instructions = with_referrer_token_accounts(
+            client,
+            rent_payer,
+            std::mem::take(&mut self.instructions),
+        )?;
+        Ok(self)
+    }
+
+    pub fn fee_payer(&self) -> Pubkey {
+        self.fee_payer
+    }
+
+    /// Instructions added to the builder, without the compute budget ones.
+    pub(crate) fn added_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Whether the transaction advances a durable nonce instead of using a recent blockhash.
+    pub(crate) fn uses_durable_nonce(&self) -> bool {
+        self.durable_nonce.is_some()
+    }
+
+    /// Limit given with [`Self::compute_unit_limit`], if any.
+    pub(crate) fn given_compute_unit_limit(&self) -> Option<u32> {
+        self.compute_unit_limit
+    }
+
+    /// Accounts written by the instructions, without duplicates.
+    pub fn writable_accounts(&self) -> Vec<Pubkey> {
+        let mut writable_accounts: Vec<Pubkey> = Vec::new();
+
+        for meta in self.instructions.iter().flat_map(|x| &x.accounts) {
+            if meta.is_writable && !writable_accounts.contains(&meta.pubkey) {
+                writable_accounts.push(meta.pubkey);
+            }
+        }
+
+        writable_accounts
+    }
+
+    /// Simulates the transaction (signatures aren't verified) and returns the compute units it needs, margin included.
+    pub fn simulate_compute_unit_limit(&self, client: &RpcClient) -> Result<u32> {
+        let transaction = self.unsigned_transaction(
+            self.priority_fee_estimator.is_some(),
+            client.get_latest_blockhash()?,
+        )?;
+
+        let simulation = client
+            .simulate_transaction_with_config(
+                &transaction,
+                unsigned_simulation_config(client.commitment()),
+            )?
+            .value;
+
+        self.compute_unit_limit_from(simulation)
+    }
+
+    /// Transaction with the max compute unit limit and empty signatures, to size the compute budget.
+    pub(crate) fn unsigned_transaction(
+        &self,
+        compute_unit_price: bool,
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        // The price instruction consumes compute units too, it's simulated with any price
+        self.build_unsigned(
+            MAX_COMPUTE_UNIT_LIMIT,
+            compute_unit_price.then_some(0),
+            recent_blockhash,
+        )
+    }
+
+    /// Compute unit limit for the units consumed by the simulation, margin included.
+    pub(crate) fn compute_unit_limit_from(
+        &self,
+        simulation: RpcSimulateTransactionResult,
+    ) -> Result<u32> {
+        let units_consumed = check_simulation(simulation)?;
+
+        Ok(compute_unit_limit_with_margin(
+            units_consumed.unwrap_or(u64::from(MAX_COMPUTE_UNIT_LIMIT)),
+            self.compute_unit_margin_bps,
+        ))
+    }
+
+    /// Sizes the compute budget, then compiles and signs the transaction (on the nonce of the durable nonce account if any).
+    pub fn build<T: Signers + ?Sized>(
+        &self,
+        client: &RpcClient,
+        signers: &T,
+    ) -> Result<VersionedTransaction> {
+        let recent_blockhash = match self.durable_nonce {
+            Some(durable_nonce) => nonce_blockhash(client, &durable_nonce.account)?,
+            None => client.get_latest_blockhash()?,
+        };
+
+        self.build_on(client, recent_blockhash, signers)
+    }
+
+    /// [`TransactionBuilder::build`] on a given blockhash.
+    pub(crate) fn build_on<T: Signers + ?Sized>(
+        &self,
+        client: &RpcClient,
+        recent_blockhash: Hash,
+        signers: &T,
+    ) -> Result<VersionedTransaction> {
+        let compute_unit_limit = match self.compute_unit_limit {
+            Some(compute_unit_limit) => compute_unit_limit,
+            None => self.simulate_compute_unit_limit(client)?,
+        };
+
+        let compute_unit_price = match self.priority_fee_estimator {
+            Some(estimator) => Some(estimator.compute_unit_price(&self.writable_accounts())?),
+            None => None,
+        };
+
+        self.build_with(
+            compute_unit_limit,
+            compute_unit_price,
+            recent_blockhash,
+            signers,
+        )
+    }
+
+    /// Compiles and signs the transaction with a known compute budget, without any request.
+    pub fn build_with<T: Signers + ?Sized>(
+        &self,
+        compute_unit_limit: u32,
+        compute_unit_price: Option<u64>,
+        recent_blockhash: Hash,
+        signers: &T,
+    ) -> Result<VersionedTransaction> {
+        let instructions = self.with_budget(Some(compute_unit_limit), compute_unit_price);
+        let message = self.compile(&instructions, recent_blockhash)?;
+
+        Ok(VersionedTransaction::try_new(message, signers)?)
+    }
+
+    /// Compiles the transaction with a known compute budget and empty signatures, to be signed offline
+    /// (see [`crate::client::offline`]).
+    pub fn build_unsigned(
+        &self,
+        compute_unit_limit: u32,
+        compute_unit_price: Option<u64>,
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        let instructions = self.with_budget(Some(compute_unit_limit), compute_unit_price);
+        let message = self.compile(&instructions, recent_blockhash)?;
+
+        Ok(VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                usize::from(message.header().num_required_signatures)
+            ],
+            message,
+        })
+    }
+
+    /// The instructions with the compute budget, after the nonce advance if any (it must come first).
+    fn with_budget(
+        &self,
+        compute_unit_limit: Option<u32>,
+        compute_unit_price: Option<u64>,
+    ) -> Vec<Instruction> {
+        let advance_nonce = self.durable_nonce.map(|durable_nonce| {
+            system_instruction::advance_nonce_account(
+                &durable_nonce.account,
+                &durable_nonce.authority,
+            )
+        });
+
+        advance_nonce
+            .into_iter()
+            .chain(with_compute_budget(
+                &self.instructions,
+                compute_unit_limit,
+                compute_unit_price,
+            ))
+            .collect()
+    }
+
+    fn compile(
+        &self,
+        instructions: &[Instruction],
+        recent_blockhash: Hash,
+    ) -> Result<VersionedMessage> {
+        if self.lookup_tables.is_empty() {
+            return Ok(VersionedMessage::Legacy(
+                legacy::Message::new_with_blockhash(
+                    instructions,
+                    Some(&self.fee_payer),
+                    &recent_blockhash,
+                ),
+            ));
+        }
+
+        Ok(VersionedMessage::V0(v0::Message::try_compile(
+            &self.fee_payer,
+            instructions,
+            &self.lookup_tables,
+            recent_blockhash,
+        )?))
+    }
+}
+
+///# Preflight
+///
+/// Simulates a signed transaction as the node would before sending it (signatures and blockhash verified),
+/// returns the compute units it consumed.
+///
+/// Errors with [`Error::Simulation`] and the program logs if the transaction would fail.
+pub fn preflight(client: &RpcClient, transaction: &VersionedTransaction) -> Result<u64> {
+    let simulation = client
+        .simulate_transaction_with_config(transaction, preflight_config(client.commitment()))?
+        .value;
+
+    Ok(check_simulation(simulation)?.unwrap_or_default())
+}
+
+/// Simulation of a transaction with empty signatures, on the latest blockhash.
+pub(crate) fn unsigned_simulation_config(
+    commitment: CommitmentConfig,
+) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(commitment),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+/// Simulation of a signed transaction, as is.
+pub(crate) fn preflight_config(commitment: CommitmentConfig) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: true,
+        replace_recent_blockhash: false,
+        commitment: Some(commitment),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+/// Units consumed by a successful simulation.
+pub(crate) fn check_simulation(simulation: RpcSimulateTransactionResult) -> Result<Option<u64>> {
+    match simulation.err {
+        Some(error) => Err(Error::Simulation {
+            error,
+            logs: simulation.logs.unwrap_or_default(),
+        }),
+        None => Ok(simulation.units_consumed),
+    }
+}
+
+/// Prepends the compute budget instructions to the instructions.
+pub fn with_compute_budget(
+    instructions: &[Instruction],
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+) -> Vec<Instruction> {
+    let mut result = Vec::with_capacity(instructions.len() + 2);
+
+    if let Some(compute_unit_limit) = compute_unit_limit {
+        result.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            compute_unit_limit,
+        ));
+    }
+
+    if let Some(compute_unit_price) = compute_unit_price {
+        result.push(ComputeBudgetInstruction::set_compute_unit_price(
+            compute_unit_price,
+        ));
+    }
+
+    result.extend_from_slice(instructions);
+
+    result
+}
+
+/// Adds the margin to the consumed compute units, capped to the maximum limit.
+pub fn compute_unit_limit_with_margin(units_consumed: u64, margin_bps: u16) -> u32 {
+    let units = units_consumed.saturating_mul(10_000 + u64::from(margin_bps)) / 10_000;
+
+    units.min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32
+}
@@ -11,3 +11,13 @@ pub const BL_PROGRAM_ID: Pubkey = pubkey!("9zE4EQ5tJbEeMYwtS2w8KrSHTtTW4UPqwfbBS
 
 #[cfg(feature = "mainnet")]
 pub const BL_PROGRAM_ID: Pubkey = pubkey!("BUDDYtQp7Di1xfojiCSVDksiYLQx511DPdj2nbtG9Yu5");
+
+// Anchor discriminators of the instructions, first 8 bytes of sha256("global:<instruction_name>")
+pub const VALIDATE_REFERRER_DISCRIMINATOR: [u8; 8] = [165, 30, 37, 202, 92, 169, 0, 103];
+pub const TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR: [u8; 8] = [228, 217, 40, 252, 58, 66, 148, 69];
+pub const TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR: [u8; 8] = [174, 15, 234, 146, 49, 183, 195, 64];
+pub const TRANSFER_REWARD_SPL_DISCRIMINATOR: [u8; 8] = [96, 240, 108, 156, 27, 222, 43, 52];
+pub const TRANSFER_REWARD_GLOBAL_DISCRIMINATOR: [u8; 8] = [160, 201, 210, 182, 93, 220, 117, 92];
+
+// Seeds of the BuddyLink accounts
+pub const TREASURY_SEED: &[u8] = b"treasury_";
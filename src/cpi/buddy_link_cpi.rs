@@ -0,0 +1,223 @@
+use crate::cpi;
+use crate::cpi::{
+    PayReferralReward, TransferCheckedGlobalOnlyRewardSol, TransferCheckedGlobalOnlyRewardSpl,
+    TransferCheckedGlobalReward, TransferRewardUncheckedMultipleSol,
+    TransferRewardUncheckedMultipleSpl, TransferSecureLocalReward, ValidateReferrer,
+};
+use crate::instruction::{RewardVariant, RewardVariantReason, UnclaimedTiers};
+use anchor_lang::prelude::*;
+use solana_program::entrypoint::ProgramResult;
+
+/// The BuddyLink operations, with the same arguments as the free functions of [`crate::cpi`].
+///
+/// Take it as a generic (or `&dyn BuddyLinkCpi`) in your reward logic: on-chain it is [`BuddyLinkProgram`],
+/// in unit tests it can be the recording mock of the `testing` feature (`cpi::testing::RecordingBuddyLinkCpi`).
+pub trait BuddyLinkCpi {
+    fn transfer_unchecked_local_shared_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_unchecked_local_shared_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_multi_tier_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_multi_tier_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_secure_local_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferSecureLocalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_checked_global_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_checked_global_only_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSol<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn transfer_checked_global_only_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSpl<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult;
+
+    fn validate_referrer<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, ValidateReferrer<'info>>,
+    ) -> ProgramResult;
+
+    fn pay_referral_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, PayReferralReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(RewardVariant, RewardVariantReason), ProgramError>;
+}
+
+/// [`BuddyLinkCpi`] invoking the BuddyLink program.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuddyLinkProgram;
+
+impl BuddyLinkCpi for BuddyLinkProgram {
+    fn transfer_unchecked_local_shared_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_unchecked_local_shared_reward_sol(
+            ctx,
+            total_amount,
+            shares_in_bps,
+            members_included,
+            transfer_signer_seeds,
+        )
+    }
+
+    fn transfer_unchecked_local_shared_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_unchecked_local_shared_reward_spl(
+            ctx,
+            total_amount,
+            shares_in_bps,
+            members_included,
+            transfer_signer_seeds,
+        )
+    }
+
+    fn transfer_multi_tier_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_multi_tier_reward_sol(
+            ctx,
+            total_amount,
+            schedule,
+            unclaimed,
+            members_included,
+            transfer_signer_seeds,
+        )
+    }
+
+    fn transfer_multi_tier_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_multi_tier_reward_spl(
+            ctx,
+            total_amount,
+            schedule,
+            unclaimed,
+            members_included,
+            transfer_signer_seeds,
+        )
+    }
+
+    fn transfer_secure_local_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferSecureLocalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_secure_local_reward(ctx, amount, transfer_signer_seeds)
+    }
+
+    fn transfer_checked_global_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_checked_global_reward(ctx, amount, transfer_signer_seeds)
+    }
+
+    fn transfer_checked_global_only_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSol<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_checked_global_only_reward_sol(ctx, amount, transfer_signer_seeds)
+    }
+
+    fn transfer_checked_global_only_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSpl<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        cpi::transfer_checked_global_only_reward_spl(ctx, amount, transfer_signer_seeds)
+    }
+
+    fn validate_referrer<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, ValidateReferrer<'info>>,
+    ) -> ProgramResult {
+        cpi::validate_referrer(ctx)
+    }
+
+    fn pay_referral_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, PayReferralReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(RewardVariant, RewardVariantReason), ProgramError> {
+        cpi::pay_referral_reward(ctx, amount, transfer_signer_seeds)
+    }
+}
@@ -1,5 +1,17 @@
+mod buddy_link_cpi;
+pub mod native;
+mod pay_referral_reward;
+mod referrer_token_account;
 mod transfer_reward;
 mod validate_referrer;
+mod wrapped_sol;
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use buddy_link_cpi::*;
+pub use pay_referral_reward::*;
+pub use referrer_token_account::*;
 pub use transfer_reward::*;
 pub use validate_referrer::*;
+pub use wrapped_sol::*;
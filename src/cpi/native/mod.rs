@@ -0,0 +1,11 @@
+mod pay_referral_reward;
+mod referrer_token_account;
+mod transfer_reward;
+mod validate_referrer;
+mod wrapped_sol;
+
+pub use pay_referral_reward::*;
+pub use referrer_token_account::*;
+pub use transfer_reward::*;
+pub use validate_referrer::*;
+pub use wrapped_sol::*;
@@ -0,0 +1,244 @@
+use crate::cpi::native::{
+    transfer_checked_global_only_reward_sol, transfer_checked_global_only_reward_spl,
+    transfer_checked_global_reward, transfer_secure_local_reward,
+    transfer_unchecked_local_shared_reward_sol,
+};
+use crate::error::BuddyLinkError;
+use crate::instruction::{
+    choose_reward_variant, GeneralTransferRewardArgs, LocalReferrer, RewardRoute, RewardVariant,
+    RewardVariantReason, TransferUncheckedLocalSharedRewardArgs,
+};
+use crate::state::{Buddy, Member};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+
+/// Accounts of [`pay_referral_reward`], None for the accounts the caller doesn't have.
+pub struct PayReferralRewardAccounts<'a, 'info> {
+    pub buddy_link_program: &'a AccountInfo<'info>,
+    /// Authority of the account sending the funds.
+    pub authority: &'a AccountInfo<'info>,
+    /// System program for SOL rewards.
+    pub system_program: Option<&'a AccountInfo<'info>>,
+    /// Mint, token program and account sending the funds for SPL rewards.
+    pub mint: Option<&'a AccountInfo<'info>>,
+    pub token_program: Option<&'a AccountInfo<'info>>,
+    pub from_token_account: Option<&'a AccountInfo<'info>>,
+    /// Buddy Link Profile of the referee.
+    pub referee_buddy_profile: &'a AccountInfo<'info>,
+    /// Buddy Link Paid buddy of the referee (could be the same as above).
+    pub referee_buddy: &'a AccountInfo<'info>,
+    /// Referee treasury and member within your organization, None to only use the global referral tree.
+    pub referee_treasury: Option<&'a AccountInfo<'info>>,
+    pub referee_member: Option<&'a AccountInfo<'info>>,
+    /// Referrer member, treasury, treasury for reward and token account (SPL) within your organization.
+    pub referrer_member: Option<&'a AccountInfo<'info>>,
+    pub referrer_treasury: Option<&'a AccountInfo<'info>>,
+    pub referrer_treasury_for_reward: Option<&'a AccountInfo<'info>>,
+    pub referrer_token_account: Option<&'a AccountInfo<'info>>,
+    /// Treasury, treasury for reward and token account (SPL) of the global referrer of the referee.
+    pub global_referrer_treasury: Option<&'a AccountInfo<'info>>,
+    pub global_referrer_treasury_for_reward: Option<&'a AccountInfo<'info>>,
+    pub global_referrer_token_account: Option<&'a AccountInfo<'info>>,
+}
+
+///# Pay Referral Reward
+///
+/// Native counterpart of [`crate::cpi::pay_referral_reward`].
+///
+/// Chooses the transfer variant from the accounts given and their data (see [`choose_reward_variant`]),
+/// sends the reward and returns the variant used. Fails with [`BuddyLinkError::InvalidReferrerTreasury`]
+/// if no referrer can be paid with the accounts given.
+pub fn pay_referral_reward(
+    accounts: &PayReferralRewardAccounts,
+    amount: u64,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> Result<(RewardVariant, RewardVariantReason), ProgramError> {
+    let SelectedRoute {
+        variant,
+        reason,
+        spl,
+        system_program,
+        global_referrer,
+    } = select_route(accounts)?;
+
+    let transfer_args = GeneralTransferRewardArgs { amount };
+    // The chosen variant guarantees the accounts it uses are given
+    let missing = || ProgramError::NotEnoughAccountKeys;
+
+    match (variant, spl, system_program) {
+        (RewardVariant::CheckedGlobalOnly, None, Some(system_program)) => {
+            transfer_checked_global_only_reward_sol(
+                accounts.buddy_link_program,
+                accounts.authority,
+                system_program,
+                accounts.global_referrer_treasury.ok_or_else(missing)?,
+                accounts
+                    .global_referrer_treasury_for_reward
+                    .ok_or_else(missing)?,
+                accounts.referee_buddy_profile,
+                accounts.referee_buddy,
+                &transfer_args,
+                transfer_signer_seeds,
+            )?
+        }
+        (RewardVariant::CheckedGlobalOnly, Some((mint, token_program, from)), _) => {
+            transfer_checked_global_only_reward_spl(
+                accounts.buddy_link_program,
+                accounts.authority,
+                mint,
+                token_program,
+                from,
+                accounts.global_referrer_token_account.ok_or_else(missing)?,
+                accounts.global_referrer_treasury.ok_or_else(missing)?,
+                accounts
+                    .global_referrer_treasury_for_reward
+                    .ok_or_else(missing)?,
+                accounts.referee_buddy_profile,
+                accounts.referee_buddy,
+                &transfer_args,
+                transfer_signer_seeds,
+            )?
+        }
+        (_, None, Some(system_program)) => transfer_unchecked_local_shared_reward_sol(
+            accounts.buddy_link_program,
+            accounts.authority,
+            system_program,
+            &[
+                accounts
+                    .referrer_treasury_for_reward
+                    .ok_or_else(missing)?
+                    .clone(),
+                accounts.referrer_member.ok_or_else(missing)?.clone(),
+            ],
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount: amount,
+                shares_in_bps: vec![10_000],
+                members_included: true,
+            },
+            transfer_signer_seeds,
+        )?,
+        (RewardVariant::SecureLocal, Some((mint, token_program, from)), _) => {
+            transfer_secure_local_reward(
+                accounts.authority,
+                mint,
+                token_program,
+                from,
+                accounts.referrer_token_account.ok_or_else(missing)?,
+                accounts.referrer_member.ok_or_else(missing)?,
+                accounts.referrer_treasury.ok_or_else(missing)?,
+                accounts.referrer_treasury_for_reward.ok_or_else(missing)?,
+                accounts.referee_buddy_profile,
+                accounts.referee_buddy,
+                accounts.referee_treasury.ok_or_else(missing)?,
+                accounts.referee_member.ok_or_else(missing)?,
+                &transfer_args,
+                transfer_signer_seeds,
+            )?
+        }
+        (_, Some((mint, token_program, from)), _) => transfer_checked_global_reward(
+            accounts.buddy_link_program,
+            accounts.authority,
+            mint,
+            token_program,
+            from,
+            accounts.referrer_token_account.ok_or_else(missing)?,
+            accounts.referrer_member,
+            accounts.referrer_treasury.ok_or_else(missing)?,
+            accounts.referrer_treasury_for_reward.ok_or_else(missing)?,
+            accounts.referee_member.ok_or_else(missing)?,
+            accounts
+                .global_referrer_treasury_for_reward
+                .filter(|_| global_referrer),
+            accounts
+                .global_referrer_token_account
+                .filter(|_| global_referrer),
+            &transfer_args,
+            transfer_signer_seeds,
+        )?,
+        (_, None, None) => return Err(missing()),
+    }
+
+    Ok((variant, reason))
+}
+
+/// Transfer variant and accounts of [`pay_referral_reward`].
+pub(crate) struct SelectedRoute<'a, 'info> {
+    pub variant: RewardVariant,
+    pub reason: RewardVariantReason,
+    /// Mint, token program and account sending the funds, None for SOL rewards.
+    pub spl: Option<(
+        &'a AccountInfo<'info>,
+        &'a AccountInfo<'info>,
+        &'a AccountInfo<'info>,
+    )>,
+    pub system_program: Option<&'a AccountInfo<'info>>,
+    /// The global referrer accounts given are the ones of the referee.
+    pub global_referrer: bool,
+}
+
+/// Chooses the transfer variant of [`pay_referral_reward`] from the accounts given and their data.
+pub(crate) fn select_route<'a, 'info>(
+    accounts: &PayReferralRewardAccounts<'a, 'info>,
+) -> Result<SelectedRoute<'a, 'info>, ProgramError> {
+    let spl = match (
+        accounts.mint,
+        accounts.token_program,
+        accounts.from_token_account,
+    ) {
+        (Some(mint), Some(token_program), Some(from)) => Some((mint, token_program, from)),
+        _ => None,
+    };
+    let system_program = match (spl, accounts.system_program) {
+        (None, Some(system_program)) => Some(system_program),
+        (None, None) => return Err(BuddyLinkError::InvalidTokenSpecified.into()),
+        _ => None,
+    };
+    let token_account = |account: Option<&'_ AccountInfo<'_>>| spl.is_none() || account.is_some();
+
+    let local_referrer = match accounts.referee_member {
+        None => LocalReferrer::NoOrganization,
+        Some(referee_member) => {
+            match Member::from_account_info(referee_member)?.referrer_treasury() {
+                None => LocalReferrer::NotReferred,
+                Some(referrer_treasury)
+                    if accounts.referrer_treasury.map(|x| *x.key) == Some(referrer_treasury)
+                        && accounts.referrer_member.is_some()
+                        && accounts.referrer_treasury_for_reward.is_some()
+                        && token_account(accounts.referrer_token_account) =>
+                {
+                    LocalReferrer::Resolved
+                }
+                Some(_) => LocalReferrer::CantReceive,
+            }
+        }
+    };
+
+    let buddy = Buddy::from_account_info(accounts.referee_buddy)?;
+    let global_referrer = buddy.referrer_treasury().is_some()
+        && buddy.referrer_treasury() == accounts.global_referrer_treasury.map(|x| *x.key)
+        && accounts.global_referrer_treasury_for_reward.is_some()
+        && token_account(accounts.global_referrer_token_account);
+
+    let referee_signed = accounts.authority.is_signer
+        && accounts.referee_treasury.is_some()
+        && buddy.authority() == *accounts.authority.key
+        && Buddy::from_account_info(accounts.referee_buddy_profile)?.authority()
+            == *accounts.authority.key;
+    drop(buddy);
+
+    let (variant, reason) = choose_reward_variant(&RewardRoute {
+        local_referrer,
+        global_referrer,
+        referee_signed,
+        sol: spl.is_none(),
+    })
+    .ok_or(BuddyLinkError::InvalidReferrerTreasury)?;
+
+    Ok(SelectedRoute {
+        variant,
+        reason,
+        spl,
+        system_program,
+        global_referrer,
+    })
+}
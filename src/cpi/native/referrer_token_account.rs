@@ -0,0 +1,54 @@
+use crate::constants::BL_PROGRAM_ID;
+use crate::error::BuddyLinkError;
+use crate::instruction::{self, ReferrerTokenAccount};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::invoke_signed;
+
+///# Create Referrer Token Account
+///
+/// Native counterpart of [`crate::cpi::create_referrer_token_account`].
+/// `rent_payer` signs the transaction, or is a system owned PDA signing with `rent_payer_signer_seeds`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_referrer_token_account<'info>(
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    rent_payer: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    rent_payer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    if *treasury.owner != BL_PROGRAM_ID {
+        return Err(BuddyLinkError::InvalidReferrerTreasury.into());
+    }
+    // Already created, saves the CPI (the transfer checks the account)
+    if token_account.owner == token_program.key {
+        return Ok(());
+    }
+
+    let instruction = instruction::create_referrer_token_account(
+        *rent_payer.key,
+        &ReferrerTokenAccount {
+            token_account: *token_account.key,
+            treasury: *treasury.key,
+            mint: *mint.key,
+            token_program: *token_program.key,
+        },
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            rent_payer.clone(),
+            token_account.clone(),
+            treasury.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+        rent_payer_signer_seeds,
+    )
+}
@@ -0,0 +1,489 @@
+use crate::constants::BL_PROGRAM_ID;
+use crate::instruction;
+use crate::instruction::{
+    GeneralTransferRewardArgs, MultiTierRewardArgs, RewardAsset, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs, UnclaimedTiers,
+};
+use crate::utils::{get_native_account_info_or_default, get_native_key_or_none};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
+use solana_program::program::invoke_signed;
+use solana_program::program_error::ProgramError;
+use std::borrow::Cow;
+
+///# Transfer Unchecked Local Shared Reward (SOL)
+///
+/// Native counterpart of [`crate::cpi::transfer_unchecked_local_shared_reward_sol`].
+/// `remaining_accounts` are the referrer treasuries (paired with the referrer members if included).
+pub fn transfer_unchecked_local_shared_reward_sol<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    transfer_unchecked_local_shared_reward(
+        authority,
+        &SharedRewardAsset::Sol,
+        [system_program, buddy_link_program, buddy_link_program, buddy_link_program],
+        remaining_accounts,
+        transfer_args,
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Unchecked Local Shared Reward (SPL)
+///
+/// Native counterpart of [`crate::cpi::transfer_unchecked_local_shared_reward_spl`].
+/// `remaining_accounts` are the referrer token accounts (paired with the referrer members if included).
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_unchecked_local_shared_reward_spl<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    from_token_account: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    transfer_unchecked_local_shared_reward(
+        authority,
+        &SharedRewardAsset::Spl {
+            mint: *mint.key,
+            token_program: *token_program.key,
+            from: *from_token_account.key,
+        },
+        [buddy_link_program, mint, token_program, from_token_account],
+        remaining_accounts,
+        transfer_args,
+        transfer_signer_seeds,
+    )
+}
+
+/// `asset_accounts` are the system program, mint, token program and token account sending the funds,
+/// with the BuddyLink program in place of the ones not used by the asset.
+fn transfer_unchecked_local_shared_reward<'info>(
+    authority: &AccountInfo<'info>,
+    asset: &SharedRewardAsset,
+    asset_accounts: [&AccountInfo<'info>; 4],
+    remaining_accounts: &[AccountInfo<'info>],
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::shared_reward_instruction(
+        *authority.key,
+        asset,
+        remaining_accounts.iter().map(|account| *account.key),
+        transfer_args,
+    );
+
+    let [system_program, mint, token_program, from_token_account] = asset_accounts;
+    let accounts = SharedRewardAccounts {
+        fixed: [
+            authority,
+            system_program,
+            mint,
+            token_program,
+            from_token_account,
+        ],
+        remaining: remaining_accounts,
+    };
+
+    match remaining_accounts.len() {
+        0 => accounts.invoke_signed::<5>(&instruction, transfer_signer_seeds),
+        1 => accounts.invoke_signed::<6>(&instruction, transfer_signer_seeds),
+        2 => accounts.invoke_signed::<7>(&instruction, transfer_signer_seeds),
+        3 => accounts.invoke_signed::<8>(&instruction, transfer_signer_seeds),
+        4 => accounts.invoke_signed::<9>(&instruction, transfer_signer_seeds),
+        5 => accounts.invoke_signed::<10>(&instruction, transfer_signer_seeds),
+        6 => accounts.invoke_signed::<11>(&instruction, transfer_signer_seeds),
+        7 => accounts.invoke_signed::<12>(&instruction, transfer_signer_seeds),
+        8 => accounts.invoke_signed::<13>(&instruction, transfer_signer_seeds),
+        // Past this the infos would take a good part of the 4 KB SBF stack frame
+        _ => {
+            let account_infos: Vec<AccountInfo<'info>> = accounts
+                .fixed
+                .into_iter()
+                .chain(remaining_accounts)
+                .cloned()
+                .collect();
+
+            invoke_signed(&instruction, &account_infos, transfer_signer_seeds)
+        }
+    }
+}
+
+/// Account infos of a shared reward: the authority and the asset accounts, then the recipients.
+struct SharedRewardAccounts<'a, 'info> {
+    fixed: [&'a AccountInfo<'info>; 5],
+    remaining: &'a [AccountInfo<'info>],
+}
+
+impl<'info> SharedRewardAccounts<'_, 'info> {
+    /// Invokes with the infos in a stack array, `N` being the total number of accounts.
+    fn invoke_signed<const N: usize>(
+        &self,
+        instruction: &Instruction,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let account_infos: [AccountInfo<'info>; N] =
+            std::array::from_fn(|index| match index.checked_sub(self.fixed.len()) {
+                None => self.fixed[index].clone(),
+                Some(index) => self.remaining[index].clone(),
+            });
+
+        invoke_signed(instruction, &account_infos, transfer_signer_seeds)
+    }
+}
+
+///# Transfer Secure Local Reward (SPL)
+///
+/// Native counterpart of [`crate::cpi::transfer_secure_local_reward`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_secure_local_reward<'info>(
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    from_token_account: &AccountInfo<'info>,
+    referrer_token_account: &AccountInfo<'info>,
+    referrer_member: &AccountInfo<'info>,
+    referrer_treasury: &AccountInfo<'info>,
+    referrer_treasury_for_reward: &AccountInfo<'info>,
+    referee_buddy_profile: &AccountInfo<'info>,
+    referee_buddy: &AccountInfo<'info>,
+    referee_treasury: &AccountInfo<'info>,
+    referee_member: &AccountInfo<'info>,
+    transfer_args: &GeneralTransferRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::transfer_secure_local_reward(
+        *authority.key,
+        *mint.key,
+        *token_program.key,
+        *from_token_account.key,
+        *referrer_token_account.key,
+        *referrer_member.key,
+        *referrer_treasury.key,
+        *referrer_treasury_for_reward.key,
+        *referee_buddy_profile.key,
+        *referee_buddy.key,
+        *referee_treasury.key,
+        *referee_member.key,
+        transfer_args,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            authority.clone(),
+            mint.clone(),
+            token_program.clone(),
+            from_token_account.clone(),
+            referrer_member.clone(),
+            referrer_treasury.clone(),
+            referrer_treasury_for_reward.clone(),
+            referee_buddy_profile.clone(),
+            referee_buddy.clone(),
+            referee_treasury.clone(),
+            referee_member.clone(),
+            referrer_token_account.clone(),
+        ],
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Checked Global Reward (SPL)
+///
+/// Native counterpart of [`crate::cpi::transfer_checked_global_reward`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_global_reward<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    from_token_account: &AccountInfo<'info>,
+    referrer_token_account: &AccountInfo<'info>,
+    referrer_member: Option<&AccountInfo<'info>>,
+    referrer_treasury: &AccountInfo<'info>,
+    referrer_treasury_for_reward: &AccountInfo<'info>,
+    referee_member: &AccountInfo<'info>,
+    buddy_global_referrer_treasury: Option<&AccountInfo<'info>>,
+    buddy_global_referrer_token_account: Option<&AccountInfo<'info>>,
+    transfer_args: &GeneralTransferRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::transfer_checked_global_reward(
+        *authority.key,
+        *mint.key,
+        *token_program.key,
+        *from_token_account.key,
+        *referrer_token_account.key,
+        get_native_key_or_none(referrer_member),
+        *referrer_treasury.key,
+        *referrer_treasury_for_reward.key,
+        *referee_member.key,
+        get_native_key_or_none(buddy_global_referrer_treasury),
+        get_native_key_or_none(buddy_global_referrer_token_account),
+        transfer_args,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            authority.clone(),
+            get_native_account_info_or_default(buddy_global_referrer_treasury, buddy_link_program)
+                .clone(),
+            get_native_account_info_or_default(
+                buddy_global_referrer_token_account,
+                buddy_link_program,
+            )
+            .clone(),
+            get_native_account_info_or_default(referrer_member, buddy_link_program).clone(),
+            referrer_treasury.clone(),
+            referrer_treasury_for_reward.clone(),
+            referee_member.clone(),
+            mint.clone(),
+            token_program.clone(),
+            from_token_account.clone(),
+            referrer_token_account.clone(),
+        ],
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Checked Global Only Reward (SOL)
+///
+/// Native counterpart of [`crate::cpi::transfer_checked_global_only_reward_sol`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_global_only_reward_sol<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    global_referrer_treasury: &AccountInfo<'info>,
+    global_referrer_treasury_for_reward: &AccountInfo<'info>,
+    referee_buddy_profile: &AccountInfo<'info>,
+    referee_buddy: &AccountInfo<'info>,
+    transfer_args: &GeneralTransferRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::transfer_checked_global_only_reward(
+        *authority.key,
+        &RewardAsset::Sol,
+        *global_referrer_treasury.key,
+        *global_referrer_treasury_for_reward.key,
+        *referee_buddy_profile.key,
+        *referee_buddy.key,
+        transfer_args,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            authority.clone(),
+            global_referrer_treasury.clone(),
+            global_referrer_treasury_for_reward.clone(),
+            referee_buddy_profile.clone(),
+            referee_buddy.clone(),
+            system_program.clone(),
+            buddy_link_program.clone(),
+        ],
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Checked Global Only Reward (SPL)
+///
+/// Native counterpart of [`crate::cpi::transfer_checked_global_only_reward_spl`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_global_only_reward_spl<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    from_token_account: &AccountInfo<'info>,
+    referrer_token_account: &AccountInfo<'info>,
+    global_referrer_treasury: &AccountInfo<'info>,
+    global_referrer_treasury_for_reward: &AccountInfo<'info>,
+    referee_buddy_profile: &AccountInfo<'info>,
+    referee_buddy: &AccountInfo<'info>,
+    transfer_args: &GeneralTransferRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::transfer_checked_global_only_reward(
+        *authority.key,
+        &RewardAsset::Spl {
+            mint: *mint.key,
+            token_program: *token_program.key,
+            from: *from_token_account.key,
+            to: *referrer_token_account.key,
+        },
+        *global_referrer_treasury.key,
+        *global_referrer_treasury_for_reward.key,
+        *referee_buddy_profile.key,
+        *referee_buddy.key,
+        transfer_args,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            authority.clone(),
+            global_referrer_treasury.clone(),
+            global_referrer_treasury_for_reward.clone(),
+            referee_buddy_profile.clone(),
+            referee_buddy.clone(),
+            buddy_link_program.clone(),
+            mint.clone(),
+            token_program.clone(),
+            referrer_token_account.clone(),
+            from_token_account.clone(),
+        ],
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Multi Tier Reward (SOL)
+///
+/// Native counterpart of [`crate::cpi::transfer_multi_tier_reward_sol`].
+/// `remaining_accounts` are the treasuries of the tiers from the direct referrer up, the BuddyLink program
+/// for the unclaimed ones (paired with the referrer members if included), followed by the fallback account(s)
+/// with [`UnclaimedTiers::Fallback`].
+pub fn transfer_multi_tier_reward_sol<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    members_included: bool,
+    transfer_args: &MultiTierRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let (remaining_accounts, shared_args) =
+        multi_tier_accounts(remaining_accounts, members_included, transfer_args)?;
+
+    transfer_unchecked_local_shared_reward_sol(
+        buddy_link_program,
+        authority,
+        system_program,
+        &remaining_accounts,
+        &shared_args,
+        transfer_signer_seeds,
+    )
+}
+
+///# Transfer Multi Tier Reward (SPL)
+///
+/// Native counterpart of [`crate::cpi::transfer_multi_tier_reward_spl`].
+/// `remaining_accounts` are the token accounts of the tiers from the direct referrer up, the BuddyLink program
+/// for the unclaimed ones (paired with the referrer members if included), followed by the fallback account(s)
+/// with [`UnclaimedTiers::Fallback`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_multi_tier_reward_spl<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    from_token_account: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    members_included: bool,
+    transfer_args: &MultiTierRewardArgs,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let (remaining_accounts, shared_args) =
+        multi_tier_accounts(remaining_accounts, members_included, transfer_args)?;
+
+    transfer_unchecked_local_shared_reward_spl(
+        buddy_link_program,
+        authority,
+        mint,
+        token_program,
+        from_token_account,
+        &remaining_accounts,
+        &shared_args,
+        transfer_signer_seeds,
+    )
+}
+
+/// Splits the remaining accounts between the tiers and the fallback, checks the fallback and drops it
+/// (as well as the unclaimed tiers and the ones after the schedule) when it isn't paid.
+///
+/// Unclaimed tiers are passed as the BuddyLink program, the accounts are only copied when one of them
+/// is followed by paid accounts.
+pub(crate) fn multi_tier_accounts<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    members_included: bool,
+    transfer_args: &MultiTierRewardArgs,
+) -> Result<
+    (
+        Cow<'a, [AccountInfo<'info>]>,
+        TransferUncheckedLocalSharedRewardArgs,
+    ),
+    ProgramError,
+> {
+    let stride = if members_included { 2 } else { 1 };
+
+    let (tiers, fallback_accounts) = match transfer_args.unclaimed {
+        UnclaimedTiers::Redistribute => (remaining_accounts, &[][..]),
+        UnclaimedTiers::Fallback(fallback) => {
+            let tiers_len = remaining_accounts
+                .len()
+                .checked_sub(stride)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let fallback_accounts = &remaining_accounts[tiers_len..];
+
+            if *fallback_accounts[0].key != fallback.recipient
+                || (members_included && Some(*fallback_accounts[1].key) != fallback.member)
+            {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            remaining_accounts.split_at(tiers_len)
+        }
+    };
+
+    if tiers.len() % stride != 0 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let tiers: Vec<&[AccountInfo<'info>]> = tiers
+        .chunks(stride)
+        .take(transfer_args.schedule.len())
+        .collect();
+    let claimed: Vec<bool> = tiers.iter().map(|x| *x[0].key != BL_PROGRAM_ID).collect();
+    let shares_in_bps = instruction::multi_tier_shares(
+        &transfer_args.schedule,
+        &claimed,
+        &transfer_args.unclaimed,
+    )?;
+    let claimed_count = claimed.iter().filter(|x| **x).count();
+    let fallback_paid = shares_in_bps.len() > claimed_count;
+
+    let remaining_accounts = if claimed.iter().all(|x| *x) {
+        // The fallback is only paid when the chain is shorter than the schedule, it then directly follows the tiers
+        if fallback_paid {
+            Cow::Borrowed(remaining_accounts)
+        } else {
+            Cow::Borrowed(&remaining_accounts[..claimed_count * stride])
+        }
+    } else {
+        tiers
+            .into_iter()
+            .zip(claimed)
+            .filter(|(_, claimed)| *claimed)
+            .flat_map(|(tier, _)| tier)
+            .chain(fallback_accounts.iter().filter(|_| fallback_paid))
+            .cloned()
+            .collect()
+    };
+
+    Ok((
+        remaining_accounts,
+        TransferUncheckedLocalSharedRewardArgs {
+            total_amount: transfer_args.total_amount,
+            shares_in_bps,
+            members_included,
+        },
+    ))
+}
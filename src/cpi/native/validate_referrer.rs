@@ -0,0 +1,57 @@
+use crate::instruction;
+use crate::utils::{get_native_account_info_or_default, get_native_key_or_none};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::invoke;
+
+///# Validate Referrer
+///
+/// Native counterpart of [`crate::cpi::validate_referrer`], for programs that don't use Anchor.
+/// Accounts are the same as [`crate::instruction::validate_referrer`], the BuddyLink program account
+/// is used in place of any optional account that is None.
+#[allow(clippy::too_many_arguments)]
+pub fn validate_referrer<'info>(
+    buddy_link_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: Option<&AccountInfo<'info>>,
+    referrer_token_account: Option<&AccountInfo<'info>>,
+    referrer_member: Option<&AccountInfo<'info>>,
+    referrer_treasury: Option<&AccountInfo<'info>>,
+    referrer_treasury_for_reward: Option<&AccountInfo<'info>>,
+    referee_buddy_profile: &AccountInfo<'info>,
+    referee_buddy: &AccountInfo<'info>,
+    referee_treasury: &AccountInfo<'info>,
+    referee_member: &AccountInfo<'info>,
+) -> ProgramResult {
+    let instruction = instruction::validate_referrer(
+        *payer.key,
+        *authority.key,
+        get_native_key_or_none(mint),
+        get_native_key_or_none(referrer_token_account),
+        get_native_key_or_none(referrer_member),
+        get_native_key_or_none(referrer_treasury),
+        get_native_key_or_none(referrer_treasury_for_reward),
+        *referee_buddy_profile.key,
+        *referee_buddy.key,
+        *referee_treasury.key,
+        *referee_member.key,
+    );
+
+    let account_infos = [
+        payer.clone(),
+        authority.clone(),
+        referee_buddy_profile.clone(),
+        referee_buddy.clone(),
+        referee_treasury.clone(),
+        referee_member.clone(),
+        get_native_account_info_or_default(referrer_member, buddy_link_program).clone(),
+        get_native_account_info_or_default(referrer_treasury, buddy_link_program).clone(),
+        get_native_account_info_or_default(referrer_treasury_for_reward, buddy_link_program)
+            .clone(),
+        get_native_account_info_or_default(mint, buddy_link_program).clone(),
+        get_native_account_info_or_default(referrer_token_account, buddy_link_program).clone(),
+    ];
+
+    invoke(&instruction, &account_infos)
+}
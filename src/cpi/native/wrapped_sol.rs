@@ -0,0 +1,80 @@
+use crate::instruction::{self, WrappedSolSource};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::{invoke, invoke_signed};
+
+///# Wrap SOL
+///
+/// Native counterpart of [`crate::cpi::wrap_sol`].
+/// `funder` signs the transaction, or is a system owned PDA signing with `funder_signer_seeds`.
+pub fn wrap_sol<'info>(
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    funder: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    lamports: u64,
+    funder_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let [transfer, sync_native] = instruction::wrap_sol(
+        *funder.key,
+        &WrappedSolSource {
+            token_account: *wsol_account.key,
+            token_program: *token_program.key,
+        },
+        lamports,
+    )?;
+
+    invoke_signed(
+        &transfer,
+        &[funder.clone(), wsol_account.clone(), system_program.clone()],
+        funder_signer_seeds,
+    )?;
+    invoke(&sync_native, &[wsol_account.clone(), token_program.clone()])
+}
+
+///# Sync Native
+///
+/// Native counterpart of [`crate::cpi::sync_native`].
+pub fn sync_native<'info>(
+    token_program: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+) -> ProgramResult {
+    let instruction = anchor_spl::token_2022::spl_token_2022::instruction::sync_native(
+        token_program.key,
+        wsol_account.key,
+    )?;
+
+    invoke(&instruction, &[wsol_account.clone(), token_program.clone()])
+}
+
+///# Unwrap SOL
+///
+/// Native counterpart of [`crate::cpi::unwrap_sol`].
+/// `owner` signs the transaction, or is the PDA owning the wSOL account signing with `owner_signer_seeds`.
+pub fn unwrap_sol<'info>(
+    token_program: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    owner_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = instruction::unwrap_sol(
+        &WrappedSolSource {
+            token_account: *wsol_account.key,
+            token_program: *token_program.key,
+        },
+        *destination.key,
+        *owner.key,
+    )?;
+
+    invoke_signed(
+        &instruction,
+        &[
+            wsol_account.clone(),
+            destination.clone(),
+            owner.clone(),
+            token_program.clone(),
+        ],
+        owner_signer_seeds,
+    )
+}
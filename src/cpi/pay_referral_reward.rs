@@ -0,0 +1,101 @@
+use crate::constants::BL_PROGRAM_ID;
+use crate::cpi::native;
+use crate::instruction::{RewardVariant, RewardVariantReason};
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+#[derive(Accounts)]
+pub struct PayReferralReward<'info> {
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+
+    /// CHECK: Authority of the account sending the funds.
+    #[account(mut, signer)]
+    pub authority: AccountInfo<'info>,
+
+    /// CHECK: System Program (None for SPL rewards)
+    #[account()]
+    pub system_program: Option<AccountInfo<'info>>,
+    /// CHECK: Mint (None for SOL rewards)
+    #[account()]
+    pub mint: Option<AccountInfo<'info>>,
+    /// CHECK: Token program (None for SOL rewards)
+    #[account()]
+    pub token_program: Option<AccountInfo<'info>>,
+    /// CHECK: Account sending the funds (None for SOL rewards).
+    #[account(mut)]
+    pub from_token_account: Option<AccountInfo<'info>>,
+
+    /// CHECK: Buddy Link Profile of the referee.
+    #[account()]
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Buddy Link Paid buddy of the referee (could be the same as above).
+    #[account()]
+    pub referee_buddy: AccountInfo<'info>,
+    /// CHECK: Referee treasury (is owned by above) (None to only use the global referral tree).
+    #[account(mut)]
+    pub referee_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Referee member (account of the referee within your organization) (None to only use the global referral tree).
+    #[account(mut)]
+    pub referee_member: Option<AccountInfo<'info>>,
+
+    /// CHECK: Referrer member (account of the referrer within your organization).
+    #[account(mut)]
+    pub referrer_member: Option<AccountInfo<'info>>,
+    /// CHECK: Referrer treasury (treasury that owns the referrer member ).
+    #[account(mut)]
+    pub referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Referrer treasury for reward (treasury that is linked to the current mint, could be the same as above).
+    #[account(mut)]
+    pub referrer_treasury_for_reward: Option<AccountInfo<'info>>,
+    /// CHECK: Account of the referrer receiving the funds (None for SOL rewards).
+    #[account(mut)]
+    pub referrer_token_account: Option<AccountInfo<'info>>,
+
+    /// CHECK: Global referrer treasury (treasury of the global referrer of current referee).
+    #[account(mut)]
+    pub global_referrer_treasury: Option<AccountInfo<'info>>,
+    /// CHECK: Global referrer treasury for reward (treasury of the global referrer that is linked to the current mint, could be the same as above).
+    #[account(mut)]
+    pub global_referrer_treasury_for_reward: Option<AccountInfo<'info>>,
+    /// CHECK: Account of the global referrer receiving the funds (None for SOL rewards).
+    #[account(mut)]
+    pub global_referrer_token_account: Option<AccountInfo<'info>>,
+}
+
+/// Pays the referrer of the referee with the transfer variant fitting the accounts given,
+/// see [`crate::instruction::choose_reward_variant`]. Returns the variant used and why.
+pub fn pay_referral_reward<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PayReferralReward<'info>>,
+    amount: u64,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> std::result::Result<(RewardVariant, RewardVariantReason), ProgramError> {
+    let accounts = &ctx.accounts;
+
+    native::pay_referral_reward(
+        &native::PayReferralRewardAccounts {
+            buddy_link_program: &accounts.buddy_link_program,
+            authority: &accounts.authority,
+            system_program: accounts.system_program.as_ref(),
+            mint: accounts.mint.as_ref(),
+            token_program: accounts.token_program.as_ref(),
+            from_token_account: accounts.from_token_account.as_ref(),
+            referee_buddy_profile: &accounts.referee_buddy_profile,
+            referee_buddy: &accounts.referee_buddy,
+            referee_treasury: accounts.referee_treasury.as_ref(),
+            referee_member: accounts.referee_member.as_ref(),
+            referrer_member: accounts.referrer_member.as_ref(),
+            referrer_treasury: accounts.referrer_treasury.as_ref(),
+            referrer_treasury_for_reward: accounts.referrer_treasury_for_reward.as_ref(),
+            referrer_token_account: accounts.referrer_token_account.as_ref(),
+            global_referrer_treasury: accounts.global_referrer_treasury.as_ref(),
+            global_referrer_treasury_for_reward: accounts
+                .global_referrer_treasury_for_reward
+                .as_ref(),
+            global_referrer_token_account: accounts.global_referrer_token_account.as_ref(),
+        },
+        amount,
+        transfer_signer_seeds,
+    )
+}
@@ -0,0 +1,51 @@
+use crate::constants::BL_PROGRAM_ID;
+use crate::cpi::native;
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use solana_program::entrypoint::ProgramResult;
+
+#[derive(Accounts)]
+pub struct CreateReferrerTokenAccount<'info> {
+    /// CHECK: Associated Token Program
+    #[account(executable, address = AssociatedToken::id())]
+    pub associated_token_program: AccountInfo<'info>,
+    /// CHECK: System Program
+    #[account(executable, address = solana_program::system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+    /// CHECK: Token program of the mint
+    #[account(executable, constraint = token_program.key() == Token::id() || token_program.key() == Token2022::id())]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Account paying the rent (signer or system owned PDA).
+    #[account(mut)]
+    pub rent_payer: AccountInfo<'info>,
+    /// CHECK: Associated token account of the treasury, created if it doesn't exist.
+    #[account(mut)]
+    pub token_account: AccountInfo<'info>,
+    /// CHECK: Referrer treasury for reward (or global referrer one), owning the token account.
+    #[account(owner = BL_PROGRAM_ID)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: Mint of the reward
+    #[account()]
+    pub mint: AccountInfo<'info>,
+}
+
+/// Creates the token account of a referrer if it doesn't exist yet, before a BuddyLink SPL transfer paying it.
+pub fn create_referrer_token_account<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateReferrerTokenAccount<'info>>,
+    rent_payer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::create_referrer_token_account(
+        &ctx.accounts.associated_token_program,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.rent_payer,
+        &ctx.accounts.token_account,
+        &ctx.accounts.treasury,
+        &ctx.accounts.mint,
+        rent_payer_signer_seeds,
+    )
+}
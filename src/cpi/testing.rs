@@ -0,0 +1,503 @@
+//! Recording [`BuddyLinkCpi`] to unit test the reward logic of a program without a bank or a validator.
+//!
+//! Nothing is invoked and no account is checked, the calls are recorded with the amounts each recipient would get.
+//! As the BuddyLink program, shared rewards fail with invalid shares, the checked global reward reads the referee
+//! member to split the amount with the global referrer (see [`reward_outcome`]) and
+//! [`BuddyLinkCpi::pay_referral_reward`] reads the referee accounts to choose its variant.
+//!
+//! [`reward_outcome`]: crate::instruction::reward_outcome
+
+use crate::cpi::native;
+use crate::cpi::{
+    BuddyLinkCpi, PayReferralReward, TransferCheckedGlobalOnlyRewardSol,
+    TransferCheckedGlobalOnlyRewardSpl, TransferCheckedGlobalReward,
+    TransferRewardUncheckedMultipleSol, TransferRewardUncheckedMultipleSpl,
+    TransferSecureLocalReward, ValidateReferrer,
+};
+use crate::error::BuddyLinkError;
+use crate::instruction::{
+    bps_amount, global_referrer_share_bps, shared_reward_amounts, MultiTierRewardArgs,
+    RewardVariant, RewardVariantReason, TransferUncheckedLocalSharedRewardArgs, UnclaimedTiers,
+};
+use crate::state::Member;
+use anchor_lang::prelude::*;
+use solana_program::entrypoint::ProgramResult;
+use std::cell::RefCell;
+
+/// Amount a recipient (treasury for SOL, token account for SPL) would receive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Payout {
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedTransfer {
+    /// Instruction of BuddyLink used (multi tier rewards are unchecked local shared ones).
+    pub variant: RewardVariant,
+    pub authority: Pubkey,
+    /// Mint of SPL rewards, None for SOL.
+    pub mint: Option<Pubkey>,
+    /// Account sending the tokens, None for SOL (sent by the authority).
+    pub from_token_account: Option<Pubkey>,
+    pub payouts: Vec<Payout>,
+    /// Accounts of the context followed by the remaining accounts.
+    pub accounts: Vec<Pubkey>,
+    pub signer_seeds: Vec<Vec<Vec<u8>>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedValidation {
+    pub payer: Pubkey,
+    pub authority: Pubkey,
+    pub referee_buddy_profile: Pubkey,
+    pub referee_buddy: Pubkey,
+    pub referee_treasury: Pubkey,
+    pub referee_member: Pubkey,
+    pub referrer_member: Option<Pubkey>,
+    pub referrer_treasury: Option<Pubkey>,
+    pub referrer_treasury_for_reward: Option<Pubkey>,
+    pub referrer_token_account: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordedCall {
+    Transfer(RecordedTransfer),
+    Validation(RecordedValidation),
+}
+
+/// [`BuddyLinkCpi`] recording the calls in order.
+#[derive(Debug, Default)]
+pub struct RecordingBuddyLinkCpi {
+    calls: RefCell<Vec<RecordedCall>>,
+    error: RefCell<Option<ProgramError>>,
+}
+
+impl RecordingBuddyLinkCpi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The next call fails with the error (as BuddyLink would), without being recorded.
+    pub fn fail_next(&self, error: impl Into<ProgramError>) {
+        *self.error.borrow_mut() = Some(error.into());
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn transfers(&self) -> Vec<RecordedTransfer> {
+        self.calls
+            .borrow()
+            .iter()
+            .filter_map(|call| match call {
+                RecordedCall::Transfer(transfer) => Some(transfer.clone()),
+                RecordedCall::Validation(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn validations(&self) -> Vec<RecordedValidation> {
+        self.calls
+            .borrow()
+            .iter()
+            .filter_map(|call| match call {
+                RecordedCall::Validation(validation) => Some(validation.clone()),
+                RecordedCall::Transfer(_) => None,
+            })
+            .collect()
+    }
+
+    /// Sum of the amounts sent to the recipient by all the transfers.
+    pub fn total_paid(&self, recipient: &Pubkey) -> u64 {
+        self.transfers()
+            .iter()
+            .flat_map(|transfer| &transfer.payouts)
+            .filter(|payout| &payout.recipient == recipient)
+            .map(|payout| payout.amount)
+            .sum()
+    }
+
+    pub fn clear(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    fn check_error(&self) -> ProgramResult {
+        match self.error.borrow_mut().take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn record_transfer<'info, T: ToAccountMetas + ToAccountInfos<'info>>(
+        &self,
+        ctx: &CpiContext<'_, '_, '_, 'info, T>,
+        variant: RewardVariant,
+        authority: &AccountInfo,
+        spl: Option<(&AccountInfo, &AccountInfo)>,
+        payouts: Vec<Payout>,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) {
+        self.calls
+            .borrow_mut()
+            .push(RecordedCall::Transfer(RecordedTransfer {
+                variant,
+                authority: *authority.key,
+                mint: spl.map(|(mint, _)| *mint.key),
+                from_token_account: spl.map(|(_, from)| *from.key),
+                payouts,
+                accounts: context_keys(ctx),
+                signer_seeds: transfer_signer_seeds
+                    .iter()
+                    .map(|seeds| seeds.iter().map(|seed| seed.to_vec()).collect())
+                    .collect(),
+            }));
+    }
+}
+
+fn context_keys<'info, T: ToAccountMetas + ToAccountInfos<'info>>(
+    ctx: &CpiContext<'_, '_, '_, 'info, T>,
+) -> Vec<Pubkey> {
+    ctx.accounts
+        .to_account_infos()
+        .iter()
+        .chain(&ctx.remaining_accounts)
+        .map(|x| *x.key)
+        .collect()
+}
+
+/// Amounts of the recipients of a shared reward, with the errors of BuddyLink for invalid shares.
+fn shared_payouts(
+    remaining_accounts: &[AccountInfo],
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+) -> std::result::Result<Vec<Payout>, ProgramError> {
+    let stride = if transfer_args.members_included { 2 } else { 1 };
+    let recipients: Vec<&AccountInfo> = remaining_accounts.iter().step_by(stride).collect();
+
+    if transfer_args.shares_in_bps.len() != recipients.len() {
+        return Err(BuddyLinkError::InvalidNumberOfSharesSpecified.into());
+    }
+
+    let amounts = shared_reward_amounts(transfer_args.total_amount, &transfer_args.shares_in_bps)?;
+
+    Ok(recipients
+        .into_iter()
+        .zip(amounts)
+        .map(|(recipient, amount)| Payout {
+            recipient: *recipient.key,
+            amount,
+        })
+        .collect())
+}
+
+fn payout(recipient: &AccountInfo, amount: u64) -> Vec<Payout> {
+    vec![Payout {
+        recipient: *recipient.key,
+        amount,
+    }]
+}
+
+impl BuddyLinkCpi for RecordingBuddyLinkCpi {
+    fn transfer_unchecked_local_shared_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        let payouts = shared_payouts(
+            &ctx.remaining_accounts,
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount,
+                shares_in_bps,
+                members_included,
+            },
+        )?;
+        self.record_transfer(
+            &ctx,
+            RewardVariant::UncheckedLocalShared,
+            &ctx.accounts.authority,
+            None,
+            payouts,
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_unchecked_local_shared_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        shares_in_bps: Vec<u16>,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        let payouts = shared_payouts(
+            &ctx.remaining_accounts,
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount,
+                shares_in_bps,
+                members_included,
+            },
+        )?;
+        self.record_transfer(
+            &ctx,
+            RewardVariant::UncheckedLocalShared,
+            &ctx.accounts.authority,
+            Some((&ctx.accounts.mint, &ctx.accounts.from_token_account)),
+            payouts,
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_multi_tier_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        let (remaining_accounts, shared_args) = native::multi_tier_accounts(
+            &ctx.remaining_accounts,
+            members_included,
+            &MultiTierRewardArgs {
+                total_amount,
+                schedule,
+                unclaimed,
+            },
+        )?;
+        let payouts = shared_payouts(&remaining_accounts, &shared_args)?;
+        self.record_transfer(
+            &ctx,
+            RewardVariant::UncheckedLocalShared,
+            &ctx.accounts.authority,
+            None,
+            payouts,
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_multi_tier_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+        total_amount: u64,
+        schedule: Vec<u16>,
+        unclaimed: UnclaimedTiers,
+        members_included: bool,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        let (remaining_accounts, shared_args) = native::multi_tier_accounts(
+            &ctx.remaining_accounts,
+            members_included,
+            &MultiTierRewardArgs {
+                total_amount,
+                schedule,
+                unclaimed,
+            },
+        )?;
+        let payouts = shared_payouts(&remaining_accounts, &shared_args)?;
+        self.record_transfer(
+            &ctx,
+            RewardVariant::UncheckedLocalShared,
+            &ctx.accounts.authority,
+            Some((&ctx.accounts.mint, &ctx.accounts.from_token_account)),
+            payouts,
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_secure_local_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferSecureLocalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        self.record_transfer(
+            &ctx,
+            RewardVariant::SecureLocal,
+            &ctx.accounts.authority,
+            Some((&ctx.accounts.mint, &ctx.accounts.from_token_account)),
+            payout(&ctx.accounts.referrer_token_account, amount),
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_checked_global_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        // The global referrer is paid its share first
+        let mut payouts = vec![];
+        let mut local_amount = amount;
+        if let Some(global_referrer_token_account) =
+            &ctx.accounts.buddy_global_referrer_token_account
+        {
+            let referee_member = ctx.accounts.referee_member.try_borrow_data()?;
+            let share_in_bps = global_referrer_share_bps(&Member::new(&referee_member[..])?)?;
+            let global_amount = bps_amount(amount, share_in_bps)?;
+            payouts.extend(payout(global_referrer_token_account, global_amount));
+            local_amount -= global_amount;
+        }
+        payouts.extend(payout(&ctx.accounts.referrer_token_account, local_amount));
+
+        self.record_transfer(
+            &ctx,
+            RewardVariant::CheckedGlobal,
+            &ctx.accounts.authority,
+            Some((&ctx.accounts.mint, &ctx.accounts.from_token_account)),
+            payouts,
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_checked_global_only_reward_sol<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSol<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        self.record_transfer(
+            &ctx,
+            RewardVariant::CheckedGlobalOnly,
+            &ctx.accounts.authority,
+            None,
+            payout(&ctx.accounts.global_referrer_treasury_for_reward, amount),
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn transfer_checked_global_only_reward_spl<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSpl<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        self.record_transfer(
+            &ctx,
+            RewardVariant::CheckedGlobalOnly,
+            &ctx.accounts.authority,
+            Some((&ctx.accounts.mint, &ctx.accounts.from_token_account)),
+            payout(&ctx.accounts.referrer_token_account, amount),
+            transfer_signer_seeds,
+        );
+
+        Ok(())
+    }
+
+    fn validate_referrer<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, ValidateReferrer<'info>>,
+    ) -> ProgramResult {
+        self.check_error()?;
+
+        let accounts = &ctx.accounts;
+        let key = |account: &Option<AccountInfo>| account.as_ref().map(|x| *x.key);
+        self.calls
+            .borrow_mut()
+            .push(RecordedCall::Validation(RecordedValidation {
+                payer: *accounts.payer.key,
+                authority: *accounts.authority.key,
+                referee_buddy_profile: *accounts.referee_buddy_profile.key,
+                referee_buddy: *accounts.referee_buddy.key,
+                referee_treasury: *accounts.referee_treasury.key,
+                referee_member: *accounts.referee_member.key,
+                referrer_member: key(&accounts.referrer_member),
+                referrer_treasury: key(&accounts.referrer_treasury),
+                referrer_treasury_for_reward: key(&accounts.referrer_treasury_for_reward),
+                referrer_token_account: key(&accounts.referrer_token_account),
+                mint: key(&accounts.mint),
+            }));
+
+        Ok(())
+    }
+
+    fn pay_referral_reward<'info>(
+        &self,
+        ctx: CpiContext<'_, '_, '_, 'info, PayReferralReward<'info>>,
+        amount: u64,
+        transfer_signer_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(RewardVariant, RewardVariantReason), ProgramError> {
+        self.check_error()?;
+
+        let accounts = &ctx.accounts;
+        let route = native::select_route(&native::PayReferralRewardAccounts {
+            buddy_link_program: &accounts.buddy_link_program,
+            authority: &accounts.authority,
+            system_program: accounts.system_program.as_ref(),
+            mint: accounts.mint.as_ref(),
+            token_program: accounts.token_program.as_ref(),
+            from_token_account: accounts.from_token_account.as_ref(),
+            referee_buddy_profile: &accounts.referee_buddy_profile,
+            referee_buddy: &accounts.referee_buddy,
+            referee_treasury: accounts.referee_treasury.as_ref(),
+            referee_member: accounts.referee_member.as_ref(),
+            referrer_member: accounts.referrer_member.as_ref(),
+            referrer_treasury: accounts.referrer_treasury.as_ref(),
+            referrer_treasury_for_reward: accounts.referrer_treasury_for_reward.as_ref(),
+            referrer_token_account: accounts.referrer_token_account.as_ref(),
+            global_referrer_treasury: accounts.global_referrer_treasury.as_ref(),
+            global_referrer_treasury_for_reward: accounts
+                .global_referrer_treasury_for_reward
+                .as_ref(),
+            global_referrer_token_account: accounts.global_referrer_token_account.as_ref(),
+        })?;
+
+        let recipient = match (route.variant, route.spl) {
+            (RewardVariant::CheckedGlobalOnly, None) => {
+                &accounts.global_referrer_treasury_for_reward
+            }
+            (RewardVariant::CheckedGlobalOnly, Some(_)) => &accounts.global_referrer_token_account,
+            (_, None) => &accounts.referrer_treasury_for_reward,
+            (_, Some(_)) => &accounts.referrer_token_account,
+        }
+        .as_ref()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        self.record_transfer(
+            &ctx,
+            route.variant,
+            &accounts.authority,
+            route.spl.map(|(mint, _, from)| (mint, from)),
+            payout(recipient, amount),
+            transfer_signer_seeds,
+        );
+
+        Ok((route.variant, route.reason))
+    }
+}
@@ -1,15 +1,16 @@
 use crate::constants::BL_PROGRAM_ID;
-use crate::instruction;
-use crate::instruction::{GeneralTransferRewardArgs, TransferUncheckedLocalSharedRewardArgs};
-use crate::utils::{get_account_info_or_default, get_key_or_none};
+use crate::cpi::native;
+use crate::instruction::{
+    GeneralTransferRewardArgs, MultiTierRewardArgs, TransferUncheckedLocalSharedRewardArgs,
+    UnclaimedTiers,
+};
 use anchor_lang::prelude::*;
-use anchor_lang::{Accounts, Key, ToAccountInfo};
+use anchor_lang::Accounts;
 use anchor_spl::token::{Token};
 use solana_program::entrypoint::ProgramResult;
-use solana_program::program::invoke_signed;
 
 #[derive(Accounts)]
-pub struct TransferRewardUncheckedMultiple<'info> {
+pub struct TransferRewardUncheckedMultipleSol<'info> {
     /// CHECK: The buddylink program
     #[account(executable, address = BL_PROGRAM_ID)]
     pub buddy_link_program: AccountInfo<'info>,
@@ -20,62 +21,137 @@ pub struct TransferRewardUncheckedMultiple<'info> {
 
     /// CHECK: System Program
     #[account(executable, address = solana_program::system_program::ID)]
-    pub system_program: Option<AccountInfo<'info>>,
+    pub system_program: AccountInfo<'info>,
+    /*
+    Remaining accounts with be the referrer treasuries paired with referrer_member
+     */
+}
+
+pub fn transfer_unchecked_local_shared_reward_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+    total_amount: u64,
+    shares_in_bps: Vec<u16>,
+    members_included: bool,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::transfer_unchecked_local_shared_reward_sol(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        &ctx.remaining_accounts,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount,
+            shares_in_bps,
+            members_included,
+        },
+        transfer_signer_seeds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct TransferRewardUncheckedMultipleSpl<'info> {
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+
+    /// CHECK: Authority of the account sending the funds.
+    #[account(mut, signer)]
+    pub authority: AccountInfo<'info>,
 
     /// CHECK: Mint
     #[account()]
-    pub mint: Option<AccountInfo<'info>>,
+    pub mint: AccountInfo<'info>,
     /// CHECK: Token program
     #[account(executable, address = Token::id())]
-    pub token_program: Option<AccountInfo<'info>>,
+    pub token_program: AccountInfo<'info>,
 
     /// CHECK: Account sending the funds.
     #[account(mut)]
-    pub from_token_account: Option<AccountInfo<'info>>,
+    pub from_token_account: AccountInfo<'info>,
     /*
-    Remaining accounts with be the referrer treasuries paired with referrer_member for sol or token accounts for spl
+    Remaining accounts with be the referrer token accounts paired with referrer_member
      */
 }
 
-pub fn transfer_unchecked_local_shared_reward<'info>(
-    ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultiple<'info>>,
+pub fn transfer_unchecked_local_shared_reward_spl<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
     total_amount: u64,
     shares_in_bps: Vec<u16>,
     members_included: bool,
     transfer_signer_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    let remaining_accounts_key: Vec<Pubkey> =
-        ctx.remaining_accounts.iter().map(|x| x.key()).collect();
-
-    let default_account_info = ctx.accounts.buddy_link_program.to_account_info();
-
-    let instruction = instruction::transfer_unchecked_local_shared_reward(
-        ctx.accounts.authority.key(),
-        Some(
-            get_account_info_or_default(&ctx.accounts.system_program, &default_account_info).key(),
-        ),
-        get_key_or_none(&ctx.accounts.mint),
-        Some(get_account_info_or_default(&ctx.accounts.token_program, &default_account_info).key()),
-        get_key_or_none(&ctx.accounts.from_token_account),
-        &remaining_accounts_key,
+    native::transfer_unchecked_local_shared_reward_spl(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        &ctx.accounts.from_token_account,
+        &ctx.remaining_accounts,
         &TransferUncheckedLocalSharedRewardArgs {
             total_amount,
             shares_in_bps,
             members_included,
         },
-    );
-
-    let mut account_infos = vec![
-        ctx.accounts.authority.to_account_info(),
-        get_account_info_or_default(&ctx.accounts.system_program, &default_account_info),
-        get_account_info_or_default(&ctx.accounts.mint, &default_account_info),
-        get_account_info_or_default(&ctx.accounts.token_program, &default_account_info),
-        get_account_info_or_default(&ctx.accounts.from_token_account, &default_account_info),
-    ];
+        transfer_signer_seeds,
+    )
+}
 
-    account_infos.extend_from_slice(&ctx.remaining_accounts);
+/// Splits the reward between the referrers following a tier schedule, see [`crate::instruction::transfer_multi_tier_reward`].
+///
+/// Remaining accounts are the referrer treasuries of the tiers from the direct referrer up, the BuddyLink program
+/// for the unclaimed ones (paired with referrer_member if included), followed by the fallback treasury with
+/// [`UnclaimedTiers::Fallback`].
+pub fn transfer_multi_tier_reward_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSol<'info>>,
+    total_amount: u64,
+    schedule: Vec<u16>,
+    unclaimed: UnclaimedTiers,
+    members_included: bool,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::transfer_multi_tier_reward_sol(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        &ctx.remaining_accounts,
+        members_included,
+        &MultiTierRewardArgs {
+            total_amount,
+            schedule,
+            unclaimed,
+        },
+        transfer_signer_seeds,
+    )
+}
 
-    invoke_signed(&instruction, &account_infos, transfer_signer_seeds)
+/// Splits the reward between the referrers following a tier schedule, see [`crate::instruction::transfer_multi_tier_reward`].
+///
+/// Remaining accounts are the referrer token accounts of the tiers from the direct referrer up, the BuddyLink program
+/// for the unclaimed ones (paired with referrer_member if included), followed by the fallback token account with
+/// [`UnclaimedTiers::Fallback`].
+pub fn transfer_multi_tier_reward_spl<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferRewardUncheckedMultipleSpl<'info>>,
+    total_amount: u64,
+    schedule: Vec<u16>,
+    unclaimed: UnclaimedTiers,
+    members_included: bool,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::transfer_multi_tier_reward_spl(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        &ctx.accounts.from_token_account,
+        &ctx.remaining_accounts,
+        members_included,
+        &MultiTierRewardArgs {
+            total_amount,
+            schedule,
+            unclaimed,
+        },
+        transfer_signer_seeds,
+    )
 }
 
 #[derive(Accounts)]
@@ -131,38 +207,20 @@ pub fn transfer_secure_local_reward<'info>(
     amount: u64,
     transfer_signer_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    let instruction = instruction::transfer_secure_local_reward(
-        ctx.accounts.authority.key(),
-        ctx.accounts.mint.key(),
-        ctx.accounts.token_program.key(),
-        ctx.accounts.from_token_account.key(),
-        ctx.accounts.referrer_token_account.key(),
-        ctx.accounts.referrer_member.key(),
-        ctx.accounts.referrer_treasury.key(),
-        ctx.accounts.referrer_treasury_for_reward.key(),
-        ctx.accounts.referee_buddy_profile.key(),
-        ctx.accounts.referee_buddy.key(),
-        ctx.accounts.referee_treasury.key(),
-        ctx.accounts.referee_member.key(),
+    native::transfer_secure_local_reward(
+        &ctx.accounts.authority,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        &ctx.accounts.from_token_account,
+        &ctx.accounts.referrer_token_account,
+        &ctx.accounts.referrer_member,
+        &ctx.accounts.referrer_treasury,
+        &ctx.accounts.referrer_treasury_for_reward,
+        &ctx.accounts.referee_buddy_profile,
+        &ctx.accounts.referee_buddy,
+        &ctx.accounts.referee_treasury,
+        &ctx.accounts.referee_member,
         &GeneralTransferRewardArgs { amount },
-    );
-
-    invoke_signed(
-        &instruction,
-        &[
-            ctx.accounts.authority.to_account_info(),
-            ctx.accounts.mint.to_account_info(),
-            ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.from_token_account.to_account_info(),
-            ctx.accounts.referrer_member.to_account_info(),
-            ctx.accounts.referrer_treasury.to_account_info(),
-            ctx.accounts.referrer_treasury_for_reward.to_account_info(),
-            ctx.accounts.referee_buddy_profile.to_account_info(),
-            ctx.accounts.referee_buddy.to_account_info(),
-            ctx.accounts.referee_treasury.to_account_info(),
-            ctx.accounts.referee_member.to_account_info(),
-            ctx.accounts.referrer_token_account.to_account_info(),
-        ],
         transfer_signer_seeds,
     )
 }
@@ -218,50 +276,26 @@ pub fn transfer_checked_global_reward<'info>(
     amount: u64,
     transfer_signer_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    let instruction = instruction::transfer_checked_global_reward(
-        ctx.accounts.authority.key(),
-        ctx.accounts.mint.key(),
-        ctx.accounts.token_program.key(),
-        ctx.accounts.from_token_account.key(),
-        ctx.accounts.referrer_token_account.key(),
-        get_key_or_none(&ctx.accounts.referrer_member),
-        ctx.accounts.referrer_treasury.key(),
-        ctx.accounts.referrer_treasury_for_reward.key(),
-        ctx.accounts.referee_member.key(),
-        get_key_or_none(&ctx.accounts.buddy_global_referrer_treasury),
-        get_key_or_none(&ctx.accounts.buddy_global_referrer_token_account),
+    native::transfer_checked_global_reward(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        &ctx.accounts.from_token_account,
+        &ctx.accounts.referrer_token_account,
+        ctx.accounts.referrer_member.as_ref(),
+        &ctx.accounts.referrer_treasury,
+        &ctx.accounts.referrer_treasury_for_reward,
+        &ctx.accounts.referee_member,
+        ctx.accounts.buddy_global_referrer_treasury.as_ref(),
+        ctx.accounts.buddy_global_referrer_token_account.as_ref(),
         &GeneralTransferRewardArgs { amount },
-    );
-
-    let default_account_info = ctx.accounts.buddy_link_program.to_account_info();
-
-    invoke_signed(
-        &instruction,
-        &[
-            ctx.accounts.authority.to_account_info(),
-            get_account_info_or_default(
-                &ctx.accounts.buddy_global_referrer_treasury,
-                &default_account_info,
-            ),
-            get_account_info_or_default(
-                &ctx.accounts.buddy_global_referrer_token_account,
-                &default_account_info,
-            ),
-            ctx.accounts.referee_member.to_account_info(),
-            ctx.accounts.referrer_treasury.to_account_info(),
-            ctx.accounts.referrer_treasury_for_reward.to_account_info(),
-            ctx.accounts.referee_member.to_account_info(),
-            ctx.accounts.mint.to_account_info(),
-            ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.from_token_account.to_account_info(),
-            ctx.accounts.referrer_token_account.to_account_info(),
-        ],
         transfer_signer_seeds,
     )
 }
 
 #[derive(Accounts)]
-pub struct TransferCheckedGlobalOnlyReward<'info> {
+pub struct TransferCheckedGlobalOnlyRewardSol<'info> {
     /// CHECK: The buddylink program
     #[account(executable, address = BL_PROGRAM_ID)]
     pub buddy_link_program: AccountInfo<'info>,
@@ -270,28 +304,71 @@ pub struct TransferCheckedGlobalOnlyReward<'info> {
     #[account(mut, signer)]
     pub authority: AccountInfo<'info>,
 
-    /// CHECK: System Program - Only used if sending SOL, None if sending SPL.
+    /// CHECK: System Program
     #[account(executable, address = solana_program::system_program::ID)]
-    pub system_program: Option<AccountInfo<'info>>,
+    pub system_program: AccountInfo<'info>,
+
+    /// CHECK: Global referrer treasury (treasury of the global referrer of current referee).
+    #[account(mut)]
+    pub global_referrer_treasury: AccountInfo<'info>,
+    /// CHECK: Global referrer treasury for reward (treasury of the global referrer of current referee that is linked to the current mint, could be same as above).
+    #[account(mut)]
+    pub global_referrer_treasury_for_reward: AccountInfo<'info>,
 
-    /// CHECK: Mint, None if sending SOL.
+    /// CHECK: Buddy Link Profile of the referee.
+    #[account()]
+    pub referee_buddy_profile: AccountInfo<'info>,
+    /// CHECK: Buddy Link Paid buddy of the referee (could be the same as above).
     #[account()]
-    pub mint: Option<AccountInfo<'info>>,
-    /// CHECK: Token program, None if sending SOL.
+    pub referee_buddy: AccountInfo<'info>,
+}
+
+pub fn transfer_checked_global_only_reward_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSol<'info>>,
+    amount: u64,
+    transfer_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::transfer_checked_global_only_reward_sol(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+        &ctx.accounts.global_referrer_treasury,
+        &ctx.accounts.global_referrer_treasury_for_reward,
+        &ctx.accounts.referee_buddy_profile,
+        &ctx.accounts.referee_buddy,
+        &GeneralTransferRewardArgs { amount },
+        transfer_signer_seeds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct TransferCheckedGlobalOnlyRewardSpl<'info> {
+    /// CHECK: The buddylink program
+    #[account(executable, address = BL_PROGRAM_ID)]
+    pub buddy_link_program: AccountInfo<'info>,
+
+    /// CHECK: Authority of the account sending the funds.
+    #[account(mut, signer)]
+    pub authority: AccountInfo<'info>,
+
+    /// CHECK: Mint
+    #[account()]
+    pub mint: AccountInfo<'info>,
+    /// CHECK: Token program
     #[account(executable, address = Token::id())]
-    pub token_program: Option<AccountInfo<'info>>,
+    pub token_program: AccountInfo<'info>,
 
-    /// CHECK: From token account - None if sending SOL (will send from authority), else is Token Account
+    /// CHECK: Account sending the funds.
     #[account(mut)]
-    pub from_token_account: Option<AccountInfo<'info>>,
-    /// CHECK: Referrer token account - None if receiving SOL (will send to treasury), else is Token Account
+    pub from_token_account: AccountInfo<'info>,
+    /// CHECK: Account receiving the funds (buddy link owned).
     #[account(mut)]
-    pub referrer_token_account: Option<AccountInfo<'info>>,
+    pub referrer_token_account: AccountInfo<'info>,
 
     /// CHECK: Global referrer treasury (treasury of the global referrer of current referee).
     #[account(mut)]
     pub global_referrer_treasury: AccountInfo<'info>,
-    /// CHECK: Global referrer treasury for reward (treasury of the global referrer of current referee that is linked to the current mint, could be same as ).
+    /// CHECK: Global referrer treasury for reward (treasury of the global referrer of current referee that is linked to the current mint, could be same as above).
     #[account(mut)]
     pub global_referrer_treasury_for_reward: AccountInfo<'info>,
 
@@ -303,48 +380,23 @@ pub struct TransferCheckedGlobalOnlyReward<'info> {
     pub referee_buddy: AccountInfo<'info>,
 }
 
-pub fn transfer_checked_global_only_reward<'info>(
-    ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyReward<'info>>,
+pub fn transfer_checked_global_only_reward_spl<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferCheckedGlobalOnlyRewardSpl<'info>>,
     amount: u64,
     transfer_signer_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    let default_account_info = ctx.accounts.buddy_link_program.to_account_info();
-
-    let instruction = instruction::transfer_checked_global_only_reward(
-        ctx.accounts.authority.key(),
-        Some(
-            get_account_info_or_default(&ctx.accounts.system_program, &default_account_info).key(),
-        ),
-        get_key_or_none(&ctx.accounts.mint),
-        Some(get_account_info_or_default(&ctx.accounts.token_program, &default_account_info).key()),
-        get_key_or_none(&ctx.accounts.from_token_account),
-        get_key_or_none(&ctx.accounts.referrer_token_account),
-        ctx.accounts.global_referrer_treasury.key(),
-        ctx.accounts.global_referrer_treasury_for_reward.key(),
-        ctx.accounts.referee_buddy_profile.key(),
-        ctx.accounts.referee_buddy.key(),
+    native::transfer_checked_global_only_reward_spl(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.authority,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program,
+        &ctx.accounts.from_token_account,
+        &ctx.accounts.referrer_token_account,
+        &ctx.accounts.global_referrer_treasury,
+        &ctx.accounts.global_referrer_treasury_for_reward,
+        &ctx.accounts.referee_buddy_profile,
+        &ctx.accounts.referee_buddy,
         &GeneralTransferRewardArgs { amount },
-    );
-
-    invoke_signed(
-        &instruction,
-        &[
-            ctx.accounts.authority.to_account_info(),
-            ctx.accounts.global_referrer_treasury.to_account_info(),
-            ctx.accounts
-                .global_referrer_treasury_for_reward
-                .to_account_info(),
-            ctx.accounts.referee_buddy_profile.to_account_info(),
-            ctx.accounts.referee_buddy.to_account_info(),
-            get_account_info_or_default(&ctx.accounts.system_program, &default_account_info),
-            get_account_info_or_default(&ctx.accounts.mint, &default_account_info),
-            get_account_info_or_default(&ctx.accounts.token_program, &default_account_info),
-            get_account_info_or_default(
-                &ctx.accounts.referrer_token_account,
-                &default_account_info,
-            ),
-            get_account_info_or_default(&ctx.accounts.from_token_account, &default_account_info),
-        ],
         transfer_signer_seeds,
     )
 }
@@ -1,10 +1,8 @@
 use crate::constants::BL_PROGRAM_ID;
-use crate::instruction;
-use crate::utils::{get_account_info_or_default, get_key_or_none};
+use crate::cpi::native;
 use anchor_lang::prelude::*;
-use anchor_lang::{Accounts, Key, ToAccountInfo};
+use anchor_lang::Accounts;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::program::invoke;
 
 #[derive(Accounts)]
 pub struct ValidateReferrer<'info> {
@@ -50,38 +48,18 @@ pub struct ValidateReferrer<'info> {
 }
 
 pub fn validate_referrer<'info>(ctx: CpiContext<'_, '_, '_, 'info, ValidateReferrer<'info>>) -> ProgramResult {
-    let instruction = instruction::validate_referrer(
-        ctx.accounts.payer.key(),
-        ctx.accounts.authority.key(),
-        get_key_or_none(&ctx.accounts.mint),
-        get_key_or_none(&ctx.accounts.referrer_token_account),
-        get_key_or_none(&ctx.accounts.referrer_member),
-        get_key_or_none(&ctx.accounts.referrer_treasury),
-        get_key_or_none(&ctx.accounts.referrer_treasury_for_reward),
-        ctx.accounts.referee_buddy_profile.key(),
-        ctx.accounts.referee_buddy.key(),
-        ctx.accounts.referee_treasury.key(),
-        ctx.accounts.referee_member.key(),
-    );
-
-    let default_account_info = ctx.accounts.buddy_link_program.to_account_info();
-
-    let account_infos = [
-        ctx.accounts.payer.to_account_info(),
-        ctx.accounts.authority.to_account_info(),
-        ctx.accounts.referee_buddy_profile.to_account_info(),
-        ctx.accounts.referee_buddy.to_account_info(),
-        ctx.accounts.referee_treasury.to_account_info(),
-        ctx.accounts.referee_member.to_account_info(),
-        get_account_info_or_default(&ctx.accounts.referrer_member, &default_account_info),
-        get_account_info_or_default(&ctx.accounts.referrer_treasury, &default_account_info),
-        get_account_info_or_default(
-            &ctx.accounts.referrer_treasury_for_reward,
-            &default_account_info,
-        ),
-        get_account_info_or_default(&ctx.accounts.mint, &default_account_info),
-        get_account_info_or_default(&ctx.accounts.referrer_token_account, &default_account_info),
-    ];
-
-    invoke(&instruction, &account_infos)
+    native::validate_referrer(
+        &ctx.accounts.buddy_link_program,
+        &ctx.accounts.payer,
+        &ctx.accounts.authority,
+        ctx.accounts.mint.as_ref(),
+        ctx.accounts.referrer_token_account.as_ref(),
+        ctx.accounts.referrer_member.as_ref(),
+        ctx.accounts.referrer_treasury.as_ref(),
+        ctx.accounts.referrer_treasury_for_reward.as_ref(),
+        &ctx.accounts.referee_buddy_profile,
+        &ctx.accounts.referee_buddy,
+        &ctx.accounts.referee_treasury,
+        &ctx.accounts.referee_member,
+    )
 }
@@ -0,0 +1,88 @@
+use crate::cpi::native;
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use solana_program::entrypoint::ProgramResult;
+
+#[derive(Accounts)]
+pub struct WrapSol<'info> {
+    /// CHECK: System Program
+    #[account(executable, address = solana_program::system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+    /// CHECK: Token program of the wSOL account
+    #[account(executable, constraint = token_program.key() == Token::id() || token_program.key() == Token2022::id())]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: Account sending the lamports (signer or system owned PDA).
+    #[account(mut)]
+    pub funder: AccountInfo<'info>,
+    /// CHECK: wSOL token account receiving them.
+    #[account(mut)]
+    pub wsol_account: AccountInfo<'info>,
+}
+
+/// Wraps `lamports` of the funder into the wSOL account, before a BuddyLink transfer in the native mint.
+pub fn wrap_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WrapSol<'info>>,
+    lamports: u64,
+    funder_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::wrap_sol(
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.funder,
+        &ctx.accounts.wsol_account,
+        lamports,
+        funder_signer_seeds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct SyncNative<'info> {
+    /// CHECK: Token program of the wSOL account
+    #[account(executable, constraint = token_program.key() == Token::id() || token_program.key() == Token2022::id())]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: wSOL token account.
+    #[account(mut)]
+    pub wsol_account: AccountInfo<'info>,
+}
+
+/// Syncs the token amount of a wSOL account after lamports were moved to it directly
+/// (from an account owned by the program, which can't use the system transfer of [`wrap_sol`]).
+pub fn sync_native<'info>(ctx: CpiContext<'_, '_, '_, 'info, SyncNative<'info>>) -> ProgramResult {
+    native::sync_native(&ctx.accounts.token_program, &ctx.accounts.wsol_account)
+}
+
+#[derive(Accounts)]
+pub struct UnwrapSol<'info> {
+    /// CHECK: Token program of the wSOL account
+    #[account(executable, constraint = token_program.key() == Token::id() || token_program.key() == Token2022::id())]
+    pub token_program: AccountInfo<'info>,
+
+    /// CHECK: wSOL token account, closed.
+    #[account(mut)]
+    pub wsol_account: AccountInfo<'info>,
+    /// CHECK: Account receiving its lamports as SOL.
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+    /// CHECK: Owner of the wSOL account (signer or PDA).
+    #[account()]
+    pub owner: AccountInfo<'info>,
+}
+
+/// Closes the wSOL account to the destination as SOL, after a BuddyLink transfer in the native mint.
+/// A wSOL account owned by a PDA is closed with its `owner_signer_seeds`.
+pub fn unwrap_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, UnwrapSol<'info>>,
+    owner_signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    native::unwrap_sol(
+        &ctx.accounts.token_program,
+        &ctx.accounts.wsol_account,
+        &ctx.accounts.destination,
+        &ctx.accounts.owner,
+        owner_signer_seeds,
+    )
+}
@@ -0,0 +1,185 @@
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+///# BuddyLink Error
+///
+/// Custom errors of the BuddyLink program, returned as `ProgramError::Custom(code)`.
+/// Codes follow the Anchor convention and start at 6000.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum BuddyLinkError {
+    InvalidMemberForReferrer = 6000,
+    CantCreateMemberWithReferrer,
+    CantReferYourself,
+    InvalidMetadataProvided,
+    InvalidCollectionProvided,
+    MissingGlobalReferrerAccount,
+    InvalidPaidBuddyName,
+    NFTMetadataNotBelongMint,
+    InvalidPaidBuddyToBurn,
+    InvalidClaimAmount,
+    InvalidReferrerTreasury,
+    InvalidNumberOfSharesSpecified,
+    DuplicatedOwner,
+    OwnersMismatched,
+    BuddyIsNotFrozen,
+    BuddyIsFrozen,
+    BuddyIsNotPartOfTheTreasuryOwners,
+    InvalidReferrerProvidedForBuddy,
+    TreasuryNotOwnedByBuddy,
+    InvalidProvidedName,
+    InvalidTokenAmount,
+    NFTTokenNotBelongMint,
+    InvalidTokenSpecified,
+    InvalidTokenAccountOwner,
+    InvalidBuddyLinkUSDCAccount,
+    MissingTokenAccountForMint,
+    InvalidMint,
+    WalletUniquenessEnforced,
+    InvalidCharacterInName,
+    InvalidAmbassadorProvided,
+    OrganizationHasNoAmbassador,
+    InvalidBPSProvided,
+    NameReservedByPaidBuddy,
+    InvalidBuddyType,
+    InvalidMasterOrganizationSolReceiverPubkey,
+    InvalidMasterOrganizationPubkey,
+    InvalidBump,
+    InvalidUserToClaim,
+    InvalidAuthorityProvided,
+    NotSuperAdmin,
+}
+
+impl BuddyLinkError {
+    const ALL: [BuddyLinkError; 40] = [
+        BuddyLinkError::InvalidMemberForReferrer,
+        BuddyLinkError::CantCreateMemberWithReferrer,
+        BuddyLinkError::CantReferYourself,
+        BuddyLinkError::InvalidMetadataProvided,
+        BuddyLinkError::InvalidCollectionProvided,
+        BuddyLinkError::MissingGlobalReferrerAccount,
+        BuddyLinkError::InvalidPaidBuddyName,
+        BuddyLinkError::NFTMetadataNotBelongMint,
+        BuddyLinkError::InvalidPaidBuddyToBurn,
+        BuddyLinkError::InvalidClaimAmount,
+        BuddyLinkError::InvalidReferrerTreasury,
+        BuddyLinkError::InvalidNumberOfSharesSpecified,
+        BuddyLinkError::DuplicatedOwner,
+        BuddyLinkError::OwnersMismatched,
+        BuddyLinkError::BuddyIsNotFrozen,
+        BuddyLinkError::BuddyIsFrozen,
+        BuddyLinkError::BuddyIsNotPartOfTheTreasuryOwners,
+        BuddyLinkError::InvalidReferrerProvidedForBuddy,
+        BuddyLinkError::TreasuryNotOwnedByBuddy,
+        BuddyLinkError::InvalidProvidedName,
+        BuddyLinkError::InvalidTokenAmount,
+        BuddyLinkError::NFTTokenNotBelongMint,
+        BuddyLinkError::InvalidTokenSpecified,
+        BuddyLinkError::InvalidTokenAccountOwner,
+        BuddyLinkError::InvalidBuddyLinkUSDCAccount,
+        BuddyLinkError::MissingTokenAccountForMint,
+        BuddyLinkError::InvalidMint,
+        BuddyLinkError::WalletUniquenessEnforced,
+        BuddyLinkError::InvalidCharacterInName,
+        BuddyLinkError::InvalidAmbassadorProvided,
+        BuddyLinkError::OrganizationHasNoAmbassador,
+        BuddyLinkError::InvalidBPSProvided,
+        BuddyLinkError::NameReservedByPaidBuddy,
+        BuddyLinkError::InvalidBuddyType,
+        BuddyLinkError::InvalidMasterOrganizationSolReceiverPubkey,
+        BuddyLinkError::InvalidMasterOrganizationPubkey,
+        BuddyLinkError::InvalidBump,
+        BuddyLinkError::InvalidUserToClaim,
+        BuddyLinkError::InvalidAuthorityProvided,
+        BuddyLinkError::NotSuperAdmin,
+    ];
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Error of a custom error code, None if the code isn't a BuddyLink error.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL
+            .get(code.checked_sub(BuddyLinkError::InvalidMemberForReferrer.code())? as usize)
+            .copied()
+    }
+
+    /// Message logged by the program.
+    pub fn message(self) -> &'static str {
+        match self {
+            BuddyLinkError::InvalidMemberForReferrer => {
+                "Invalid member account provided for referrer"
+            }
+            BuddyLinkError::CantCreateMemberWithReferrer => {
+                "Can't create a member with a referrer if you don't own the buddy"
+            }
+            BuddyLinkError::CantReferYourself => "User cannot refer himself",
+            BuddyLinkError::InvalidMetadataProvided => "Invalid Metadata Provided",
+            BuddyLinkError::InvalidCollectionProvided => "Invalid Collection Provided",
+            BuddyLinkError::MissingGlobalReferrerAccount => "Missing global referrer account",
+            BuddyLinkError::InvalidPaidBuddyName => "Invalid paid buddy name provided for metadata",
+            BuddyLinkError::NFTMetadataNotBelongMint => "Invalid mint provided for metadata",
+            BuddyLinkError::InvalidPaidBuddyToBurn => "Invalid paid buddy provided for metadata",
+            BuddyLinkError::InvalidClaimAmount => "Invalid amount to claim",
+            BuddyLinkError::InvalidReferrerTreasury => "Invalid referrer treasury provided",
+            BuddyLinkError::InvalidNumberOfSharesSpecified => "Invalid number of shares specified",
+            BuddyLinkError::DuplicatedOwner => "Duplicated owners",
+            BuddyLinkError::OwnersMismatched => "Owner's don't match",
+            BuddyLinkError::BuddyIsNotFrozen => "Buddy is not frozen",
+            BuddyLinkError::BuddyIsFrozen => "Buddy is frozen",
+            BuddyLinkError::BuddyIsNotPartOfTheTreasuryOwners => {
+                "Provided buddy not part of treasury owners"
+            }
+            BuddyLinkError::InvalidReferrerProvidedForBuddy => {
+                "Invalid referral provided for buddy"
+            }
+            BuddyLinkError::TreasuryNotOwnedByBuddy => "Treasury not owned by buddy",
+            BuddyLinkError::InvalidProvidedName => "Invalid name for burning NFT",
+            BuddyLinkError::InvalidTokenAmount => "Invalid token amount for burn",
+            BuddyLinkError::NFTTokenNotBelongMint => "NFT Token does not belong to NFT mint",
+            BuddyLinkError::InvalidTokenSpecified => "You need to specific SOL or SPL token",
+            BuddyLinkError::InvalidTokenAccountOwner => "Invalid token account owner",
+            BuddyLinkError::InvalidBuddyLinkUSDCAccount => "Invalid USDC account for BuddyLink",
+            BuddyLinkError::MissingTokenAccountForMint => "Missing token account for mint",
+            BuddyLinkError::InvalidMint => "Invalid mint provided",
+            BuddyLinkError::WalletUniquenessEnforced => {
+                "Wallet uniqueness enforced for this organization"
+            }
+            BuddyLinkError::InvalidCharacterInName => {
+                "Only alphanumeric character are supported for the name (a-z and 0-9)"
+            }
+            BuddyLinkError::InvalidAmbassadorProvided => {
+                "Invalid ambassador provided for organization"
+            }
+            BuddyLinkError::OrganizationHasNoAmbassador => "Organization has no ambassador",
+            BuddyLinkError::InvalidBPSProvided => "Invalid BPS provided",
+            BuddyLinkError::NameReservedByPaidBuddy => "Paid buddy with this name exists",
+            BuddyLinkError::InvalidBuddyType => "Invalid buddy type",
+            BuddyLinkError::InvalidMasterOrganizationSolReceiverPubkey => {
+                "Invalid pub key for master organization receiver account"
+            }
+            BuddyLinkError::InvalidMasterOrganizationPubkey => {
+                "Invalid pub key for master organization"
+            }
+            BuddyLinkError::InvalidBump => "Invalid bump",
+            BuddyLinkError::InvalidUserToClaim => "Invalid user to claim",
+            BuddyLinkError::InvalidAuthorityProvided => "Invalid authority provided",
+            BuddyLinkError::NotSuperAdmin => "Only super admin can do this action",
+        }
+    }
+}
+
+impl fmt::Display for BuddyLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({}): {}", self, self.code(), self.message())
+    }
+}
+
+impl std::error::Error for BuddyLinkError {}
+
+impl From<BuddyLinkError> for ProgramError {
+    fn from(error: BuddyLinkError) -> Self {
+        ProgramError::Custom(error.code())
+    }
+}
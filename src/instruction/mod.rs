@@ -1,5 +1,17 @@
+mod multi_tier;
+mod reward_asset;
+mod reward_outcome;
+mod referrer_token_account;
+mod reward_variant;
 mod transfer_reward;
 mod validate_referrer;
+mod wrapped_sol;
 
+pub use multi_tier::*;
+pub use reward_asset::*;
+pub use reward_outcome::*;
+pub use referrer_token_account::*;
+pub use reward_variant::*;
 pub use transfer_reward::*;
 pub use validate_referrer::*;
+pub use wrapped_sol::*;
@@ -0,0 +1,137 @@
+use crate::error::BuddyLinkError;
+use crate::instruction::{
+    transfer_unchecked_local_shared_reward, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs,
+};
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+const MAX_BPS: u32 = 10_000;
+
+/// Account receiving the reward of a tier: token account for SPL, treasury for SOL.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TierRecipient {
+    pub recipient: Pubkey,
+    /// Member of the referrer, paired with the recipient for the on-chain analytics.
+    pub member: Option<Pubkey>,
+}
+
+/// What happens to the tiers of the schedule without a referrer (short referral chain).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnclaimedTiers {
+    /// Split between the paid tiers, proportionally to their share of the schedule.
+    Redistribute,
+    /// Paid to this account, after the tiers.
+    Fallback(TierRecipient),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MultiTierRewardArgs {
+    /// The amount of tokens to be split between the tiers
+    pub total_amount: u64,
+    /// Share of each tier in bps, from the direct referrer up, must add up to 10_000
+    pub schedule: Vec<u16>,
+    pub unclaimed: UnclaimedTiers,
+}
+
+/// Shares in bps of the paid tiers, followed by the share of the fallback account if some tiers are unclaimed.
+///
+/// `claimed` tells for each tier, from the direct referrer up, whether it's paid. Missing tiers are unclaimed,
+/// tiers after the schedule are ignored.
+pub fn multi_tier_shares(
+    schedule: &[u16],
+    claimed: &[bool],
+    unclaimed: &UnclaimedTiers,
+) -> Result<Vec<u16>, ProgramError> {
+    if schedule.iter().map(|x| u32::from(*x)).sum::<u32>() != MAX_BPS {
+        return Err(BuddyLinkError::InvalidBPSProvided.into());
+    }
+
+    let mut shares: Vec<u16> = schedule
+        .iter()
+        .zip(claimed)
+        .filter(|(_, claimed)| **claimed)
+        .map(|(share, _)| *share)
+        .collect();
+    let claimed_bps: u32 = shares.iter().map(|x| u32::from(*x)).sum();
+    let unclaimed_bps = MAX_BPS - claimed_bps;
+
+    if unclaimed_bps == 0 {
+        return Ok(shares);
+    }
+
+    match unclaimed {
+        UnclaimedTiers::Redistribute => {
+            if claimed_bps == 0 {
+                return Err(BuddyLinkError::InvalidNumberOfSharesSpecified.into());
+            }
+
+            for share in shares.iter_mut() {
+                // Fits in u16, below the share of the claimed bps
+                *share = (u32::from(*share) * MAX_BPS / claimed_bps) as u16;
+            }
+
+            // Rounding leftover to the direct (first paid) referrer
+            let leftover = MAX_BPS - shares.iter().map(|x| u32::from(*x)).sum::<u32>();
+            if let Some(first) = shares.iter_mut().find(|x| **x > 0) {
+                *first += leftover as u16;
+            }
+        }
+        UnclaimedTiers::Fallback(_) => shares.push(unclaimed_bps as u16),
+    }
+
+    Ok(shares)
+}
+
+///# Transfer Multi Tier Reward (SPL & SOL)
+///
+/// Splits a reward between the referrers of a referee following a tier schedule (50/30/20% for the first three
+/// levels for example), in a single [`transfer_unchecked_local_shared_reward`].
+///
+/// 1. `[writable, signer]` Authority of the account sending the funds.
+/// 2. `[]` Asset: SOL (sent from the authority) or SPL (mint, token program and token account sending the funds).
+/// 3. Recipient of each tier from the direct referrer up, None if the tier can't be paid (handled as unclaimed).
+/// 4. Transfer arguments
+///
+/// Members are included if all the paid recipients have one.
+pub fn transfer_multi_tier_reward(
+    authority: Pubkey,
+    asset: &SharedRewardAsset,
+    referrers: &[Option<TierRecipient>],
+    transfer_args: &MultiTierRewardArgs,
+) -> Result<Instruction, ProgramError> {
+    let claimed: Vec<bool> = referrers.iter().map(Option::is_some).collect();
+    let shares_in_bps =
+        multi_tier_shares(&transfer_args.schedule, &claimed, &transfer_args.unclaimed)?;
+
+    let mut recipients: Vec<TierRecipient> = referrers
+        .iter()
+        .take(transfer_args.schedule.len())
+        .flatten()
+        .copied()
+        .collect();
+    if let UnclaimedTiers::Fallback(fallback) = transfer_args.unclaimed {
+        if shares_in_bps.len() > recipients.len() {
+            recipients.push(fallback);
+        }
+    }
+
+    let members_included = recipients.iter().all(|x| x.member.is_some());
+    let remaining_accounts: Vec<Pubkey> = recipients
+        .iter()
+        .flat_map(|x| [Some(x.recipient), x.member.filter(|_| members_included)])
+        .flatten()
+        .collect();
+
+    Ok(transfer_unchecked_local_shared_reward(
+        authority,
+        asset,
+        &remaining_accounts,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount: transfer_args.total_amount,
+            shares_in_bps,
+            members_included,
+        },
+    ))
+}
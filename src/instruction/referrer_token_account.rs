@@ -0,0 +1,83 @@
+use crate::constants::{
+    BL_PROGRAM_ID, TRANSFER_REWARD_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SPL_DISCRIMINATOR,
+};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+/// Token account receiving the reward of a referrer, owned by its treasury for reward.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReferrerTokenAccount {
+    pub token_account: Pubkey,
+    /// BuddyLink treasury of the referrer linked to the mint (`referrer_treasury_for_reward`).
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl ReferrerTokenAccount {
+    /// Whether the token account is the associated token account of the treasury, the only one that can be created
+    /// by [`create_referrer_token_account`].
+    pub fn is_associated(&self) -> bool {
+        self.token_account
+            == get_associated_token_address_with_program_id(
+                &self.treasury,
+                &self.mint,
+                &self.token_program,
+            )
+    }
+}
+
+///# Referrer Token Accounts
+///
+/// Token accounts of the referrer and of the global referrer receiving the reward of a BuddyLink SPL transfer
+/// instruction (secure local, checked global and checked global only), empty for any other instruction.
+/// The transfer fails if one of them doesn't exist yet.
+pub fn referrer_token_accounts(instruction: &Instruction) -> Vec<ReferrerTokenAccount> {
+    if instruction.program_id != BL_PROGRAM_ID || instruction.data.len() < 8 {
+        return Vec::new();
+    }
+
+    // Indexes of the token account, treasury for reward, mint and token program
+    let indexes: &[[usize; 4]] = match instruction.data[..8].try_into().unwrap() {
+        TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR => &[[11, 6, 1, 2]],
+        TRANSFER_REWARD_SPL_DISCRIMINATOR => &[[2, 1, 7, 8], [10, 5, 7, 8]],
+        TRANSFER_REWARD_GLOBAL_DISCRIMINATOR => &[[8, 2, 6, 7]],
+        _ => &[],
+    };
+
+    indexes
+        .iter()
+        .filter_map(|indexes| {
+            let [token_account, treasury, mint, token_program] =
+                indexes.map(|index| instruction.accounts.get(index).map(|x| x.pubkey));
+
+            Some(ReferrerTokenAccount {
+                token_account: token_account?,
+                treasury: treasury?,
+                mint: mint?,
+                token_program: token_program?,
+            })
+        })
+        // Optional accounts and SOL rewards use the program id as placeholder
+        .filter(|x| x.token_account != BL_PROGRAM_ID && x.mint != BL_PROGRAM_ID)
+        .collect()
+}
+
+///# Create Referrer Token Account
+///
+/// Creates the associated token account of the treasury for the mint if it doesn't exist (idempotent),
+/// the rent is paid by `rent_payer`.
+pub fn create_referrer_token_account(
+    rent_payer: Pubkey,
+    token_account: &ReferrerTokenAccount,
+) -> Instruction {
+    create_associated_token_account_idempotent(
+        &rent_payer,
+        &token_account.treasury,
+        &token_account.mint,
+        &token_account.token_program,
+    )
+}
@@ -0,0 +1,84 @@
+use solana_program::pubkey::Pubkey;
+
+/// Asset of a reward sent to a single referrer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RewardAsset {
+    /// SOL, sent from the authority to the referrer treasury.
+    Sol,
+    /// SPL token, sent from a token account of the authority to the token account of the referrer.
+    Spl {
+        mint: Pubkey,
+        token_program: Pubkey,
+        /// Token account sending the funds.
+        from: Pubkey,
+        /// Token account receiving the funds (buddy link owned).
+        to: Pubkey,
+    },
+}
+
+/// Asset of a reward shared between multiple referrers, the receiving accounts are passed in the remaining accounts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SharedRewardAsset {
+    /// SOL, sent from the authority to the referrer treasuries.
+    Sol,
+    /// SPL token, sent from a token account of the authority to the token accounts of the referrers.
+    Spl {
+        mint: Pubkey,
+        token_program: Pubkey,
+        /// Token account sending the funds.
+        from: Pubkey,
+    },
+}
+
+pub(crate) struct RewardAssetKeys {
+    pub system_program: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+    pub token_program: Option<Pubkey>,
+    pub from: Option<Pubkey>,
+    pub to: Option<Pubkey>,
+}
+
+impl RewardAsset {
+    pub(crate) fn keys(&self) -> RewardAssetKeys {
+        match *self {
+            RewardAsset::Sol => RewardAssetKeys {
+                system_program: Some(solana_program::system_program::ID),
+                mint: None,
+                token_program: None,
+                from: None,
+                to: None,
+            },
+            RewardAsset::Spl {
+                mint,
+                token_program,
+                from,
+                to,
+            } => RewardAssetKeys {
+                system_program: None,
+                mint: Some(mint),
+                token_program: Some(token_program),
+                from: Some(from),
+                to: Some(to),
+            },
+        }
+    }
+}
+
+impl SharedRewardAsset {
+    pub(crate) fn keys(&self) -> RewardAssetKeys {
+        match *self {
+            SharedRewardAsset::Sol => RewardAsset::Sol.keys(),
+            SharedRewardAsset::Spl {
+                mint,
+                token_program,
+                from,
+            } => RewardAssetKeys {
+                system_program: None,
+                mint: Some(mint),
+                token_program: Some(token_program),
+                from: Some(from),
+                to: None,
+            },
+        }
+    }
+}
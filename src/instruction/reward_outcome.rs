@@ -0,0 +1,114 @@
+use crate::error::BuddyLinkError;
+use crate::instruction::RewardVariant;
+use crate::state::{Buddy, Member};
+use solana_program::program_error::ProgramError;
+use std::ops::Deref;
+
+const MAX_BPS: u64 = 10_000;
+
+/// Share in bps of the global referrer in a checked global reward.
+pub const GLOBAL_REFERRER_SHARE_BPS: u16 = 1_000;
+
+/// Share in bps of the global referrer in a checked global reward, when the referee member was referred.
+pub const REFERRED_GLOBAL_REFERRER_SHARE_BPS: u16 = 250;
+
+/// Amounts received by the referrers of a reward, as transferred by the program.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RewardOutcome {
+    /// Referrer of the referee within the organization.
+    pub local_referrer: u64,
+    /// Referrer of the referee buddy.
+    pub global_referrer: u64,
+}
+
+impl RewardOutcome {
+    /// Amount sent by the authority.
+    pub fn total(&self) -> u64 {
+        self.local_referrer + self.global_referrer
+    }
+}
+
+/// Share in bps of the global referrer in a checked global reward paid for this referee member.
+pub fn global_referrer_share_bps<D: Deref<Target = [u8]>>(
+    referee_member: &Member<D>,
+) -> Result<u16, ProgramError> {
+    Ok(if referee_member.is_referred()? {
+        REFERRED_GLOBAL_REFERRER_SHARE_BPS
+    } else {
+        GLOBAL_REFERRER_SHARE_BPS
+    })
+}
+
+/// Amount of a share in bps, rounded down like the program does.
+pub fn bps_amount(amount: u64, share_in_bps: u16) -> Result<u64, ProgramError> {
+    amount
+        .checked_mul(u64::from(share_in_bps))
+        .map(|x| x / MAX_BPS)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+///# Reward Outcome
+///
+/// Amounts received by the referrers of a reward of `amount` paid with `variant`, exactly as the program
+/// transfers them (no RPC needed, on-chain or off-chain).
+///
+/// The split doesn't depend on the organization, the transfer instructions don't read it:
+/// - [`RewardVariant::CheckedGlobal`] pays the global referrer its share (see [`global_referrer_share_bps`],
+///   rounded down) when the referee buddy has one, the local referrer gets the rest. `referee_member` is required.
+/// - [`RewardVariant::CheckedGlobalOnly`] pays the full amount to the global referrer.
+/// - [`RewardVariant::SecureLocal`] and [`RewardVariant::UncheckedLocalShared`] (the local referrer as only
+///   recipient) pay the full amount to the local referrer.
+///
+/// Errors with `ArithmeticOverflow` when the program would.
+pub fn reward_outcome<M, B>(
+    variant: RewardVariant,
+    amount: u64,
+    referee_member: Option<&Member<M>>,
+    referee_buddy: Option<&Buddy<B>>,
+) -> Result<RewardOutcome, ProgramError>
+where
+    M: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    match variant {
+        RewardVariant::CheckedGlobal => {
+            let referee_member = referee_member.ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            let global_referrer = match referee_buddy.and_then(|x| x.referrer_treasury()) {
+                Some(_) => bps_amount(amount, global_referrer_share_bps(referee_member)?)?,
+                None => 0,
+            };
+
+            Ok(RewardOutcome {
+                local_referrer: amount - global_referrer,
+                global_referrer,
+            })
+        }
+        RewardVariant::CheckedGlobalOnly => Ok(RewardOutcome {
+            local_referrer: 0,
+            global_referrer: amount,
+        }),
+        RewardVariant::SecureLocal | RewardVariant::UncheckedLocalShared => Ok(RewardOutcome {
+            local_referrer: amount,
+            global_referrer: 0,
+        }),
+    }
+}
+
+/// Amounts received by the recipients of a shared reward, in the order of the shares.
+///
+/// Each share is rounded down, the rest (`total_amount` minus the sum) stays with the authority.
+/// Errors like the program when the shares don't add up to 10_000 bps or the amounts overflow.
+pub fn shared_reward_amounts(
+    total_amount: u64,
+    shares_in_bps: &[u16],
+) -> Result<Vec<u64>, ProgramError> {
+    if shares_in_bps.iter().map(|x| u64::from(*x)).sum::<u64>() != MAX_BPS {
+        return Err(BuddyLinkError::InvalidBPSProvided.into());
+    }
+
+    shares_in_bps
+        .iter()
+        .map(|share| bps_amount(total_amount, *share))
+        .collect()
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+/// Transfer instruction paying a single referral reward.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RewardVariant {
+    /// [`transfer_unchecked_local_shared_reward`](crate::instruction::transfer_unchecked_local_shared_reward)
+    /// with the organization referrer as the only recipient.
+    UncheckedLocalShared,
+    /// [`transfer_secure_local_reward`](crate::instruction::transfer_secure_local_reward)
+    SecureLocal,
+    /// [`transfer_checked_global_reward`](crate::instruction::transfer_checked_global_reward)
+    CheckedGlobal,
+    /// [`transfer_checked_global_only_reward`](crate::instruction::transfer_checked_global_only_reward)
+    CheckedGlobalOnly,
+}
+
+/// Referrer of the referee within the organization paying the reward.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LocalReferrer {
+    /// The reward isn't paid by an organization.
+    NoOrganization,
+    /// The referee isn't a member of the organization.
+    NotAMember,
+    /// The referee member wasn't referred.
+    NotReferred,
+    /// The referrer has no treasury (or token account) for the reward mint.
+    CantReceive,
+    /// The referrer can receive the reward.
+    Resolved,
+}
+
+/// What is known about the referee when choosing the [`RewardVariant`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RewardRoute {
+    pub local_referrer: LocalReferrer,
+    /// The referee buddy has a global referrer able to receive the reward.
+    pub global_referrer: bool,
+    /// The authority sending the funds owns the referee buddy (the referee pays).
+    pub referee_signed: bool,
+    /// The reward is paid in SOL.
+    pub sol: bool,
+}
+
+/// Why a [`RewardVariant`] was chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RewardVariantReason {
+    /// The referee signs, its buddy, treasury and member are checked along with the organization referrer.
+    RefereeSigned,
+    /// The organization referrer is paid with checks, the global referrer is included if there is one.
+    OrganizationReferrer { global_referrer: bool },
+    /// No checked variant transfers SOL to an organization referrer.
+    SolOrganizationReferrer,
+    /// The organization referrer can't be paid, the global referrer is.
+    GlobalReferrer(LocalReferrer),
+}
+
+impl fmt::Display for RewardVariantReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewardVariantReason::RefereeSigned => {
+                write!(f, "the referee signs, the referral is fully checked")
+            }
+            RewardVariantReason::OrganizationReferrer { global_referrer } => {
+                write!(f, "the referee was referred in the organization")?;
+                if *global_referrer {
+                    write!(f, ", the global referrer is included")?;
+                }
+                Ok(())
+            }
+            RewardVariantReason::SolOrganizationReferrer => write!(
+                f,
+                "the referee was referred in the organization and SOL can only be sent unchecked"
+            ),
+            RewardVariantReason::GlobalReferrer(local_referrer) => {
+                let local_referrer = match local_referrer {
+                    LocalReferrer::NoOrganization => "no organization is paying",
+                    LocalReferrer::NotAMember => "the referee isn't a member of the organization",
+                    LocalReferrer::NotReferred => "the referee wasn't referred in the organization",
+                    LocalReferrer::CantReceive => {
+                        "the organization referrer can't receive the mint"
+                    }
+                    LocalReferrer::Resolved => "the organization referrer isn't used",
+                };
+                write!(f, "{}, the global referrer is paid", local_referrer)
+            }
+        }
+    }
+}
+
+/// Chooses the transfer variant paying the referrer of a referee, None if no referrer can receive the reward.
+///
+/// The organization referrer comes first: with the secure variant when the referee signs,
+/// with the checked global variant otherwise (SPL only, SOL goes through the unchecked shared variant).
+/// The global referrer is paid when there is no organization referrer.
+pub fn choose_reward_variant(route: &RewardRoute) -> Option<(RewardVariant, RewardVariantReason)> {
+    if route.local_referrer == LocalReferrer::Resolved {
+        return Some(if route.sol {
+            (
+                RewardVariant::UncheckedLocalShared,
+                RewardVariantReason::SolOrganizationReferrer,
+            )
+        } else if route.referee_signed {
+            (
+                RewardVariant::SecureLocal,
+                RewardVariantReason::RefereeSigned,
+            )
+        } else {
+            (
+                RewardVariant::CheckedGlobal,
+                RewardVariantReason::OrganizationReferrer {
+                    global_referrer: route.global_referrer,
+                },
+            )
+        });
+    }
+
+    if route.global_referrer {
+        return Some((
+            RewardVariant::CheckedGlobalOnly,
+            RewardVariantReason::GlobalReferrer(route.local_referrer),
+        ));
+    }
+
+    None
+}
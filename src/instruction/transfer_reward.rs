@@ -1,8 +1,15 @@
-use crate::constants::BL_PROGRAM_ID;
+use crate::constants::{
+    BL_PROGRAM_ID, TRANSFER_REWARD_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SPL_DISCRIMINATOR,
+};
+use crate::instruction::{RewardAsset, SharedRewardAsset};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
-use crate::utils::{get_account_meta_or_read_default, get_instruction_name_data};
+use crate::utils::{
+    get_account_meta_or_read_default, get_general_transfer_reward_data,
+    get_transfer_unchecked_local_shared_reward_data,
+};
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
@@ -31,42 +38,49 @@ pub struct TransferUncheckedLocalSharedRewardArgs {
 /// LOCAL: does not use the global referral system of Buddylink
 ///
 /// 1. `[writable, signer]` Authority of the account sending the funds.
-/// 2. `[optional]` Only used if sending SOL, None if sending SPL.
-/// 3. `[optional]` Mint, None if sending SOL.
-/// 4. `[optional]` Token program, None if sending SOL.
-/// 5. `[writable, optional]` None if sending SOL (will send from authority), else is Token Account
-/// 7. `[writable]` Combination of referrer treasury / token account AND referrer member (if you want analytics on-chain).
-/// 8. Transfer arguments
+/// 2. `[]` Asset: SOL (sent from the authority) or SPL (mint, token program and token account sending the funds).
+/// 3. `[writable]` Combination of referrer treasury / token account AND referrer member (if you want analytics on-chain).
+/// 4. Transfer arguments
 pub fn transfer_unchecked_local_shared_reward(
     authority: Pubkey,
-    system_program: Option<Pubkey>,
-    mint: Option<Pubkey>,
-    token_program: Option<Pubkey>,
-    from_account: Option<Pubkey>,
+    asset: &SharedRewardAsset,
     remaining_accounts: &[Pubkey],
     transfer_args: &TransferUncheckedLocalSharedRewardArgs,
 ) -> Instruction {
-    let mut instruction_data = get_instruction_name_data("transfer_reward_unchecked_multiple");
-    instruction_data.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    shared_reward_instruction(
+        authority,
+        asset,
+        remaining_accounts.iter().copied(),
+        transfer_args,
+    )
+}
+
+/// Builds [`transfer_unchecked_local_shared_reward`] from any source of recipient keys,
+/// so the native wrappers can read them straight from their account infos.
+pub(crate) fn shared_reward_instruction(
+    authority: Pubkey,
+    asset: &SharedRewardAsset,
+    remaining_accounts: impl ExactSizeIterator<Item = Pubkey>,
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+) -> Instruction {
+    let instruction_data = get_transfer_unchecked_local_shared_reward_data(transfer_args);
+    let asset = asset.keys();
+
+    let mut accounts = Vec::with_capacity(5 + remaining_accounts.len());
 
-    let mut accounts = vec![
+    accounts.extend_from_slice(&[
         AccountMeta::new(authority, true),
-        AccountMeta::new_readonly(system_program.unwrap_or(BL_PROGRAM_ID), false),
-        AccountMeta::new_readonly(mint.unwrap_or(BL_PROGRAM_ID), false),
-        AccountMeta::new_readonly(token_program.unwrap_or(BL_PROGRAM_ID), false),
-        get_account_meta_or_read_default(&from_account),
-    ];
+        AccountMeta::new_readonly(asset.system_program.unwrap_or(BL_PROGRAM_ID), false),
+        AccountMeta::new_readonly(asset.mint.unwrap_or(BL_PROGRAM_ID), false),
+        AccountMeta::new_readonly(asset.token_program.unwrap_or(BL_PROGRAM_ID), false),
+        get_account_meta_or_read_default(&asset.from),
+    ]);
 
-    accounts.extend_from_slice(
-        &remaining_accounts
-            .iter()
-            .map(|account| AccountMeta {
-                pubkey: *account,
-                is_signer: false,
-                is_writable: true,
-            })
-            .collect::<Vec<AccountMeta>>(),
-    );
+    accounts.extend(remaining_accounts.map(|pubkey| AccountMeta {
+        pubkey,
+        is_signer: false,
+        is_writable: true,
+    }));
 
     Instruction {
         program_id: BL_PROGRAM_ID,
@@ -111,8 +125,8 @@ pub fn transfer_secure_local_reward(
     referee_member: Pubkey,
     transfer_args: &GeneralTransferRewardArgs,
 ) -> Instruction {
-    let mut instruction_data = get_instruction_name_data("transfer_reward_secure_no_global");
-    instruction_data.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    let instruction_data =
+        get_general_transfer_reward_data(&TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR, transfer_args);
 
     Instruction {
         program_id: BL_PROGRAM_ID,
@@ -130,7 +144,7 @@ pub fn transfer_secure_local_reward(
             AccountMeta::new(referee_member, false),
             AccountMeta::new(referrer_token_account, false),
         ],
-        data: instruction_data,
+        data: instruction_data.to_vec(),
     }
 }
 
@@ -168,8 +182,8 @@ pub fn transfer_checked_global_reward(
     buddy_global_referrer_token_account: Option<Pubkey>,
     transfer_args: &GeneralTransferRewardArgs,
 ) -> Instruction {
-    let mut instruction_data = get_instruction_name_data("transfer_reward_spl");
-    instruction_data.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    let instruction_data =
+        get_general_transfer_reward_data(&TRANSFER_REWARD_SPL_DISCRIMINATOR, transfer_args);
 
     Instruction {
         program_id: BL_PROGRAM_ID,
@@ -186,7 +200,7 @@ pub fn transfer_checked_global_reward(
             AccountMeta::new(from_token_account, false),
             AccountMeta::new(referrer_token_account, false),
         ],
-        data: instruction_data,
+        data: instruction_data.to_vec(),
     }
 }
 
@@ -199,32 +213,24 @@ pub fn transfer_checked_global_reward(
 /// GLOBAL ONLY: using only the global referral system of buddylink
 ///
 /// 1. `[writable, signer]` Authority of the account sending the funds.
-/// 2. `[optional]` Only used if sending SOL, None if sending SPL.
-/// 3. `[optional]` Mint, None if sending SOL.
-/// 4. `[optional]` Token program, None if sending SOL.
-/// 5. `[writable, optional]` None if sending SOL (will send from authority), else is Token Account
-/// 6. `[writable, optional]` None if receiving SOL (will send to treasury), else is Token Account
-/// 7. `[writable]` Global referrer treasury (treasury of the global referrer of current referee).
-/// 8. `[writable]` Global referrer treasury for reward (treasury of the global referrer of current referee that is linked to the current mint, could be same as #10).
-/// 9. `[writable]` Buddy Link Profile of the referee.
-/// 10. `[writable]` Buddy Link Paid buddy of the referee (could be the same as #9).
-/// 11. Transfer arguments
-#[allow(clippy::too_many_arguments)]
+/// 2. `[]` Asset: SOL (sent from the authority to the treasury) or SPL (mint, token program, token account sending and token account receiving the funds).
+/// 3. `[writable]` Global referrer treasury (treasury of the global referrer of current referee).
+/// 4. `[writable]` Global referrer treasury for reward (treasury of the global referrer of current referee that is linked to the current mint, could be same as #3).
+/// 5. `[writable]` Buddy Link Profile of the referee.
+/// 6. `[writable]` Buddy Link Paid buddy of the referee (could be the same as #5).
+/// 7. Transfer arguments
 pub fn transfer_checked_global_only_reward(
     authority: Pubkey,
-    system_program: Option<Pubkey>,
-    mint: Option<Pubkey>,
-    token_program: Option<Pubkey>,
-    from_token_account: Option<Pubkey>,
-    referrer_token_account: Option<Pubkey>,
+    asset: &RewardAsset,
     buddy_global_referrer_treasury: Pubkey,
     buddy_global_referrer_treasury_for_reward: Pubkey,
     referee_buddy_profile: Pubkey,
     referee_buddy: Pubkey,
     transfer_args: &GeneralTransferRewardArgs,
 ) -> Instruction {
-    let mut instruction_data = get_instruction_name_data("transfer_reward_global");
-    instruction_data.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    let instruction_data =
+        get_general_transfer_reward_data(&TRANSFER_REWARD_GLOBAL_DISCRIMINATOR, transfer_args);
+    let asset = asset.keys();
 
     Instruction {
         program_id: BL_PROGRAM_ID,
@@ -234,12 +240,12 @@ pub fn transfer_checked_global_only_reward(
             AccountMeta::new(buddy_global_referrer_treasury_for_reward, false),
             AccountMeta::new_readonly(referee_buddy_profile, false),
             AccountMeta::new_readonly(referee_buddy, false),
-            AccountMeta::new_readonly(system_program.unwrap_or(BL_PROGRAM_ID), false),
-            AccountMeta::new_readonly(mint.unwrap_or(BL_PROGRAM_ID), false),
-            AccountMeta::new_readonly(token_program.unwrap_or(BL_PROGRAM_ID), false),
-            get_account_meta_or_read_default(&referrer_token_account),
-            get_account_meta_or_read_default(&from_token_account),
+            AccountMeta::new_readonly(asset.system_program.unwrap_or(BL_PROGRAM_ID), false),
+            AccountMeta::new_readonly(asset.mint.unwrap_or(BL_PROGRAM_ID), false),
+            AccountMeta::new_readonly(asset.token_program.unwrap_or(BL_PROGRAM_ID), false),
+            get_account_meta_or_read_default(&asset.to),
+            get_account_meta_or_read_default(&asset.from),
         ],
-        data: instruction_data,
+        data: instruction_data.to_vec(),
     }
 }
@@ -1,7 +1,6 @@
-use crate::constants::BL_PROGRAM_ID;
+use crate::constants::{BL_PROGRAM_ID, VALIDATE_REFERRER_DISCRIMINATOR};
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
-use crate::utils::get_instruction_name_data;
 
 ///# Validate Referrer
 ///
@@ -32,7 +31,7 @@ pub fn validate_referrer(
     referee_treasury: Pubkey,
     referee_member: Pubkey,
 ) -> Instruction {
-    let instruction_data = get_instruction_name_data("validate_referrer");
+    let instruction_data = VALIDATE_REFERRER_DISCRIMINATOR.to_vec();
 
     let accounts = vec![
         AccountMeta::new(payer, true),
@@ -0,0 +1,153 @@
+use crate::instruction::{RewardAsset, SharedRewardAsset};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+
+/// Whether the mint is the native mint (wrapped SOL) of the token program or of token-2022.
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// wSOL token account sending a reward in the native mint.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WrappedSolSource {
+    pub token_account: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl RewardAsset {
+    /// Token account sending the reward, if the asset is the native mint.
+    pub fn wrapped_sol_source(&self) -> Option<WrappedSolSource> {
+        match *self {
+            RewardAsset::Spl {
+                mint,
+                token_program,
+                from,
+                ..
+            } if is_native_mint(&mint) => Some(WrappedSolSource {
+                token_account: from,
+                token_program,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SharedRewardAsset {
+    /// Token account sending the reward, if the asset is the native mint.
+    pub fn wrapped_sol_source(&self) -> Option<WrappedSolSource> {
+        match *self {
+            SharedRewardAsset::Spl {
+                mint,
+                token_program,
+                from,
+            } if is_native_mint(&mint) => Some(WrappedSolSource {
+                token_account: from,
+                token_program,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Moves lamports of `funder` to a wSOL token account and syncs its token amount.
+pub fn wrap_sol(
+    funder: Pubkey,
+    source: &WrappedSolSource,
+    lamports: u64,
+) -> Result<[Instruction; 2], ProgramError> {
+    Ok([
+        system_instruction::transfer(&funder, &source.token_account, lamports),
+        spl_token_2022::instruction::sync_native(&source.token_program, &source.token_account)?,
+    ])
+}
+
+/// Closes a wSOL token account, its lamports (wSOL left and rent) going to `destination` as SOL.
+pub fn unwrap_sol(
+    source: &WrappedSolSource,
+    destination: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::close_account(
+        &source.token_program,
+        &source.token_account,
+        &destination,
+        &owner,
+        &[],
+    )
+}
+
+/// What to do around a transfer in the native mint.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct WrappedSolOptions {
+    /// Lamports of the authority wrapped into the source account before the transfer (0 for none).
+    pub wrap_lamports: u64,
+    /// Closes the source account after the transfer, sending what's left to this account as SOL.
+    pub unwrap_to: Option<Pubkey>,
+}
+
+///# With Wrapped SOL
+///
+/// Surrounds the BuddyLink transfer instructions of a reward sent from `source` (see
+/// [`RewardAsset::wrapped_sol_source`]) with the wrapping and unwrapping asked in `options`:
+/// 1. transfer of `wrap_lamports` from the authority to the source account, then SyncNative
+/// 2. the transfer instructions
+/// 3. CloseAccount of the source account to `unwrap_to`
+///
+/// The instructions are returned unchanged when the asset isn't the native mint (`source` is None).
+pub fn with_wrapped_sol(
+    authority: Pubkey,
+    source: Option<WrappedSolSource>,
+    transfer_instructions: Vec<Instruction>,
+    options: &WrappedSolOptions,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let Some(source) = source else {
+        return Ok(transfer_instructions);
+    };
+
+    let mut instructions = Vec::with_capacity(transfer_instructions.len() + 3);
+    if options.wrap_lamports > 0 {
+        instructions.extend(wrap_sol(authority, &source, options.wrap_lamports)?);
+    }
+    instructions.extend(transfer_instructions);
+    if let Some(destination) = options.unwrap_to {
+        instructions.push(unwrap_sol(&source, destination, authority)?);
+    }
+
+    Ok(instructions)
+}
+
+///# Unwrap To SOL
+///
+/// To pay the referrers in SOL from a wSOL account: when the asset is the native mint, returns the instruction
+/// closing the source account to the authority (unwrapping all of it) and [`RewardAsset::Sol`] to build the
+/// transfer with. Other assets are returned as is, without instruction.
+pub fn unwrap_to_sol(
+    asset: &RewardAsset,
+    authority: Pubkey,
+) -> Result<(Option<Instruction>, RewardAsset), ProgramError> {
+    match asset.wrapped_sol_source() {
+        Some(source) => Ok((
+            Some(unwrap_sol(&source, authority, authority)?),
+            RewardAsset::Sol,
+        )),
+        None => Ok((None, *asset)),
+    }
+}
+
+/// [`unwrap_to_sol`] for a shared reward.
+pub fn unwrap_shared_to_sol(
+    asset: &SharedRewardAsset,
+    authority: Pubkey,
+) -> Result<(Option<Instruction>, SharedRewardAsset), ProgramError> {
+    match asset.wrapped_sol_source() {
+        Some(source) => Ok((
+            Some(unwrap_sol(&source, authority, authority)?),
+            SharedRewardAsset::Sol,
+        )),
+        None => Ok((None, *asset)),
+    }
+}
@@ -1,6 +1,11 @@
+#[cfg(feature = "client")]
+pub mod client;
 pub mod constants;
 pub mod cpi;
+pub mod error;
 pub mod instruction;
+pub mod policy;
+pub mod state;
 mod utils;
 
 pub use solana_program;
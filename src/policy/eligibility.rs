@@ -0,0 +1,137 @@
+use crate::state::{Buddy, Member};
+use solana_program::clock::Clock;
+use solana_program::program_error::ProgramError;
+use solana_program::sysvar::Sysvar;
+use std::ops::Deref;
+
+/// Seconds in a day.
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// When the referee joined, the start of the time windows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum JoinedAt {
+    /// Creation of the referee member, when they joined the organization.
+    #[default]
+    Member,
+    /// Creation of the referee buddy, when they joined BuddyLink.
+    Buddy,
+}
+
+/// Period during which the referrals of a referee are paid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EligibilityWindow {
+    /// Seconds after the referee joined (see [`JoinedAt`]).
+    Seconds(i64),
+    /// First transactions of the referee, as counted by the caller (BuddyLink doesn't track them).
+    Transactions(u64),
+}
+
+impl EligibilityWindow {
+    /// First `days` after the referee joined.
+    pub const fn days(days: i64) -> Self {
+        EligibilityWindow::Seconds(days.saturating_mul(SECONDS_PER_DAY))
+    }
+}
+
+/// Whether a referral is paid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Eligibility {
+    Eligible,
+    /// This window is over, the referral is skipped.
+    Expired(EligibilityWindow),
+}
+
+impl Eligibility {
+    pub fn is_eligible(&self) -> bool {
+        *self == Eligibility::Eligible
+    }
+}
+
+/// Referee of a referral, with what the windows are evaluated on.
+pub struct Referee<'a, M, B> {
+    pub member: Option<&'a Member<M>>,
+    pub buddy: Option<&'a Buddy<B>>,
+    /// Transactions of the referee so far, this one included. None if they aren't tracked.
+    pub transactions: Option<u64>,
+}
+
+///# Referral Eligibility
+///
+/// Pays the referrals of a referee only within windows starting when they joined, such as their first 90 days
+/// or their first 10 transactions. A referral is eligible while every window is open, with no window it always is.
+///
+/// Call it before the transfer CPI ([`Self::check_now`] reads the `Clock` sysvar) or off-chain with a given time
+/// ([`Self::check`]), and skip the referral when it has expired.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ReferralEligibility {
+    joined_at: JoinedAt,
+    windows: Vec<EligibilityWindow>,
+}
+
+impl ReferralEligibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts the time windows at the creation of the referee member (default) or buddy.
+    pub fn joined_at(mut self, joined_at: JoinedAt) -> Self {
+        self.joined_at = joined_at;
+        self
+    }
+
+    pub fn window(mut self, window: EligibilityWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    /// Evaluates the windows at the unix timestamp `now`.
+    ///
+    /// Errors with `NotEnoughAccountKeys` if the member or buddy the time windows start from isn't given,
+    /// and `InvalidArgument` if the transactions of the referee aren't given for a window on transactions.
+    pub fn check<M, B>(
+        &self,
+        referee: &Referee<M, B>,
+        now: i64,
+    ) -> Result<Eligibility, ProgramError>
+    where
+        M: Deref<Target = [u8]>,
+        B: Deref<Target = [u8]>,
+    {
+        for window in &self.windows {
+            let open = match *window {
+                EligibilityWindow::Seconds(seconds) => {
+                    let joined_at = match self.joined_at {
+                        JoinedAt::Member => referee
+                            .member
+                            .ok_or(ProgramError::NotEnoughAccountKeys)?
+                            .created_at()?,
+                        JoinedAt::Buddy => referee
+                            .buddy
+                            .ok_or(ProgramError::NotEnoughAccountKeys)?
+                            .created_at(),
+                    };
+
+                    now.saturating_sub(joined_at) < seconds
+                }
+                EligibilityWindow::Transactions(count) => {
+                    referee.transactions.ok_or(ProgramError::InvalidArgument)? <= count
+                }
+            };
+
+            if !open {
+                return Ok(Eligibility::Expired(*window));
+            }
+        }
+
+        Ok(Eligibility::Eligible)
+    }
+
+    /// [`Self::check`] at the time of the `Clock` sysvar, on-chain.
+    pub fn check_now<M, B>(&self, referee: &Referee<M, B>) -> Result<Eligibility, ProgramError>
+    where
+        M: Deref<Target = [u8]>,
+        B: Deref<Target = [u8]>,
+    {
+        self.check(referee, Clock::get()?.unix_timestamp)
+    }
+}
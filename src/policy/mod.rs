@@ -0,0 +1,313 @@
+//! Referral amounts computed from the volume of the referees, and the window during which referrals are paid,
+//! on-chain (before the transfer CPI) or off-chain.
+
+mod eligibility;
+
+pub use eligibility::*;
+
+use crate::error::BuddyLinkError;
+use crate::instruction::GeneralTransferRewardArgs;
+use crate::state::Member;
+use solana_program::program_error::ProgramError;
+use std::ops::Deref;
+
+const MAX_BPS: u128 = 10_000;
+
+/// Multiplier of 1, in bps.
+pub const NEUTRAL_MULTIPLIER_BPS: u32 = 10_000;
+
+/// How a division with a remainder is rounded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Rounding {
+    /// Towards zero, like the program does for the shares.
+    #[default]
+    Down,
+    Up,
+    /// To the nearest, half up.
+    Nearest,
+}
+
+/// `numerator / denominator` with the rounding.
+fn div(numerator: u128, denominator: u128, rounding: Rounding) -> u128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::Nearest => remainder >= denominator - remainder,
+    };
+
+    quotient + u128::from(round_up)
+}
+
+/// What happens to a payout below the minimum.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BelowMinimum {
+    /// Nothing is paid.
+    Skip,
+    /// The minimum is paid.
+    Raise,
+}
+
+/// Tier multiplier of a threshold of seniority.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SeniorityTier {
+    /// Seconds since the creation of the referee member.
+    pub min_age: i64,
+    pub multiplier_bps: u32,
+}
+
+/// Multiplier of the reward read from the member of the referee (x1 without member).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum Tiers {
+    #[default]
+    None,
+    /// Multiplier of the members who joined with a referrer, and of the others.
+    Referred {
+        referred_bps: u32,
+        not_referred_bps: u32,
+    },
+    /// Multiplier of the highest tier reached by the seniority of the member (x1 below the first one).
+    Seniority(Vec<SeniorityTier>),
+}
+
+/// Limit that changed the amount.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PolicyLimit {
+    /// Below the minimum, skipped or raised.
+    Minimum,
+    Maximum,
+    /// What's left of the lifetime cap of the referee (0 once reached).
+    LifetimeCap,
+}
+
+/// Amount of a payout, with the multiplier applied and the limit that changed it (if any).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PolicyAmount {
+    pub amount: u64,
+    pub multiplier_bps: u32,
+    pub limited_by: Option<PolicyLimit>,
+}
+
+impl PolicyAmount {
+    /// Arguments of the transfer instructions and CPIs paying this amount.
+    pub fn transfer_args(&self) -> GeneralTransferRewardArgs {
+        GeneralTransferRewardArgs {
+            amount: self.amount,
+        }
+    }
+}
+
+///# Reward Policy
+///
+/// Computes the referral amount of a payout from the volume traded by the referee (in base units of the reward mint):
+/// 1. `volume * volume_bps * tier multiplier`, rounded once with the rounding of the policy
+/// 2. capped to the maximum per payout
+/// 3. skipped or raised when below the minimum per payout
+/// 4. capped to what's left of the lifetime cap of the referee, given what was already paid to them
+///
+/// The math is checked in u128, errors are `ProgramError`s so it can be used before the transfer CPI as well as
+/// off-chain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RewardPolicy {
+    volume_bps: u16,
+    rounding: Rounding,
+    min_per_payout: Option<(u64, BelowMinimum)>,
+    max_per_payout: Option<u64>,
+    lifetime_cap: Option<u64>,
+    tiers: Tiers,
+}
+
+impl RewardPolicy {
+    /// Pays `volume_bps` of the volume, up to 10_000.
+    pub fn new(volume_bps: u16) -> Self {
+        Self {
+            volume_bps,
+            rounding: Rounding::Down,
+            min_per_payout: None,
+            max_per_payout: None,
+            lifetime_cap: None,
+            tiers: Tiers::None,
+        }
+    }
+
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn min_per_payout(mut self, min_per_payout: u64, below_minimum: BelowMinimum) -> Self {
+        self.min_per_payout = Some((min_per_payout, below_minimum));
+        self
+    }
+
+    pub fn max_per_payout(mut self, max_per_payout: u64) -> Self {
+        self.max_per_payout = Some(max_per_payout);
+        self
+    }
+
+    /// Total paid for the same referee, across payouts.
+    pub fn lifetime_cap(mut self, lifetime_cap: u64) -> Self {
+        self.lifetime_cap = Some(lifetime_cap);
+        self
+    }
+
+    pub fn tiers(mut self, tiers: Tiers) -> Self {
+        self.tiers = tiers;
+        self
+    }
+
+    /// Errors with `InvalidBPSProvided` above 10_000 bps and `InvalidArgument` if the minimum is above the maximum.
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if u128::from(self.volume_bps) > MAX_BPS {
+            return Err(BuddyLinkError::InvalidBPSProvided.into());
+        }
+
+        match (self.min_per_payout, self.max_per_payout) {
+            (Some((min, _)), Some(max)) if min > max => Err(ProgramError::InvalidArgument),
+            _ => Ok(()),
+        }
+    }
+
+    /// Multiplier of the tier of the referee member at the unix timestamp `now`.
+    pub fn multiplier_bps<D: Deref<Target = [u8]>>(
+        &self,
+        referee_member: Option<&Member<D>>,
+        now: i64,
+    ) -> Result<u32, ProgramError> {
+        let Some(member) = referee_member else {
+            return Ok(NEUTRAL_MULTIPLIER_BPS);
+        };
+
+        match &self.tiers {
+            Tiers::None => Ok(NEUTRAL_MULTIPLIER_BPS),
+            Tiers::Referred {
+                referred_bps,
+                not_referred_bps,
+            } => Ok(match member.is_referred()? {
+                true => *referred_bps,
+                false => *not_referred_bps,
+            }),
+            Tiers::Seniority(tiers) => {
+                let age = now.saturating_sub(member.created_at()?);
+
+                Ok(tiers
+                    .iter()
+                    .filter(|tier| age >= tier.min_age)
+                    .max_by_key(|tier| tier.min_age)
+                    .map_or(NEUTRAL_MULTIPLIER_BPS, |tier| tier.multiplier_bps))
+            }
+        }
+    }
+
+    /// Amount of a payout with a known multiplier, `paid_to_referee` being what the referee already earned to
+    /// their referrers (for the lifetime cap).
+    pub fn amount_with_multiplier(
+        &self,
+        volume: u64,
+        multiplier_bps: u32,
+        paid_to_referee: u64,
+    ) -> Result<PolicyAmount, ProgramError> {
+        self.validate()?;
+
+        let numerator = u128::from(volume)
+            .checked_mul(u128::from(self.volume_bps))
+            .and_then(|x| x.checked_mul(u128::from(multiplier_bps)))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let mut amount = div(numerator, MAX_BPS * MAX_BPS, self.rounding);
+        let mut limited_by = None;
+
+        if let Some(max) = self.max_per_payout.map(u128::from) {
+            if amount > max {
+                amount = max;
+                limited_by = Some(PolicyLimit::Maximum);
+            }
+        }
+
+        if let Some((min, below_minimum)) = self.min_per_payout {
+            let min = u128::from(min);
+            if amount < min {
+                amount = match below_minimum {
+                    BelowMinimum::Skip => 0,
+                    BelowMinimum::Raise => min,
+                };
+                limited_by = Some(PolicyLimit::Minimum);
+            }
+        }
+
+        if let Some(cap) = self.lifetime_cap {
+            let left = u128::from(cap.saturating_sub(paid_to_referee));
+            if amount > left {
+                amount = left;
+                limited_by = Some(PolicyLimit::LifetimeCap);
+            }
+        }
+
+        Ok(PolicyAmount {
+            amount: u64::try_from(amount).map_err(|_| ProgramError::ArithmeticOverflow)?,
+            multiplier_bps,
+            limited_by,
+        })
+    }
+
+    /// Amount of a payout, with the multiplier of the tier of the referee member at the unix timestamp `now`
+    /// (`Clock::get()?.unix_timestamp` on-chain).
+    pub fn amount<D: Deref<Target = [u8]>>(
+        &self,
+        volume: u64,
+        referee_member: Option<&Member<D>>,
+        now: i64,
+        paid_to_referee: u64,
+    ) -> Result<PolicyAmount, ProgramError> {
+        let multiplier_bps = self.multiplier_bps(referee_member, now)?;
+
+        self.amount_with_multiplier(volume, multiplier_bps, paid_to_referee)
+    }
+}
+
+///# UI Amount To Base Units
+///
+/// Converts a decimal amount (`"12.5"`) to base units of a mint with `decimals`, the digits beyond the decimals being
+/// rounded. Errors with `InvalidArgument` if it isn't a positive decimal number and `ArithmeticOverflow` if it
+/// doesn't fit in a u64.
+pub fn ui_amount_to_base_units(
+    ui_amount: &str,
+    decimals: u8,
+    rounding: Rounding,
+) -> Result<u64, ProgramError> {
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    let is_digits = |x: &str| x.bytes().all(|x| x.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let overflow = || ProgramError::ArithmeticOverflow;
+    let pow10 = |exponent: usize| {
+        u32::try_from(exponent)
+            .ok()
+            .and_then(|x| 10u128.checked_pow(x))
+            .ok_or_else(overflow)
+    };
+    let parse = |digits: &str| -> Result<u128, ProgramError> {
+        match digits.trim_start_matches('0') {
+            "" => Ok(0),
+            digits => digits.parse().map_err(|_| overflow()),
+        }
+    };
+
+    // The whole amount in units of the last digit, then rounded to the decimals
+    let digits = fraction.len();
+    let units = parse(whole)?
+        .checked_mul(pow10(digits)?)
+        .and_then(|x| x.checked_add(parse(fraction).ok()?))
+        .ok_or_else(overflow)?;
+    let base_units = match digits.checked_sub(usize::from(decimals)) {
+        Some(extra) => div(units, pow10(extra)?, rounding),
+        None => units
+            .checked_mul(pow10(usize::from(decimals) - digits)?)
+            .ok_or_else(overflow)?,
+    };
+
+    u64::try_from(base_units).map_err(|_| overflow())
+}
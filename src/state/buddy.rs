@@ -0,0 +1,76 @@
+use crate::state::{
+    borrow_account_data, check_discriminator, read_i64, read_optional_pubkey, read_pubkey,
+    read_string,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use std::cell::Ref;
+use std::ops::Deref;
+
+///# Buddy
+///
+/// Zero-copy view over a BuddyLink buddy (global profile of a user, or paid buddy).
+/// Fields are read in place, nothing is deserialized or allocated.
+///
+/// Layout:
+/// 1. `[0..8]` Discriminator
+/// 2. `[8..40]` Authority (wallet owning the buddy)
+/// 3. `[40]` Bump
+/// 4. `[42..50]` Creation timestamp
+/// 5. `[51..83]` Global referrer treasury (default pubkey if the buddy has no referrer)
+/// 6. `[84..]` Name (Borsh string)
+pub struct Buddy<D> {
+    data: D,
+}
+
+impl<D> Buddy<D> {
+    pub const DISCRIMINATOR: [u8; 8] = [31, 157, 214, 107, 159, 151, 93, 221];
+    pub const LEN: usize = 424;
+
+    pub const AUTHORITY_OFFSET: usize = 8;
+    pub const BUMP_OFFSET: usize = 40;
+    pub const CREATED_AT_OFFSET: usize = 42;
+    pub const REFERRER_TREASURY_OFFSET: usize = 51;
+    pub const NAME_OFFSET: usize = 84;
+}
+
+impl<'a> Buddy<Ref<'a, [u8]>> {
+    /// Borrows the data of a buddy account, checking the owner and the discriminator.
+    pub fn from_account_info(account: &'a AccountInfo) -> Result<Self, ProgramError> {
+        Self::new(borrow_account_data(account)?)
+    }
+}
+
+impl<D: Deref<Target = [u8]>> Buddy<D> {
+    /// Wraps raw buddy account data, checking the discriminator.
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        check_discriminator(&data, &Self::DISCRIMINATOR, Self::NAME_OFFSET)?;
+
+        Ok(Self { data })
+    }
+
+    /// Wallet owning the buddy.
+    pub fn authority(&self) -> Pubkey {
+        read_pubkey(&self.data, Self::AUTHORITY_OFFSET)
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.data[Self::BUMP_OFFSET]
+    }
+
+    /// Unix timestamp of the creation of the buddy.
+    pub fn created_at(&self) -> i64 {
+        // Always in bounds, checked by the minimum length
+        read_i64(&self.data, Self::CREATED_AT_OFFSET).unwrap_or_default()
+    }
+
+    /// Treasury of the global referrer of this buddy, None if the buddy wasn't referred.
+    pub fn referrer_treasury(&self) -> Option<Pubkey> {
+        read_optional_pubkey(&self.data, Self::REFERRER_TREASURY_OFFSET)
+    }
+
+    pub fn name(&self) -> Result<&str, ProgramError> {
+        Ok(read_string(&self.data, Self::NAME_OFFSET)?.0)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::state::{
+    borrow_account_data, check_discriminator, read_i64, read_optional_pubkey, read_pubkey,
+    read_string,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use std::cell::Ref;
+use std::ops::Deref;
+
+///# Member
+///
+/// Zero-copy view over a BuddyLink member (account of a user within an organization).
+/// Fields are read in place, nothing is deserialized or allocated.
+///
+/// Layout:
+/// 1. `[0..8]` Discriminator
+/// 2. `[8]` Bump
+/// 3. `[9..41]` Referrer treasury (default pubkey if the member has no referrer)
+/// 4. `[41..73]` Owner treasury (treasury of the member)
+/// 5. `[73..105]` Key of the treasuries of the referrer (default pubkey if the member has no referrer)
+/// 6. `[105]` Referred flag (Borsh bool, set when the member joined with a referrer)
+/// 7. `[106..]` Name, organization name (Borsh strings, each followed by a reserved string) and creation timestamp
+pub struct Member<D> {
+    data: D,
+}
+
+impl<D> Member<D> {
+    pub const DISCRIMINATOR: [u8; 8] = [54, 19, 162, 21, 29, 166, 17, 198];
+    pub const LEN: usize = 514;
+
+    pub const BUMP_OFFSET: usize = 8;
+    pub const REFERRER_TREASURY_OFFSET: usize = 9;
+    pub const OWNER_TREASURY_OFFSET: usize = 41;
+    pub const REFERRER_TREASURY_KEY_OFFSET: usize = 73;
+    pub const REFERRED_OFFSET: usize = 105;
+    pub const NAME_OFFSET: usize = 106;
+}
+
+impl<'a> Member<Ref<'a, [u8]>> {
+    /// Borrows the data of a member account, checking the owner and the discriminator.
+    pub fn from_account_info(account: &'a AccountInfo) -> Result<Self, ProgramError> {
+        Self::new(borrow_account_data(account)?)
+    }
+}
+
+impl<D: Deref<Target = [u8]>> Member<D> {
+    /// Wraps raw member account data, checking the discriminator.
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        check_discriminator(&data, &Self::DISCRIMINATOR, Self::NAME_OFFSET)?;
+
+        Ok(Self { data })
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.data[Self::BUMP_OFFSET]
+    }
+
+    /// Treasury of the referrer of this member, None if the member wasn't referred.
+    pub fn referrer_treasury(&self) -> Option<Pubkey> {
+        read_optional_pubkey(&self.data, Self::REFERRER_TREASURY_OFFSET)
+    }
+
+    /// Treasury owning this member.
+    pub fn owner_treasury(&self) -> Pubkey {
+        read_pubkey(&self.data, Self::OWNER_TREASURY_OFFSET)
+    }
+
+    /// Key seeding the treasuries of the referrer, one per mint (see [`find_treasury_address`]).
+    /// None if the member wasn't referred.
+    ///
+    /// [`find_treasury_address`]: crate::state::find_treasury_address
+    pub fn referrer_treasury_key(&self) -> Option<Pubkey> {
+        read_optional_pubkey(&self.data, Self::REFERRER_TREASURY_KEY_OFFSET)
+    }
+
+    /// Whether the member joined with a referrer. The program reads this flag to set the share of the global
+    /// referrer in the checked global reward (see [`reward_outcome`]).
+    ///
+    /// [`reward_outcome`]: crate::instruction::reward_outcome
+    pub fn is_referred(&self) -> Result<bool, ProgramError> {
+        match self.data[Self::REFERRED_OFFSET] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    pub fn name(&self) -> Result<&str, ProgramError> {
+        Ok(read_string(&self.data, Self::NAME_OFFSET)?.0)
+    }
+
+    pub fn organization_name(&self) -> Result<&str, ProgramError> {
+        Ok(read_string(&self.data, self.organization_name_offset()?)?.0)
+    }
+
+    /// Unix timestamp of the creation of the member.
+    pub fn created_at(&self) -> Result<i64, ProgramError> {
+        let (_, reserved_offset) = read_string(&self.data, self.organization_name_offset()?)?;
+        let (_, created_at_offset) = read_string(&self.data, reserved_offset)?;
+
+        read_i64(&self.data, created_at_offset)
+    }
+
+    fn organization_name_offset(&self) -> Result<usize, ProgramError> {
+        let (_, reserved_offset) = read_string(&self.data, Self::NAME_OFFSET)?;
+        let (_, organization_name_offset) = read_string(&self.data, reserved_offset)?;
+
+        Ok(organization_name_offset)
+    }
+}
@@ -0,0 +1,83 @@
+mod buddy;
+mod member;
+mod treasury;
+
+pub use buddy::*;
+pub use member::*;
+pub use treasury::*;
+
+use crate::constants::BL_PROGRAM_ID;
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+use std::cell::Ref;
+
+pub(crate) fn borrow_account_data<'a>(
+    account: &'a AccountInfo,
+) -> Result<Ref<'a, [u8]>, ProgramError> {
+    if account.owner != &BL_PROGRAM_ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(Ref::map(account.try_borrow_data()?, |data| &**data))
+}
+
+pub(crate) fn check_discriminator(
+    data: &[u8],
+    discriminator: &[u8; 8],
+    min_len: usize,
+) -> Result<(), ProgramError> {
+    if data.len() < min_len {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    if &data[..8] != discriminator {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut pubkey = [0u8; PUBKEY_BYTES];
+    pubkey.copy_from_slice(&data[offset..offset + PUBKEY_BYTES]);
+    Pubkey::new_from_array(pubkey)
+}
+
+pub(crate) fn read_optional_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    let pubkey = read_pubkey(data, offset);
+
+    if pubkey == Pubkey::default() {
+        return None;
+    }
+
+    Some(pubkey)
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(crate) fn read_i64(data: &[u8], offset: usize) -> Result<i64, ProgramError> {
+    let bytes = data
+        .get(offset..offset + 8)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    Ok(i64::from_le_bytes(value))
+}
+
+/// Reads a Borsh string at `offset`, returns it with the offset right after it.
+pub(crate) fn read_string(data: &[u8], offset: usize) -> Result<(&str, usize), ProgramError> {
+    let len = read_u32(data, offset)? as usize;
+    let start = offset + 4;
+    let bytes = data
+        .get(start..start + len)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let string = std::str::from_utf8(bytes).map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok((string, start + len))
+}
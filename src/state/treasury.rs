@@ -0,0 +1,112 @@
+use crate::constants::{BL_PROGRAM_ID, TREASURY_SEED};
+use crate::state::{borrow_account_data, check_discriminator, read_pubkey, read_u32};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+use std::cell::Ref;
+use std::ops::Deref;
+
+/// Owner of a treasury and its share of the rewards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TreasuryOwner {
+    /// Buddy owning (part of) the treasury.
+    pub buddy: Pubkey,
+    /// Share of the rewards going to this buddy in bps.
+    pub share_in_bps: u16,
+}
+
+///# Treasury
+///
+/// Zero-copy view over a BuddyLink treasury (holds the rewards of one or more buddies for a mint).
+/// Fields are read in place, nothing is deserialized or allocated.
+///
+/// Layout:
+/// 1. `[0..8]` Discriminator
+/// 2. `[8]` Bump
+/// 3. `[19..51]` Mint
+/// 4. `[51..55]` Number of owners
+/// 5. `[55..]` Owners, each one is a buddy followed by its share in bps
+pub struct Treasury<D> {
+    data: D,
+}
+
+impl<D> Treasury<D> {
+    pub const DISCRIMINATOR: [u8; 8] = [238, 239, 123, 238, 89, 1, 168, 253];
+    pub const LEN: usize = 548;
+
+    pub const BUMP_OFFSET: usize = 8;
+    pub const MINT_OFFSET: usize = 19;
+    pub const OWNERS_OFFSET: usize = 51;
+    pub const OWNER_LEN: usize = PUBKEY_BYTES + 2;
+}
+
+impl<'a> Treasury<Ref<'a, [u8]>> {
+    /// Borrows the data of a treasury account, checking the owner and the discriminator.
+    pub fn from_account_info(account: &'a AccountInfo) -> Result<Self, ProgramError> {
+        Self::new(borrow_account_data(account)?)
+    }
+}
+
+impl<D: Deref<Target = [u8]>> Treasury<D> {
+    /// Wraps raw treasury account data, checking the discriminator and the owners.
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        check_discriminator(&data, &Self::DISCRIMINATOR, Self::OWNERS_OFFSET + 4)?;
+
+        let owners_count = read_u32(&data, Self::OWNERS_OFFSET)? as usize;
+        if data.len() < Self::OWNERS_OFFSET + 4 + owners_count * Self::OWNER_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self { data })
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.data[Self::BUMP_OFFSET]
+    }
+
+    /// Mint the treasury holds rewards for.
+    pub fn mint(&self) -> Pubkey {
+        read_pubkey(&self.data, Self::MINT_OFFSET)
+    }
+
+    pub fn owners_count(&self) -> usize {
+        u32::from_le_bytes([
+            self.data[Self::OWNERS_OFFSET],
+            self.data[Self::OWNERS_OFFSET + 1],
+            self.data[Self::OWNERS_OFFSET + 2],
+            self.data[Self::OWNERS_OFFSET + 3],
+        ]) as usize
+    }
+
+    pub fn owner(&self, index: usize) -> Option<TreasuryOwner> {
+        if index >= self.owners_count() {
+            return None;
+        }
+
+        let offset = Self::OWNERS_OFFSET + 4 + index * Self::OWNER_LEN;
+        let share_offset = offset + PUBKEY_BYTES;
+
+        Some(TreasuryOwner {
+            buddy: read_pubkey(&self.data, offset),
+            share_in_bps: u16::from_le_bytes([
+                self.data[share_offset],
+                self.data[share_offset + 1],
+            ]),
+        })
+    }
+
+    pub fn owners(&self) -> impl Iterator<Item = TreasuryOwner> + '_ {
+        (0..self.owners_count()).filter_map(move |index| self.owner(index))
+    }
+
+    /// Whether the buddy is one of the owners of the treasury.
+    pub fn is_owned_by(&self, buddy: &Pubkey) -> bool {
+        self.owners().any(|owner| &owner.buddy == buddy)
+    }
+}
+
+/// Address of the treasury holding the rewards of `mint` for the owners identified by `key`
+/// (the key is kept by the members referred by these owners).
+pub fn find_treasury_address(mint: &Pubkey, key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED, mint.as_ref(), key.as_ref()], &BL_PROGRAM_ID)
+}
@@ -0,0 +1,20 @@
+//! BuddyLink program of the amman fixtures (as deployed on devnet, not the mock) in program-test,
+//! shared by the program tests.
+#![allow(dead_code)]
+
+use buddy_link::client::AccountSnapshot;
+use solana_program_test::ProgramTest;
+use std::path::Path;
+
+/// Accounts of the amman fixtures, at the root of the workspace.
+pub fn snapshot() -> AccountSnapshot {
+    AccountSnapshot::load_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(".amman/accounts"))
+        .unwrap()
+}
+
+/// Adds the programs and the other accounts of the snapshot.
+pub fn add_snapshot(program_test: &mut ProgramTest, snapshot: &AccountSnapshot) {
+    for (pubkey, account) in snapshot.program_test_accounts() {
+        program_test.add_account(pubkey, account);
+    }
+}
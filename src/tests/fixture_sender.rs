@@ -0,0 +1,296 @@
+//! RPC sender serving the accounts of the amman fixtures, shared by the client tests.
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use base64::Engine;
+use buddy_link::constants::BL_PROGRAM_ID;
+use serde_json::{json, Value};
+use solana_client::client_error::Result as ClientResult;
+use solana_client::nonblocking;
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::{AccountSharedData, WritableAccount};
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Units consumed by the simulations of the sender.
+pub const SIMULATED_UNITS: u64 = 12_345;
+
+/// Serves the accounts of the amman fixtures, counting the requests.
+///
+/// Simulations consume [`SIMULATED_UNITS`] (or fail with [`FixtureSender::fail_simulations`]),
+/// sent transactions are confirmed right away (unless dropped with [`FixtureSender::drop_transactions`]).
+#[derive(Clone, Default)]
+pub struct FixtureSender {
+    accounts: Arc<Mutex<HashMap<Pubkey, Value>>>,
+    requests: Arc<Mutex<Vec<RpcRequest>>>,
+    simulation_error: Arc<Mutex<Option<Value>>>,
+    simulated_accounts: Arc<Mutex<HashMap<Pubkey, Value>>>,
+    block_height: Arc<Mutex<u64>>,
+    latest_blockhash: Arc<Mutex<Option<(Hash, u64)>>>,
+    dropping: Arc<Mutex<bool>>,
+    landed: Arc<Mutex<HashMap<Signature, Option<Value>>>>,
+    sent: Arc<Mutex<Vec<VersionedTransaction>>>,
+}
+
+impl FixtureSender {
+    pub fn new() -> Self {
+        let sender = Self::default();
+
+        for entry in
+            std::fs::read_dir(format!("{}/.amman/accounts", env!("CARGO_MANIFEST_DIR"))).unwrap()
+        {
+            let json: Value =
+                serde_json::from_str(&std::fs::read_to_string(entry.unwrap().path()).unwrap())
+                    .unwrap();
+            sender.accounts.lock().unwrap().insert(
+                json["pubkey"].as_str().unwrap().parse().unwrap(),
+                json["account"].clone(),
+            );
+        }
+
+        sender
+    }
+
+    pub fn data(&self, pubkey: &Pubkey) -> Vec<u8> {
+        let accounts = self.accounts.lock().unwrap();
+        base64::engine::general_purpose::STANDARD
+            .decode(accounts[pubkey]["data"][0].as_str().unwrap())
+            .unwrap()
+    }
+
+    pub fn set_data(&self, pubkey: Pubkey, data: &[u8]) {
+        self.set_account(pubkey, 1_000_000, data, BL_PROGRAM_ID);
+    }
+
+    pub fn set_account(&self, pubkey: Pubkey, lamports: u64, data: &[u8], owner: Pubkey) {
+        self.accounts
+            .lock()
+            .unwrap()
+            .insert(pubkey, account_json(lamports, data, owner));
+    }
+
+    /// The account after the simulations, the accounts not set are unchanged.
+    pub fn simulate_account(&self, pubkey: Pubkey, lamports: u64, data: &[u8], owner: Pubkey) {
+        self.simulated_accounts
+            .lock()
+            .unwrap()
+            .insert(pubkey, account_json(lamports, data, owner));
+    }
+
+    /// Simulations fail with this transaction error (JSON of `TransactionError`).
+    pub fn fail_simulations(&self, error: Value) {
+        *self.simulation_error.lock().unwrap() = Some(error);
+    }
+
+    pub fn set_block_height(&self, block_height: u64) {
+        *self.block_height.lock().unwrap() = block_height;
+    }
+
+    /// Latest blockhash and its last valid block height, `[1; 32]` valid until 150 by default.
+    pub fn set_latest_blockhash(&self, blockhash: Hash, last_valid_block_height: u64) {
+        *self.latest_blockhash.lock().unwrap() = Some((blockhash, last_valid_block_height));
+    }
+
+    /// Sent transactions don't land, their signatures are unknown until [`FixtureSender::land`].
+    pub fn drop_transactions(&self, dropping: bool) {
+        *self.dropping.lock().unwrap() = dropping;
+    }
+
+    /// Lands a signature, failed with this transaction error (JSON of `TransactionError`) if any.
+    pub fn land(&self, signature: Signature, error: Option<Value>) {
+        self.landed.lock().unwrap().insert(signature, error);
+    }
+
+    /// Transactions sent, in order.
+    pub fn sent(&self) -> Vec<VersionedTransaction> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn requests(&self, request: RpcRequest) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|x| **x == request)
+            .count()
+    }
+}
+
+#[async_trait]
+impl RpcSender for FixtureSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        self.requests.lock().unwrap().push(request);
+        let accounts = self.accounts.lock().unwrap();
+
+        Ok(match request {
+            RpcRequest::GetVersion => json!({ "solana-core": "1.18.26" }),
+            RpcRequest::GetLatestBlockhash => {
+                let (blockhash, last_valid_block_height) = self
+                    .latest_blockhash
+                    .lock()
+                    .unwrap()
+                    .unwrap_or((Hash::new_from_array([1; 32]), 150));
+                json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "blockhash": blockhash.to_string(),
+                        "lastValidBlockHeight": last_valid_block_height,
+                    },
+                })
+            }
+            RpcRequest::GetBlockHeight => json!(*self.block_height.lock().unwrap()),
+            RpcRequest::IsBlockhashValid => json!({ "context": { "slot": 1 }, "value": true }),
+            RpcRequest::SimulateTransaction => json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "err": self.simulation_error.lock().unwrap().clone(),
+                    "logs": ["Program log: simulated"],
+                    "accounts": params[1]["accounts"]["addresses"].as_array().map(|addresses| {
+                        let simulated = self.simulated_accounts.lock().unwrap();
+                        addresses
+                            .iter()
+                            .map(|x| {
+                                let pubkey = x.as_str().unwrap().parse().unwrap();
+                                simulated.get(&pubkey).or(accounts.get(&pubkey)).cloned()
+                            })
+                            .collect::<Vec<_>>()
+                    }),
+                    "unitsConsumed": SIMULATED_UNITS,
+                    "returnData": null,
+                },
+            }),
+            RpcRequest::SendTransaction => {
+                let transaction: VersionedTransaction = bincode::deserialize(
+                    &base64::engine::general_purpose::STANDARD
+                        .decode(params[0].as_str().unwrap())
+                        .unwrap(),
+                )
+                .unwrap();
+                let signature = transaction.signatures[0];
+                self.sent.lock().unwrap().push(transaction);
+                json!(signature.to_string())
+            }
+            RpcRequest::GetSignatureStatuses => {
+                let landed = self.landed.lock().unwrap();
+                let dropping = *self.dropping.lock().unwrap();
+
+                json!({
+                    "context": { "slot": 1 },
+                    "value": params[0]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|x| {
+                            let signature: Signature = x.as_str().unwrap().parse().unwrap();
+                            let error = match landed.get(&signature) {
+                                Some(error) => error.clone(),
+                                None if dropping => return Value::Null,
+                                None => None,
+                            };
+                            json!({
+                                "slot": 1,
+                                "confirmations": null,
+                                "err": error,
+                                "status": match &error {
+                                    Some(error) => json!({ "Err": error }),
+                                    None => json!({ "Ok": null }),
+                                },
+                                "confirmationStatus": "finalized",
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            }
+            RpcRequest::GetAccountInfo => json!({
+                "context": { "slot": 1 },
+                "value": accounts.get(&params[0].as_str().unwrap().parse().unwrap()).cloned(),
+            }),
+            RpcRequest::GetMultipleAccounts => json!({
+                "context": { "slot": 1 },
+                "value": params[0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| accounts.get(&x.as_str().unwrap().parse().unwrap()).cloned())
+                    .collect::<Vec<_>>(),
+            }),
+            RpcRequest::GetProgramAccounts => {
+                let program: Pubkey = params[0].as_str().unwrap().parse().unwrap();
+                let filters: Vec<RpcFilterType> =
+                    serde_json::from_value(params[1]["filters"].clone()).unwrap();
+
+                Value::Array(
+                    accounts
+                        .iter()
+                        .filter(|(_, account)| account["owner"] == program.to_string())
+                        .filter(|(_, account)| {
+                            let data = base64::engine::general_purpose::STANDARD
+                                .decode(account["data"][0].as_str().unwrap())
+                                .unwrap();
+                            let account = AccountSharedData::create(0, data, program, false, 0);
+                            filters.iter().all(|filter| filter.allows(&account))
+                        })
+                        .map(|(pubkey, account)| {
+                            json!({
+                                "pubkey": pubkey.to_string(),
+                                "account": slice(account, &params[1]["dataSlice"]),
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            request => panic!("unexpected request {request}"),
+        })
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "fixtures".to_string()
+    }
+}
+
+pub fn client(sender: &FixtureSender) -> RpcClient {
+    RpcClient::new_sender(sender.clone(), RpcClientConfig::default())
+}
+
+pub fn nonblocking_client(sender: &FixtureSender) -> nonblocking::rpc_client::RpcClient {
+    nonblocking::rpc_client::RpcClient::new_sender(sender.clone(), RpcClientConfig::default())
+}
+
+fn account_json(lamports: u64, data: &[u8], owner: Pubkey) -> Value {
+    json!({
+        "lamports": lamports,
+        "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+        "owner": owner.to_string(),
+        "executable": false,
+        "rentEpoch": 0,
+    })
+}
+
+fn slice(account: &Value, data_slice: &Value) -> Value {
+    if data_slice.is_null() {
+        return account.clone();
+    }
+
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(account["data"][0].as_str().unwrap())
+        .unwrap();
+    let offset = (data_slice["offset"].as_u64().unwrap() as usize).min(data.len());
+    let length = data_slice["length"].as_u64().unwrap() as usize;
+
+    let mut account = account.clone();
+    account["data"][0] = base64::engine::general_purpose::STANDARD
+        .encode(&data[offset..data.len().min(offset + length)])
+        .into();
+    account
+}
@@ -0,0 +1,233 @@
+mod fixture_sender;
+
+use buddy_link::client::account_source::{AccountSnapshot, AccountSource, AsyncAccountSource};
+use buddy_link::client::discovery::Discovery;
+use buddy_link::client::referral_chain::ReferralChainFetcher;
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::state::Member;
+use fixture_sender::{client, nonblocking_client, FixtureSender};
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::RpcRequest;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+const MISSING: Pubkey = pubkey!("11111111111111111111111111111112");
+
+fn snapshot() -> AccountSnapshot {
+    AccountSnapshot::load_dir(format!("{}/.amman/accounts", env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+fn member_filters(treasury: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &Member::<&[u8]>::DISCRIMINATOR,
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            Member::<&[u8]>::OWNER_TREASURY_OFFSET,
+            treasury.as_ref(),
+        )),
+    ]
+}
+
+#[test]
+fn test_snapshot_matches_rpc() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let snapshot = snapshot();
+
+    assert_eq!(
+        snapshot.accounts().len(),
+        std::fs::read_dir(format!("{}/.amman/accounts", env!("CARGO_MANIFEST_DIR")))
+            .unwrap()
+            .count()
+    );
+
+    let keys = [MINT, REFERRER_TREASURY, MISSING, REFEREE_MEMBER];
+    let from_rpc = AccountSource::get_multiple_accounts(&client, &keys).unwrap();
+    let from_snapshot = AccountSource::get_multiple_accounts(&snapshot, &keys).unwrap();
+    assert_eq!(from_rpc, from_snapshot);
+    assert!(from_snapshot[2].is_none());
+
+    assert_eq!(
+        AccountSource::get_account(&snapshot, &REFERRER_MEMBER).unwrap(),
+        AccountSource::get_account(&client, &REFERRER_MEMBER).unwrap()
+    );
+
+    // Same filters and data slice as getProgramAccounts
+    let data_slice = Some(UiDataSliceConfig {
+        offset: 8,
+        length: 32,
+    });
+    let filters = member_filters(&REFEREE_TREASURY);
+    assert_eq!(
+        AccountSource::get_program_accounts(&snapshot, &BL_PROGRAM_ID, &filters, data_slice)
+            .unwrap(),
+        AccountSource::get_program_accounts(&client, &BL_PROGRAM_ID, &filters, data_slice).unwrap()
+    );
+    let members =
+        AccountSource::get_program_accounts(&snapshot, &BL_PROGRAM_ID, &filters, data_slice)
+            .unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].0, REFEREE_MEMBER);
+    assert_eq!(members[0].1.data.len(), 32);
+}
+
+#[test]
+fn test_client_helpers_on_snapshot() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let snapshot = snapshot();
+
+    let from_rpc = Discovery::new(&client)
+        .referees(&REFERRER_TREASURY)
+        .unwrap()
+        .all()
+        .unwrap();
+    let from_snapshot = Discovery::new(&snapshot)
+        .referees(&REFERRER_TREASURY)
+        .unwrap()
+        .all()
+        .unwrap();
+    assert_eq!(
+        from_rpc.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        from_snapshot
+            .iter()
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>()
+    );
+
+    let from_rpc = ReferralChainFetcher::new(&client)
+        .reward_mint(MINT, anchor_spl::token::ID)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+    let from_snapshot = ReferralChainFetcher::new(&snapshot)
+        .reward_mint(MINT, anchor_spl::token::ID)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+    assert_eq!(from_rpc.links, from_snapshot.links);
+    assert_eq!(from_rpc.end, from_snapshot.end);
+    assert_eq!(from_snapshot.links[0].member, Some(REFERRER_MEMBER));
+
+    // The snapshot doesn't hit the node
+    let requests = sender.requests(RpcRequest::GetProgramAccounts);
+    Discovery::new(&snapshot)
+        .referees(&REFERRER_TREASURY)
+        .unwrap();
+    assert_eq!(sender.requests(RpcRequest::GetProgramAccounts), requests);
+}
+
+#[test]
+fn test_snapshot_edits() {
+    let mut snapshot = snapshot();
+
+    let member = snapshot.remove(&REFEREE_MEMBER).unwrap();
+    assert!(ReferralChainFetcher::new(&snapshot)
+        .fetch_local(&REFEREE_MEMBER)
+        .is_err());
+
+    snapshot.insert(REFEREE_MEMBER, member);
+    assert!(ReferralChainFetcher::new(&snapshot)
+        .fetch_local(&REFEREE_MEMBER)
+        .is_ok());
+
+    let snapshot: AccountSnapshot = [(MISSING, Account::default())].into_iter().collect();
+    assert_eq!(snapshot.accounts().len(), 1);
+
+    assert!(matches!(
+        AccountSnapshot::load_dir("/nonexistent"),
+        Err(Error::Snapshot(_))
+    ));
+}
+
+/// Sources without `getProgramAccounts` still serve the account reads.
+struct ReadOnly(AccountSnapshot);
+
+impl AccountSource for ReadOnly {
+    fn get_account(&self, pubkey: &Pubkey) -> buddy_link::client::Result<Option<Account>> {
+        AccountSource::get_account(&self.0, pubkey)
+    }
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> buddy_link::client::Result<Vec<Option<Account>>> {
+        AccountSource::get_multiple_accounts(&self.0, pubkeys)
+    }
+}
+
+#[test]
+fn test_unsupported_program_accounts() {
+    let source = ReadOnly(snapshot());
+
+    assert!(AccountSource::get_account(&source, &REFEREE_MEMBER)
+        .unwrap()
+        .is_some());
+    assert!(matches!(
+        Discovery::new(&source).referees(&REFERRER_TREASURY),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_async_sources() {
+    let sender = FixtureSender::new();
+    let client = nonblocking_client(&sender);
+    let snapshot = snapshot();
+
+    let keys = [MINT, MISSING, REFEREE_MEMBER];
+    let from_rpc = AsyncAccountSource::get_multiple_accounts(&client, &keys)
+        .await
+        .unwrap();
+    let from_snapshot = AsyncAccountSource::get_multiple_accounts(&snapshot, &keys)
+        .await
+        .unwrap();
+    assert_eq!(from_rpc, from_snapshot);
+
+    let filters = member_filters(&REFEREE_TREASURY);
+    assert_eq!(
+        AsyncAccountSource::get_program_accounts(&client, &BL_PROGRAM_ID, &filters, None)
+            .await
+            .unwrap(),
+        AsyncAccountSource::get_program_accounts(&snapshot, &BL_PROGRAM_ID, &filters, None)
+            .await
+            .unwrap()
+    );
+}
+
+#[cfg(feature = "banks-client")]
+#[tokio::test]
+async fn test_banks_client() {
+    let snapshot = snapshot();
+    let mut program_test = solana_program_test::ProgramTest::default();
+    for (pubkey, account) in snapshot.accounts() {
+        if account.executable {
+            continue;
+        }
+        program_test.add_account(*pubkey, account.clone());
+    }
+    let (banks_client, _, _) = program_test.start().await;
+
+    let keys = [MINT, MISSING, REFEREE_MEMBER];
+    assert_eq!(
+        AsyncAccountSource::get_multiple_accounts(&banks_client, &keys)
+            .await
+            .unwrap(),
+        AsyncAccountSource::get_multiple_accounts(&snapshot, &keys)
+            .await
+            .unwrap()
+    );
+    assert!(matches!(
+        AsyncAccountSource::get_program_accounts(&banks_client, &BL_PROGRAM_ID, &[], None).await,
+        Err(Error::Unsupported(_))
+    ));
+}
@@ -0,0 +1,282 @@
+use borsh::BorshDeserialize;
+use buddy_link::client::batching::{
+    exact_shared_reward_splits, share_amount, SharedRewardBatcher, MAX_BPS,
+    MAX_TRANSACTION_ACCOUNTS,
+};
+use buddy_link::constants::TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR;
+use buddy_link::instruction::{SharedRewardAsset, TransferUncheckedLocalSharedRewardArgs};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::{legacy, v0, VersionedMessage};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use std::collections::HashMap;
+
+// Deterministic amounts, no need for a rand dependency
+fn amounts(count: usize, seed: u64, max: u64) -> Vec<u64> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) % max + 1
+        })
+        .collect()
+}
+
+fn assert_exact_split(amounts: &[u64]) -> usize {
+    let splits = exact_shared_reward_splits(amounts);
+    let mut paid = vec![0u64; amounts.len()];
+
+    for split in &splits {
+        assert_eq!(
+            split.shares.iter().map(|x| u32::from(x.1)).sum::<u32>(),
+            u32::from(MAX_BPS)
+        );
+
+        for &(index, share) in &split.shares {
+            paid[index] += share_amount(split.total_amount, share).unwrap();
+        }
+    }
+
+    assert_eq!(paid, amounts);
+
+    splits.len()
+}
+
+#[test]
+fn test_split_is_exact() {
+    assert_eq!(assert_exact_split(&[]), 0);
+    assert_eq!(assert_exact_split(&[7]), 1);
+    assert_eq!(assert_exact_split(&[250, 250, 500]), 1);
+    assert_eq!(assert_exact_split(&[1_000_000_000; 4]), 1);
+    // 10%, 20% and 70%, although 1 and 2 don't share exactly together
+    assert_eq!(assert_exact_split(&[1, 2, 7]), 1);
+    // Thirds can't be expressed in bps
+    assert_eq!(assert_exact_split(&[1_000_000_000; 3]), 2);
+    // Too large to be multiplied by 10_000 bps on-chain
+    assert_exact_split(&[u64::MAX / 2, 3]);
+
+    for seed in 0..20 {
+        assert!(assert_exact_split(&amounts(30, seed, 5_000_000_000)) <= 5);
+        assert_exact_split(&amounts(10, seed, 10_000));
+        assert_exact_split(&amounts(5, seed, 3));
+    }
+}
+
+/// Whether a single shared reward pays exactly the amounts, trying every share of each total.
+/// Each share loses less than one unit to the rounding, so the total is below the sum plus the count.
+fn brute_force_single_split(amounts: &[u64]) -> bool {
+    let bps = usize::from(MAX_BPS);
+    let sum: u64 = amounts.iter().sum();
+
+    (sum..sum + amounts.len() as u64).any(|total_amount| {
+        // Sums of shares reachable by the recipients so far, each paid exactly
+        let mut reachable = vec![false; bps + 1];
+        reachable[0] = true;
+
+        for &amount in amounts {
+            let shares: Vec<usize> = (0..=bps)
+                .filter(|&share| share_amount(total_amount, share as u16) == Some(amount))
+                .collect();
+
+            let mut next = vec![false; bps + 1];
+            for reached in (0..=bps).filter(|&x| reachable[x]) {
+                for share in shares.iter().filter(|&share| reached + share <= bps) {
+                    next[reached + share] = true;
+                }
+            }
+            reachable = next;
+        }
+
+        reachable[bps]
+    })
+}
+
+#[test]
+fn test_split_is_minimal() {
+    let mut checked = Vec::new();
+
+    // Pairs around the sums where the shares are about one unit each
+    for sum in [9_999, 10_000, 10_001, 10_007, 19_999, 20_001, 33_333] {
+        for first in (1..sum).step_by(97) {
+            checked.push(vec![first, sum - first]);
+        }
+    }
+    for seed in 0..200 {
+        checked.push(amounts(3, seed, 15_000));
+    }
+
+    for amounts in checked {
+        let splits = assert_exact_split(&amounts);
+
+        if brute_force_single_split(&amounts) {
+            assert_eq!(splits, 1, "{:?}", amounts);
+        } else if amounts.len() == 2 {
+            // Each amount alone
+            assert_eq!(splits, 2, "{:?}", amounts);
+        } else {
+            assert!(splits > 1, "{:?}", amounts);
+        }
+    }
+}
+
+#[test]
+fn test_split_skips_zero_amounts() {
+    let splits = exact_shared_reward_splits(&[0, 10, 0, 30]);
+
+    assert!(splits
+        .iter()
+        .flat_map(|x| &x.shares)
+        .all(|x| x.0 == 1 || x.0 == 3));
+}
+
+fn transaction_size(
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> (usize, usize) {
+    // The room left by the batcher
+    let instructions: Vec<Instruction> = [
+        ComputeBudgetInstruction::set_compute_unit_limit(0),
+        ComputeBudgetInstruction::set_compute_unit_price(0),
+    ]
+    .into_iter()
+    .chain(instructions.iter().cloned())
+    .collect();
+
+    let message = if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(legacy::Message::new(&instructions, Some(fee_payer)))
+    } else {
+        VersionedMessage::V0(
+            v0::Message::try_compile(fee_payer, &instructions, lookup_tables, Hash::default())
+                .unwrap(),
+        )
+    };
+
+    let accounts = message.static_account_keys().len()
+        + message.address_table_lookups().map_or(0, |lookups| {
+            lookups
+                .iter()
+                .map(|x| x.writable_indexes.len() + x.readonly_indexes.len())
+                .sum()
+        });
+
+    (
+        1 + 64 * usize::from(message.header().num_required_signatures) + message.serialize().len(),
+        accounts,
+    )
+}
+
+fn assert_plan_pays(
+    transactions: &[Vec<Instruction>],
+    payouts: &[(Pubkey, u64)],
+    lookup_tables: &[AddressLookupTableAccount],
+    fee_payer: &Pubkey,
+) {
+    let mut paid: HashMap<Pubkey, u64> = HashMap::new();
+
+    for (index, instructions) in transactions.iter().enumerate() {
+        let (size, accounts) = transaction_size(fee_payer, instructions, lookup_tables);
+        assert!(size <= PACKET_DATA_SIZE);
+        assert!(accounts <= MAX_TRANSACTION_ACCOUNTS);
+
+        // Filled up: the first instruction of the next transaction doesn't fit
+        if let Some(next) = transactions.get(index + 1) {
+            let mut instructions = instructions.clone();
+            instructions.push(next[0].clone());
+            let (size, accounts) = transaction_size(fee_payer, &instructions, lookup_tables);
+            assert!(size > PACKET_DATA_SIZE || accounts > MAX_TRANSACTION_ACCOUNTS);
+        }
+
+        for instruction in instructions {
+            assert_eq!(
+                instruction.data[..8],
+                TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR
+            );
+            let args =
+                TransferUncheckedLocalSharedRewardArgs::try_from_slice(&instruction.data[8..])
+                    .unwrap();
+            assert_eq!(args.shares_in_bps.len(), instruction.accounts.len() - 5);
+
+            for (meta, share) in instruction.accounts[5..].iter().zip(&args.shares_in_bps) {
+                *paid.entry(meta.pubkey).or_default() +=
+                    share_amount(args.total_amount, *share).unwrap();
+            }
+        }
+    }
+
+    let mut expected: HashMap<Pubkey, u64> = HashMap::new();
+    for (recipient, amount) in payouts {
+        *expected.entry(*recipient).or_default() += amount;
+    }
+    expected.retain(|_, amount| *amount > 0);
+
+    assert_eq!(paid, expected);
+}
+
+#[test]
+fn test_plan_legacy_and_v0() {
+    let authority = Pubkey::new_unique();
+    let payouts: Vec<(Pubkey, u64)> = amounts(120, 7, 2_000_000_000)
+        .into_iter()
+        .map(|x| (Pubkey::new_unique(), x))
+        .collect();
+
+    let legacy = SharedRewardBatcher::new(authority, SharedRewardAsset::Sol)
+        .plan(&payouts)
+        .unwrap();
+    assert_plan_pays(&legacy, &payouts, &[], &authority);
+
+    let lookup_tables = [AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: payouts
+            .iter()
+            .map(|x| x.0)
+            .chain([
+                buddy_link::constants::BL_PROGRAM_ID,
+                solana_program::system_program::ID,
+            ])
+            .collect(),
+    }];
+
+    let v0 = SharedRewardBatcher::new(authority, SharedRewardAsset::Sol)
+        .lookup_tables(&lookup_tables)
+        .plan(&payouts)
+        .unwrap();
+    assert_plan_pays(&v0, &payouts, &lookup_tables, &authority);
+
+    assert!(v0.len() < legacy.len());
+}
+
+#[test]
+fn test_plan_merges_recipients_and_sponsor() {
+    let authority = Pubkey::new_unique();
+    let sponsor = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let payouts = vec![
+        (recipient, 10),
+        (Pubkey::new_unique(), 30),
+        (recipient, 20),
+        (Pubkey::new_unique(), 0),
+    ];
+
+    let transactions = SharedRewardBatcher::new(
+        authority,
+        SharedRewardAsset::Spl {
+            mint: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+            from: Pubkey::new_unique(),
+        },
+    )
+    .fee_payer(sponsor)
+    .plan(&payouts)
+    .unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].len(), 1);
+    assert_plan_pays(&transactions, &payouts, &[], &sponsor);
+}
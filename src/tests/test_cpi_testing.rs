@@ -0,0 +1,476 @@
+use anchor_lang::context::CpiContext;
+use base64::Engine;
+use borsh::BorshDeserialize;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::cpi::testing::{Payout, RecordedCall, RecordingBuddyLinkCpi};
+use buddy_link::cpi::{
+    BuddyLinkCpi, PayReferralReward, TransferCheckedGlobalOnlyRewardSol,
+    TransferCheckedGlobalReward, TransferRewardUncheckedMultipleSol,
+    TransferRewardUncheckedMultipleSpl, ValidateReferrer,
+};
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    shared_reward_amounts, transfer_multi_tier_reward, MultiTierRewardArgs, RewardVariant,
+    RewardVariantReason, SharedRewardAsset, TierRecipient, TransferUncheckedLocalSharedRewardArgs,
+    UnclaimedTiers,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+fn account(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        false,
+        true,
+        Box::leak(Box::new(0)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+fn empty(key: Pubkey) -> AccountInfo<'static> {
+    account(key, Pubkey::default(), vec![])
+}
+
+fn fixture(key: Pubkey) -> AccountInfo<'static> {
+    let path = format!(
+        "{}/.amman/accounts/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        key
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+    let owner = json["account"]["owner"].as_str().unwrap().parse().unwrap();
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(json["account"]["data"][0].as_str().unwrap())
+        .unwrap();
+
+    account(key, owner, data)
+}
+
+/// Business logic of an integrator: 1% of the volume to the global referrer of the user.
+fn pay_volume_fee(
+    cpi: &impl BuddyLinkCpi,
+    accounts: TransferCheckedGlobalOnlyRewardSol<'static>,
+    volume: u64,
+) -> Result<(), ProgramError> {
+    let fee = volume / 100;
+    if fee == 0 {
+        return Ok(());
+    }
+
+    cpi.transfer_checked_global_only_reward_sol(
+        CpiContext::new(accounts.buddy_link_program.clone(), accounts),
+        fee,
+        &[&[b"vault", &[255]]],
+    )
+}
+
+#[test]
+fn test_record_transfers() {
+    let cpi = RecordingBuddyLinkCpi::new();
+    let authority = empty(Pubkey::new_unique());
+    let global_only = || TransferCheckedGlobalOnlyRewardSol {
+        buddy_link_program: empty(BL_PROGRAM_ID),
+        authority: authority.clone(),
+        system_program: empty(solana_program::system_program::ID),
+        global_referrer_treasury: empty(REFERRER_TREASURY),
+        global_referrer_treasury_for_reward: empty(REFERRER_TREASURY),
+        referee_buddy_profile: empty(REFEREE_GLOBAL_BUDDY),
+        referee_buddy: empty(REFEREE_GLOBAL_BUDDY),
+    };
+
+    pay_volume_fee(&cpi, global_only(), 250_000).unwrap();
+    pay_volume_fee(&cpi, global_only(), 99).unwrap();
+
+    let transfers = cpi.transfers();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].variant, RewardVariant::CheckedGlobalOnly);
+    assert_eq!(transfers[0].authority, *authority.key);
+    assert_eq!(transfers[0].mint, None);
+    assert_eq!(
+        transfers[0].payouts,
+        [Payout {
+            recipient: REFERRER_TREASURY,
+            amount: 2_500
+        }]
+    );
+    assert_eq!(transfers[0].accounts[1], *authority.key);
+    assert_eq!(transfers[0].signer_seeds, [[b"vault".to_vec(), vec![255]]]);
+
+    // BuddyLink fails, nothing is recorded
+    cpi.fail_next(BuddyLinkError::InvalidReferrerProvidedForBuddy);
+    assert_eq!(
+        pay_volume_fee(&cpi, global_only(), 250_000),
+        Err(BuddyLinkError::InvalidReferrerProvidedForBuddy.into())
+    );
+    assert_eq!(cpi.transfers().len(), 1);
+
+    let from = empty(Pubkey::new_unique());
+    let checked_global = TransferCheckedGlobalReward {
+        buddy_link_program: empty(BL_PROGRAM_ID),
+        authority: authority.clone(),
+        mint: empty(MINT),
+        token_program: empty(anchor_spl::token::ID),
+        from_token_account: from.clone(),
+        referrer_token_account: empty(REFERRER_ATA),
+        referrer_member: Some(empty(REFERRER_MEMBER)),
+        referrer_treasury: empty(REFERRER_TREASURY),
+        referrer_treasury_for_reward: empty(REFERRER_TREASURY),
+        referee_member: empty(REFEREE_MEMBER),
+        buddy_global_referrer_treasury: None,
+        buddy_global_referrer_token_account: None,
+    };
+    cpi.transfer_checked_global_reward(
+        CpiContext::new(empty(BL_PROGRAM_ID), checked_global),
+        10,
+        &[],
+    )
+    .unwrap();
+
+    let transfer = &cpi.transfers()[1];
+    assert_eq!(transfer.variant, RewardVariant::CheckedGlobal);
+    assert_eq!(transfer.mint, Some(MINT));
+    assert_eq!(transfer.from_token_account, Some(*from.key));
+    assert_eq!(cpi.total_paid(&REFERRER_ATA), 10);
+    assert_eq!(cpi.total_paid(&REFERRER_TREASURY), 2_500);
+
+    cpi.validate_referrer(CpiContext::new(
+        empty(BL_PROGRAM_ID),
+        ValidateReferrer {
+            buddy_link_program: empty(BL_PROGRAM_ID),
+            payer: authority.clone(),
+            authority: authority.clone(),
+            referee_buddy_profile: empty(REFEREE_GLOBAL_BUDDY),
+            referee_buddy: empty(REFEREE_GLOBAL_BUDDY),
+            referee_treasury: empty(REFEREE_TREASURY),
+            referee_member: empty(REFEREE_MEMBER),
+            referrer_member: Some(empty(REFERRER_MEMBER)),
+            referrer_treasury: Some(empty(REFERRER_TREASURY)),
+            referrer_treasury_for_reward: Some(empty(REFERRER_TREASURY)),
+            referrer_token_account: None,
+            mint: None,
+        },
+    ))
+    .unwrap();
+
+    let validations = cpi.validations();
+    assert_eq!(validations.len(), 1);
+    assert_eq!(validations[0].referee_member, REFEREE_MEMBER);
+    assert_eq!(validations[0].referrer_treasury, Some(REFERRER_TREASURY));
+    assert_eq!(validations[0].mint, None);
+    assert!(matches!(cpi.calls()[2], RecordedCall::Validation(_)));
+
+    // The referee member was referred, the global referrer gets 2.5% first
+    let global_referrer_ata = Pubkey::new_unique();
+    cpi.transfer_checked_global_reward(
+        CpiContext::new(
+            empty(BL_PROGRAM_ID),
+            TransferCheckedGlobalReward {
+                buddy_link_program: empty(BL_PROGRAM_ID),
+                authority: authority.clone(),
+                mint: empty(MINT),
+                token_program: empty(anchor_spl::token::ID),
+                from_token_account: from.clone(),
+                referrer_token_account: empty(REFERRER_ATA),
+                referrer_member: Some(empty(REFERRER_MEMBER)),
+                referrer_treasury: empty(REFERRER_TREASURY),
+                referrer_treasury_for_reward: empty(REFERRER_TREASURY),
+                referee_member: fixture(REFEREE_MEMBER),
+                buddy_global_referrer_treasury: Some(empty(REFERRER_TREASURY)),
+                buddy_global_referrer_token_account: Some(empty(global_referrer_ata)),
+            },
+        ),
+        999,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        cpi.transfers()[2].payouts,
+        [
+            Payout {
+                recipient: global_referrer_ata,
+                amount: 24,
+            },
+            Payout {
+                recipient: REFERRER_ATA,
+                amount: 975,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_record_shared_rewards() {
+    let cpi = RecordingBuddyLinkCpi::new();
+    let authority = empty(Pubkey::new_unique());
+    let sol = || {
+        CpiContext::new(
+            empty(BL_PROGRAM_ID),
+            TransferRewardUncheckedMultipleSol {
+                buddy_link_program: empty(BL_PROGRAM_ID),
+                authority: authority.clone(),
+                system_program: empty(solana_program::system_program::ID),
+            },
+        )
+    };
+    let recipients = vec![
+        empty(REFERRER_TREASURY),
+        empty(REFERRER_MEMBER),
+        empty(REFEREE_TREASURY),
+        empty(REFEREE_MEMBER),
+    ];
+
+    cpi.transfer_unchecked_local_shared_reward_sol(
+        sol().with_remaining_accounts(recipients.clone()),
+        10_000,
+        vec![7_500, 2_500],
+        true,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        cpi.transfers()[0].payouts,
+        [
+            Payout {
+                recipient: REFERRER_TREASURY,
+                amount: 7_500
+            },
+            Payout {
+                recipient: REFEREE_TREASURY,
+                amount: 2_500
+            }
+        ]
+    );
+    assert_eq!(cpi.transfers()[0].accounts.len(), 3 + 4);
+
+    let result = cpi.transfer_unchecked_local_shared_reward_sol(
+        sol().with_remaining_accounts(recipients.clone()),
+        10_000,
+        vec![7_500, 2_000],
+        true,
+        &[],
+    );
+    assert_eq!(result, Err(BuddyLinkError::InvalidBPSProvided.into()));
+    let result = cpi.transfer_unchecked_local_shared_reward_sol(
+        sol().with_remaining_accounts(recipients.clone()),
+        10_000,
+        vec![10_000],
+        true,
+        &[],
+    );
+    assert_eq!(
+        result,
+        Err(BuddyLinkError::InvalidNumberOfSharesSpecified.into())
+    );
+    assert_eq!(cpi.transfers().len(), 1);
+
+    // One referrer, the unclaimed tiers go to the fallback
+    cpi.clear();
+    cpi.transfer_multi_tier_reward_spl(
+        CpiContext::new(
+            empty(BL_PROGRAM_ID),
+            TransferRewardUncheckedMultipleSpl {
+                buddy_link_program: empty(BL_PROGRAM_ID),
+                authority: authority.clone(),
+                mint: empty(MINT),
+                token_program: empty(anchor_spl::token::ID),
+                from_token_account: empty(Pubkey::new_unique()),
+            },
+        )
+        .with_remaining_accounts(recipients),
+        1_000,
+        vec![5_000, 3_000, 2_000],
+        UnclaimedTiers::Fallback(TierRecipient {
+            recipient: REFEREE_TREASURY,
+            member: Some(REFEREE_MEMBER),
+        }),
+        true,
+        &[],
+    )
+    .unwrap();
+
+    let transfer = &cpi.transfers()[0];
+    assert_eq!(transfer.variant, RewardVariant::UncheckedLocalShared);
+    assert_eq!(transfer.mint, Some(MINT));
+    assert_eq!(cpi.total_paid(&REFERRER_TREASURY), 500);
+    assert_eq!(cpi.total_paid(&REFEREE_TREASURY), 500);
+}
+
+// A referrer without a recipient in the middle of the chain, paid the same as the off-chain builder
+#[test]
+fn test_multi_tier_missing_middle_tier() {
+    let authority = empty(Pubkey::new_unique());
+    let tiers = [REFERRER_TREASURY, REFEREE_TREASURY];
+    let fallback = TierRecipient {
+        recipient: Pubkey::new_unique(),
+        member: None,
+    };
+
+    for unclaimed in [
+        UnclaimedTiers::Redistribute,
+        UnclaimedTiers::Fallback(fallback),
+    ] {
+        let transfer_args = MultiTierRewardArgs {
+            total_amount: 1_000,
+            schedule: vec![5_000, 3_000, 2_000],
+            unclaimed,
+        };
+
+        let instruction = transfer_multi_tier_reward(
+            *authority.key,
+            &SharedRewardAsset::Sol,
+            &[
+                Some(TierRecipient {
+                    recipient: tiers[0],
+                    member: None,
+                }),
+                None,
+                Some(TierRecipient {
+                    recipient: tiers[1],
+                    member: None,
+                }),
+            ],
+            &transfer_args,
+        )
+        .unwrap();
+        let args =
+            TransferUncheckedLocalSharedRewardArgs::try_from_slice(&instruction.data[8..]).unwrap();
+        let expected: Vec<Payout> = instruction.accounts[5..]
+            .iter()
+            .zip(shared_reward_amounts(args.total_amount, &args.shares_in_bps).unwrap())
+            .map(|(meta, amount)| Payout {
+                recipient: meta.pubkey,
+                amount,
+            })
+            .collect();
+
+        let mut remaining_accounts = vec![empty(tiers[0]), empty(BL_PROGRAM_ID), empty(tiers[1])];
+        if let UnclaimedTiers::Fallback(fallback) = unclaimed {
+            remaining_accounts.push(empty(fallback.recipient));
+        }
+
+        let cpi = RecordingBuddyLinkCpi::new();
+        cpi.transfer_multi_tier_reward_sol(
+            CpiContext::new(
+                empty(BL_PROGRAM_ID),
+                TransferRewardUncheckedMultipleSol {
+                    buddy_link_program: empty(BL_PROGRAM_ID),
+                    authority: authority.clone(),
+                    system_program: empty(solana_program::system_program::ID),
+                },
+            )
+            .with_remaining_accounts(remaining_accounts),
+            transfer_args.total_amount,
+            transfer_args.schedule,
+            unclaimed,
+            false,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(cpi.transfers()[0].payouts, expected);
+        // The third tier keeps its share of the schedule
+        match unclaimed {
+            UnclaimedTiers::Redistribute => {
+                assert_eq!(cpi.total_paid(&tiers[0]), 714);
+                assert_eq!(cpi.total_paid(&tiers[1]), 285);
+            }
+            UnclaimedTiers::Fallback(fallback) => {
+                assert_eq!(cpi.total_paid(&tiers[0]), 500);
+                assert_eq!(cpi.total_paid(&tiers[1]), 200);
+                assert_eq!(cpi.total_paid(&fallback.recipient), 300);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_record_pay_referral_reward() {
+    let cpi = RecordingBuddyLinkCpi::new();
+    let authority = empty(Pubkey::new_unique());
+    let accounts = || PayReferralReward {
+        buddy_link_program: empty(BL_PROGRAM_ID),
+        authority: authority.clone(),
+        system_program: Some(empty(solana_program::system_program::ID)),
+        mint: None,
+        token_program: None,
+        from_token_account: None,
+        referee_buddy_profile: fixture(REFEREE_GLOBAL_BUDDY),
+        referee_buddy: fixture(REFEREE_GLOBAL_BUDDY),
+        referee_treasury: None,
+        referee_member: None,
+        referrer_member: None,
+        referrer_treasury: None,
+        referrer_treasury_for_reward: None,
+        referrer_token_account: None,
+        global_referrer_treasury: Some(empty(REFERRER_TREASURY)),
+        global_referrer_treasury_for_reward: Some(empty(REFERRER_TREASURY)),
+        global_referrer_token_account: None,
+    };
+
+    let (variant, reason) = cpi
+        .pay_referral_reward(
+            CpiContext::new(empty(BL_PROGRAM_ID), accounts()),
+            1_000,
+            &[],
+        )
+        .unwrap();
+    assert_eq!(variant, RewardVariant::CheckedGlobalOnly);
+    assert!(matches!(reason, RewardVariantReason::GlobalReferrer(_)));
+    assert_eq!(cpi.total_paid(&REFERRER_TREASURY), 1_000);
+
+    // The referrer within the organization, in tokens
+    let (variant, _) = cpi
+        .pay_referral_reward(
+            CpiContext::new(
+                empty(BL_PROGRAM_ID),
+                PayReferralReward {
+                    system_program: None,
+                    mint: Some(empty(MINT)),
+                    token_program: Some(empty(anchor_spl::token::ID)),
+                    from_token_account: Some(empty(Pubkey::new_unique())),
+                    referee_treasury: Some(fixture(REFEREE_TREASURY)),
+                    referee_member: Some(fixture(REFEREE_MEMBER)),
+                    referrer_member: Some(empty(REFERRER_MEMBER)),
+                    referrer_treasury: Some(empty(REFERRER_TREASURY)),
+                    referrer_treasury_for_reward: Some(empty(REFERRER_TREASURY)),
+                    referrer_token_account: Some(empty(REFERRER_ATA)),
+                    ..accounts()
+                },
+            ),
+            10,
+            &[],
+        )
+        .unwrap();
+    assert_eq!(variant, RewardVariant::CheckedGlobal);
+    assert_eq!(cpi.total_paid(&REFERRER_ATA), 10);
+
+    // Nobody to pay
+    let result = cpi.pay_referral_reward(
+        CpiContext::new(
+            empty(BL_PROGRAM_ID),
+            PayReferralReward {
+                global_referrer_treasury: Some(empty(REFEREE_TREASURY)),
+                ..accounts()
+            },
+        ),
+        10,
+        &[],
+    );
+    assert_eq!(result, Err(BuddyLinkError::InvalidReferrerTreasury.into()));
+}
@@ -0,0 +1,156 @@
+mod fixture_sender;
+
+use buddy_link::client::discovery::{Discovery, MAX_TREASURY_OWNERS};
+use fixture_sender::{client, FixtureSender};
+use solana_client::rpc_request::RpcRequest;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_AUTHORITY: Pubkey = pubkey!("DK1FtDDy2RkydDuhprUNKmsyVv8JQb5YDrUZe3GB8ZFc");
+const REFERRER_GLOBAL_BUDDY: Pubkey = pubkey!("4jHbHkwjJoZgDBsx774LAmmqxPuGwk65SVdV6yr5Xjsm");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_AUTHORITY: Pubkey = pubkey!("HFnGHHTEKdggiHVFYEs1VAKKmjPvoD31HQsApkZqHqEx");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+fn sorted(mut keys: Vec<Pubkey>) -> Vec<Pubkey> {
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_referees() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let discovery = Discovery::new(&client);
+
+    let referees = discovery
+        .referees(&REFERRER_TREASURY)
+        .unwrap()
+        .all()
+        .unwrap();
+    assert_eq!(referees.len(), 1);
+    assert_eq!(referees[0].0, REFEREE_MEMBER);
+    assert_eq!(referees[0].1.owner_treasury(), REFEREE_TREASURY);
+
+    let referees = discovery
+        .global_referees(&REFERRER_TREASURY)
+        .unwrap()
+        .all()
+        .unwrap();
+    assert_eq!(referees.len(), 1);
+    assert_eq!(referees[0].0, REFEREE_GLOBAL_BUDDY);
+    assert_eq!(referees[0].1.authority(), REFEREE_AUTHORITY);
+
+    assert!(discovery
+        .referees(&REFEREE_TREASURY)
+        .unwrap()
+        .keys()
+        .is_empty());
+}
+
+#[test]
+fn test_organization_members() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let discovery = Discovery::new(&client);
+
+    let members = discovery
+        .organization_members("goose")
+        .unwrap()
+        .all()
+        .unwrap();
+    assert_eq!(
+        sorted(members.iter().map(|x| x.0).collect()),
+        sorted(vec![REFERRER_MEMBER, REFEREE_MEMBER])
+    );
+
+    assert!(discovery
+        .organization_members("gooses")
+        .unwrap()
+        .all()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_wallet_and_mint_treasuries() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let discovery = Discovery::new(&client);
+
+    let buddies = discovery.buddies(&REFERRER_AUTHORITY).unwrap();
+    assert_eq!(buddies.keys(), [REFERRER_GLOBAL_BUDDY]);
+
+    let treasuries = discovery
+        .wallet_treasuries(&REFERRER_AUTHORITY)
+        .unwrap()
+        .all()
+        .unwrap();
+    assert_eq!(treasuries.len(), 1);
+    assert_eq!(treasuries[0].0, REFERRER_TREASURY);
+    assert!(treasuries[0].1.is_owned_by(&REFERRER_GLOBAL_BUDDY));
+
+    let treasuries = discovery.wallet_treasuries(&REFEREE_AUTHORITY).unwrap();
+    assert_eq!(treasuries.keys(), [REFEREE_TREASURY]);
+
+    let treasuries = discovery.mint_treasuries(&MINT).unwrap();
+    assert_eq!(
+        treasuries.keys(),
+        sorted(vec![REFERRER_TREASURY, REFEREE_TREASURY])
+    );
+    assert!(discovery
+        .mint_treasuries(&Pubkey::new_unique())
+        .unwrap()
+        .keys()
+        .is_empty());
+}
+
+#[test]
+fn test_pagination() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+
+    let requests = sender.requests(RpcRequest::GetProgramAccounts);
+    let pages: Vec<_> = Discovery::new(&client)
+        .page_size(1)
+        .max_treasury_owners(2)
+        .wallet_treasuries(&REFERRER_AUTHORITY)
+        .unwrap()
+        .collect();
+
+    // One query for the buddies, one per owner position
+    assert_eq!(
+        sender.requests(RpcRequest::GetProgramAccounts),
+        requests + 3
+    );
+    assert_eq!(pages.len(), 1);
+
+    let requests = sender.requests(RpcRequest::GetMultipleAccounts);
+    let pages: Vec<_> = Discovery::new(&client)
+        .page_size(1)
+        .mint_treasuries(&MINT)
+        .unwrap()
+        .map(|page| page.unwrap())
+        .collect();
+
+    assert_eq!(pages.len(), 2);
+    assert!(pages.iter().all(|page| page.len() == 1));
+    assert_eq!(
+        sender.requests(RpcRequest::GetMultipleAccounts),
+        requests + 2
+    );
+
+    let requests = sender.requests(RpcRequest::GetProgramAccounts);
+    Discovery::new(&client)
+        .wallet_treasuries(&REFEREE_AUTHORITY)
+        .unwrap();
+    assert_eq!(
+        sender.requests(RpcRequest::GetProgramAccounts),
+        requests + 1 + MAX_TREASURY_OWNERS
+    );
+}
@@ -0,0 +1,70 @@
+use borsh::BorshSerialize;
+use buddy_link::constants::{
+    TRANSFER_REWARD_GLOBAL_DISCRIMINATOR, TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR,
+    TRANSFER_REWARD_SPL_DISCRIMINATOR, TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR,
+    VALIDATE_REFERRER_DISCRIMINATOR,
+};
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, transfer_unchecked_local_shared_reward,
+    GeneralTransferRewardArgs, RewardAsset, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs,
+};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+
+fn anchor_discriminator(instruction_name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", instruction_name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+#[test]
+fn test_discriminators_match_hash() {
+    assert_eq!(VALIDATE_REFERRER_DISCRIMINATOR, anchor_discriminator("validate_referrer"));
+    assert_eq!(
+        TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR,
+        anchor_discriminator("transfer_reward_unchecked_multiple")
+    );
+    assert_eq!(
+        TRANSFER_REWARD_SECURE_NO_GLOBAL_DISCRIMINATOR,
+        anchor_discriminator("transfer_reward_secure_no_global")
+    );
+    assert_eq!(TRANSFER_REWARD_SPL_DISCRIMINATOR, anchor_discriminator("transfer_reward_spl"));
+    assert_eq!(TRANSFER_REWARD_GLOBAL_DISCRIMINATOR, anchor_discriminator("transfer_reward_global"));
+}
+
+#[test]
+fn test_instruction_data_matches_borsh() {
+    let transfer_args = GeneralTransferRewardArgs { amount: 1_234_567_890 };
+
+    let instruction = transfer_checked_global_only_reward(
+        Pubkey::new_unique(),
+        &RewardAsset::Sol,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &transfer_args,
+    );
+
+    let mut expected = anchor_discriminator("transfer_reward_global").to_vec();
+    expected.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    assert_eq!(instruction.data, expected);
+
+    let transfer_args = TransferUncheckedLocalSharedRewardArgs {
+        total_amount: 10_000,
+        shares_in_bps: vec![5_000, 3_000, 2_000],
+        members_included: true,
+    };
+
+    let instruction = transfer_unchecked_local_shared_reward(
+        Pubkey::new_unique(),
+        &SharedRewardAsset::Sol,
+        &[Pubkey::new_unique(), Pubkey::new_unique()],
+        &transfer_args,
+    );
+
+    let mut expected = anchor_discriminator("transfer_reward_unchecked_multiple").to_vec();
+    expected.extend_from_slice(&transfer_args.try_to_vec().unwrap());
+    assert_eq!(instruction.data, expected);
+}
@@ -0,0 +1,115 @@
+mod fixture_sender;
+
+use buddy_link::policy::{
+    Eligibility, EligibilityWindow, JoinedAt, Referee, ReferralEligibility, SECONDS_PER_DAY,
+};
+use buddy_link::state::{Buddy, Member};
+use fixture_sender::FixtureSender;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+/// Creation of the referee member.
+const MEMBER_CREATED_AT: i64 = 1_690_463_390;
+/// Creation of the referee buddy.
+const BUDDY_CREATED_AT: i64 = 1_687_026_902;
+
+#[test]
+fn test_time_window() {
+    let fixtures = FixtureSender::new();
+    let member_data = fixtures.data(&REFEREE_MEMBER);
+    let buddy_data = fixtures.data(&REFEREE_GLOBAL_BUDDY);
+    let referee = Referee {
+        member: Some(&Member::new(member_data.as_slice()).unwrap()),
+        buddy: Some(&Buddy::new(buddy_data.as_slice()).unwrap()),
+        transactions: None,
+    };
+    let window = EligibilityWindow::days(90);
+    assert_eq!(window, EligibilityWindow::Seconds(90 * SECONDS_PER_DAY));
+
+    let eligibility = ReferralEligibility::new().window(window);
+    let day_89 = MEMBER_CREATED_AT + 89 * SECONDS_PER_DAY;
+    let day_90 = MEMBER_CREATED_AT + 90 * SECONDS_PER_DAY;
+    assert_eq!(
+        eligibility.check(&referee, day_89),
+        Ok(Eligibility::Eligible)
+    );
+    assert_eq!(
+        eligibility.check(&referee, day_90),
+        Ok(Eligibility::Expired(window))
+    );
+    // Clock behind the creation
+    assert!(eligibility
+        .check(&referee, MEMBER_CREATED_AT - 1)
+        .unwrap()
+        .is_eligible());
+
+    // From the creation of the buddy, joined BuddyLink earlier
+    let from_buddy = eligibility.clone().joined_at(JoinedAt::Buddy);
+    assert_eq!(
+        from_buddy.check(&referee, BUDDY_CREATED_AT + 89 * SECONDS_PER_DAY),
+        Ok(Eligibility::Eligible)
+    );
+    assert_eq!(
+        from_buddy.check(&referee, day_89),
+        Ok(Eligibility::Expired(window))
+    );
+
+    // The account the window starts from is required
+    let without_member = Referee {
+        member: None,
+        ..referee
+    };
+    assert_eq!(
+        eligibility.check(&without_member, day_89),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+    assert!(from_buddy.check(&without_member, BUDDY_CREATED_AT).is_ok());
+}
+
+#[test]
+fn test_transactions_window() {
+    let member_data = FixtureSender::new().data(&REFEREE_MEMBER);
+    let member = Member::new(member_data.as_slice()).unwrap();
+    let referee = |transactions| Referee::<_, &[u8]> {
+        member: Some(&member),
+        buddy: None,
+        transactions,
+    };
+    let eligibility = ReferralEligibility::new()
+        .window(EligibilityWindow::days(90))
+        .window(EligibilityWindow::Transactions(10));
+    let now = MEMBER_CREATED_AT + SECONDS_PER_DAY;
+
+    assert_eq!(
+        eligibility.check(&referee(Some(10)), now),
+        Ok(Eligibility::Eligible)
+    );
+    assert_eq!(
+        eligibility.check(&referee(Some(11)), now),
+        Ok(Eligibility::Expired(EligibilityWindow::Transactions(10)))
+    );
+    // The first window over is reported
+    assert_eq!(
+        eligibility.check(&referee(Some(11)), now + 90 * SECONDS_PER_DAY),
+        Ok(Eligibility::Expired(EligibilityWindow::days(90)))
+    );
+    assert_eq!(
+        eligibility.check(&referee(None), now),
+        Err(ProgramError::InvalidArgument)
+    );
+
+    // Without window, always eligible
+    let none = Referee::<&[u8], &[u8]> {
+        member: None,
+        buddy: None,
+        transactions: None,
+    };
+    assert_eq!(
+        ReferralEligibility::new().check(&none, i64::MAX),
+        Ok(Eligibility::Eligible)
+    );
+}
@@ -0,0 +1,270 @@
+use borsh::BorshDeserialize;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    choose_reward_variant, multi_tier_shares, transfer_checked_global_only_reward,
+    transfer_multi_tier_reward, transfer_unchecked_local_shared_reward, GeneralTransferRewardArgs,
+    LocalReferrer, MultiTierRewardArgs, RewardAsset, RewardRoute, RewardVariant,
+    RewardVariantReason, SharedRewardAsset, TierRecipient, TransferUncheckedLocalSharedRewardArgs,
+    UnclaimedTiers,
+};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+
+#[test]
+fn test_global_only_reward_asset_accounts() {
+    let authority = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_program = Pubkey::new_unique();
+    let from = Pubkey::new_unique();
+    let to = Pubkey::new_unique();
+
+    let build = |asset: &RewardAsset| {
+        transfer_checked_global_only_reward(
+            authority,
+            asset,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &GeneralTransferRewardArgs { amount: 1 },
+        )
+    };
+
+    let sol = build(&RewardAsset::Sol);
+    assert_eq!(
+        sol.accounts[5..],
+        [
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+        ]
+    );
+
+    let spl = build(&RewardAsset::Spl {
+        mint,
+        token_program,
+        from,
+        to,
+    });
+    assert_eq!(
+        spl.accounts[5..],
+        [
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new(to, false),
+            AccountMeta::new(from, false),
+        ]
+    );
+}
+
+#[test]
+fn test_shared_reward_asset_accounts() {
+    let authority = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_program = Pubkey::new_unique();
+    let from = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+
+    let transfer_args = TransferUncheckedLocalSharedRewardArgs {
+        total_amount: 100,
+        shares_in_bps: vec![10_000],
+        members_included: false,
+    };
+
+    let spl = transfer_unchecked_local_shared_reward(
+        authority,
+        &SharedRewardAsset::Spl {
+            mint,
+            token_program,
+            from,
+        },
+        &[referrer],
+        &transfer_args,
+    );
+
+    assert_eq!(
+        spl.accounts,
+        [
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new(from, false),
+            AccountMeta::new(referrer, false),
+        ]
+    );
+}
+
+#[test]
+fn test_multi_tier_shares() {
+    let schedule = [5_000, 3_000, 2_000];
+    let fallback = UnclaimedTiers::Fallback(TierRecipient {
+        recipient: Pubkey::new_unique(),
+        member: None,
+    });
+
+    // Extra referrers are ignored
+    assert_eq!(
+        multi_tier_shares(&schedule, &[true; 4], &UnclaimedTiers::Redistribute),
+        Ok(vec![5_000, 3_000, 2_000])
+    );
+    assert_eq!(
+        multi_tier_shares(&schedule, &[true, false, true], &fallback),
+        Ok(vec![5_000, 2_000, 3_000])
+    );
+    assert_eq!(
+        multi_tier_shares(&schedule, &[true, true], &UnclaimedTiers::Redistribute),
+        Ok(vec![6_250, 3_750])
+    );
+    // Rounding leftover to the direct referrer
+    assert_eq!(
+        multi_tier_shares(
+            &[3_333, 3_333, 3_334],
+            &[true, true],
+            &UnclaimedTiers::Redistribute
+        ),
+        Ok(vec![5_000, 5_000])
+    );
+    assert_eq!(
+        multi_tier_shares(
+            &[4_000, 4_000, 2_000],
+            &[false, true, true],
+            &UnclaimedTiers::Redistribute
+        ),
+        Ok(vec![6_667, 3_333])
+    );
+
+    assert_eq!(
+        multi_tier_shares(&schedule, &[], &UnclaimedTiers::Redistribute),
+        Err(BuddyLinkError::InvalidNumberOfSharesSpecified.into())
+    );
+    assert_eq!(
+        multi_tier_shares(&schedule, &[], &fallback),
+        Ok(vec![10_000])
+    );
+    assert_eq!(
+        multi_tier_shares(&[5_000, 3_000], &[true], &fallback),
+        Err(BuddyLinkError::InvalidBPSProvided.into())
+    );
+}
+
+#[test]
+fn test_multi_tier_reward_accounts() {
+    let authority = Pubkey::new_unique();
+    let tiers: Vec<TierRecipient> = (0..2)
+        .map(|_| TierRecipient {
+            recipient: Pubkey::new_unique(),
+            member: Some(Pubkey::new_unique()),
+        })
+        .collect();
+    let fallback = TierRecipient {
+        recipient: Pubkey::new_unique(),
+        member: None,
+    };
+
+    let build = |referrers: &[Option<TierRecipient>], unclaimed| {
+        let instruction = transfer_multi_tier_reward(
+            authority,
+            &SharedRewardAsset::Sol,
+            referrers,
+            &MultiTierRewardArgs {
+                total_amount: 100,
+                schedule: vec![6_000, 4_000],
+                unclaimed,
+            },
+        )
+        .unwrap();
+        let args =
+            TransferUncheckedLocalSharedRewardArgs::try_from_slice(&instruction.data[8..]).unwrap();
+        let remaining: Vec<Pubkey> = instruction.accounts[5..].iter().map(|x| x.pubkey).collect();
+
+        (args, remaining)
+    };
+
+    // Members are paired with the recipients
+    let (args, remaining) = build(
+        &[Some(tiers[0]), Some(tiers[1])],
+        UnclaimedTiers::Fallback(fallback),
+    );
+    assert_eq!(args.shares_in_bps, [6_000, 4_000]);
+    assert!(args.members_included);
+    assert_eq!(
+        remaining,
+        [
+            tiers[0].recipient,
+            tiers[0].member.unwrap(),
+            tiers[1].recipient,
+            tiers[1].member.unwrap()
+        ]
+    );
+
+    // The fallback has no member, none are included
+    let (args, remaining) = build(&[None, Some(tiers[1])], UnclaimedTiers::Fallback(fallback));
+    assert_eq!(args.shares_in_bps, [4_000, 6_000]);
+    assert!(!args.members_included);
+    assert_eq!(remaining, [tiers[1].recipient, fallback.recipient]);
+}
+
+#[test]
+fn test_choose_reward_variant() {
+    let route = RewardRoute {
+        local_referrer: LocalReferrer::Resolved,
+        global_referrer: true,
+        referee_signed: false,
+        sol: false,
+    };
+
+    assert_eq!(
+        choose_reward_variant(&route),
+        Some((
+            RewardVariant::CheckedGlobal,
+            RewardVariantReason::OrganizationReferrer {
+                global_referrer: true
+            }
+        ))
+    );
+    assert_eq!(
+        choose_reward_variant(&RewardRoute {
+            referee_signed: true,
+            ..route
+        }),
+        Some((
+            RewardVariant::SecureLocal,
+            RewardVariantReason::RefereeSigned
+        ))
+    );
+    assert_eq!(
+        choose_reward_variant(&RewardRoute {
+            referee_signed: true,
+            sol: true,
+            ..route
+        }),
+        Some((
+            RewardVariant::UncheckedLocalShared,
+            RewardVariantReason::SolOrganizationReferrer
+        ))
+    );
+    assert_eq!(
+        choose_reward_variant(&RewardRoute {
+            local_referrer: LocalReferrer::CantReceive,
+            sol: true,
+            ..route
+        }),
+        Some((
+            RewardVariant::CheckedGlobalOnly,
+            RewardVariantReason::GlobalReferrer(LocalReferrer::CantReceive)
+        ))
+    );
+    assert_eq!(
+        choose_reward_variant(&RewardRoute {
+            local_referrer: LocalReferrer::NoOrganization,
+            global_referrer: false,
+            ..route
+        }),
+        None
+    );
+}
@@ -0,0 +1,245 @@
+mod fixture_sender;
+
+use buddy_link::client::journal::{
+    FileJournalStore, JobId, JournalStore, MemoryJournalStore, PayoutJournal, PayoutState,
+};
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::client::Error;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, GeneralTransferRewardArgs, RewardAsset,
+};
+use fixture_sender::{client, FixtureSender};
+use serde_json::json;
+use solana_program::hash::Hash;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use std::io::Write;
+
+fn reward_builder(authority: &Keypair, amount: u64) -> TransactionBuilder<'static> {
+    TransactionBuilder::new(authority.pubkey())
+        .instruction(transfer_checked_global_only_reward(
+            authority.pubkey(),
+            &RewardAsset::Sol,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &GeneralTransferRewardArgs { amount },
+        ))
+        .compute_unit_limit(30_000)
+}
+
+#[test]
+fn test_job_id_regrouped_parts() {
+    // The same bytes split into other parts are other payouts
+    assert_ne!(JobId::new(&[b"ab", b"c"]), JobId::new(&[b"a", b"bc"]));
+    assert_ne!(JobId::new(&[b"abc"]), JobId::new(&[b"ab", b"c"]));
+    assert_ne!(JobId::new(&[b"a", b""]), JobId::new(&[b"a"]));
+    assert_ne!(JobId::new(&[]), JobId::new(&[b""]));
+}
+
+#[test]
+fn test_pay_once() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let builder = reward_builder(&authority, 1_000);
+
+    let job = JobId::new(&[b"trade", &7u64.to_le_bytes()]);
+    assert_eq!(job, JobId::new(&[b"trade", &7u64.to_le_bytes()]));
+    assert_ne!(job, JobId::new(&[b"trade", &8u64.to_le_bytes()]));
+
+    let mut journal = PayoutJournal::open(&client, MemoryJournalStore::default()).unwrap();
+    assert_eq!(
+        journal.pay(job, &builder, &[&authority]).unwrap(),
+        PayoutState::Pending
+    );
+    assert_eq!(sender.sent().len(), 1);
+
+    let entry = journal.entry(&job).unwrap();
+    assert_eq!(entry.attempts, 1);
+    assert_eq!(entry.last_valid_block_height, 150);
+    assert_eq!(entry.instructions.len(), 1);
+    assert_eq!(entry.signature(), sender.sent()[0].signatures[0]);
+
+    // Landed: paying the job again only settles it
+    for _ in 0..2 {
+        assert_eq!(
+            journal.pay(job, &builder, &[&authority]).unwrap(),
+            PayoutState::Paid { slot: 1 }
+        );
+    }
+    assert_eq!(sender.sent().len(), 1);
+}
+
+#[test]
+fn test_rebroadcast_until_expired() {
+    let sender = FixtureSender::new();
+    sender.drop_transactions(true);
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let job = JobId::new(&[b"epoch", &[1]]);
+
+    let mut journal = PayoutJournal::open(&client, MemoryJournalStore::default()).unwrap();
+    journal
+        .pay(job, &reward_builder(&authority, 1_000), &[&authority])
+        .unwrap();
+    let first = journal.entry(&job).unwrap().signature();
+
+    // Not landed, blockhash still valid: sent again as is
+    sender.set_block_height(150);
+    assert_eq!(
+        journal.reconcile(&job, &[&authority]).unwrap(),
+        PayoutState::Pending
+    );
+    let sent = sender.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1], sent[0]);
+
+    // Expired: re-signed on the latest blockhash
+    sender.set_block_height(151);
+    sender.set_latest_blockhash(Hash::new_from_array([2; 32]), 300);
+    assert_eq!(
+        journal.reconcile(&job, &[&authority]).unwrap(),
+        PayoutState::Pending
+    );
+    let entry = journal.entry(&job).unwrap().clone();
+    assert_eq!(entry.attempts, 2);
+    assert_eq!(entry.last_valid_block_height, 300);
+    assert_eq!(
+        *entry.transaction.message.recent_blockhash(),
+        Hash::new_from_array([2; 32])
+    );
+    assert_ne!(entry.signature(), first);
+    assert_eq!(sender.sent().len(), 3);
+    assert_eq!(sender.sent()[2].signatures[0], entry.signature());
+
+    sender.land(entry.signature(), None);
+    assert_eq!(
+        journal.reconcile(&job, &[&authority]).unwrap(),
+        PayoutState::Paid { slot: 1 }
+    );
+    assert_eq!(sender.sent().len(), 3);
+}
+
+#[test]
+fn test_durable_nonce_rejected() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let builder =
+        reward_builder(&authority, 1_000).durable_nonce(Pubkey::new_unique(), authority.pubkey());
+    let job = JobId::new(&[b"trade", &7u64.to_le_bytes()]);
+
+    let mut journal = PayoutJournal::open(&client, MemoryJournalStore::default()).unwrap();
+    assert!(matches!(
+        journal.pay(job, &builder, &[&authority]),
+        Err(Error::Unsupported(_))
+    ));
+    assert!(journal.entry(&job).is_none());
+    assert!(sender.sent().is_empty());
+}
+
+#[test]
+fn test_failed_payout() {
+    let sender = FixtureSender::new();
+    sender.drop_transactions(true);
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let builder = reward_builder(&authority, 1_000);
+    let job = JobId::new(&[b"epoch", &[2]]);
+
+    let mut journal = PayoutJournal::open(&client, MemoryJournalStore::default()).unwrap();
+    journal.pay(job, &builder, &[&authority]).unwrap();
+    sender.land(
+        journal.entry(&job).unwrap().signature(),
+        Some(json!({ "InstructionError": [0, { "Custom": 6000 }] })),
+    );
+
+    let failed = PayoutState::Failed {
+        error: TransactionError::InstructionError(0, InstructionError::Custom(6000)),
+    };
+    assert_eq!(journal.reconcile(&job, &[&authority]).unwrap(), failed);
+    // Not sent again by pay
+    assert_eq!(journal.pay(job, &builder, &[&authority]).unwrap(), failed);
+    assert_eq!(sender.sent().len(), 1);
+
+    // The same blockhash would give the same signature
+    assert!(matches!(
+        journal.retry(&job, &[&authority]),
+        Err(Error::Journal(_))
+    ));
+
+    sender.set_latest_blockhash(Hash::new_from_array([3; 32]), 400);
+    assert_eq!(
+        journal.retry(&job, &[&authority]).unwrap(),
+        PayoutState::Pending
+    );
+    assert_eq!(journal.entry(&job).unwrap().attempts, 2);
+    assert_eq!(sender.sent().len(), 2);
+}
+
+#[test]
+fn test_recover_from_file() {
+    let sender = FixtureSender::new();
+    sender.drop_transactions(true);
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let (landed, lost) = (JobId::new(&[b"landed"]), JobId::new(&[b"lost"]));
+
+    let path = std::env::temp_dir().join(format!(
+        "buddy-link-journal-{}.jsonl",
+        Keypair::new().pubkey()
+    ));
+    {
+        let mut journal =
+            PayoutJournal::open(&client, FileJournalStore::open(&path).unwrap()).unwrap();
+        journal
+            .pay(landed, &reward_builder(&authority, 1_000), &[&authority])
+            .unwrap();
+        journal
+            .pay(lost, &reward_builder(&authority, 2_000), &[&authority])
+            .unwrap();
+        sender.land(journal.entry(&landed).unwrap().signature(), None);
+    }
+
+    // Crash in the middle of a save
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    write!(file, "{{\"job\":\"").unwrap();
+    drop(file);
+
+    let mut journal = PayoutJournal::open(&client, FileJournalStore::open(&path).unwrap()).unwrap();
+    assert_eq!(journal.entries().count(), 2);
+
+    let mut recovered = journal.recover(&[&authority]).unwrap();
+    recovered.sort_by_key(|(job, _)| *job == lost);
+    assert_eq!(
+        recovered,
+        vec![
+            (landed, PayoutState::Paid { slot: 1 }),
+            (lost, PayoutState::Pending)
+        ]
+    );
+    // Only the lost one is sent again
+    assert_eq!(sender.sent().len(), 3);
+    assert_eq!(
+        sender.sent()[2].signatures[0],
+        journal.entry(&lost).unwrap().signature()
+    );
+
+    let mut store = FileJournalStore::open(&path).unwrap();
+    store.compact().unwrap();
+    let mut entries = store.load().unwrap();
+    entries.sort_by_key(|x| x.job == lost);
+    assert_eq!(entries[0].state, PayoutState::Paid { slot: 1 });
+    assert_eq!(entries[1], *journal.entry(&lost).unwrap());
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,174 @@
+use buddy_link::client::lookup_table::{
+    build_v0_transaction, create_organization_lookup_table, decode_lookup_table,
+    extend_organization_lookup_table, OrganizationLookupTableAccounts, MAX_ADDRESSES_PER_EXTEND,
+};
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::instruction::{
+    transfer_unchecked_local_shared_reward, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs,
+};
+use solana_program::address_lookup_table::state::{
+    AddressLookupTable, LookupTableMeta, LOOKUP_TABLE_MAX_ADDRESSES,
+};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::message::VersionedMessage;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use std::borrow::Cow;
+
+fn organization_accounts(referrers: usize) -> OrganizationLookupTableAccounts {
+    let mint = Pubkey::new_unique();
+
+    OrganizationLookupTableAccounts {
+        treasuries: vec![Pubkey::new_unique()],
+        mints: vec![mint, mint],
+        token_programs: vec![spl_associated_token_account::ID],
+        referrer_treasuries: (0..referrers).map(|_| Pubkey::new_unique()).collect(),
+    }
+}
+
+#[test]
+fn test_addresses_are_deduplicated() {
+    let accounts = organization_accounts(2);
+    let addresses = accounts.addresses().unwrap();
+
+    assert_eq!(addresses[0], BL_PROGRAM_ID);
+    assert_eq!(addresses[1], solana_program::system_program::ID);
+    assert_eq!(addresses.len(), 2 + 1 + 1 + 1 + 2);
+}
+
+#[test]
+fn test_create_is_chunked() {
+    let authority = Pubkey::new_unique();
+    let accounts = organization_accounts(40);
+
+    let (lookup_table, instructions) =
+        create_organization_lookup_table(authority, authority, 42, &accounts).unwrap();
+
+    let expected_extends = accounts
+        .addresses()
+        .unwrap()
+        .len()
+        .div_ceil(MAX_ADDRESSES_PER_EXTEND);
+    assert_eq!(instructions.len(), 1 + expected_extends);
+    assert!(instructions
+        .iter()
+        .all(|instruction| instruction.accounts[0].pubkey == lookup_table));
+}
+
+#[test]
+fn test_extend_only_missing_addresses() {
+    let authority = Pubkey::new_unique();
+    let accounts = organization_accounts(3);
+    let addresses = accounts.addresses().unwrap();
+
+    let complete = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: addresses.clone(),
+    };
+    assert!(
+        extend_organization_lookup_table(&complete, authority, authority, &accounts)
+            .unwrap()
+            .is_empty()
+    );
+
+    let partial = AddressLookupTableAccount {
+        key: complete.key,
+        addresses: addresses[..2].to_vec(),
+    };
+    let instructions =
+        extend_organization_lookup_table(&partial, authority, authority, &accounts).unwrap();
+    assert_eq!(instructions.len(), 1);
+
+    // Extend data: u32 variant, u64 length, then the addresses
+    let data = &instructions[0].data;
+    assert_eq!(data.len(), 4 + 8 + (addresses.len() - 2) * 32);
+    assert_eq!(&data[12..44], addresses[2].as_ref());
+}
+
+#[test]
+fn test_addresses_over_capacity() {
+    let authority = Pubkey::new_unique();
+    // 2 fixed + 3 organization accounts
+    let full = organization_accounts(LOOKUP_TABLE_MAX_ADDRESSES - 5);
+    assert_eq!(full.addresses().unwrap().len(), LOOKUP_TABLE_MAX_ADDRESSES);
+
+    let over = organization_accounts(LOOKUP_TABLE_MAX_ADDRESSES - 4);
+    assert!(matches!(
+        over.addresses(),
+        Err(Error::LookupTableFull(addresses)) if addresses == LOOKUP_TABLE_MAX_ADDRESSES + 1
+    ));
+    assert!(matches!(
+        create_organization_lookup_table(authority, authority, 42, &over),
+        Err(Error::LookupTableFull(_))
+    ));
+
+    // Fits on its own, but not with the addresses already in the table
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: (0..10).map(|_| Pubkey::new_unique()).collect(),
+    };
+    assert!(matches!(
+        extend_organization_lookup_table(&table, authority, authority, &full),
+        Err(Error::LookupTableFull(addresses)) if addresses == LOOKUP_TABLE_MAX_ADDRESSES + 10
+    ));
+}
+
+#[test]
+fn test_decode_lookup_table() {
+    let key = Pubkey::new_unique();
+    let addresses = organization_accounts(1).addresses().unwrap();
+
+    let data = AddressLookupTable {
+        meta: LookupTableMeta::default(),
+        addresses: Cow::Borrowed(&addresses),
+    }
+    .serialize_for_tests()
+    .unwrap();
+
+    let table = decode_lookup_table(&key, &data).unwrap();
+    assert_eq!(table.key, key);
+    assert_eq!(table.addresses, addresses);
+
+    assert!(decode_lookup_table(&key, &data[..10]).is_err());
+}
+
+#[test]
+fn test_v0_transaction_uses_lookup_table() {
+    let authority = Keypair::new();
+    let accounts = organization_accounts(10);
+
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: accounts.addresses().unwrap(),
+    };
+
+    let instruction = transfer_unchecked_local_shared_reward(
+        authority.pubkey(),
+        &SharedRewardAsset::Sol,
+        &accounts.referrer_treasuries,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount: 1_000,
+            shares_in_bps: vec![1_000; 10],
+            members_included: false,
+        },
+    );
+
+    let transaction =
+        build_v0_transaction(&[instruction], &[table], Hash::default(), &[&authority]).unwrap();
+
+    let VersionedMessage::V0(message) = &transaction.message else {
+        panic!("expected a v0 message");
+    };
+
+    // Only the authority and the program stay static, the program has to be invoked
+    assert_eq!(
+        message.account_keys,
+        vec![authority.pubkey(), BL_PROGRAM_ID]
+    );
+    assert_eq!(message.address_table_lookups.len(), 1);
+    assert!(transaction.verify_with_results().iter().all(|valid| *valid));
+}
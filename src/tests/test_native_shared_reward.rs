@@ -0,0 +1,127 @@
+mod deployed_program;
+
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::cpi;
+use buddy_link::instruction::{shared_reward_amounts, TransferUncheckedLocalSharedRewardArgs};
+use deployed_program::{add_snapshot, snapshot};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+/// Program paying shared rewards in SOL from its PDA, see [`process_treasury`].
+const TREASURY_PROGRAM_ID: Pubkey = pubkey!("Treasury11111111111111111111111111111111111");
+
+/// Shares the amount of the data equally between the recipients (the accounts after the PDA).
+fn process_treasury(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (fixed_accounts, recipients) = accounts.split_at(3);
+    let fixed_accounts = &mut fixed_accounts.iter();
+    let system_program = next_account_info(fixed_accounts)?;
+    let buddy_link_program = next_account_info(fixed_accounts)?;
+    let treasury = next_account_info(fixed_accounts)?;
+    let (_, bump) = Pubkey::find_program_address(&[b"treasury"], program_id);
+
+    cpi::native::transfer_unchecked_local_shared_reward_sol(
+        buddy_link_program,
+        treasury,
+        system_program,
+        recipients,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount: u64::from_le_bytes(data.try_into().unwrap()),
+            shares_in_bps: equal_shares(recipients.len()),
+            members_included: false,
+        },
+        &[&[b"treasury", &[bump]]],
+    )
+}
+
+fn equal_shares(recipients: usize) -> Vec<u16> {
+    vec![10_000 / recipients as u16; recipients]
+}
+
+/// The deployed BuddyLink program with the treasury program, its PDA and the recipients funded.
+async fn start(treasury: Pubkey, recipients: &[Pubkey]) -> ProgramTestContext {
+    let mut program_test = ProgramTest::default();
+    // The treasury is native
+    program_test.prefer_bpf(false);
+    program_test.add_program(
+        "treasury",
+        TREASURY_PROGRAM_ID,
+        processor!(process_treasury),
+    );
+    add_snapshot(&mut program_test, &snapshot());
+
+    program_test.add_account(
+        treasury,
+        Account::new(1_000_000_000, 0, &system_program::ID),
+    );
+    for recipient in recipients {
+        program_test.add_account(
+            *recipient,
+            Account::new(1_000_000_000, 0, &system_program::ID),
+        );
+    }
+
+    program_test.start_with_context().await
+}
+
+async fn balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+/// Pays 1 SOL from the treasury to the recipients and checks their balances.
+async fn assert_native_shared_reward(recipients_count: usize) {
+    let (treasury, _) = Pubkey::find_program_address(&[b"treasury"], &TREASURY_PROGRAM_ID);
+    let recipients: Vec<Pubkey> = (0..recipients_count)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let mut context = start(treasury, &recipients).await;
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(BL_PROGRAM_ID, false),
+        AccountMeta::new(treasury, false),
+    ];
+    accounts.extend(recipients.iter().map(|x| AccountMeta::new(*x, false)));
+    let total_amount = 1_000_000_000u64;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: TREASURY_PROGRAM_ID,
+            accounts,
+            data: total_amount.to_le_bytes().to_vec(),
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let amounts = shared_reward_amounts(total_amount, &equal_shares(recipients_count)).unwrap();
+    for (recipient, amount) in recipients.into_iter().zip(amounts) {
+        assert_eq!(
+            balance(&mut context, recipient).await,
+            1_000_000_000 + amount
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_native_shared_reward_stack_accounts() {
+    assert_native_shared_reward(2).await;
+}
+
+// Past 8 recipients the account infos are on the heap
+#[tokio::test]
+async fn test_native_shared_reward_heap_accounts() {
+    assert_native_shared_reward(10).await;
+}
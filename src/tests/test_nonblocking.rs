@@ -0,0 +1,251 @@
+mod fixture_sender;
+
+use buddy_link::client::nonblocking::discovery::Discovery;
+use buddy_link::client::nonblocking::referral_chain::{multi_tier_reward, ReferralChainFetcher};
+use buddy_link::client::nonblocking::referral_reward::{pay_referral_reward, pay_referral_rewards};
+use buddy_link::client::nonblocking::transaction::{
+    build, preflight, send_and_confirm, simulate_compute_unit_limit,
+};
+use buddy_link::client::referral_chain::{ChainEnd, Referee};
+use buddy_link::client::referral_reward::ReferralReward;
+use buddy_link::client::transaction::{
+    compute_unit_limit_with_margin, FixedPriorityFee, TransactionBuilder,
+    DEFAULT_COMPUTE_UNIT_MARGIN_BPS,
+};
+use buddy_link::client::{discovery, referral_chain, referral_reward, Error};
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, GeneralTransferRewardArgs, MultiTierRewardArgs,
+    RewardAsset, RewardVariant, SharedRewardAsset, TierRecipient, UnclaimedTiers,
+};
+use fixture_sender::{client, nonblocking_client, FixtureSender, SIMULATED_UNITS};
+use serde_json::json;
+use solana_client::rpc_request::RpcRequest;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+fn reward(organization: Option<&str>) -> ReferralReward {
+    ReferralReward {
+        referee_buddy: REFEREE_GLOBAL_BUDDY,
+        referee_buddy_profile: None,
+        organization: organization.map(str::to_string),
+        asset: SharedRewardAsset::Spl {
+            mint: MINT,
+            token_program: anchor_spl::token::ID,
+            from: Pubkey::new_unique(),
+        },
+        amount: 100,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_chains() {
+    let sender = FixtureSender::new();
+    let blocking = client(&sender);
+    let client = nonblocking_client(&sender);
+
+    for referee in [
+        Referee::Member(REFEREE_MEMBER),
+        Referee::Buddy(REFEREE_GLOBAL_BUDDY),
+    ] {
+        let chain = ReferralChainFetcher::new(&client)
+            .reward_mint(MINT, anchor_spl::token::ID)
+            .fetch(&referee)
+            .await
+            .unwrap();
+        let expected = referral_chain::ReferralChainFetcher::new(&blocking)
+            .reward_mint(MINT, anchor_spl::token::ID)
+            .fetch(&referee)
+            .unwrap();
+        assert_eq!(chain, expected);
+        assert_eq!(chain.links[0].treasury, REFERRER_TREASURY);
+    }
+
+    let chain = ReferralChainFetcher::new(&client)
+        .max_depth(0)
+        .fetch_local(&REFEREE_MEMBER)
+        .await
+        .unwrap();
+    assert_eq!(chain.end, ChainEnd::MaxDepth);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fetch_many() {
+    let sender = FixtureSender::new();
+    let client = nonblocking_client(&sender);
+    let fetcher = ReferralChainFetcher::new(&client).concurrency(2);
+
+    let mut referees = vec![Referee::Member(REFEREE_MEMBER); 150];
+    referees[1] = Referee::Buddy(REFEREE_GLOBAL_BUDDY);
+    referees[2] = Referee::Member(Pubkey::new_unique());
+
+    let chains = fetcher.fetch_many(&referees).await.unwrap();
+    assert_eq!(chains.len(), 150);
+    assert_eq!(
+        chains[0].as_ref().unwrap().links[0].member,
+        Some(REFERRER_MEMBER)
+    );
+    assert_eq!(chains[1].as_ref().unwrap().links[0].member, None);
+    assert!(matches!(chains[2], Err(Error::AccountNotFound(_))));
+    let expected = chains[0].as_ref().unwrap();
+    assert!(chains[3..].iter().all(|x| x.as_ref().unwrap() == expected));
+
+    // The referees are fetched together (2 requests of 100 accounts),
+    // then one request per link of the local chains and two for the global one
+    assert_eq!(
+        sender.requests(RpcRequest::GetMultipleAccounts),
+        2 + 148 + 2
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_discovery() {
+    let sender = FixtureSender::new();
+    let blocking = client(&sender);
+    let blocking = discovery::Discovery::new(&blocking);
+    let client = nonblocking_client(&sender);
+    let discovery = Discovery::new(&client).page_size(1);
+
+    let referees = discovery
+        .referees(&REFERRER_TREASURY)
+        .await
+        .unwrap()
+        .all()
+        .await
+        .unwrap();
+    assert_eq!(referees.len(), 1);
+    assert_eq!(referees[0].0, REFEREE_MEMBER);
+
+    let treasuries = discovery
+        .buddy_treasuries(&REFEREE_GLOBAL_BUDDY)
+        .await
+        .unwrap();
+    assert_eq!(
+        treasuries.keys(),
+        blocking
+            .buddy_treasuries(&REFEREE_GLOBAL_BUDDY)
+            .unwrap()
+            .keys()
+    );
+    assert!(treasuries.keys().contains(&REFEREE_TREASURY));
+
+    let mut members = discovery.organization_members("goose").await.unwrap();
+    let mut found = Vec::new();
+    while let Some(page) = members.next_page().await {
+        found.extend(page.unwrap().into_iter().map(|(key, _)| key));
+    }
+    found.sort();
+    let mut expected = vec![REFEREE_MEMBER, REFERRER_MEMBER];
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rewards() {
+    let sender = FixtureSender::new();
+    let blocking = client(&sender);
+    let client = nonblocking_client(&sender);
+    let authority = Pubkey::new_unique();
+
+    for organization in [Some("goose"), Some("unknown"), None] {
+        let reward = reward(organization);
+        assert_eq!(
+            pay_referral_reward(&client, authority, &reward)
+                .await
+                .unwrap(),
+            referral_reward::pay_referral_reward(&blocking, authority, &reward).unwrap()
+        );
+    }
+
+    let mut rewards = vec![reward(Some("goose")); 20];
+    rewards[3].referee_buddy = Pubkey::new_unique();
+    let plans = pay_referral_rewards(&client, authority, &rewards, 4).await;
+    assert_eq!(plans.len(), 20);
+    assert_eq!(
+        plans[0].as_ref().unwrap().variant,
+        RewardVariant::CheckedGlobal
+    );
+    assert!(plans[3].is_err());
+
+    let args = MultiTierRewardArgs {
+        total_amount: 1_000,
+        schedule: vec![7_000, 3_000],
+        unclaimed: UnclaimedTiers::Fallback(TierRecipient {
+            recipient: Pubkey::new_unique(),
+            member: None,
+        }),
+    };
+    let asset = reward(None).asset;
+    let referee = Referee::Member(REFEREE_MEMBER);
+    assert_eq!(
+        multi_tier_reward(&client, authority, &referee, &asset, &args)
+            .await
+            .unwrap(),
+        referral_chain::multi_tier_reward(&blocking, authority, &referee, &asset, &args).unwrap()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_transactions() {
+    let sender = FixtureSender::new();
+    let client = nonblocking_client(&sender);
+    let authority = Keypair::new();
+
+    let builder = TransactionBuilder::new(authority.pubkey()).instruction(
+        transfer_checked_global_only_reward(
+            authority.pubkey(),
+            &RewardAsset::Sol,
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        ),
+    );
+    let limit = compute_unit_limit_with_margin(SIMULATED_UNITS, DEFAULT_COMPUTE_UNIT_MARGIN_BPS);
+
+    assert_eq!(
+        simulate_compute_unit_limit(&builder, &client, None)
+            .await
+            .unwrap(),
+        limit
+    );
+
+    let transaction = build(&builder, &client, Some(&FixedPriorityFee(7)), &[&authority])
+        .await
+        .unwrap();
+    let instructions = transaction.message.instructions();
+    assert_eq!(
+        instructions[0].data,
+        ComputeBudgetInstruction::set_compute_unit_limit(limit).data
+    );
+    assert_eq!(
+        instructions[1].data,
+        ComputeBudgetInstruction::set_compute_unit_price(7).data
+    );
+
+    assert_eq!(
+        preflight(&client, &transaction).await.unwrap(),
+        SIMULATED_UNITS
+    );
+    assert_eq!(
+        send_and_confirm(&client, &transaction).await.unwrap(),
+        transaction.signatures[0]
+    );
+
+    sender.fail_simulations(json!({ "InstructionError": [0, { "Custom": 6004 }] }));
+    let Err(Error::Simulation { logs, .. }) = preflight(&client, &transaction).await else {
+        panic!("the preflight should fail");
+    };
+    assert_eq!(logs, ["Program log: simulated"]);
+}
@@ -0,0 +1,305 @@
+mod fixture_sender;
+
+use buddy_link::client::offline::{
+    export_transaction, import_transaction, missing_signers, nonce_blockhash, sign_partial,
+    summarize, DurableNonce, Encoding, InstructionSummary,
+};
+use buddy_link::client::simulation::Party;
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, GeneralTransferRewardArgs, RewardAsset, RewardVariant,
+};
+use fixture_sender::{client, FixtureSender};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::message::legacy::Message;
+use solana_program::message::{MessageHeader, VersionedMessage};
+use solana_program::nonce::state::{self, State, Versions};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::{Signer, SignerError};
+use solana_sdk::transaction::VersionedTransaction;
+
+struct Nonce {
+    account: Pubkey,
+    blockhash: Hash,
+}
+
+/// Nonce account of the authority, set in the fixtures.
+fn nonce_account(sender: &FixtureSender, authority: &Pubkey) -> Nonce {
+    let account = Pubkey::new_unique();
+    let durable_nonce = state::DurableNonce::from_blockhash(&Hash::new_from_array([7; 32]));
+    let data = bincode::serialize(&Versions::new(State::new_initialized(
+        authority,
+        durable_nonce,
+        5_000,
+    )))
+    .unwrap();
+    sender.set_account(account, 1_447_680, &data, system_program::ID);
+
+    Nonce {
+        account,
+        blockhash: *durable_nonce.as_hash(),
+    }
+}
+
+fn reward_builder(
+    fee_payer: &Pubkey,
+    authority: &Pubkey,
+    nonce: &Nonce,
+) -> TransactionBuilder<'static> {
+    TransactionBuilder::new(*fee_payer)
+        .instruction(transfer_checked_global_only_reward(
+            *authority,
+            &RewardAsset::Sol,
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+            Pubkey::new_from_array([3; 32]),
+            Pubkey::new_from_array([4; 32]),
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        ))
+        .durable_nonce(nonce.account, *authority)
+}
+
+#[test]
+fn test_build_on_durable_nonce() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let nonce = nonce_account(&sender, &authority.pubkey());
+
+    assert_eq!(
+        nonce_blockhash(&client, &nonce.account).unwrap(),
+        nonce.blockhash
+    );
+    assert!(matches!(
+        nonce_blockhash(&client, &Pubkey::new_unique()),
+        Err(Error::AccountNotFound(_))
+    ));
+    // A BuddyLink account isn't a nonce account
+    let not_a_nonce = Pubkey::new_unique();
+    sender.set_data(not_a_nonce, &[0; 80]);
+    assert!(matches!(
+        nonce_blockhash(&client, &not_a_nonce),
+        Err(Error::InvalidAccountData(pubkey)) if pubkey == not_a_nonce
+    ));
+
+    let transaction = reward_builder(&authority.pubkey(), &authority.pubkey(), &nonce)
+        .compute_unit_limit(30_000)
+        .build(&client, &[&authority])
+        .unwrap();
+
+    assert_eq!(*transaction.message.recent_blockhash(), nonce.blockhash);
+    let summary = summarize(&transaction, &[]).unwrap();
+    assert_eq!(
+        summary.durable_nonce,
+        Some(DurableNonce {
+            account: nonce.account,
+            authority: authority.pubkey(),
+        })
+    );
+    // The nonce is advanced first
+    assert_eq!(
+        summary.instructions[..2],
+        [
+            InstructionSummary::AdvanceNonce {
+                nonce_account: nonce.account,
+                nonce_authority: authority.pubkey(),
+            },
+            InstructionSummary::ComputeUnitLimit(30_000),
+        ]
+    );
+    assert!(missing_signers(&transaction).is_empty());
+}
+
+#[test]
+fn test_offline_signing() {
+    let sender = FixtureSender::new();
+    let fee_payer = Keypair::new();
+    let custody = Keypair::new();
+    let nonce = nonce_account(&sender, &custody.pubkey());
+
+    // Online: built without any signer
+    let unsigned = reward_builder(&fee_payer.pubkey(), &custody.pubkey(), &nonce)
+        .build_unsigned(30_000, Some(10), nonce.blockhash)
+        .unwrap();
+    let exported = export_transaction(&unsigned, Encoding::Base64).unwrap();
+
+    // Offline: reviewed and signed by the custody
+    let mut transaction = import_transaction(&exported, Encoding::Base64).unwrap();
+    assert_eq!(transaction, unsigned);
+    assert_eq!(
+        missing_signers(&transaction),
+        vec![fee_payer.pubkey(), custody.pubkey()]
+    );
+
+    let summary = summarize(&transaction, &[]).unwrap();
+    assert_eq!(summary.fee_payer, fee_payer.pubkey());
+    assert_eq!(summary.recent_blockhash, nonce.blockhash);
+    assert_eq!(
+        summary.instructions,
+        vec![
+            InstructionSummary::AdvanceNonce {
+                nonce_account: nonce.account,
+                nonce_authority: custody.pubkey(),
+            },
+            InstructionSummary::ComputeUnitLimit(30_000),
+            InstructionSummary::ComputeUnitPrice(10),
+            InstructionSummary::Reward {
+                variant: RewardVariant::CheckedGlobalOnly,
+                amount: 1_000,
+                parties: vec![
+                    (Party::Authority, custody.pubkey()),
+                    (
+                        Party::GlobalReferrerTreasury,
+                        Pubkey::new_from_array([1; 32])
+                    ),
+                    (
+                        Party::GlobalReferrerTreasuryForReward,
+                        Pubkey::new_from_array([2; 32])
+                    ),
+                ],
+            },
+        ]
+    );
+    let text = summary.to_string();
+    assert!(text.contains(&format!(
+        "Durable nonce: {} of account {}",
+        nonce.blockhash, nonce.account
+    )));
+    assert!(text.contains(&format!("{} (missing)", custody.pubkey())));
+    assert!(text.contains("BuddyLink CheckedGlobalOnly reward of 1000"));
+    assert!(text.contains("Compute unit price: 10 micro-lamports"));
+
+    assert!(matches!(
+        sign_partial(&mut transaction, &[&Keypair::new()]),
+        Err(Error::Signer(SignerError::KeypairPubkeyMismatch))
+    ));
+    sign_partial(&mut transaction, &[&custody]).unwrap();
+    assert_eq!(missing_signers(&transaction), vec![fee_payer.pubkey()]);
+    let exported = export_transaction(&transaction, Encoding::Base58).unwrap();
+
+    // Online: the fee payer completes it
+    let mut transaction = import_transaction(&exported, Encoding::Base58).unwrap();
+    sign_partial(&mut transaction, &[&fee_payer]).unwrap();
+    assert!(missing_signers(&transaction).is_empty());
+    assert!(transaction.verify_with_results().into_iter().all(|x| x));
+    assert!(summarize(&transaction, &[])
+        .unwrap()
+        .to_string()
+        .contains(&format!("{} (signed)", custody.pubkey())));
+}
+
+#[test]
+fn test_import_errors() {
+    let authority = Keypair::new();
+    let nonce = Nonce {
+        account: Pubkey::new_unique(),
+        blockhash: Hash::new_unique(),
+    };
+    let mut transaction = reward_builder(&authority.pubkey(), &authority.pubkey(), &nonce)
+        .build_unsigned(30_000, None, nonce.blockhash)
+        .unwrap();
+
+    assert!(matches!(
+        import_transaction("not base64!", Encoding::Base64),
+        Err(Error::InvalidTransaction(_))
+    ));
+    assert!(matches!(
+        import_transaction("0OIl", Encoding::Base58),
+        Err(Error::InvalidTransaction(_))
+    ));
+
+    transaction.signatures.clear();
+    let exported = export_transaction(&transaction, Encoding::Base64).unwrap();
+    assert!(matches!(
+        import_transaction(&exported, Encoding::Base64),
+        Err(Error::InvalidTransaction(_))
+    ));
+}
+
+#[test]
+fn test_malformed_message() {
+    let authority = Keypair::new();
+    let malformed = |num_required_signatures, account_keys: Vec<Pubkey>| VersionedTransaction {
+        signatures: vec![Signature::default(); usize::from(num_required_signatures)],
+        message: VersionedMessage::Legacy(Message {
+            header: MessageHeader {
+                num_required_signatures,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys,
+            recent_blockhash: Hash::new_unique(),
+            instructions: Vec::new(),
+        }),
+    };
+
+    // More signers than account keys, and no account key (no fee payer)
+    for mut transaction in [
+        malformed(3, vec![authority.pubkey()]),
+        malformed(0, Vec::new()),
+    ] {
+        let exported = export_transaction(&transaction, Encoding::Base64).unwrap();
+        assert!(matches!(
+            import_transaction(&exported, Encoding::Base64),
+            Err(Error::InvalidTransaction(_))
+        ));
+        // Hand-built, never imported
+        assert!(matches!(
+            summarize(&transaction, &[]),
+            Err(Error::InvalidTransaction(_))
+        ));
+        assert!(sign_partial(&mut transaction, &[&authority]).is_err());
+    }
+
+    let mut transaction = malformed(3, vec![authority.pubkey()]);
+    assert!(matches!(
+        sign_partial(&mut transaction, &[&authority]),
+        Err(Error::InvalidTransaction(_))
+    ));
+}
+
+#[test]
+fn test_summarize_v0() {
+    let authority = Keypair::new();
+    let nonce = Nonce {
+        account: Pubkey::new_unique(),
+        blockhash: Hash::new_unique(),
+    };
+    let lookup_tables = [AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: vec![
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+            Pubkey::new_from_array([3; 32]),
+            Pubkey::new_from_array([4; 32]),
+            BL_PROGRAM_ID,
+        ],
+    }];
+
+    let builder = reward_builder(&authority.pubkey(), &authority.pubkey(), &nonce);
+    let legacy = builder
+        .build_unsigned(30_000, None, nonce.blockhash)
+        .unwrap();
+    let v0 = builder
+        .lookup_tables(lookup_tables.clone())
+        .build_unsigned(30_000, None, nonce.blockhash)
+        .unwrap();
+    assert!(v0
+        .message
+        .address_table_lookups()
+        .is_some_and(|x| !x.is_empty()));
+
+    assert!(matches!(
+        summarize(&v0, &[]),
+        Err(Error::InvalidTransaction(_))
+    ));
+    assert_eq!(
+        summarize(&v0, &lookup_tables).unwrap().instructions,
+        summarize(&legacy, &[]).unwrap().instructions
+    );
+}
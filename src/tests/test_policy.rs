@@ -0,0 +1,240 @@
+mod fixture_sender;
+
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::GeneralTransferRewardArgs;
+use buddy_link::policy::{
+    ui_amount_to_base_units, BelowMinimum, PolicyAmount, PolicyLimit, RewardPolicy, Rounding,
+    SeniorityTier, Tiers, NEUTRAL_MULTIPLIER_BPS,
+};
+use buddy_link::state::Member;
+use fixture_sender::FixtureSender;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+/// Creation of the referee member.
+const CREATED_AT: i64 = 1_690_463_390;
+const DAY: i64 = 86_400;
+
+fn amount(amount: u64, limited_by: Option<PolicyLimit>) -> PolicyAmount {
+    PolicyAmount {
+        amount,
+        multiplier_bps: NEUTRAL_MULTIPLIER_BPS,
+        limited_by,
+    }
+}
+
+#[test]
+fn test_volume_bps_and_rounding() {
+    // 0.3% of 1_001
+    let volume = 1_001;
+    for (rounding, expected) in [
+        (Rounding::Down, 3),
+        (Rounding::Up, 4),
+        (Rounding::Nearest, 3),
+    ] {
+        assert_eq!(
+            RewardPolicy::new(30)
+                .rounding(rounding)
+                .amount_with_multiplier(volume, NEUTRAL_MULTIPLIER_BPS, 0),
+            Ok(amount(expected, None))
+        );
+    }
+    // 0.5% of 1_500 = 7.5, the half is rounded up
+    assert_eq!(
+        RewardPolicy::new(50)
+            .rounding(Rounding::Nearest)
+            .amount_with_multiplier(1_500, NEUTRAL_MULTIPLIER_BPS, 0)
+            .unwrap()
+            .amount,
+        8
+    );
+    // Exact amounts aren't rounded
+    assert_eq!(
+        RewardPolicy::new(50)
+            .rounding(Rounding::Up)
+            .amount_with_multiplier(2_000, NEUTRAL_MULTIPLIER_BPS, 0)
+            .unwrap()
+            .amount,
+        10
+    );
+    // The multiplier is applied before rounding: 1.5 * 0.3% of 1_001 = 4.5045
+    assert_eq!(
+        RewardPolicy::new(30)
+            .amount_with_multiplier(volume, 15_000, 0)
+            .unwrap()
+            .amount,
+        4
+    );
+
+    // No overflow in u128, only if the amount doesn't fit in a u64
+    assert_eq!(
+        RewardPolicy::new(10_000)
+            .amount_with_multiplier(u64::MAX, NEUTRAL_MULTIPLIER_BPS, 0)
+            .unwrap()
+            .amount,
+        u64::MAX
+    );
+    assert_eq!(
+        RewardPolicy::new(10_000).amount_with_multiplier(u64::MAX, 20_000, 0),
+        Err(ProgramError::ArithmeticOverflow)
+    );
+    assert_eq!(
+        RewardPolicy::new(10_001).amount_with_multiplier(1, NEUTRAL_MULTIPLIER_BPS, 0),
+        Err(BuddyLinkError::InvalidBPSProvided.into())
+    );
+}
+
+#[test]
+fn test_limits() {
+    let policy = RewardPolicy::new(100)
+        .min_per_payout(10, BelowMinimum::Skip)
+        .max_per_payout(1_000)
+        .lifetime_cap(1_500);
+    let with = |volume, paid| policy.amount_with_multiplier(volume, NEUTRAL_MULTIPLIER_BPS, paid);
+
+    assert_eq!(with(50_000, 0), Ok(amount(500, None)));
+    assert_eq!(with(999, 0), Ok(amount(0, Some(PolicyLimit::Minimum))));
+    assert_eq!(
+        with(1_000_000, 0),
+        Ok(amount(1_000, Some(PolicyLimit::Maximum)))
+    );
+    assert_eq!(
+        with(50_000, 1_200),
+        Ok(amount(300, Some(PolicyLimit::LifetimeCap)))
+    );
+    assert_eq!(
+        with(50_000, 1_500),
+        Ok(amount(0, Some(PolicyLimit::LifetimeCap)))
+    );
+    // The lifetime cap wins over the raised minimum
+    let raised = policy.clone().min_per_payout(10, BelowMinimum::Raise);
+    assert_eq!(
+        raised.amount_with_multiplier(999, NEUTRAL_MULTIPLIER_BPS, 0),
+        Ok(amount(10, Some(PolicyLimit::Minimum)))
+    );
+    assert_eq!(
+        raised.amount_with_multiplier(999, NEUTRAL_MULTIPLIER_BPS, 1_495),
+        Ok(amount(5, Some(PolicyLimit::LifetimeCap)))
+    );
+
+    assert_eq!(
+        with(50_000, 0).unwrap().transfer_args(),
+        GeneralTransferRewardArgs { amount: 500 }
+    );
+    assert_eq!(
+        RewardPolicy::new(100)
+            .min_per_payout(10, BelowMinimum::Skip)
+            .max_per_payout(9)
+            .validate(),
+        Err(ProgramError::InvalidArgument)
+    );
+}
+
+#[test]
+fn test_tiers() {
+    let fixtures = FixtureSender::new();
+    let referee_data = fixtures.data(&REFEREE_MEMBER);
+    let referrer_data = fixtures.data(&REFERRER_MEMBER);
+    let referee = Member::new(referee_data.as_slice()).unwrap();
+    // Not referred
+    let referrer = Member::new(referrer_data.as_slice()).unwrap();
+    let now = CREATED_AT + 45 * DAY;
+
+    let referred = RewardPolicy::new(100).tiers(Tiers::Referred {
+        referred_bps: 12_000,
+        not_referred_bps: 8_000,
+    });
+    assert_eq!(referred.multiplier_bps(Some(&referee), now), Ok(12_000));
+    assert_eq!(referred.multiplier_bps(Some(&referrer), now), Ok(8_000));
+    assert_eq!(
+        referred.multiplier_bps::<&[u8]>(None, now),
+        Ok(NEUTRAL_MULTIPLIER_BPS)
+    );
+
+    let seniority = |tiers: &[(i64, u32)]| {
+        RewardPolicy::new(100).tiers(Tiers::Seniority(
+            tiers
+                .iter()
+                .map(|(min_age, multiplier_bps)| SeniorityTier {
+                    min_age: *min_age,
+                    multiplier_bps: *multiplier_bps,
+                })
+                .collect(),
+        ))
+    };
+    // Highest tier reached, in any order
+    let policy = seniority(&[(90 * DAY, 20_000), (0, 10_000), (30 * DAY, 15_000)]);
+    assert_eq!(policy.multiplier_bps(Some(&referee), now), Ok(15_000));
+    assert_eq!(
+        policy.multiplier_bps(Some(&referee), CREATED_AT + 90 * DAY),
+        Ok(20_000)
+    );
+    // Below the first tier
+    assert_eq!(
+        seniority(&[(60 * DAY, 5_000)]).multiplier_bps(Some(&referee), now),
+        Ok(NEUTRAL_MULTIPLIER_BPS)
+    );
+
+    assert_eq!(
+        policy.amount(100_000, Some(&referee), now, 0),
+        Ok(PolicyAmount {
+            amount: 1_500,
+            multiplier_bps: 15_000,
+            limited_by: None,
+        })
+    );
+}
+
+#[test]
+fn test_ui_amount_to_base_units() {
+    assert_eq!(
+        ui_amount_to_base_units("12.5", 6, Rounding::Down),
+        Ok(12_500_000)
+    );
+    assert_eq!(ui_amount_to_base_units("7", 0, Rounding::Down), Ok(7));
+    assert_eq!(ui_amount_to_base_units(".25", 2, Rounding::Down), Ok(25));
+    assert_eq!(
+        ui_amount_to_base_units("3.", 9, Rounding::Down),
+        Ok(3_000_000_000)
+    );
+    assert_eq!(ui_amount_to_base_units("000.001", 3, Rounding::Down), Ok(1));
+
+    // Beyond the decimals
+    assert_eq!(
+        ui_amount_to_base_units("1.2345", 2, Rounding::Down),
+        Ok(123)
+    );
+    assert_eq!(ui_amount_to_base_units("1.2345", 2, Rounding::Up), Ok(124));
+    assert_eq!(
+        ui_amount_to_base_units("1.2350", 2, Rounding::Nearest),
+        Ok(124)
+    );
+    assert_eq!(
+        ui_amount_to_base_units("1.2349", 2, Rounding::Nearest),
+        Ok(123)
+    );
+
+    assert_eq!(
+        ui_amount_to_base_units("18446744073709551615", 0, Rounding::Down),
+        Ok(u64::MAX)
+    );
+    assert_eq!(
+        ui_amount_to_base_units("18446744073709551616", 0, Rounding::Down),
+        Err(ProgramError::ArithmeticOverflow)
+    );
+    assert_eq!(
+        ui_amount_to_base_units("18446744073.709551616", 9, Rounding::Down),
+        Err(ProgramError::ArithmeticOverflow)
+    );
+    for invalid in ["", ".", "-1", "1.2.3", "1e6", " 1", "1,5"] {
+        assert_eq!(
+            ui_amount_to_base_units(invalid, 6, Rounding::Down),
+            Err(ProgramError::InvalidArgument),
+            "{invalid}"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+mod fixture_sender;
+
+use borsh::BorshDeserialize;
+use buddy_link::client::referral_chain::{
+    multi_tier_reward, ChainEnd, Referee, ReferralChainFetcher, ReferrerLink,
+};
+use buddy_link::instruction::{
+    MultiTierRewardArgs, SharedRewardAsset, TierRecipient, TransferUncheckedLocalSharedRewardArgs,
+    UnclaimedTiers,
+};
+use buddy_link::state::{find_treasury_address, Member};
+use fixture_sender::{client, FixtureSender};
+use solana_client::rpc_request::RpcRequest;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+use spl_associated_token_account::get_associated_token_address;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_GLOBAL_BUDDY: Pubkey = pubkey!("4jHbHkwjJoZgDBsx774LAmmqxPuGwk65SVdV6yr5Xjsm");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+#[test]
+fn test_local_chain() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+
+    let chain = ReferralChainFetcher::new(&client)
+        .reward_mint(MINT, anchor_spl::token::ID)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+
+    // The referee member keeps the key of the treasuries of its referrer
+    let key = Member::new(sender.data(&REFEREE_MEMBER).as_slice())
+        .unwrap()
+        .referrer_treasury_key()
+        .unwrap();
+    assert_eq!(find_treasury_address(&MINT, &key).0, REFERRER_TREASURY);
+
+    assert_eq!(chain.end, ChainEnd::Root);
+    assert_eq!(
+        chain.links,
+        vec![ReferrerLink {
+            treasury: REFERRER_TREASURY,
+            member: Some(REFERRER_MEMBER),
+            buddy: None,
+            treasury_for_reward: Some(REFERRER_TREASURY),
+            token_account: Some(REFERRER_ATA),
+        }]
+    );
+    assert_eq!(chain.recipients(), Some(vec![REFERRER_ATA]));
+
+    let chain = ReferralChainFetcher::new(&client)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+    assert_eq!(chain.recipients(), Some(vec![REFERRER_TREASURY]));
+    assert_eq!(
+        chain.remaining_accounts(true),
+        Some(vec![REFERRER_TREASURY, REFERRER_MEMBER])
+    );
+
+    assert!(ReferralChainFetcher::new(&client)
+        .fetch_local(&REFERRER_MEMBER)
+        .unwrap()
+        .links
+        .is_empty());
+}
+
+#[test]
+fn test_global_chain() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+
+    assert_eq!(
+        get_associated_token_address(&REFERRER_TREASURY, &MINT),
+        REFERRER_ATA
+    );
+
+    let chain = ReferralChainFetcher::new(&client)
+        .reward_mint(MINT, anchor_spl::token::ID)
+        .fetch_global(&REFEREE_GLOBAL_BUDDY)
+        .unwrap();
+
+    assert_eq!(chain.end, ChainEnd::Root);
+    assert_eq!(
+        chain.links,
+        vec![ReferrerLink {
+            treasury: REFERRER_TREASURY,
+            member: None,
+            buddy: Some(REFERRER_GLOBAL_BUDDY),
+            treasury_for_reward: Some(REFERRER_TREASURY),
+            token_account: Some(REFERRER_ATA),
+        }]
+    );
+    assert_eq!(chain.recipients(), Some(vec![REFERRER_ATA]));
+    assert_eq!(chain.remaining_accounts(true), None);
+
+    // Other mints can't be derived without a member
+    let chain = ReferralChainFetcher::new(&client)
+        .reward_mint(Pubkey::new_unique(), anchor_spl::token::ID)
+        .fetch_global(&REFEREE_GLOBAL_BUDDY)
+        .unwrap();
+    assert_eq!(chain.links[0].treasury_for_reward, None);
+    assert_eq!(chain.links[0].token_account, None);
+}
+
+#[test]
+fn test_cycle_and_max_depth() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+
+    // The referrer member is referred by the referee treasury, closing the loop
+    let mut data = sender.data(&REFERRER_MEMBER);
+    data[Member::<&[u8]>::REFERRER_TREASURY_OFFSET..][..32]
+        .copy_from_slice(REFEREE_TREASURY.as_ref());
+    sender.set_data(REFERRER_MEMBER, &data);
+
+    let chain = ReferralChainFetcher::new(&client)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+
+    assert_eq!(chain.end, ChainEnd::Cycle(REFEREE_TREASURY));
+    assert_eq!(chain.links.len(), 1);
+
+    let requests = sender.requests(RpcRequest::GetMultipleAccounts);
+    let chain = ReferralChainFetcher::new(&client)
+        .max_depth(0)
+        .fetch_local(&REFEREE_MEMBER)
+        .unwrap();
+
+    assert_eq!(chain.end, ChainEnd::MaxDepth);
+    assert!(chain.links.is_empty());
+    // Only the referee member was fetched
+    assert_eq!(
+        sender.requests(RpcRequest::GetMultipleAccounts),
+        requests + 1
+    );
+}
+
+#[test]
+fn test_multi_tier_reward() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Pubkey::new_unique();
+    let fallback = TierRecipient {
+        recipient: Pubkey::new_unique(),
+        member: Some(Pubkey::new_unique()),
+    };
+
+    let (instruction, chain) = multi_tier_reward(
+        &client,
+        authority,
+        &Referee::Member(REFEREE_MEMBER),
+        &SharedRewardAsset::Spl {
+            mint: MINT,
+            token_program: anchor_spl::token::ID,
+            from: Pubkey::new_unique(),
+        },
+        &MultiTierRewardArgs {
+            total_amount: 1_000,
+            schedule: vec![7_000, 3_000],
+            unclaimed: UnclaimedTiers::Fallback(fallback),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(chain.links.len(), 1);
+    let args =
+        TransferUncheckedLocalSharedRewardArgs::try_from_slice(&instruction.data[8..]).unwrap();
+    assert_eq!(args.shares_in_bps, [7_000, 3_000]);
+    assert!(args.members_included);
+    assert_eq!(
+        instruction.accounts[5..]
+            .iter()
+            .map(|x| x.pubkey)
+            .collect::<Vec<_>>(),
+        [
+            REFERRER_ATA,
+            REFERRER_MEMBER,
+            fallback.recipient,
+            fallback.member.unwrap()
+        ]
+    );
+
+    // Global chains have no members, the only referrer gets everything
+    let (instruction, chain) = multi_tier_reward(
+        &client,
+        authority,
+        &Referee::Buddy(REFEREE_GLOBAL_BUDDY),
+        &SharedRewardAsset::Sol,
+        &MultiTierRewardArgs {
+            total_amount: 1_000,
+            schedule: vec![7_000, 3_000],
+            unclaimed: UnclaimedTiers::Redistribute,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(chain.links[0].buddy, Some(REFERRER_GLOBAL_BUDDY));
+    let args =
+        TransferUncheckedLocalSharedRewardArgs::try_from_slice(&instruction.data[8..]).unwrap();
+    assert_eq!(args.shares_in_bps, [10_000]);
+    assert!(!args.members_included);
+    assert_eq!(instruction.accounts[5].pubkey, REFERRER_TREASURY);
+    assert_eq!(instruction.accounts.len(), 6);
+}
@@ -0,0 +1,150 @@
+mod fixture_sender;
+
+use buddy_link::client::referral_reward::{
+    pay_referral_reward, ReferralReward, ReferralRewardPlan,
+};
+use buddy_link::client::Error;
+use buddy_link::instruction::{
+    LocalReferrer, RewardVariant, RewardVariantReason, SharedRewardAsset,
+};
+use fixture_sender::{client, FixtureSender};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_GLOBAL_BUDDY: Pubkey = pubkey!("4jHbHkwjJoZgDBsx774LAmmqxPuGwk65SVdV6yr5Xjsm");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_AUTHORITY: Pubkey = pubkey!("HFnGHHTEKdggiHVFYEs1VAKKmjPvoD31HQsApkZqHqEx");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+fn reward(organization: Option<&str>, asset: SharedRewardAsset) -> ReferralReward {
+    ReferralReward {
+        referee_buddy: REFEREE_GLOBAL_BUDDY,
+        referee_buddy_profile: None,
+        organization: organization.map(str::to_string),
+        asset,
+        amount: 100,
+    }
+}
+
+fn spl(from: Pubkey) -> SharedRewardAsset {
+    SharedRewardAsset::Spl {
+        mint: MINT,
+        token_program: anchor_spl::token::ID,
+        from,
+    }
+}
+
+fn keys(plan: &ReferralRewardPlan) -> Vec<Pubkey> {
+    plan.instruction.accounts.iter().map(|x| x.pubkey).collect()
+}
+
+#[test]
+fn test_organization_referrer() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let from = Pubkey::new_unique();
+
+    let plan = pay_referral_reward(
+        &client,
+        Pubkey::new_unique(),
+        &reward(Some("goose"), spl(from)),
+    )
+    .unwrap();
+    assert_eq!(plan.variant, RewardVariant::CheckedGlobal);
+    assert_eq!(
+        plan.reason,
+        RewardVariantReason::OrganizationReferrer {
+            global_referrer: true
+        }
+    );
+    assert_eq!(plan.referrer.member, Some(REFERRER_MEMBER));
+    // Global referrer treasury and token account, referrer member
+    assert_eq!(
+        keys(&plan)[1..7],
+        [
+            REFERRER_TREASURY,
+            REFERRER_ATA,
+            REFERRER_MEMBER,
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_MEMBER
+        ]
+    );
+
+    // The referee signs
+    let plan = pay_referral_reward(
+        &client,
+        REFEREE_AUTHORITY,
+        &reward(Some("goose"), spl(from)),
+    )
+    .unwrap();
+    assert_eq!(plan.variant, RewardVariant::SecureLocal);
+    assert_eq!(plan.reason, RewardVariantReason::RefereeSigned);
+    assert_eq!(
+        keys(&plan)[7..],
+        [
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_TREASURY,
+            REFEREE_MEMBER,
+            REFERRER_ATA
+        ]
+    );
+
+    let plan = pay_referral_reward(
+        &client,
+        REFEREE_AUTHORITY,
+        &reward(Some("goose"), SharedRewardAsset::Sol),
+    )
+    .unwrap();
+    assert_eq!(plan.variant, RewardVariant::UncheckedLocalShared);
+    assert_eq!(plan.reason, RewardVariantReason::SolOrganizationReferrer);
+    assert_eq!(keys(&plan)[5..], [REFERRER_TREASURY, REFERRER_MEMBER]);
+}
+
+#[test]
+fn test_global_referrer() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Pubkey::new_unique();
+
+    let plan =
+        pay_referral_reward(&client, authority, &reward(None, SharedRewardAsset::Sol)).unwrap();
+    assert_eq!(plan.variant, RewardVariant::CheckedGlobalOnly);
+    assert_eq!(
+        plan.reason,
+        RewardVariantReason::GlobalReferrer(LocalReferrer::NoOrganization)
+    );
+    assert_eq!(plan.referrer.buddy, Some(REFERRER_GLOBAL_BUDDY));
+    assert_eq!(keys(&plan)[1..3], [REFERRER_TREASURY, REFERRER_TREASURY]);
+
+    let plan = pay_referral_reward(
+        &client,
+        authority,
+        &reward(Some("gooses"), spl(Pubkey::new_unique())),
+    )
+    .unwrap();
+    assert_eq!(plan.variant, RewardVariant::CheckedGlobalOnly);
+    assert_eq!(
+        plan.reason,
+        RewardVariantReason::GlobalReferrer(LocalReferrer::NotAMember)
+    );
+    assert_eq!(keys(&plan)[8], REFERRER_ATA);
+
+    // The referrer buddy is at the top of the referral tree
+    let result = pay_referral_reward(
+        &client,
+        authority,
+        &ReferralReward {
+            referee_buddy: REFERRER_GLOBAL_BUDDY,
+            ..reward(Some("goose"), SharedRewardAsset::Sol)
+        },
+    );
+    assert!(matches!(result, Err(Error::NoReferrer(buddy)) if buddy == REFERRER_GLOBAL_BUDDY));
+}
@@ -0,0 +1,416 @@
+mod deployed_program;
+
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token;
+use buddy_link::client::nonblocking::token_accounts as nonblocking;
+use buddy_link::client::token_accounts::{
+    missing_referrer_token_accounts, with_referrer_token_accounts,
+};
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::cpi;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    referrer_token_accounts, transfer_checked_global_only_reward, transfer_checked_global_reward,
+    transfer_secure_local_reward, GeneralTransferRewardArgs, ReferrerTokenAccount, RewardAsset,
+};
+use deployed_program::{add_snapshot, snapshot};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+/// Program creating referrer token accounts with the rent of its PDA, see [`process_sponsor`].
+const SPONSOR_PROGRAM_ID: Pubkey = pubkey!("Sponsor111111111111111111111111111111111111");
+
+fn process_sponsor(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let (_, bump) = Pubkey::find_program_address(&[b"sponsor"], program_id);
+
+    cpi::native::create_referrer_token_account(
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        next_account_info(accounts)?,
+        &[&[b"sponsor", &[bump]]],
+    )
+}
+
+/// The deployed BuddyLink program with the fixtures (without the referrer token account) and the sponsor program.
+async fn start(accounts: &[(Pubkey, Account)]) -> ProgramTestContext {
+    let mut snapshot = snapshot();
+
+    snapshot.remove(&REFERRER_ATA);
+    for (pubkey, account) in accounts {
+        snapshot.insert(*pubkey, account.clone());
+    }
+
+    let mut program_test = ProgramTest::default();
+    // The sponsor is native
+    program_test.prefer_bpf(false);
+    program_test.add_program("sponsor", SPONSOR_PROGRAM_ID, processor!(process_sponsor));
+    add_snapshot(&mut program_test, &snapshot);
+
+    program_test.start_with_context().await
+}
+
+async fn execute(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Token account of the authority with tokens of the fixture mint.
+fn source_account(authority: &Pubkey) -> (Pubkey, Account) {
+    let mut account = snapshot().accounts()[&REFERRER_ATA].clone();
+    let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    token_account.owner = *authority;
+    token_account.amount = 1_000_000;
+    token_account.pack_into_slice(&mut account.data);
+
+    (Pubkey::new_unique(), account)
+}
+
+fn referrer_token_account() -> ReferrerTokenAccount {
+    ReferrerTokenAccount {
+        token_account: REFERRER_ATA,
+        treasury: REFERRER_TREASURY,
+        mint: MINT,
+        token_program: spl_token::ID,
+    }
+}
+
+fn checked_global_reward(authority: Pubkey, from: Pubkey) -> Instruction {
+    // The global referrer of the referee buddy is also the organization referrer in the fixtures
+    transfer_checked_global_reward(
+        authority,
+        MINT,
+        spl_token::ID,
+        from,
+        REFERRER_ATA,
+        Some(REFERRER_MEMBER),
+        REFERRER_TREASURY,
+        REFERRER_TREASURY,
+        REFEREE_MEMBER,
+        Some(REFERRER_TREASURY),
+        Some(REFERRER_ATA),
+        &GeneralTransferRewardArgs { amount: 1_000 },
+    )
+}
+
+#[test]
+fn test_referrer_token_accounts() {
+    let [authority, from, referrer_treasury, treasury_for_reward, token_account] =
+        [(); 5].map(|_| Pubkey::new_unique());
+    let [global_treasury_for_reward, global_token_account, referee] =
+        [(); 3].map(|_| Pubkey::new_unique());
+    let args = GeneralTransferRewardArgs { amount: 1_000 };
+    let expected = |token_account, treasury| ReferrerTokenAccount {
+        token_account,
+        treasury,
+        mint: MINT,
+        token_program: spl_token::ID,
+    };
+
+    let instruction = transfer_checked_global_reward(
+        authority,
+        MINT,
+        spl_token::ID,
+        from,
+        token_account,
+        None,
+        referrer_treasury,
+        treasury_for_reward,
+        referee,
+        Some(global_treasury_for_reward),
+        Some(global_token_account),
+        &args,
+    );
+    assert_eq!(
+        referrer_token_accounts(&instruction),
+        [
+            expected(global_token_account, global_treasury_for_reward),
+            expected(token_account, treasury_for_reward),
+        ]
+    );
+    // Without global referrer
+    let instruction = transfer_checked_global_reward(
+        authority,
+        MINT,
+        spl_token::ID,
+        from,
+        token_account,
+        None,
+        referrer_treasury,
+        treasury_for_reward,
+        referee,
+        None,
+        None,
+        &args,
+    );
+    assert_eq!(
+        referrer_token_accounts(&instruction),
+        [expected(token_account, treasury_for_reward)]
+    );
+
+    let instruction = transfer_secure_local_reward(
+        authority,
+        MINT,
+        spl_token::ID,
+        from,
+        token_account,
+        Pubkey::new_unique(),
+        referrer_treasury,
+        treasury_for_reward,
+        referee,
+        referee,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &args,
+    );
+    assert_eq!(
+        referrer_token_accounts(&instruction),
+        [expected(token_account, treasury_for_reward)]
+    );
+
+    let global_only = |asset: &RewardAsset| {
+        transfer_checked_global_only_reward(
+            authority,
+            asset,
+            referrer_treasury,
+            treasury_for_reward,
+            referee,
+            referee,
+            &args,
+        )
+    };
+    let asset = RewardAsset::Spl {
+        mint: MINT,
+        token_program: spl_token::ID,
+        from,
+        to: token_account,
+    };
+    assert_eq!(
+        referrer_token_accounts(&global_only(&asset)),
+        [expected(token_account, treasury_for_reward)]
+    );
+    assert_eq!(referrer_token_accounts(&global_only(&RewardAsset::Sol)), []);
+    assert_eq!(
+        referrer_token_accounts(
+            &spl_token::instruction::sync_native(&spl_token::ID, &from).unwrap()
+        ),
+        []
+    );
+
+    assert!(referrer_token_account().is_associated());
+    assert!(!expected(token_account, treasury_for_reward).is_associated());
+}
+
+#[test]
+fn test_with_referrer_token_accounts() {
+    let authority = Pubkey::new_unique();
+    let rent_payer = Pubkey::new_unique();
+    let mut snapshot = snapshot();
+    let instruction = checked_global_reward(authority, Pubkey::new_unique());
+
+    // Paid twice to the same token account, it exists
+    assert_eq!(
+        missing_referrer_token_accounts(&snapshot, std::slice::from_ref(&instruction)).unwrap(),
+        []
+    );
+    assert_eq!(
+        with_referrer_token_accounts(&snapshot, rent_payer, vec![instruction.clone()]).unwrap(),
+        std::slice::from_ref(&instruction)
+    );
+
+    snapshot.remove(&REFERRER_ATA);
+    assert_eq!(
+        missing_referrer_token_accounts(&snapshot, &[instruction.clone(), instruction.clone()])
+            .unwrap(),
+        [referrer_token_account()]
+    );
+    let builder = TransactionBuilder::new(rent_payer)
+        .instruction(instruction.clone())
+        .create_referrer_token_accounts(&snapshot, rent_payer)
+        .unwrap();
+    let transaction = builder
+        .build_unsigned(200_000, None, Default::default())
+        .unwrap();
+    let keys = transaction.message.static_account_keys();
+    let instructions = transaction.message.instructions();
+    // Compute unit limit, creation, transfer
+    assert_eq!(instructions.len(), 3);
+    assert_eq!(
+        keys[usize::from(instructions[1].program_id_index)],
+        anchor_spl::associated_token::ID
+    );
+    assert_eq!(
+        keys[usize::from(instructions[2].program_id_index)],
+        BL_PROGRAM_ID
+    );
+
+    // Not the associated token account of the treasury, it can't be created
+    let token_account = Pubkey::new_unique();
+    let instruction = transfer_checked_global_reward(
+        authority,
+        MINT,
+        spl_token::ID,
+        Pubkey::new_unique(),
+        token_account,
+        None,
+        REFERRER_TREASURY,
+        REFERRER_TREASURY,
+        REFEREE_MEMBER,
+        None,
+        None,
+        &GeneralTransferRewardArgs { amount: 1_000 },
+    );
+    assert!(matches!(
+        with_referrer_token_accounts(&snapshot, rent_payer, vec![instruction]),
+        Err(Error::AccountNotFound(pubkey)) if pubkey == token_account
+    ));
+}
+
+#[tokio::test]
+async fn test_create_missing_referrer_token_account() {
+    let authority = Keypair::new();
+    let (from, source) = source_account(&authority.pubkey());
+    let mut context = start(&[(from, source)]).await;
+    let instruction = checked_global_reward(authority.pubkey(), from);
+
+    // Fails without the referrer token account
+    assert!(
+        execute(&mut context, std::slice::from_ref(&instruction), &authority)
+            .await
+            .is_err()
+    );
+
+    let rent_payer = context.payer.pubkey();
+    let instructions = nonblocking::with_referrer_token_accounts(
+        &context.banks_client,
+        rent_payer,
+        vec![instruction.clone()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(instructions.len(), 2);
+    execute(&mut context, &instructions, &authority)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(REFERRER_ATA)
+        .await
+        .unwrap()
+        .unwrap();
+    let token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(token_account.owner, REFERRER_TREASURY);
+    assert_eq!(token_account.amount, 1_000);
+    // Nothing to create anymore
+    assert_eq!(
+        nonblocking::missing_referrer_token_accounts(&context.banks_client, &[instruction])
+            .await
+            .unwrap(),
+        []
+    );
+}
+
+#[tokio::test]
+async fn test_cpi_create_referrer_token_account() {
+    let (sponsor, _) = Pubkey::find_program_address(&[b"sponsor"], &SPONSOR_PROGRAM_ID);
+    let mut context =
+        start(&[(sponsor, Account::new(1_000_000_000, 0, &system_program::ID))]).await;
+    let sponsor_instruction = |token_account, treasury| Instruction {
+        program_id: SPONSOR_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(anchor_spl::associated_token::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(sponsor, false),
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(treasury, false),
+            AccountMeta::new_readonly(MINT, false),
+        ],
+        data: vec![],
+    };
+    let payer = context.payer.insecure_clone();
+
+    // The owner must be a BuddyLink treasury
+    let wallet = Pubkey::new_unique();
+    let wallet_account =
+        get_associated_token_address_with_program_id(&wallet, &MINT, &spl_token::ID);
+    let error = execute(
+        &mut context,
+        &[sponsor_instruction(wallet_account, wallet)],
+        &payer,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(BuddyLinkError::InvalidReferrerTreasury.code())
+        )
+    );
+
+    let before = context.banks_client.get_balance(sponsor).await.unwrap();
+    execute(
+        &mut context,
+        &[sponsor_instruction(REFERRER_ATA, REFERRER_TREASURY)],
+        &payer,
+    )
+    .await
+    .unwrap();
+    let rent = context
+        .banks_client
+        .get_balance(REFERRER_ATA)
+        .await
+        .unwrap();
+    assert!(rent > 0);
+    assert_eq!(
+        context.banks_client.get_balance(sponsor).await.unwrap(),
+        before - rent
+    );
+
+    // Already created, nothing is paid
+    context.get_new_latest_blockhash().await.unwrap();
+    execute(
+        &mut context,
+        &[sponsor_instruction(REFERRER_ATA, REFERRER_TREASURY)],
+        &payer,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(sponsor).await.unwrap(),
+        before - rent
+    );
+}
@@ -0,0 +1,357 @@
+mod deployed_program;
+
+use anchor_spl::token::spl_token;
+use buddy_link::client::AccountSnapshot;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    reward_outcome, shared_reward_amounts, transfer_checked_global_reward,
+    transfer_unchecked_local_shared_reward, GeneralTransferRewardArgs, RewardOutcome,
+    RewardVariant, SharedRewardAsset, TransferUncheckedLocalSharedRewardArgs,
+};
+use buddy_link::state::{Buddy, Member};
+use deployed_program::{add_snapshot, snapshot};
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+/// The deployed BuddyLink program (not the mock) with the fixtures, `edit` changes them before the start.
+async fn start(edit: impl FnOnce(&mut AccountSnapshot)) -> ProgramTestContext {
+    let mut snapshot = snapshot();
+    edit(&mut snapshot);
+
+    let mut program_test = ProgramTest::default();
+    add_snapshot(&mut program_test, &snapshot);
+
+    program_test.start_with_context().await
+}
+
+fn set_token_amount(snapshot: &mut AccountSnapshot, address: Pubkey, owner: Pubkey, amount: u64) {
+    let mut account = snapshot.accounts()[&REFERRER_ATA].clone();
+    let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    token_account.owner = owner;
+    token_account.amount = amount;
+    token_account.pack_into_slice(&mut account.data);
+    snapshot.insert(address, account);
+}
+
+async fn token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+async fn execute(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn checked_global_reward(authority: Pubkey, from: Pubkey, amount: u64) -> Instruction {
+    // The global referrer of the referee buddy is also the organization referrer in the fixtures
+    transfer_checked_global_reward(
+        authority,
+        MINT,
+        anchor_spl::token::ID,
+        from,
+        REFERRER_ATA,
+        Some(REFERRER_MEMBER),
+        REFERRER_TREASURY,
+        REFERRER_TREASURY,
+        REFEREE_MEMBER,
+        Some(REFERRER_TREASURY),
+        Some(REFERRER_ATA),
+        &GeneralTransferRewardArgs { amount },
+    )
+}
+
+/// Token transfers run by the program before the end (or the failure) of the instruction.
+async fn simulated_transfers(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    authority: &Keypair,
+) -> usize {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+
+    simulation
+        .simulation_details
+        .unwrap()
+        .logs
+        .iter()
+        .filter(|x| *x == "Program log: Instruction: Transfer")
+        .count()
+}
+
+fn set_referred(context: &mut ProgramTestContext, snapshot: &AccountSnapshot, referred: bool) {
+    let mut account = snapshot.accounts()[&REFEREE_MEMBER].clone();
+    account.data[Member::<&[u8]>::REFERRED_OFFSET] = u8::from(referred);
+    context.set_account(&REFEREE_MEMBER, &account.into());
+}
+
+/// Sets the amount of the referrer token account so that only `headroom` more tokens can be received.
+fn set_headroom(context: &mut ProgramTestContext, snapshot: &mut AccountSnapshot, headroom: u64) {
+    set_token_amount(
+        snapshot,
+        REFERRER_ATA,
+        REFERRER_TREASURY,
+        u64::MAX - headroom,
+    );
+    context.set_account(
+        &REFERRER_ATA,
+        &snapshot.accounts()[&REFERRER_ATA].clone().into(),
+    );
+}
+
+#[test]
+fn test_reward_outcome() {
+    let snapshot = snapshot();
+    let member = Member::new(&snapshot.accounts()[&REFEREE_MEMBER].data[..]).unwrap();
+    let buddy = Buddy::new(&snapshot.accounts()[&REFEREE_GLOBAL_BUDDY].data[..]).unwrap();
+    let mut not_referred_data = snapshot.accounts()[&REFEREE_MEMBER].data.clone();
+    not_referred_data[Member::<&[u8]>::REFERRED_OFFSET] = 0;
+    let not_referred = Member::new(&not_referred_data[..]).unwrap();
+    let mut no_global_referrer_data = snapshot.accounts()[&REFEREE_GLOBAL_BUDDY].data.clone();
+    no_global_referrer_data[Buddy::<&[u8]>::REFERRER_TREASURY_OFFSET..][..32].fill(0);
+    let no_global_referrer = Buddy::new(&no_global_referrer_data[..]).unwrap();
+
+    assert!(member.is_referred().unwrap());
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            Some(&member),
+            Some(&buddy)
+        ),
+        Ok(RewardOutcome {
+            local_referrer: 975,
+            global_referrer: 24,
+        })
+    );
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            Some(&not_referred),
+            Some(&buddy)
+        ),
+        Ok(RewardOutcome {
+            local_referrer: 900,
+            global_referrer: 99,
+        })
+    );
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            Some(&member),
+            Some(&no_global_referrer)
+        ),
+        Ok(RewardOutcome {
+            local_referrer: 999,
+            global_referrer: 0,
+        })
+    );
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            Some(&member),
+            None::<&Buddy<&[u8]>>
+        ),
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            Some(&member),
+            Some(&no_global_referrer)
+        )
+    );
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            999,
+            None::<&Member<&[u8]>>,
+            Some(&buddy)
+        ),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+    assert_eq!(
+        reward_outcome(
+            RewardVariant::CheckedGlobal,
+            u64::MAX / 500,
+            Some(&not_referred),
+            Some(&buddy)
+        ),
+        Err(ProgramError::ArithmeticOverflow)
+    );
+
+    for (variant, local_referrer, global_referrer) in [
+        (RewardVariant::CheckedGlobalOnly, 0, 999),
+        (RewardVariant::SecureLocal, 999, 0),
+        (RewardVariant::UncheckedLocalShared, 999, 0),
+    ] {
+        let outcome = reward_outcome(variant, 999, Some(&member), Some(&buddy)).unwrap();
+        assert_eq!(outcome.local_referrer, local_referrer);
+        assert_eq!(outcome.global_referrer, global_referrer);
+        assert_eq!(outcome.total(), 999);
+    }
+
+    assert_eq!(
+        shared_reward_amounts(1_000, &[3_333, 3_333, 3_334]),
+        Ok(vec![333, 333, 333])
+    );
+    assert_eq!(
+        shared_reward_amounts(1_000, &[5_000, 4_000]),
+        Err(BuddyLinkError::InvalidBPSProvided.into())
+    );
+}
+
+/// The amounts of the calculator are the ones transferred by the deployed program.
+#[tokio::test]
+async fn test_checked_global_reward() {
+    let authority = Keypair::new();
+    let from = Pubkey::new_unique();
+    let mut snapshot = snapshot();
+    let mut context = start(|snapshot| {
+        set_token_amount(snapshot, from, authority.pubkey(), 10_000_000);
+    })
+    .await;
+    let buddy_data = snapshot.accounts()[&REFEREE_GLOBAL_BUDDY].data.clone();
+    let buddy = Buddy::new(&buddy_data[..]).unwrap();
+
+    for referred in [true, false] {
+        set_referred(&mut context, &snapshot, referred);
+        // The same transfers are sent again
+        context.get_new_latest_blockhash().await.unwrap();
+        let mut member_data = snapshot.accounts()[&REFEREE_MEMBER].data.clone();
+        member_data[Member::<&[u8]>::REFERRED_OFFSET] = u8::from(referred);
+        let member = Member::new(&member_data[..]).unwrap();
+
+        for amount in [1, 9, 10, 39, 40, 999, 1_000_000] {
+            let outcome = reward_outcome(
+                RewardVariant::CheckedGlobal,
+                amount,
+                Some(&member),
+                Some(&buddy),
+            )
+            .unwrap();
+            let instruction = checked_global_reward(authority.pubkey(), from, amount);
+
+            // Both referrers are paid to the same token account, the global referrer first:
+            // its share fits exactly in the first transfer
+            set_headroom(&mut context, &mut snapshot, outcome.global_referrer);
+            assert_eq!(
+                simulated_transfers(&mut context, instruction.clone(), &authority).await,
+                2,
+                "{amount} {referred}"
+            );
+            if outcome.global_referrer > 0 {
+                set_headroom(&mut context, &mut snapshot, outcome.global_referrer - 1);
+                assert_eq!(
+                    simulated_transfers(&mut context, instruction.clone(), &authority).await,
+                    1,
+                    "{amount} {referred}"
+                );
+            }
+
+            set_headroom(&mut context, &mut snapshot, amount);
+            let before = token_amount(&mut context, from).await;
+            execute(&mut context, instruction, &authority)
+                .await
+                .unwrap();
+            assert_eq!(
+                before - token_amount(&mut context, from).await,
+                outcome.total()
+            );
+            assert_eq!(token_amount(&mut context, REFERRER_ATA).await, u64::MAX);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_shared_reward() {
+    let authority = Keypair::new();
+    let recipients = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let mut context = start(|snapshot| {
+        for recipient in recipients.iter().chain([&authority.pubkey()]) {
+            snapshot.insert(
+                *recipient,
+                Account::new(1_000_000_000, 0, &solana_program::system_program::ID),
+            );
+        }
+    })
+    .await;
+
+    for (total_amount, shares_in_bps) in [
+        (1_000, vec![3_333, 3_333, 3_334]),
+        (999, vec![5_000, 2_500, 2_500]),
+        (1_000_001, vec![1, 9_998, 1]),
+    ] {
+        let amounts = shared_reward_amounts(total_amount, &shares_in_bps).unwrap();
+        let mut before = vec![];
+        for recipient in recipients {
+            before.push(context.banks_client.get_balance(recipient).await.unwrap());
+        }
+
+        let instruction = transfer_unchecked_local_shared_reward(
+            authority.pubkey(),
+            &SharedRewardAsset::Sol,
+            &recipients,
+            &TransferUncheckedLocalSharedRewardArgs {
+                total_amount,
+                shares_in_bps,
+                members_included: false,
+            },
+        );
+        execute(&mut context, instruction, &authority)
+            .await
+            .unwrap();
+
+        for (i, recipient) in recipients.into_iter().enumerate() {
+            let after = context.banks_client.get_balance(recipient).await.unwrap();
+            assert_eq!(after - before[i], amounts[i]);
+        }
+    }
+}
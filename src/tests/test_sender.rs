@@ -0,0 +1,174 @@
+mod fixture_sender;
+
+use buddy_link::client::sender::{
+    decode_buddy_link_error, ReliableSender, SendOutcome, SendProgress,
+};
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::error::BuddyLinkError;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, GeneralTransferRewardArgs, RewardAsset,
+};
+use fixture_sender::{client, FixtureSender};
+use serde_json::json;
+use solana_program::hash::Hash;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use std::cell::RefCell;
+use std::time::Duration;
+
+/// Compute unit limit then the reward, at index 1.
+fn reward_transaction(authority: &Keypair) -> VersionedTransaction {
+    TransactionBuilder::new(authority.pubkey())
+        .instruction(transfer_checked_global_only_reward(
+            authority.pubkey(),
+            &RewardAsset::Sol,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        ))
+        .build_with(30_000, None, Hash::new_from_array([1; 32]), &[authority])
+        .unwrap()
+}
+
+#[test]
+fn test_confirmed() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let transaction = reward_transaction(&authority);
+
+    let progress = RefCell::new(Vec::new());
+    let on_progress = |x: &SendProgress| progress.borrow_mut().push(x.clone());
+    let outcome = ReliableSender::new(&client)
+        .on_progress(&on_progress)
+        .send(&transaction, 150)
+        .unwrap();
+
+    let signature = transaction.signatures[0];
+    assert_eq!(outcome, SendOutcome::Confirmed { signature, slot: 1 });
+    assert!(!outcome.is_safe_to_retry());
+    assert_eq!(
+        progress.into_inner(),
+        vec![SendProgress::Sent {
+            signature,
+            broadcast: 1
+        }]
+    );
+    assert_eq!(sender.sent(), vec![transaction]);
+}
+
+#[test]
+fn test_rebroadcast_until_expired() {
+    let sender = FixtureSender::new();
+    sender.drop_transactions(true);
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let transaction = reward_transaction(&authority);
+
+    // The blockhash expires after the third broadcast
+    let waiting = RefCell::new(0);
+    let on_progress = |x: &SendProgress| match x {
+        SendProgress::Sent { broadcast: 3, .. } => sender.set_block_height(151),
+        SendProgress::Waiting {
+            block_height,
+            last_valid_block_height,
+            ..
+        } => {
+            assert_eq!((*block_height, *last_valid_block_height), (0, 150));
+            *waiting.borrow_mut() += 1;
+        }
+        _ => {}
+    };
+    let outcome = ReliableSender::new(&client)
+        .rebroadcast_interval(Duration::ZERO)
+        .poll_interval(Duration::ZERO)
+        .on_progress(&on_progress)
+        .send(&transaction, 150)
+        .unwrap();
+
+    assert_eq!(
+        outcome,
+        SendOutcome::Expired {
+            signature: transaction.signatures[0]
+        }
+    );
+    assert!(outcome.is_safe_to_retry());
+    assert_eq!(waiting.into_inner(), 2);
+    assert_eq!(sender.sent(), vec![transaction; 3]);
+}
+
+#[test]
+fn test_unknown() {
+    let sender = FixtureSender::new();
+    sender.drop_transactions(true);
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let transaction = reward_transaction(&authority);
+
+    let outcome = ReliableSender::new(&client)
+        .timeout(Duration::ZERO)
+        .send(&transaction, 150)
+        .unwrap();
+
+    // Still valid, it may land
+    assert_eq!(
+        outcome,
+        SendOutcome::Unknown {
+            signature: transaction.signatures[0]
+        }
+    );
+    assert!(!outcome.is_safe_to_retry());
+}
+
+#[test]
+fn test_failed() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+    let transaction = reward_transaction(&authority);
+    sender.land(
+        transaction.signatures[0],
+        Some(json!({ "InstructionError": [1, { "Custom": 6001 }] })),
+    );
+
+    let outcome = ReliableSender::new(&client)
+        .send(&transaction, 150)
+        .unwrap();
+
+    assert_eq!(
+        outcome,
+        SendOutcome::Failed {
+            signature: transaction.signatures[0],
+            slot: 1,
+            error: TransactionError::InstructionError(1, InstructionError::Custom(6001)),
+            buddy_link_error: Some(BuddyLinkError::CantCreateMemberWithReferrer),
+        }
+    );
+    assert!(outcome.is_safe_to_retry());
+}
+
+#[test]
+fn test_decode_buddy_link_error() {
+    let message = reward_transaction(&Keypair::new()).message;
+    let custom =
+        |index, code| TransactionError::InstructionError(index, InstructionError::Custom(code));
+
+    assert_eq!(
+        decode_buddy_link_error(&message, &custom(1, 6031)),
+        Some(BuddyLinkError::InvalidBPSProvided)
+    );
+    // Not a BuddyLink instruction
+    assert_eq!(decode_buddy_link_error(&message, &custom(0, 6031)), None);
+    // Error of the token program (insufficient funds)
+    assert_eq!(decode_buddy_link_error(&message, &custom(1, 1)), None);
+    assert_eq!(decode_buddy_link_error(&message, &custom(2, 6031)), None);
+    assert_eq!(
+        decode_buddy_link_error(&message, &TransactionError::AccountNotFound),
+        None
+    );
+}
@@ -0,0 +1,328 @@
+mod fixture_sender;
+
+use buddy_link::client::nonblocking;
+use buddy_link::client::simulation::{
+    decode_logs, instruction_parties, simulate_reward, BalanceChange, Party, ProgramLog,
+};
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, transfer_checked_global_reward,
+    transfer_unchecked_local_shared_reward, GeneralTransferRewardArgs, RewardAsset,
+    SharedRewardAsset, TransferUncheckedLocalSharedRewardArgs,
+};
+use fixture_sender::{client, nonblocking_client, FixtureSender, SIMULATED_UNITS};
+use serde_json::json;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_ATA: Pubkey = pubkey!("C4yA9kJKohWhmGKAMGhJWRB827UdR6aVRUu82mGnmNwV");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+
+/// Token account of the mint of the fixtures with another owner and amount.
+fn token_account(sender: &FixtureSender, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = sender.data(&REFERRER_ATA);
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_instruction_parties() {
+    let authority = Pubkey::new_unique();
+    let from = Pubkey::new_unique();
+    let args = GeneralTransferRewardArgs { amount: 1_000 };
+
+    let instruction = transfer_checked_global_only_reward(
+        authority,
+        &RewardAsset::Sol,
+        REFERRER_TREASURY,
+        REFERRER_TREASURY,
+        REFEREE_GLOBAL_BUDDY,
+        REFEREE_GLOBAL_BUDDY,
+        &args,
+    );
+    assert_eq!(
+        instruction_parties(&instruction),
+        vec![
+            (Party::Authority, authority),
+            (Party::GlobalReferrerTreasury, REFERRER_TREASURY),
+            (Party::GlobalReferrerTreasuryForReward, REFERRER_TREASURY),
+        ]
+    );
+
+    // The optional accounts left out aren't parties
+    let referrer_treasury = Pubkey::new_unique();
+    let referee_member = Pubkey::new_unique();
+    let instruction = transfer_checked_global_reward(
+        authority,
+        MINT,
+        anchor_spl::token::ID,
+        from,
+        REFERRER_ATA,
+        None,
+        referrer_treasury,
+        referrer_treasury,
+        referee_member,
+        None,
+        None,
+        &args,
+    );
+    assert_eq!(
+        instruction_parties(&instruction),
+        vec![
+            (Party::Authority, authority),
+            (Party::ReferrerTreasury, referrer_treasury),
+            (Party::ReferrerTreasuryForReward, referrer_treasury),
+            (Party::RefereeMember, referee_member),
+            (Party::Source, from),
+            (Party::ReferrerTokenAccount, REFERRER_ATA),
+        ]
+    );
+
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let members = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let instruction = transfer_unchecked_local_shared_reward(
+        authority,
+        &SharedRewardAsset::Sol,
+        &[recipients[0], members[0], recipients[1], members[1]],
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount: 1_000,
+            shares_in_bps: vec![5_000, 5_000],
+            members_included: true,
+        },
+    );
+    assert_eq!(
+        instruction_parties(&instruction),
+        vec![
+            (Party::Authority, authority),
+            (Party::SharedRecipient(0), recipients[0]),
+            (Party::SharedRecipientMember(0), members[0]),
+            (Party::SharedRecipient(1), recipients[1]),
+            (Party::SharedRecipientMember(1), members[1]),
+        ]
+    );
+
+    let other = solana_sdk::system_instruction::transfer(&authority, &from, 1);
+    assert!(instruction_parties(&other).is_empty());
+}
+
+#[test]
+fn test_decode_logs() {
+    let logs = [
+        format!("Program {} invoke [1]", BL_PROGRAM_ID),
+        "Program log: Instruction: TransferRewardGlobal".to_string(),
+        format!("Program {} invoke [2]", anchor_spl::token::ID),
+        "Program log: Instruction: TransferChecked".to_string(),
+        format!(
+            "Program {} consumed 6200 of 180000 compute units",
+            anchor_spl::token::ID
+        ),
+        format!("Program {} success", anchor_spl::token::ID),
+        "Program data: AQID BAU=".to_string(),
+        format!("Program return: {} Kg==", BL_PROGRAM_ID),
+        format!(
+            "Program {} failed: custom program error: 0x1774",
+            BL_PROGRAM_ID
+        ),
+        "Log truncated".to_string(),
+    ];
+
+    assert_eq!(
+        decode_logs(&logs),
+        vec![
+            ProgramLog::Invoke {
+                program: BL_PROGRAM_ID,
+                depth: 1,
+            },
+            ProgramLog::Log {
+                program: Some(BL_PROGRAM_ID),
+                message: "Instruction: TransferRewardGlobal".to_string(),
+            },
+            ProgramLog::Invoke {
+                program: anchor_spl::token::ID,
+                depth: 2,
+            },
+            ProgramLog::Log {
+                program: Some(anchor_spl::token::ID),
+                message: "Instruction: TransferChecked".to_string(),
+            },
+            ProgramLog::Consumed {
+                program: anchor_spl::token::ID,
+                units: 6_200,
+                limit: 180_000,
+            },
+            ProgramLog::Success {
+                program: anchor_spl::token::ID,
+            },
+            ProgramLog::Data {
+                program: Some(BL_PROGRAM_ID),
+                data: vec![vec![1, 2, 3], vec![4, 5]],
+            },
+            ProgramLog::Return {
+                program: BL_PROGRAM_ID,
+                data: vec![42],
+            },
+            ProgramLog::Failed {
+                program: BL_PROGRAM_ID,
+                error: "custom program error: 0x1774".to_string(),
+            },
+            ProgramLog::Other("Log truncated".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_simulate_sol_reward() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Pubkey::new_unique();
+    let treasury_lamports = 4_704_960;
+
+    sender.set_account(authority, 1_000_000_000, &[], system_program::ID);
+    sender.simulate_account(authority, 1_000_000_000 - 1_000, &[], system_program::ID);
+    sender.simulate_account(
+        REFERRER_TREASURY,
+        treasury_lamports + 1_000,
+        &sender.data(&REFERRER_TREASURY),
+        BL_PROGRAM_ID,
+    );
+
+    let builder =
+        TransactionBuilder::new(authority).instruction(transfer_checked_global_only_reward(
+            authority,
+            &RewardAsset::Sol,
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        ));
+    let simulation = simulate_reward(&client, &builder).unwrap();
+
+    assert_eq!(simulation.balances.len(), 2);
+    let payer = simulation.party(Party::Authority).unwrap();
+    assert_eq!(payer.parties, [Party::FeePayer, Party::Authority]);
+    assert_eq!(payer.lamports.delta(), -1_000);
+    assert_eq!(payer.token, None);
+
+    let treasury = simulation.account(&REFERRER_TREASURY).unwrap();
+    assert_eq!(
+        treasury.parties,
+        [
+            Party::GlobalReferrerTreasury,
+            Party::GlobalReferrerTreasuryForReward
+        ]
+    );
+    assert_eq!(
+        treasury.lamports,
+        BalanceChange {
+            pre: treasury_lamports,
+            post: treasury_lamports + 1_000,
+        }
+    );
+
+    assert_eq!(simulation.units_consumed, Some(SIMULATED_UNITS));
+    assert_eq!(
+        simulation.logs,
+        [ProgramLog::Log {
+            program: None,
+            message: "simulated".to_string(),
+        }]
+    );
+
+    sender.fail_simulations(json!({ "InstructionError": [0, { "Custom": 6004 }] }));
+    let Err(Error::Simulation { logs, .. }) = simulate_reward(&client, &builder) else {
+        panic!("the simulation should fail");
+    };
+    assert_eq!(logs, ["Program log: simulated"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_simulate_spl_reward() {
+    let sender = FixtureSender::new();
+    let authority = Pubkey::new_unique();
+    let from = Pubkey::new_unique();
+    let ata_lamports = 2_039_280;
+
+    sender.set_account(
+        from,
+        ata_lamports,
+        &token_account(&sender, &authority, 5_000),
+        anchor_spl::token::ID,
+    );
+    sender.simulate_account(
+        from,
+        ata_lamports,
+        &token_account(&sender, &authority, 4_000),
+        anchor_spl::token::ID,
+    );
+    sender.simulate_account(
+        REFERRER_ATA,
+        ata_lamports,
+        &token_account(&sender, &REFERRER_TREASURY, 1_000),
+        anchor_spl::token::ID,
+    );
+
+    let builder =
+        TransactionBuilder::new(authority).instruction(transfer_checked_global_only_reward(
+            authority,
+            &RewardAsset::Spl {
+                mint: MINT,
+                token_program: anchor_spl::token::ID,
+                from,
+                to: REFERRER_ATA,
+            },
+            REFERRER_TREASURY,
+            REFERRER_TREASURY,
+            REFEREE_GLOBAL_BUDDY,
+            REFEREE_GLOBAL_BUDDY,
+            &GeneralTransferRewardArgs { amount: 1_000 },
+        ));
+    let simulation = simulate_reward(&client(&sender), &builder).unwrap();
+
+    // The authority doesn't exist, its balances are 0
+    assert_eq!(
+        simulation.party(Party::Authority).unwrap().lamports,
+        BalanceChange::default()
+    );
+    assert_eq!(
+        simulation
+            .party(Party::GlobalReferrerTreasury)
+            .unwrap()
+            .lamports
+            .delta(),
+        0
+    );
+
+    let source = simulation.party(Party::Source).unwrap().token.unwrap();
+    assert_eq!(source.mint, MINT);
+    assert_eq!(source.owner, authority);
+    assert_eq!(source.amount.delta(), -1_000);
+
+    let referrer = simulation
+        .party(Party::GlobalReferrerTokenAccount)
+        .unwrap()
+        .token
+        .unwrap();
+    assert_eq!(referrer.owner, REFERRER_TREASURY);
+    assert_eq!(
+        referrer.amount,
+        BalanceChange {
+            pre: 0,
+            post: 1_000,
+        }
+    );
+
+    assert_eq!(
+        nonblocking::simulation::simulate_reward(&nonblocking_client(&sender), &builder)
+            .await
+            .unwrap(),
+        simulation
+    );
+}
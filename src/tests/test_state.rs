@@ -0,0 +1,129 @@
+use base64::Engine;
+use buddy_link::state::{Buddy, Member, Treasury, TreasuryOwner};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::pubkey;
+
+//Taken from the amman configs (copied from devnet)
+const MINT: Pubkey = pubkey!("3Q6dz8cLd4BW1kyuGyUaS7qhTtFP7tGS55Y7fybCUfNy");
+const REFERRER_GLOBAL_BUDDY: Pubkey = pubkey!("4jHbHkwjJoZgDBsx774LAmmqxPuGwk65SVdV6yr5Xjsm");
+const REFERRER_TREASURY: Pubkey = pubkey!("AsY9QzsVwu6KX9N5Yy85M5jaMYitPYrAC7vuCY6A3YKf");
+const REFERRER_MEMBER: Pubkey = pubkey!("GZ3oVbxW1LY26LsbZKJEqbv7AXiJGsMtW9emm4wdexN9");
+const REFEREE_AUTHORITY: Pubkey = pubkey!("HFnGHHTEKdggiHVFYEs1VAKKmjPvoD31HQsApkZqHqEx");
+const REFEREE_GLOBAL_BUDDY: Pubkey = pubkey!("DLCAgJho2Fm3g2SEWHzfiuJdLMRhqUVDWtssqhCeCdYr");
+const REFEREE_TREASURY: Pubkey = pubkey!("CMLckMKGfa5MTeovcfr9Rhgg31rFcAGS9ZKMXigaHMWJ");
+const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
+
+fn load_fixture(pubkey: &Pubkey) -> (Pubkey, Vec<u8>) {
+    let path = format!("{}/.amman/accounts/{}.json", env!("CARGO_MANIFEST_DIR"), pubkey);
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+    let owner = json["account"]["owner"].as_str().unwrap().parse().unwrap();
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(json["account"]["data"][0].as_str().unwrap())
+        .unwrap();
+
+    (owner, data)
+}
+
+#[test]
+fn test_member_view() {
+    let (_, data) = load_fixture(&REFEREE_MEMBER);
+    let member = Member::new(data.as_slice()).unwrap();
+
+    assert_eq!(data.len(), Member::<&[u8]>::LEN);
+    assert_eq!(member.referrer_treasury(), Some(REFERRER_TREASURY));
+    assert_eq!(member.owner_treasury(), REFEREE_TREASURY);
+    assert_eq!(member.name().unwrap(), "9nahl85m8acshkeb");
+    assert_eq!(member.organization_name().unwrap(), "goose");
+    assert_eq!(member.created_at().unwrap(), 1_690_463_390);
+
+    let (_, data) = load_fixture(&REFERRER_MEMBER);
+    let member = Member::new(data.as_slice()).unwrap();
+
+    assert_eq!(member.referrer_treasury(), None);
+    assert_eq!(member.owner_treasury(), REFERRER_TREASURY);
+}
+
+#[test]
+fn test_treasury_view() {
+    let (_, data) = load_fixture(&REFERRER_TREASURY);
+    let treasury = Treasury::new(data.as_slice()).unwrap();
+
+    assert_eq!(data.len(), Treasury::<&[u8]>::LEN);
+    assert_eq!(treasury.mint(), MINT);
+    assert_eq!(treasury.owners_count(), 1);
+    assert_eq!(
+        treasury.owner(0),
+        Some(TreasuryOwner {
+            buddy: REFERRER_GLOBAL_BUDDY,
+            share_in_bps: 10_000,
+        })
+    );
+    assert!(treasury.is_owned_by(&REFERRER_GLOBAL_BUDDY));
+    assert!(!treasury.is_owned_by(&REFEREE_GLOBAL_BUDDY));
+    assert_eq!(treasury.owner(1), None);
+}
+
+#[test]
+fn test_buddy_view() {
+    let (_, data) = load_fixture(&REFEREE_GLOBAL_BUDDY);
+    let buddy = Buddy::new(data.as_slice()).unwrap();
+
+    assert_eq!(data.len(), Buddy::<&[u8]>::LEN);
+    assert_eq!(buddy.authority(), REFEREE_AUTHORITY);
+    assert_eq!(buddy.referrer_treasury(), Some(REFERRER_TREASURY));
+    assert_eq!(buddy.created_at(), 1_687_026_902);
+    assert_eq!(buddy.name().unwrap(), "77ojbdt7wv2fnaaxo9");
+
+    let (_, data) = load_fixture(&REFERRER_GLOBAL_BUDDY);
+    let buddy = Buddy::new(data.as_slice()).unwrap();
+
+    assert_eq!(buddy.referrer_treasury(), None);
+    assert!(Buddy::new(load_fixture(&REFEREE_MEMBER).1.as_slice()).is_err());
+}
+
+#[test]
+fn test_view_from_account_info() {
+    let (owner, mut data) = load_fixture(&REFEREE_TREASURY);
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &REFEREE_TREASURY,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let treasury = Treasury::from_account_info(&account).unwrap();
+    assert!(treasury.is_owned_by(&REFEREE_GLOBAL_BUDDY));
+
+    assert_eq!(
+        Member::from_account_info(&account).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+
+    let wrong_owner = Pubkey::new_unique();
+    let (_, mut data) = load_fixture(&REFEREE_MEMBER);
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &REFEREE_MEMBER,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &wrong_owner,
+        false,
+        0,
+    );
+
+    assert_eq!(
+        Member::from_account_info(&account).err(),
+        Some(ProgramError::IllegalOwner)
+    );
+}
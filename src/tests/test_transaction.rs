@@ -0,0 +1,149 @@
+mod fixture_sender;
+
+use buddy_link::client::transaction::{
+    compute_unit_limit_with_margin, preflight, with_compute_budget, FixedPriorityFee,
+    PriorityFeeEstimator, TransactionBuilder, MAX_COMPUTE_UNIT_LIMIT,
+};
+use buddy_link::client::Error;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::instruction::{
+    transfer_checked_global_only_reward, GeneralTransferRewardArgs, RewardAsset,
+};
+use fixture_sender::{client, FixtureSender, SIMULATED_UNITS};
+use serde_json::json;
+use solana_program::address_lookup_table::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::VersionedMessage;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+fn reward_instruction(authority: Pubkey) -> Instruction {
+    transfer_checked_global_only_reward(
+        authority,
+        &RewardAsset::Sol,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &GeneralTransferRewardArgs { amount: 1_000 },
+    )
+}
+
+#[test]
+fn test_compute_budget_instructions() {
+    let instruction = reward_instruction(Pubkey::new_unique());
+
+    let instructions =
+        with_compute_budget(std::slice::from_ref(&instruction), Some(30_000), Some(5));
+    assert_eq!(instructions.len(), 3);
+    assert_eq!(
+        instructions[0],
+        ComputeBudgetInstruction::set_compute_unit_limit(30_000)
+    );
+    assert_eq!(
+        instructions[1],
+        ComputeBudgetInstruction::set_compute_unit_price(5)
+    );
+    assert_eq!(instructions[2], instruction);
+
+    assert_eq!(with_compute_budget(&[instruction], None, None).len(), 1);
+}
+
+#[test]
+fn test_compute_unit_margin() {
+    assert_eq!(compute_unit_limit_with_margin(20_000, 1_000), 22_000);
+    assert_eq!(compute_unit_limit_with_margin(20_000, 0), 20_000);
+    assert_eq!(
+        compute_unit_limit_with_margin(1_300_000, 1_000),
+        MAX_COMPUTE_UNIT_LIMIT
+    );
+}
+
+#[test]
+fn test_separate_fee_payer() {
+    let sponsor = Keypair::new();
+    let authority = Keypair::new();
+    let instruction = reward_instruction(authority.pubkey());
+
+    let builder = TransactionBuilder::new(sponsor.pubkey()).instruction(instruction);
+    let estimator = FixedPriorityFee(10);
+    let price = estimator
+        .compute_unit_price(&builder.writable_accounts())
+        .unwrap();
+
+    let transaction = builder
+        .build_with(
+            25_000,
+            Some(price),
+            Hash::default(),
+            &[&sponsor, &authority],
+        )
+        .unwrap();
+
+    let VersionedMessage::Legacy(message) = &transaction.message else {
+        panic!("expected a legacy message");
+    };
+    assert_eq!(message.account_keys[0], sponsor.pubkey());
+    assert_eq!(message.account_keys[1], authority.pubkey());
+    assert_eq!(message.header.num_required_signatures, 2);
+    assert_eq!(message.instructions.len(), 3);
+    assert!(transaction.verify_with_results().iter().all(|valid| *valid));
+
+    // The authority has to sign
+    assert!(TransactionBuilder::new(sponsor.pubkey())
+        .instruction(reward_instruction(authority.pubkey()))
+        .build_with(25_000, None, Hash::default(), &[&sponsor])
+        .is_err());
+}
+
+#[test]
+fn test_v0_with_lookup_tables() {
+    let authority = Keypair::new();
+    let instruction = reward_instruction(authority.pubkey());
+
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: instruction.accounts[1..5]
+            .iter()
+            .map(|x| x.pubkey)
+            .chain([solana_program::system_program::ID])
+            .collect(),
+    };
+
+    let transaction = TransactionBuilder::new(authority.pubkey())
+        .instruction(instruction)
+        .lookup_tables([table])
+        .build_with(25_000, None, Hash::default(), &[&authority])
+        .unwrap();
+
+    let VersionedMessage::V0(message) = &transaction.message else {
+        panic!("expected a v0 message");
+    };
+    assert_eq!(
+        message.account_keys,
+        vec![authority.pubkey(), compute_budget::ID, BL_PROGRAM_ID]
+    );
+    assert_eq!(message.address_table_lookups.len(), 1);
+}
+
+#[test]
+fn test_preflight() {
+    let sender = FixtureSender::new();
+    let client = client(&sender);
+    let authority = Keypair::new();
+
+    let transaction = TransactionBuilder::new(authority.pubkey())
+        .instruction(reward_instruction(authority.pubkey()))
+        .build_with(25_000, None, Hash::default(), &[&authority])
+        .unwrap();
+    assert_eq!(preflight(&client, &transaction).unwrap(), SIMULATED_UNITS);
+
+    sender.fail_simulations(json!({ "InstructionError": [0, { "Custom": 6004 }] }));
+    let Err(Error::Simulation { logs, .. }) = preflight(&client, &transaction) else {
+        panic!("the preflight should fail");
+    };
+    assert_eq!(logs, ["Program log: simulated"]);
+}
@@ -1,21 +1,26 @@
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::Id;
 use anchor_spl::token::Token;
+use buddy_link::client::sender::{ReliableSender, SendOutcome};
+use buddy_link::client::transaction::TransactionBuilder;
+use buddy_link::client::Error;
 use buddy_link::instruction::{
     transfer_checked_global_only_reward, transfer_checked_global_reward,
     transfer_secure_local_reward, transfer_unchecked_local_shared_reward, validate_referrer,
-    GeneralTransferRewardArgs, TransferUncheckedLocalSharedRewardArgs,
+    GeneralTransferRewardArgs, RewardAsset, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs,
 };
 use lazy_static::lazy_static;
 use solana_client::client_error::{ClientError, ClientErrorKind};
 use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_program::instruction::{Instruction, InstructionError};
 use solana_program::pubkey::Pubkey;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey;
 use solana_sdk::signature::{Keypair, Signature, Signer};
-use solana_sdk::system_transaction;
-use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::TransactionError;
 use spl_associated_token_account::get_associated_token_address;
 use spl_associated_token_account::instruction::create_associated_token_account;
 use std::thread::sleep;
@@ -52,12 +57,16 @@ const REFEREE_ATA: Pubkey = pubkey!("C2LZp5DNf6JsjEWoiQiS1jXQPopi3y4K2H7zN6App7m
 const REFEREE_MEMBER: Pubkey = pubkey!("9xNqfpwRUEyqpURcgNZWFUrurrSRtdQTYpUQGbpJXWpp");
 
 fn is_error_code(signature: &Result<Signature, ClientError>, error_code: u32) -> bool {
-    if let Err(ClientError { kind, .. }) = signature {
-        if let ClientErrorKind::TransactionError(e) = kind {
-            if let TransactionError::InstructionError(_, InstructionError::Custom(code)) = e {
-                return code == &error_code;
-            }
-        }
+    if let Err(ClientError {
+        kind:
+            ClientErrorKind::TransactionError(TransactionError::InstructionError(
+                _,
+                InstructionError::Custom(code),
+            )),
+        ..
+    }) = signature
+    {
+        return code == &error_code;
     }
     false
 }
@@ -73,54 +82,34 @@ fn airdrop(admin: &Keypair) {
 fn transfer(alice: &Keypair, bob: &Keypair) -> Result<Signature, ClientError> {
     // Transfer lamports from Alice to Bob
     let lamports = 5000;
-    let latest_blockhash = CLIENT.get_latest_blockhash()?;
-    let mut tx = system_transaction::transfer(&alice, &bob.pubkey(), lamports, latest_blockhash);
-
-    tx.sign(&[alice], CLIENT.get_latest_blockhash().unwrap());
-
-    let signature = CLIENT.send_and_confirm_transaction_with_spinner_and_config(
-        &tx,
-        CommitmentConfig::confirmed(),
-        RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: None,
-            encoding: None,
-            max_retries: None,
-            min_context_slot: None,
-        },
-    );
-
-    sleep(Duration::from_secs(1));
+    let instruction = system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), lamports);
 
-    signature
+    send(alice, instruction)
 }
 
 fn execute_txn(admin: &Keypair, instruction: Instruction) -> Result<Signature, ClientError> {
-    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&admin.pubkey()));
-
-    transaction.sign(&[admin], CLIENT.get_latest_blockhash().unwrap());
-
-    let signature = CLIENT.send_and_confirm_transaction_with_spinner_and_config(
-        &transaction,
-        CommitmentConfig::confirmed(),
-        RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: None,
-            encoding: None,
-            max_retries: None,
-            min_context_slot: None,
-        },
-    );
-
-    sleep(Duration::from_secs(1));
+    send(admin, instruction)
+}
 
-    signature
+/// Sends until the transaction lands, a failed transaction is returned as its error.
+fn send(payer: &Keypair, instruction: Instruction) -> Result<Signature, ClientError> {
+    let builder = TransactionBuilder::new(payer.pubkey())
+        .instruction(instruction)
+        .compute_unit_limit(200_000);
+
+    match ReliableSender::new(&CLIENT).sign_and_send(&builder, &[payer]) {
+        Ok(SendOutcome::Confirmed { signature, .. }) => Ok(signature),
+        Ok(SendOutcome::Failed { error, .. }) => Err(error.into()),
+        Ok(outcome) => panic!("transaction didn't land: {:?}", outcome),
+        Err(Error::Rpc(error)) => Err(*error),
+        Err(error) => panic!("{}", error),
+    }
 }
 
 fn create_ata(admin: &Keypair) -> Pubkey {
     let ix = create_associated_token_account(&admin.pubkey(), &admin.pubkey(), &MINT, &Token::id());
 
-    let _ = execute_txn(&admin, ix);
+    let _ = execute_txn(admin, ix);
 
     sleep(Duration::from_secs(1));
 
@@ -135,8 +124,6 @@ fn test_airdrop_working() {
     let admin: Keypair = Keypair::new();
 
     airdrop(&admin);
-
-    assert!(true);
 }
 
 #[test]
@@ -192,10 +179,7 @@ fn test_transfer_unchecked_local_shared_reward() {
 
     let instruction = transfer_unchecked_local_shared_reward(
         admin.pubkey(),
-        Some(solana_program::system_program::id()),
-        None,
-        None,
-        None,
+        &SharedRewardAsset::Sol,
         &[REFERRER_TREASURY, REFERRER_MEMBER],
         &TransferUncheckedLocalSharedRewardArgs {
             total_amount: 10,
@@ -282,12 +266,13 @@ fn test_transfer_checked_global_only_reward() {
 
     let instruction = transfer_checked_global_only_reward(
         admin.pubkey(),
-        None,
-        Some(MINT),
-        Some(Token::id()),
-        //We use random ata because it needs to be owned by the authority here, so it doesn't matter
-        Some(ata_to_use),
-        Some(REFERRER_ATA),
+        &RewardAsset::Spl {
+            mint: MINT,
+            token_program: Token::id(),
+            //We use random ata because it needs to be owned by the authority here, so it doesn't matter
+            from: ata_to_use,
+            to: REFERRER_ATA,
+        },
         REFERRER_TREASURY,
         REFERRER_TREASURY,
         REFEREE_GLOBAL_BUDDY,
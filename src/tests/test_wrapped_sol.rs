@@ -0,0 +1,349 @@
+mod deployed_program;
+
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use buddy_link::constants::BL_PROGRAM_ID;
+use buddy_link::cpi;
+use buddy_link::instruction::{
+    is_native_mint, shared_reward_amounts, transfer_checked_global_only_reward,
+    transfer_unchecked_local_shared_reward, unwrap_shared_to_sol, unwrap_to_sol, with_wrapped_sol,
+    GeneralTransferRewardArgs, RewardAsset, SharedRewardAsset,
+    TransferUncheckedLocalSharedRewardArgs, WrappedSolOptions, WrappedSolSource,
+};
+use deployed_program::{add_snapshot, snapshot};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+/// Program keeping SOL in a wSOL account of its PDA, see [`process_vault`].
+const VAULT_PROGRAM_ID: Pubkey = pubkey!("Vau1t11111111111111111111111111111111111111");
+
+/// Wraps the lamports of its PDA (instruction 0, amount in the data) or unwraps all of its wSOL account back to it
+/// (instruction 1).
+fn process_vault(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let system_program = next_account_info(accounts)?;
+    let token_program = next_account_info(accounts)?;
+    let vault = next_account_info(accounts)?;
+    let wsol_account = next_account_info(accounts)?;
+    let (_, bump) = Pubkey::find_program_address(&[b"vault"], program_id);
+    let seeds: &[&[&[u8]]] = &[&[b"vault", &[bump]]];
+
+    match data.split_first() {
+        Some((0, lamports)) => cpi::native::wrap_sol(
+            system_program,
+            token_program,
+            vault,
+            wsol_account,
+            u64::from_le_bytes(lamports.try_into().unwrap()),
+            seeds,
+        ),
+        _ => cpi::native::unwrap_sol(token_program, wsol_account, vault, vault, seeds),
+    }
+}
+
+/// The deployed BuddyLink program with the fixtures and the vault program.
+async fn start(accounts: &[(Pubkey, Account)]) -> ProgramTestContext {
+    let mut snapshot = snapshot();
+
+    for (pubkey, account) in accounts {
+        snapshot.insert(*pubkey, account.clone());
+    }
+
+    let mut program_test = ProgramTest::default();
+    // The vault is native
+    program_test.prefer_bpf(false);
+    program_test.add_program("vault", VAULT_PROGRAM_ID, processor!(process_vault));
+    add_snapshot(&mut program_test, &snapshot);
+
+    program_test.start_with_context().await
+}
+
+async fn execute(context: &mut ProgramTestContext, instructions: &[Instruction]) {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+async fn token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+fn create_wsol_account(payer: &Pubkey, owner: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(
+        payer,
+        owner,
+        &spl_token::native_mint::ID,
+        &spl_token::ID,
+    )
+}
+
+fn shared_reward(
+    authority: Pubkey,
+    asset: &SharedRewardAsset,
+    recipients: &[Pubkey],
+    total_amount: u64,
+) -> Instruction {
+    transfer_unchecked_local_shared_reward(
+        authority,
+        asset,
+        recipients,
+        &TransferUncheckedLocalSharedRewardArgs {
+            total_amount,
+            shares_in_bps: vec![5_000, 5_000],
+            members_included: false,
+        },
+    )
+}
+
+#[test]
+fn test_with_wrapped_sol() {
+    let authority = Pubkey::new_unique();
+    let from = Pubkey::new_unique();
+    let transfer = transfer_checked_global_only_reward(
+        authority,
+        &RewardAsset::Sol,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &GeneralTransferRewardArgs { amount: 1_000 },
+    );
+    let options = WrappedSolOptions {
+        wrap_lamports: 1_000,
+        unwrap_to: Some(authority),
+    };
+
+    let wsol = RewardAsset::Spl {
+        mint: spl_token::native_mint::ID,
+        token_program: spl_token::ID,
+        from,
+        to: Pubkey::new_unique(),
+    };
+    let source = wsol.wrapped_sol_source();
+    assert_eq!(
+        source,
+        Some(WrappedSolSource {
+            token_account: from,
+            token_program: spl_token::ID,
+        })
+    );
+    let instructions =
+        with_wrapped_sol(authority, source, vec![transfer.clone()], &options).unwrap();
+    let program_ids: Vec<_> = instructions.iter().map(|x| x.program_id).collect();
+    assert_eq!(
+        program_ids,
+        [
+            system_program::ID,
+            spl_token::ID,
+            BL_PROGRAM_ID,
+            spl_token::ID
+        ]
+    );
+    assert_eq!(instructions[2], transfer);
+    // Nothing asked
+    let instructions = with_wrapped_sol(
+        authority,
+        source,
+        vec![transfer.clone()],
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(instructions, std::slice::from_ref(&transfer));
+
+    // Not the native mint
+    let other = RewardAsset::Spl {
+        mint: Pubkey::new_unique(),
+        token_program: spl_token::ID,
+        from,
+        to: Pubkey::new_unique(),
+    };
+    assert_eq!(other.wrapped_sol_source(), None);
+    assert_eq!(RewardAsset::Sol.wrapped_sol_source(), None);
+    assert_eq!(
+        with_wrapped_sol(authority, None, vec![transfer.clone()], &options).unwrap(),
+        [transfer]
+    );
+
+    assert!(is_native_mint(&spl_token_2022::native_mint::ID));
+    let shared = SharedRewardAsset::Spl {
+        mint: spl_token_2022::native_mint::ID,
+        token_program: spl_token_2022::ID,
+        from,
+    };
+    assert_eq!(
+        shared.wrapped_sol_source().map(|x| x.token_program),
+        Some(spl_token_2022::ID)
+    );
+
+    let (close, asset) = unwrap_to_sol(&wsol, authority).unwrap();
+    assert_eq!(asset, RewardAsset::Sol);
+    assert_eq!(close.unwrap().accounts[0].pubkey, from);
+    assert_eq!(unwrap_to_sol(&other, authority).unwrap(), (None, other));
+    let (close, asset) = unwrap_shared_to_sol(&shared, authority).unwrap();
+    assert_eq!(asset, SharedRewardAsset::Sol);
+    assert_eq!(close.unwrap().program_id, spl_token_2022::ID);
+}
+
+#[tokio::test]
+async fn test_wrapped_sol_reward() {
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut context = start(&[]).await;
+    let payer = context.payer.pubkey();
+    let from = get_associated_token_address(&payer, &spl_token::native_mint::ID);
+    let recipient_accounts =
+        recipients.map(|x| get_associated_token_address(&x, &spl_token::native_mint::ID));
+    let asset = SharedRewardAsset::Spl {
+        mint: spl_token::native_mint::ID,
+        token_program: spl_token::ID,
+        from,
+    };
+
+    let mut instructions = vec![create_wsol_account(&payer, &payer)];
+    instructions.extend(recipients.iter().map(|x| create_wsol_account(&payer, x)));
+    execute(&mut context, &instructions).await;
+    let rent = balance(&mut context, from).await;
+    let before = balance(&mut context, payer).await;
+
+    let total_amount = 1_000_001;
+    let instructions = with_wrapped_sol(
+        payer,
+        asset.wrapped_sol_source(),
+        vec![shared_reward(
+            payer,
+            &asset,
+            &recipient_accounts,
+            total_amount,
+        )],
+        &WrappedSolOptions {
+            wrap_lamports: 2_000_000,
+            unwrap_to: Some(payer),
+        },
+    )
+    .unwrap();
+    execute(&mut context, &instructions).await;
+
+    let amounts = shared_reward_amounts(total_amount, &[5_000, 5_000]).unwrap();
+    for (i, account) in recipient_accounts.into_iter().enumerate() {
+        assert_eq!(token_amount(&mut context, account).await, amounts[i]);
+        assert_eq!(balance(&mut context, account).await, rent + amounts[i]);
+    }
+    // What wasn't sent is back as SOL with the rent, the source is closed
+    assert_eq!(balance(&mut context, from).await, 0);
+    assert_eq!(
+        balance(&mut context, payer).await,
+        before + rent - amounts.iter().sum::<u64>() - 5_000
+    );
+}
+
+#[tokio::test]
+async fn test_unwrap_to_sol_reward() {
+    let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let accounts = recipients.map(|x| (x, Account::new(1_000_000_000, 0, &system_program::ID)));
+    let mut context = start(&accounts).await;
+    let payer = context.payer.pubkey();
+    let from = get_associated_token_address(&payer, &spl_token::native_mint::ID);
+    let wsol = SharedRewardAsset::Spl {
+        mint: spl_token::native_mint::ID,
+        token_program: spl_token::ID,
+        from,
+    };
+
+    let mut instructions = vec![create_wsol_account(&payer, &payer)];
+    instructions.extend(
+        with_wrapped_sol(
+            payer,
+            wsol.wrapped_sol_source(),
+            vec![],
+            &WrappedSolOptions {
+                wrap_lamports: 3_000_000,
+                unwrap_to: None,
+            },
+        )
+        .unwrap(),
+    );
+    execute(&mut context, &instructions).await;
+    assert_eq!(token_amount(&mut context, from).await, 3_000_000);
+
+    // The referrers are paid in SOL from the wSOL account
+    let (close, asset) = unwrap_shared_to_sol(&wsol, payer).unwrap();
+    execute(
+        &mut context,
+        &[
+            close.unwrap(),
+            shared_reward(payer, &asset, &recipients, 2_000_000),
+        ],
+    )
+    .await;
+
+    assert_eq!(balance(&mut context, from).await, 0);
+    for recipient in recipients {
+        assert_eq!(balance(&mut context, recipient).await, 1_001_000_000);
+    }
+}
+
+#[tokio::test]
+async fn test_cpi_pda_wrapped_sol() {
+    let (vault, _) = Pubkey::find_program_address(&[b"vault"], &VAULT_PROGRAM_ID);
+    let mut context = start(&[(vault, Account::new(1_000_000_000, 0, &system_program::ID))]).await;
+    let payer = context.payer.pubkey();
+    let wsol_account = get_associated_token_address(&vault, &spl_token::native_mint::ID);
+    let vault_instruction = |data: Vec<u8>| Instruction {
+        program_id: VAULT_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(wsol_account, false),
+        ],
+        data,
+    };
+
+    execute(
+        &mut context,
+        &[
+            create_wsol_account(&payer, &vault),
+            vault_instruction([&[0][..], &400_000_000u64.to_le_bytes()].concat()),
+        ],
+    )
+    .await;
+    assert_eq!(token_amount(&mut context, wsol_account).await, 400_000_000);
+    assert_eq!(balance(&mut context, vault).await, 600_000_000);
+
+    execute(&mut context, &[vault_instruction(vec![1])]).await;
+    assert_eq!(balance(&mut context, wsol_account).await, 0);
+    // The rent paid by the payer goes to the vault too
+    assert!(balance(&mut context, vault).await > 1_000_000_000);
+}
@@ -1,29 +1,18 @@
-use anchor_lang::{Key, ToAccountInfo};
 use solana_program::account_info::AccountInfo;
-use solana_program::hash::hash;
 use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
-use crate::constants::BL_PROGRAM_ID;
+use crate::constants::{BL_PROGRAM_ID, TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR};
+use crate::instruction::{GeneralTransferRewardArgs, TransferUncheckedLocalSharedRewardArgs};
 
-pub fn get_key_or_none<T>(account: &Option<T>) -> Option<Pubkey>
-    where
-        T: Key,
-{
-    account.as_ref().map(|account| account.key())
+pub fn get_native_key_or_none(account: Option<&AccountInfo>) -> Option<Pubkey> {
+    account.map(|account| *account.key)
 }
 
-pub fn get_account_info_or_default<'info, T>(
-    account: &Option<T>,
-    default_account_info: &AccountInfo<'info>,
-) -> AccountInfo<'info>
-    where
-        T: ToAccountInfo<'info>,
-{
-    if let Some(account) = account {
-        return account.to_account_info();
-    }
-
-    default_account_info.to_account_info()
+pub fn get_native_account_info_or_default<'a, 'info>(
+    account: Option<&'a AccountInfo<'info>>,
+    default_account_info: &'a AccountInfo<'info>,
+) -> &'a AccountInfo<'info> {
+    account.unwrap_or(default_account_info)
 }
 
 pub fn get_account_meta_or_read_default(pubkey: &Option<Pubkey>) -> AccountMeta {
@@ -34,10 +23,34 @@ pub fn get_account_meta_or_read_default(pubkey: &Option<Pubkey>) -> AccountMeta
     AccountMeta::new_readonly(BL_PROGRAM_ID, false)
 }
 
-pub fn get_instruction_name_data(instruction_name: &str) -> Vec<u8> {
-    let mut instruction_data: Vec<u8> = vec![];
+/// Discriminator followed by the amount, the only field of [`GeneralTransferRewardArgs`].
+pub const GENERAL_TRANSFER_REWARD_DATA_LEN: usize = 16;
+
+pub fn get_general_transfer_reward_data(
+    discriminator: &[u8; 8],
+    transfer_args: &GeneralTransferRewardArgs,
+) -> [u8; GENERAL_TRANSFER_REWARD_DATA_LEN] {
+    let mut instruction_data = [0; GENERAL_TRANSFER_REWARD_DATA_LEN];
 
-    instruction_data.extend_from_slice(&hash(format!("global:{}", instruction_name).as_bytes()).to_bytes()[..8]);
+    instruction_data[..8].copy_from_slice(discriminator);
+    instruction_data[8..].copy_from_slice(&transfer_args.amount.to_le_bytes());
 
     instruction_data
-}
\ No newline at end of file
+}
+
+pub fn get_transfer_unchecked_local_shared_reward_data(
+    transfer_args: &TransferUncheckedLocalSharedRewardArgs,
+) -> Vec<u8> {
+    let shares_in_bps = &transfer_args.shares_in_bps;
+    let mut instruction_data = Vec::with_capacity(21 + shares_in_bps.len() * 2);
+
+    instruction_data.extend_from_slice(&TRANSFER_REWARD_UNCHECKED_MULTIPLE_DISCRIMINATOR);
+    instruction_data.extend_from_slice(&transfer_args.total_amount.to_le_bytes());
+    instruction_data.extend_from_slice(&(shares_in_bps.len() as u32).to_le_bytes());
+    for share_in_bps in shares_in_bps {
+        instruction_data.extend_from_slice(&share_in_bps.to_le_bytes());
+    }
+    instruction_data.push(transfer_args.members_included as u8);
+
+    instruction_data
+}